@@ -0,0 +1,99 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Type};
+
+/// Implements `AnimationLens` for a struct with `start` and `end` fields,
+/// interpolating one field of a component between them
+///
+/// ```ignore
+/// #[derive(AnimationLens)]
+/// #[lens(component = "Transform", field = "translation")]
+/// struct MyLens {
+///     start: Vec3,
+///     end: Vec3,
+/// }
+/// ```
+/// `field` can be a dotted path into the component, e.g. `"color"` on `Sprite`
+#[proc_macro_derive(AnimationLens, attributes(lens))]
+pub fn derive_animation_lens(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "AnimationLens can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "AnimationLens requires named `start` and `end` fields",
+        ));
+    };
+    for required in ["start", "end"] {
+        let found = fields
+            .named
+            .iter()
+            .any(|field| field.ident.as_ref().is_some_and(|ident| ident == required));
+        if !found {
+            return Err(syn::Error::new_spanned(
+                name,
+                format!("AnimationLens requires the `{required}` field"),
+            ));
+        }
+    }
+
+    let mut component: Option<Type> = None;
+    let mut field: Option<Vec<Ident>> = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("lens"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let value: LitStr = meta.value()?.parse()?;
+            if meta.path.is_ident("component") {
+                component = Some(value.parse()?);
+            } else if meta.path.is_ident("field") {
+                let path = value
+                    .value()
+                    .split('.')
+                    .map(|segment| syn::parse_str::<Ident>(segment.trim()))
+                    .collect::<syn::Result<Vec<_>>>()
+                    .map_err(|_| syn::Error::new_spanned(&value, "invalid field path"))?;
+                field = Some(path);
+            } else {
+                return Err(meta.error("expected `component` or `field`"));
+            }
+            Ok(())
+        })?;
+    }
+    let missing = |key: &str| {
+        syn::Error::new_spanned(
+            name,
+            format!("missing `#[lens({key} = \"...\")]` attribute"),
+        )
+    };
+    let component = component.ok_or_else(|| missing("component"))?;
+    let field = field.ok_or_else(|| missing("field"))?;
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::bevy_toolbox::animation::AnimationLens for #name #ty_generics #where_clause {
+            type C = #component;
+
+            fn lerp(&self, target: &mut Self::C, progress: f32) {
+                target.#(#field).* =
+                    ::bevy_toolbox::animation::Lerpable::lerp(&self.start, &self.end, progress);
+            }
+        }
+    })
+}
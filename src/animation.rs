@@ -1,22 +1,41 @@
-use std::time::Duration;
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
 
 use bevy::{
+    ecs::system::SystemParam,
     prelude::{
-        debug, Component, Entity, Event, Events, Plugin, Query, Res, ResMut, Transform, Update,
-        Vec3,
+        debug, Added, App, Color, Commands, Component, Entity, Event, Events, Gizmos,
+        GlobalTransform, IntoSystemConfigs, Local, Plugin, PostUpdate, Quat, Query, Res, ResMut,
+        Resource, Text, Transform, Update, Vec2, Vec3, Vec4,
     },
     time::Time,
+    transform::TransformSystem,
 };
 use interpolation::{Ease, EaseFunction};
 
+#[derive(Clone)]
 pub enum AnimationCurve {
     EaseFunction(EaseFunction),
     Linear,
     Step(f32),
     Custom(fn(f32) -> f32),
+    /// Evenly spaced samples, first at progress 0 and last at progress 1,
+    /// linearly interpolated in between
+    Samples(Vec<f32>),
 }
 
 impl AnimationCurve {
+    /// None if there are less than 2 samples
+    pub fn from_samples(samples: &[f32]) -> Option<Self> {
+        if samples.len() < 2 {
+            return None;
+        }
+        Some(Self::Samples(samples.to_vec()))
+    }
+
     pub fn eval(&self, progress: f32) -> f32 {
         match self {
             AnimationCurve::EaseFunction(ease_func) => Ease::calc(progress, *ease_func),
@@ -29,6 +48,30 @@ impl AnimationCurve {
                 }
             }
             AnimationCurve::Custom(func) => (func)(progress),
+            AnimationCurve::Samples(samples) => match samples.len() {
+                0 => progress,
+                1 => samples[0],
+                n => {
+                    let position = progress.clamp(0.0, 1.0) * (n - 1) as f32;
+                    let i = (position.floor() as usize).min(n - 2);
+                    let t = position - i as f32;
+                    samples[i] + (samples[i + 1] - samples[i]) * t
+                }
+            },
+        }
+    }
+}
+
+/// Custom curves are equal when they are the same function
+impl PartialEq for AnimationCurve {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::EaseFunction(a), Self::EaseFunction(b)) => a == b,
+            (Self::Linear, Self::Linear) => true,
+            (Self::Step(a), Self::Step(b)) => a == b,
+            (Self::Custom(a), Self::Custom(b)) => std::ptr::fn_addr_eq(*a, *b),
+            (Self::Samples(a), Self::Samples(b)) => a == b,
+            _ => false,
         }
     }
 }
@@ -67,9 +110,154 @@ pub struct Delay {
     pub duration: Duration,
 }
 
+/// What a [`AnimationStep::WaitFor`] step holds for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitCondition {
+    /// Until the signal is raised in [`AnimationSignals`]
+    Signal(u32),
+}
+
+impl WaitCondition {
+    fn is_met(&self, signals: &AnimationSignals) -> bool {
+        match self {
+            WaitCondition::Signal(id) => signals.is_raised(*id),
+        }
+    }
+}
+
+/// Signals that [`AnimationStep::WaitFor`] steps wait on
+///
+/// Signals stay raised until cleared, so a step reached after its signal
+/// was raised passes right away, as does the same step on later loops
+#[derive(Resource, Default)]
+pub struct AnimationSignals(HashSet<u32>);
+
+impl AnimationSignals {
+    pub fn raise(&mut self, id: u32) {
+        self.0.insert(id);
+    }
+
+    pub fn clear(&mut self, id: u32) {
+        self.0.remove(&id);
+    }
+
+    pub fn is_raised(&self, id: u32) -> bool {
+        self.0.contains(&id)
+    }
+}
+
 pub enum AnimationStep<TLens: AnimationLens> {
     Animation(Animation, TLens),
     Delay(Delay),
+    /// Plays the inner step the given number of times before advancing,
+    /// a count of 0 plays it once
+    Repeated(Box<AnimationStep<TLens>>, u32),
+    /// Plays the inner steps to completion before advancing,
+    /// in reverse order while travelling backward
+    Sequence(Vec<AnimationStep<TLens>>),
+    /// Holds without applying anything until the condition is met,
+    /// travelling backward waits for it again
+    WaitFor(WaitCondition),
+}
+
+impl<TLens: AnimationLens> AnimationStep<TLens> {
+    pub fn repeated(self, count: u32) -> Self {
+        Self::Repeated(Box::new(self), count)
+    }
+
+    /// Pushes the cursor of the first leaf step in the given direction
+    fn enter(&self, direction: AnimationDirection, cursor: &mut Vec<usize>) {
+        match self {
+            AnimationStep::Repeated(inner, count) => {
+                cursor.push(match direction {
+                    AnimationDirection::Forward => 0,
+                    AnimationDirection::Backward => (*count).max(1) as usize - 1,
+                });
+                inner.enter(direction, cursor);
+            }
+            AnimationStep::Sequence(steps) => enter_steps(steps, direction, cursor),
+            _ => {}
+        }
+    }
+
+    /// Moves `cursor[depth..]` to the next leaf step inside this step,
+    /// false if this step is exhausted in the given direction
+    fn advance(
+        &self,
+        direction: AnimationDirection,
+        cursor: &mut Vec<usize>,
+        depth: usize,
+    ) -> bool {
+        match self {
+            AnimationStep::Repeated(inner, count) => {
+                if inner.advance(direction, cursor, depth + 1) {
+                    return true;
+                }
+                let repetition = &mut cursor[depth];
+                match direction {
+                    AnimationDirection::Forward if *repetition + 1 < (*count).max(1) as usize => {
+                        *repetition += 1
+                    }
+                    AnimationDirection::Backward if *repetition > 0 => *repetition -= 1,
+                    _ => return false,
+                }
+                cursor.truncate(depth + 1);
+                inner.enter(direction, cursor);
+                true
+            }
+            AnimationStep::Sequence(steps) => advance_steps(steps, direction, cursor, depth),
+            _ => false,
+        }
+    }
+
+    /// The leaf step under `cursor`, relative to this step
+    fn leaf(&self, cursor: &[usize]) -> &Self {
+        match self {
+            AnimationStep::Repeated(inner, _) => inner.leaf(&cursor[1..]),
+            AnimationStep::Sequence(steps) => steps[cursor[0]].leaf(&cursor[1..]),
+            _ => self,
+        }
+    }
+
+    fn leaf_mut(&mut self, cursor: &[usize]) -> &mut Self {
+        match self {
+            AnimationStep::Repeated(inner, _) => inner.leaf_mut(&cursor[1..]),
+            AnimationStep::Sequence(steps) => steps[cursor[0]].leaf_mut(&cursor[1..]),
+            _ => self,
+        }
+    }
+
+    /// Repetition index of the innermost repeated step around the leaf under `cursor`
+    fn repetition(&self, cursor: &[usize]) -> Option<u32> {
+        match self {
+            AnimationStep::Repeated(inner, _) => {
+                inner.repetition(&cursor[1..]).or(Some(cursor[0] as u32))
+            }
+            AnimationStep::Sequence(steps) => steps[cursor[0]].repetition(&cursor[1..]),
+            _ => None,
+        }
+    }
+
+    fn last_lens(&self) -> Option<&TLens> {
+        match self {
+            AnimationStep::Animation(_, lens) => Some(lens),
+            AnimationStep::Delay(_) | AnimationStep::WaitFor(_) => None,
+            AnimationStep::Repeated(inner, _) => inner.last_lens(),
+            AnimationStep::Sequence(steps) => steps.iter().rev().find_map(Self::last_lens),
+        }
+    }
+
+    /// Drops empty nested sequences, false if nothing playable is left
+    fn prune(&mut self) -> bool {
+        match self {
+            AnimationStep::Repeated(inner, _) => inner.prune(),
+            AnimationStep::Sequence(steps) => {
+                steps.retain_mut(Self::prune);
+                !steps.is_empty()
+            }
+            _ => true,
+        }
+    }
 }
 
 // impl<TLens: AnimationLens> AnimationStep<TLens> {
@@ -141,7 +329,7 @@ pub enum AnimationStep<TLens: AnimationLens> {
 //     }
 // }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AnimationDirection {
     Forward,
     Backward,
@@ -176,6 +364,8 @@ impl std::ops::Not for AnimationDirection {
 
 struct AnimationState {
     // TODO
+    /// Whether the lens of the current step has begun
+    started: bool,
     completed: bool,
     direction: AnimationDirection,
     progress: f32,
@@ -188,17 +378,218 @@ pub enum Repeat {
     Mirrored,
 }
 
+/// Clock an animator advances with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeSource {
+    /// Game time, follows pausing and relative speed of [`Time`]
+    #[default]
+    Virtual,
+    /// Wall-clock time, keeps playing while the game time is paused or slowed
+    Real,
+}
+
+impl TimeSource {
+    pub fn delta_seconds(&self, time: &Time) -> f32 {
+        match self {
+            TimeSource::Virtual => time.delta_seconds(),
+            TimeSource::Real => time.raw_delta_seconds(),
+        }
+    }
+}
+
+/// Global settings for all animators, inserted by [`AnimationPlugin`]
+#[derive(Resource, Clone, Default)]
+pub struct AnimationSettings {
+    /// Upper bound on the time an animator advances in a single frame
+    pub max_delta: Option<Duration>,
+}
+
+/// Logical group of animated entities that are paused or sped up together,
+/// see [`AnimationGroups`]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AnimationGroup(pub u32);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupState {
+    pub paused: bool,
+    pub speed: f32,
+}
+
+impl Default for GroupState {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: 1.0,
+        }
+    }
+}
+
+impl GroupState {
+    fn scale(&self) -> f32 {
+        if self.paused {
+            0.0
+        } else {
+            self.speed
+        }
+    }
+}
+
+/// Playback state of each [`AnimationGroup`]
+#[derive(Resource, Default)]
+pub struct AnimationGroups {
+    groups: HashMap<u32, GroupState>,
+    /// State of entities without a group
+    pub default: GroupState,
+}
+
+impl AnimationGroups {
+    pub fn get(&self, id: u32) -> GroupState {
+        self.groups.get(&id).copied().unwrap_or_default()
+    }
+
+    pub fn pause(&mut self, id: u32) {
+        self.groups.entry(id).or_default().paused = true;
+    }
+
+    pub fn resume(&mut self, id: u32) {
+        self.groups.entry(id).or_default().paused = false;
+    }
+
+    pub fn set_speed(&mut self, id: u32, speed: f32) {
+        self.groups.entry(id).or_default().speed = speed;
+    }
+
+    fn scale(&self, group: Option<&AnimationGroup>) -> f32 {
+        match group {
+            Some(group) => self.get(group.0).scale(),
+            None => self.default.scale(),
+        }
+    }
+}
+
+/// Everything deciding how far animators advance in a frame
+#[derive(SystemParam)]
+pub struct AnimationTime<'w> {
+    time: Res<'w, Time>,
+    settings: Res<'w, AnimationSettings>,
+    groups: Res<'w, AnimationGroups>,
+}
+
+impl AnimationTime<'_> {
+    fn delta_seconds(&self, clock: &AnimationClock, group: Option<&AnimationGroup>) -> f32 {
+        clock.delta_seconds(&self.time, &self.settings, self.groups.scale(group))
+    }
+}
+
+/// How much time an animator advances each frame
+#[derive(Clone, Copy, Default)]
+struct AnimationClock {
+    time_source: TimeSource,
+    max_delta: Option<Duration>,
+}
+
+impl AnimationClock {
+    fn delta_seconds(&self, time: &Time, settings: &AnimationSettings, speed: f32) -> f32 {
+        let mut delta = self.time_source.delta_seconds(time) * speed;
+        for max_delta in [self.max_delta, settings.max_delta].into_iter().flatten() {
+            delta = delta.min(max_delta.as_secs_f32());
+        }
+        delta
+    }
+}
+
+/// Values a derived [`AnimationLens`] can interpolate
+pub trait Lerpable {
+    fn lerp(start: &Self, end: &Self, progress: f32) -> Self;
+}
+
+impl Lerpable for f32 {
+    fn lerp(start: &Self, end: &Self, progress: f32) -> Self {
+        start + (end - start) * progress
+    }
+}
+
+impl Lerpable for Vec2 {
+    fn lerp(start: &Self, end: &Self, progress: f32) -> Self {
+        *start + (*end - *start) * progress
+    }
+}
+
+impl Lerpable for Vec3 {
+    fn lerp(start: &Self, end: &Self, progress: f32) -> Self {
+        *start + (*end - *start) * progress
+    }
+}
+
+impl Lerpable for Quat {
+    fn lerp(start: &Self, end: &Self, progress: f32) -> Self {
+        start.slerp(*end, progress)
+    }
+}
+
+/// Component-wise in sRGBA
+impl Lerpable for Color {
+    fn lerp(start: &Self, end: &Self, progress: f32) -> Self {
+        let [r, g, b, a] = Vec4::lerp(
+            Vec4::from(start.as_rgba_f32()),
+            Vec4::from(end.as_rgba_f32()),
+            progress,
+        )
+        .to_array();
+        Color::rgba(r, g, b, a)
+    }
+}
+
+pub use bevy_toolbox_derive::AnimationLens;
+
 pub trait AnimationLens: Send + Sync + 'static {
     type C: Component;
+
+    /// Called with the current value of the target before the lens is first applied,
+    /// lenses can capture their starting point here
+    fn begin(&mut self, _target: &Self::C) {}
+
     fn lerp(&self, target: &mut Self::C, progress: f32);
+
+    fn reversed(self) -> Reversed<Self>
+    where
+        Self: Sized,
+    {
+        Reversed(self)
+    }
+}
+
+/// Plays the inner lens from its end value to its start value
+///
+/// Only the values are swapped, the direction of the animator still applies on top:
+/// a reversed lens in an animator going [`AnimationDirection::Backward`]
+/// plays from start to end again
+pub struct Reversed<TLens>(pub TLens);
+
+impl<TLens: AnimationLens> AnimationLens for Reversed<TLens> {
+    type C = TLens::C;
+
+    fn begin(&mut self, target: &Self::C) {
+        self.0.begin(target);
+    }
+
+    fn lerp(&self, target: &mut Self::C, progress: f32) {
+        self.0.lerp(target, 1.0 - progress);
+    }
 }
 
 #[derive(Component)]
 pub struct Animator<TLens: AnimationLens> {
     id: Option<u32>,
+    clock: AnimationClock,
     state: AnimationState,
     animation: Animation,
     repeat: Repeat,
+    loop_delay: Duration,
+    backward_duration: Option<Duration>,
+    backward_curve: Option<AnimationCurve>,
+    /// Rest left before the next repetition starts, if at the end of one
+    rest: Option<f32>,
     lens: TLens,
 }
 
@@ -206,13 +597,19 @@ impl<TLens: AnimationLens> Animator<TLens> {
     pub fn new(animation: Animation, repeat: Repeat, lens: TLens) -> Self {
         Self {
             id: None,
+            clock: AnimationClock::default(),
             state: AnimationState {
+                started: false,
                 completed: false,
                 direction: AnimationDirection::Forward,
                 progress: 0.0,
             },
             animation,
             repeat,
+            loop_delay: Duration::ZERO,
+            backward_duration: None,
+            backward_curve: None,
+            rest: None,
             lens,
         }
     }
@@ -225,13 +622,19 @@ impl<TLens: AnimationLens> Animator<TLens> {
     ) -> Self {
         Self {
             id: None,
+            clock: AnimationClock::default(),
             state: AnimationState {
+                started: false,
                 completed: false,
                 direction,
                 progress: direction.start_point(),
             },
             animation,
             repeat,
+            loop_delay: Duration::ZERO,
+            backward_duration: None,
+            backward_curve: None,
+            rest: None,
             lens,
         }
     }
@@ -241,117 +644,200 @@ impl<TLens: AnimationLens> Animator<TLens> {
         self
     }
 
-    fn tick(
-        &mut self,
-        target: &mut TLens::C,
-        time_elapsed: f32,
-        entity: Entity,
-        events: &mut Events<AnimationCompleted>,
-    ) -> f32 {
+    /// Reported as [`AnimationCompleted::animator_id`]
+    pub fn id(&self) -> Option<u32> {
+        self.id
+    }
+
+    pub fn with_time_source(mut self, time_source: TimeSource) -> Self {
+        self.clock.time_source = time_source;
+        self
+    }
+
+    /// Caps the time advanced in a single frame, on top of [`AnimationSettings::max_delta`]
+    pub fn with_max_delta(mut self, max_delta: Duration) -> Self {
+        self.clock.max_delta = Some(max_delta);
+        self
+    }
+
+    /// Time spent holding the end value before the next repetition,
+    /// at both ends for [`Repeat::Mirrored`]
+    pub fn with_loop_delay(mut self, loop_delay: Duration) -> Self {
+        self.loop_delay = loop_delay;
+        self
+    }
+
+    /// Duration of the backward leg, the forward duration by default
+    pub fn with_backward_duration(mut self, duration: Duration) -> Self {
+        self.backward_duration = Some(duration);
+        self
+    }
+
+    /// Curve of the backward leg, the forward curve by default
+    pub fn with_backward_curve(mut self, curve: impl Into<AnimationCurve>) -> Self {
+        self.backward_curve = Some(curve.into());
+        self
+    }
+
+    pub fn lens(&self) -> &TLens {
+        &self.lens
+    }
+
+    /// Duration and curve for the current direction
+    fn leg(&self) -> (f32, &AnimationCurve) {
+        match self.state.direction {
+            AnimationDirection::Forward => {
+                (self.animation.duration.as_secs_f32(), &self.animation.curve)
+            }
+            AnimationDirection::Backward => (
+                self.backward_duration
+                    .unwrap_or(self.animation.duration)
+                    .as_secs_f32(),
+                self.backward_curve
+                    .as_ref()
+                    .unwrap_or(&self.animation.curve),
+            ),
+        }
+    }
+
+    pub fn lens_mut(&mut self) -> &mut TLens {
+        &mut self.lens
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.state.completed
+    }
+
+    /// Jumps to the end of the current direction and completes
+    fn finish(&mut self, target: &mut TLens::C) -> Vec<AnimationEvent> {
         if self.state.completed {
-            return match self.state.direction {
-                AnimationDirection::Forward => 1.0,
-                AnimationDirection::Backward => 0.0,
-            };
+            return Vec::new();
         }
+        let final_progress = (!self.state.direction).start_point();
+        self.state.completed = true;
+        self.state.progress = final_progress;
+        self.lens.lerp(target, self.leg().1.eval(final_progress));
+        vec![self.completed(final_progress)]
+    }
 
-        let full_duration = self.animation.duration.as_secs_f32();
-        let progress_made = time_elapsed / full_duration;
-        self.state.progress += progress_made * self.state.direction.factor();
+    fn completed(&self, final_progress: f32) -> AnimationEvent {
+        AnimationEvent {
+            animator_id: self.id,
+            animation_id: 0,
+            direction: self.state.direction,
+            final_progress,
+            last_step: true,
+            repetition: 0,
+        }
+    }
 
-        match self.repeat {
-            Repeat::Once => {
-                if self.state.progress > 1.0 {
-                    self.state.completed = true;
-                    self.state.progress = 1.0;
-                    events.send(AnimationCompleted {
-                        entity,
-                        animator_id: self.id,
-                        animation_id: 0,
-                    });
-                } else if self.state.progress < 0.0 {
-                    self.state.completed = true;
-                    self.state.progress = 0.0;
-                    events.send(AnimationCompleted {
-                        entity,
-                        animator_id: self.id,
-                        animation_id: 0,
-                    });
+    /// Applies the lens at the current progress without advancing
+    fn apply_start(&mut self, target: &mut TLens::C) {
+        if !self.state.started {
+            self.state.started = true;
+            self.lens.begin(target);
+        }
+        self.lens
+            .lerp(target, self.leg().1.eval(self.state.progress));
+    }
+
+    /// Advances the animation by `time_elapsed` seconds and applies it to `target`
+    pub fn advance(&mut self, target: &mut TLens::C, time_elapsed: f32) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+        if self.state.completed {
+            return events;
+        }
+        if !self.state.started {
+            self.state.started = true;
+            self.lens.begin(target);
+        }
+
+        let mut remaining = time_elapsed;
+        loop {
+            // Holding the terminal value between repetitions
+            if let Some(rest) = self.rest {
+                if remaining < rest {
+                    self.rest = Some(rest - remaining);
+                    break;
                 }
-            }
-            Repeat::Always => {
-                if self.state.progress > 1.0 {
-                    let over = self.state.progress - 1.0;
-                    self.state.progress = 0.0 + over;
-                } else if self.state.progress < 0.0 {
-                    let over = 0.0 - self.state.progress;
-                    self.state.progress = 1.0 - over;
+                remaining -= rest;
+                self.rest = None;
+                match self.repeat {
+                    Repeat::Once => unreachable!("rests only between repetitions"),
+                    Repeat::Always => self.state.progress = self.state.direction.start_point(),
+                    Repeat::Mirrored => self.state.direction = !self.state.direction,
                 }
             }
-            Repeat::Mirrored => {
-                if self.state.progress > 1.0 {
-                    let over = self.state.progress - 1.0;
-                    self.state.progress = 1.0 - over;
-                    self.state.direction = !self.state.direction;
-                } else if self.state.progress < 0.0 {
-                    let over = 0.0 - self.state.progress;
-                    self.state.progress = 0.0 + over;
-                    self.state.direction = !self.state.direction;
-                }
+
+            // Overtime after a flip is converted with the new leg's duration
+            let (full_duration, _) = self.leg();
+            let direction = self.state.direction;
+            let end = (!direction).start_point();
+            let left = (end - self.state.progress).abs() * full_duration;
+            if remaining <= left {
+                self.state.progress += remaining / full_duration * direction.factor();
+                break;
+            }
+
+            // Reached the end, carry the overtime into the next repetition
+            remaining -= left;
+            self.state.progress = end;
+            if let Repeat::Once = self.repeat {
+                self.state.completed = true;
+                events.push(self.completed(end));
+                break;
+            }
+            self.rest = Some(self.loop_delay.as_secs_f32());
+            if full_duration == 0.0 && self.loop_delay.is_zero() {
+                break;
             }
         }
 
         let time_progress = self.state.progress;
-        let anim_progress = self.animation.curve.eval(time_progress);
+        let anim_progress = self.leg().1.eval(time_progress);
         debug!("time: {}, anim: {}", time_progress, anim_progress);
         self.lens.lerp(target, anim_progress);
 
-        time_progress
+        events
     }
 }
 
 #[derive(Component)]
 pub struct SequenceAnimator<TLens: AnimationLens> {
     id: Option<u32>,
+    clock: AnimationClock,
     state: AnimationState,
-    current: usize,
+    /// Path to the current leaf step, top level index first
+    cursor: Vec<usize>,
     seq: Vec<AnimationStep<TLens>>,
     repeat: Repeat,
 }
 
 impl<TLens: AnimationLens> SequenceAnimator<TLens> {
     pub fn new(seq: Vec<AnimationStep<TLens>>, repeat: Repeat) -> Self {
-        let completed = if seq.is_empty() { true } else { false };
-        Self {
-            id: None,
-            state: AnimationState {
-                completed,
-                direction: AnimationDirection::Forward,
-                progress: 0.0,
-            },
-            current: 0,
-            seq: seq,
-            repeat,
-        }
+        Self::new_with_direction(seq, AnimationDirection::Forward, repeat)
     }
 
-    pub fn new_with_direction<const N: usize>(
-        seq: Vec<AnimationStep<TLens>>,
+    /// Empty nested sequences are skipped
+    pub fn new_with_direction(
+        mut seq: Vec<AnimationStep<TLens>>,
         direction: AnimationDirection,
         repeat: Repeat,
     ) -> Self {
-        let completed = if seq.is_empty() { true } else { false };
+        seq.retain_mut(AnimationStep::prune);
+        let completed = seq.is_empty();
+        let mut cursor = Vec::new();
+        enter_steps(&seq, direction, &mut cursor);
         Self {
             id: None,
+            clock: AnimationClock::default(),
             state: AnimationState {
+                started: false,
                 completed,
                 direction,
                 progress: direction.start_point(),
             },
-            current: match direction {
-                AnimationDirection::Forward => 0,
-                AnimationDirection::Backward => seq.len() - 1,
-            },
+            cursor,
             seq,
             repeat,
         }
@@ -362,78 +848,131 @@ impl<TLens: AnimationLens> SequenceAnimator<TLens> {
         self
     }
 
-    /// Does not take overtime into account
-    fn next_animation(&mut self) {
-        let last = self.seq.len() - 1;
-        match (self.repeat, self.state.direction, self.current) {
-            (Repeat::Once, AnimationDirection::Forward, i) if i == last => {
-                self.state.completed = true;
-                self.state.progress = 1.0;
-            }
-            (Repeat::Once, AnimationDirection::Forward, _) => {
-                self.current += 1;
-                self.state.progress = 0.0;
-            }
-            (Repeat::Once, AnimationDirection::Backward, 0) => {
+    pub fn with_time_source(mut self, time_source: TimeSource) -> Self {
+        self.clock.time_source = time_source;
+        self
+    }
+
+    /// Caps the time advanced in a single frame, on top of [`AnimationSettings::max_delta`]
+    pub fn with_max_delta(mut self, max_delta: Duration) -> Self {
+        self.clock.max_delta = Some(max_delta);
+        self
+    }
+
+    /// Returns whether this was the last step in the current direction
+    fn complete_step(&mut self, events: &mut Vec<AnimationEvent>, final_progress: f32) -> bool {
+        let animation_id = self.cursor[0];
+        let repetition = self.seq[animation_id]
+            .repetition(&self.cursor[1..])
+            .unwrap_or(0);
+        let direction = self.state.direction;
+        let last_step = !self.next_animation();
+        events.push(AnimationEvent {
+            animator_id: self.id,
+            animation_id,
+            direction,
+            final_progress,
+            last_step,
+            repetition,
+        });
+        last_step
+    }
+
+    /// Does not take overtime into account,
+    /// false if the end of the sequence was reached in the current direction
+    fn next_animation(&mut self) -> bool {
+        self.state.started = false;
+        let direction = self.state.direction;
+        if advance_steps(&self.seq, direction, &mut self.cursor, 0) {
+            self.state.progress = direction.start_point();
+            return true;
+        }
+        match (self.repeat, direction) {
+            (Repeat::Once, _) => {
                 self.state.completed = true;
-                self.state.progress = 0.0
-            }
-            (Repeat::Once, AnimationDirection::Backward, _) => {
-                self.current -= 1;
-                self.state.progress = 1.0;
-            }
-            // --
-            (Repeat::Always, AnimationDirection::Forward, i) if i == last => {
-                self.current = 0;
-                self.state.progress = 0.0;
+                self.state.progress = (!direction).start_point();
             }
-            (Repeat::Always, AnimationDirection::Forward, _) => {
-                self.current += 1;
-                self.state.progress = 0.0;
-            }
-            (Repeat::Always, AnimationDirection::Backward, 0) => {
-                self.current = last;
-                self.state.progress = 1.0;
-            }
-            (Repeat::Always, AnimationDirection::Backward, _) => {
-                self.current -= 1;
-                self.state.progress = 1.0
+            (Repeat::Always, _) => {
+                self.cursor.clear();
+                enter_steps(&self.seq, direction, &mut self.cursor);
+                self.state.progress = direction.start_point();
             }
-            // --
-            (Repeat::Mirrored, AnimationDirection::Forward, i) if i == last => {
-                // self.current = i;
+            (Repeat::Mirrored, AnimationDirection::Forward) => {
                 self.state.direction = AnimationDirection::Backward;
                 self.state.progress = 1.0;
             }
-            (Repeat::Mirrored, AnimationDirection::Forward, _) => {
-                self.current += 1;
-                self.state.progress = 0.0;
-            }
-            (Repeat::Mirrored, AnimationDirection::Backward, 0) => {
-                // self.current = 0;
+            (Repeat::Mirrored, AnimationDirection::Backward) => {
                 self.state.direction = AnimationDirection::Forward;
                 self.state.progress = 0.0;
             }
-            (Repeat::Mirrored, AnimationDirection::Backward, _) => {
-                self.current -= 1;
-                self.state.progress = 1.0;
+        }
+        false
+    }
+
+    /// Applies the first animation step at its starting value,
+    /// looking past any delays and waits in front of it
+    fn apply_start(&mut self, target: &mut TLens::C) {
+        if self.state.completed {
+            return;
+        }
+        if !self.state.started {
+            let step = self.seq[self.cursor[0]].leaf_mut(&self.cursor[1..]);
+            if let AnimationStep::Animation(_, lens) = step {
+                self.state.started = true;
+                lens.begin(target);
+            }
+        }
+
+        let direction = self.state.direction;
+        let mut cursor = self.cursor.clone();
+        loop {
+            if let AnimationStep::Animation(anim, lens) = self.seq[cursor[0]].leaf(&cursor[1..]) {
+                let progress = if cursor == self.cursor {
+                    self.state.progress
+                } else {
+                    direction.start_point()
+                };
+                lens.lerp(target, anim.curve.eval(progress));
+                return;
+            }
+            if !advance_steps(&self.seq, direction, &mut cursor, 0) {
+                return;
             }
         }
     }
 
-    pub fn tick(
+    /// Advances the sequence by `time_elapsed` seconds and applies it to `target`
+    pub fn advance(
+        &mut self,
+        target: &mut TLens::C,
+        time_elapsed: f32,
+        signals: &AnimationSignals,
+    ) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+        self.tick(target, time_elapsed, &mut events, signals);
+        events
+    }
+
+    fn tick(
         &mut self,
         target: &mut TLens::C,
         time_elapsed: f32,
-        entity: Entity,
-        events: &mut Events<AnimationCompleted>,
+        events: &mut Vec<AnimationEvent>,
+        signals: &AnimationSignals,
     ) {
         if self.state.completed {
             return;
         }
+        if !self.state.started {
+            self.state.started = true;
+            let step = self.seq[self.cursor[0]].leaf_mut(&self.cursor[1..]);
+            if let AnimationStep::Animation(_, lens) = step {
+                lens.begin(target);
+            }
+        }
 
         let mut overtime = 0.0;
-        match &self.seq[self.current] {
+        match self.seq[self.cursor[0]].leaf(&self.cursor[1..]) {
             AnimationStep::Animation(anim, lens) => {
                 let full_duration = anim.duration.as_secs_f32();
                 let progress_made = time_elapsed / full_duration;
@@ -445,20 +984,10 @@ impl<TLens: AnimationLens> SequenceAnimator<TLens> {
 
                 if self.state.progress > 1.0 {
                     overtime = (self.state.progress - 1.0) * full_duration;
-                    events.send(AnimationCompleted {
-                        entity,
-                        animator_id: self.id,
-                        animation_id: self.current,
-                    });
-                    self.next_animation();
+                    self.complete_step(events, 1.0);
                 } else if self.state.progress < 0.0 {
                     overtime = (0.0 - self.state.progress) * full_duration;
-                    events.send(AnimationCompleted {
-                        entity,
-                        animator_id: self.id,
-                        animation_id: self.current,
-                    });
-                    self.next_animation();
+                    self.complete_step(events, 0.0);
                 }
             }
             AnimationStep::Delay(delay) => {
@@ -468,61 +997,506 @@ impl<TLens: AnimationLens> SequenceAnimator<TLens> {
 
                 if self.state.progress > 1.0 {
                     overtime = (self.state.progress - 1.0) * delay_duration;
-                    events.send(AnimationCompleted {
-                        entity,
-                        animator_id: self.id,
-                        animation_id: self.current,
-                    });
-                    self.next_animation();
+                    self.complete_step(events, 1.0);
                 } else if self.state.progress < 0.0 {
                     overtime = (0.0 - self.state.progress) * delay_duration;
-                    events.send(AnimationCompleted {
-                        entity,
-                        animator_id: self.id,
-                        animation_id: self.current,
-                    });
-                    self.next_animation();
+                    self.complete_step(events, 0.0);
+                }
+            }
+            AnimationStep::WaitFor(condition) => {
+                if condition.is_met(signals) {
+                    let final_progress = (!self.state.direction).start_point();
+                    // Passing waits take no time, stop at the end of the sequence
+                    // so a sequence of met waits can't loop forever
+                    if !self.complete_step(events, final_progress) {
+                        overtime = time_elapsed;
+                    }
                 }
             }
+            AnimationStep::Repeated(..) | AnimationStep::Sequence(_) => {
+                unreachable!("cursor always points at a leaf step")
+            }
         }
 
         // Tick once more for the overtime
         if overtime != 0.0 {
-            self.tick(target, overtime, entity, events);
+            self.tick(target, overtime, events, signals);
         }
     }
-}
 
-#[derive(Event)]
-pub struct AnimationCompleted {
-    pub entity: Entity,
+    pub fn builder() -> SequenceAnimatorBuilder<TLens> {
+        SequenceAnimatorBuilder::new()
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.state.completed
+    }
+}
+
+/// What an [`AnimationQueue`] does once it runs out of animations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueueEmpty {
+    /// Stays on the entity waiting for more
+    #[default]
+    Idle,
+    /// Removes itself from the entity
+    Remove,
+}
+
+/// Plays animations pushed at runtime one after another, front first
+#[derive(Component)]
+pub struct AnimationQueue<TLens: AnimationLens> {
+    id: Option<u32>,
+    clock: AnimationClock,
+    on_empty: QueueEmpty,
+    /// Progress of the front step
+    progress: f32,
+    started: bool,
+    /// Number of steps completed so far, used as the animation id
+    completed: usize,
+    queue: VecDeque<AnimationStep<TLens>>,
+}
+
+impl<TLens: AnimationLens> Default for AnimationQueue<TLens> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TLens: AnimationLens> AnimationQueue<TLens> {
+    pub fn new() -> Self {
+        Self {
+            id: None,
+            clock: AnimationClock::default(),
+            on_empty: QueueEmpty::Idle,
+            progress: 0.0,
+            started: false,
+            completed: 0,
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn with_id(mut self, id: u32) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn with_time_source(mut self, time_source: TimeSource) -> Self {
+        self.clock.time_source = time_source;
+        self
+    }
+
+    /// Caps the time advanced in a single frame, on top of [`AnimationSettings::max_delta`]
+    pub fn with_max_delta(mut self, max_delta: Duration) -> Self {
+        self.clock.max_delta = Some(max_delta);
+        self
+    }
+
+    pub fn with_on_empty(mut self, on_empty: QueueEmpty) -> Self {
+        self.on_empty = on_empty;
+        self
+    }
+
+    pub fn push(&mut self, animation: Animation, lens: TLens) {
+        self.queue
+            .push_back(AnimationStep::Animation(animation, lens));
+    }
+
+    pub fn push_delay(&mut self, delay: Delay) {
+        self.queue.push_back(AnimationStep::Delay(delay));
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Drops all queued animations, the target keeps its current value
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.progress = 0.0;
+        self.started = false;
+    }
+
+    /// Advances the queue by `time_elapsed` seconds and applies it to `target`
+    pub fn advance(&mut self, target: &mut TLens::C, time_elapsed: f32) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+        let mut remaining = time_elapsed;
+        while let Some(front) = self.queue.front_mut() {
+            let duration = match front {
+                AnimationStep::Animation(anim, lens) => {
+                    if !self.started {
+                        self.started = true;
+                        lens.begin(target);
+                    }
+                    anim.duration.as_secs_f32()
+                }
+                AnimationStep::Delay(delay) => delay.duration.as_secs_f32(),
+                _ => unreachable!("only animations and delays are queued"),
+            };
+
+            let left = (1.0 - self.progress) * duration;
+            if remaining < left {
+                self.progress += remaining / duration;
+                if let AnimationStep::Animation(anim, lens) = front {
+                    lens.lerp(target, anim.curve.eval(self.progress));
+                }
+                return events;
+            }
+
+            // Front completes, carry the overtime into the next one
+            remaining -= left;
+            if let AnimationStep::Animation(anim, lens) = front {
+                lens.lerp(target, anim.curve.eval(1.0));
+            }
+            self.queue.pop_front();
+            self.progress = 0.0;
+            self.started = false;
+            events.push(AnimationEvent {
+                animator_id: self.id,
+                animation_id: self.completed,
+                direction: AnimationDirection::Forward,
+                final_progress: 1.0,
+                last_step: self.queue.is_empty(),
+                repetition: 0,
+            });
+            self.completed += 1;
+        }
+        events
+    }
+}
+
+/// Pushes the cursor of the first leaf step of `steps` in the given direction
+fn enter_steps<TLens: AnimationLens>(
+    steps: &[AnimationStep<TLens>],
+    direction: AnimationDirection,
+    cursor: &mut Vec<usize>,
+) {
+    if steps.is_empty() {
+        return;
+    }
+    let i = match direction {
+        AnimationDirection::Forward => 0,
+        AnimationDirection::Backward => steps.len() - 1,
+    };
+    cursor.push(i);
+    steps[i].enter(direction, cursor);
+}
+
+/// Moves `cursor[depth..]` to the next leaf step of `steps`,
+/// false if `steps` is exhausted in the given direction
+fn advance_steps<TLens: AnimationLens>(
+    steps: &[AnimationStep<TLens>],
+    direction: AnimationDirection,
+    cursor: &mut Vec<usize>,
+    depth: usize,
+) -> bool {
+    let i = cursor[depth];
+    if steps[i].advance(direction, cursor, depth + 1) {
+        return true;
+    }
+    let next = match direction {
+        AnimationDirection::Forward if i + 1 < steps.len() => i + 1,
+        AnimationDirection::Backward if i > 0 => i - 1,
+        _ => return false,
+    };
+    cursor.truncate(depth);
+    cursor.push(next);
+    steps[next].enter(direction, cursor);
+    true
+}
+
+/// Chained construction of a [`SequenceAnimator`]
+///
+/// The sequence below is the one spawned in `spawn_initial`:
+/// ```
+/// # use std::time::Duration;
+/// # use bevy::prelude::Vec3;
+/// # use bevy_toolbox::animation::{AnimationCurve, Repeat, SequenceAnimator, TranslationLens};
+/// # use interpolation::EaseFunction;
+/// # let pos1 = Vec3::ZERO;
+/// # let (pos2, pos3) = (pos1 + Vec3::new(250.0, 250.0, 0.0), pos1 + Vec3::new(450.0, 250.0, 0.0));
+/// let secs = Duration::from_secs;
+/// let animator = SequenceAnimator::<TranslationLens>::builder()
+///     .move_to(pos1, pos2, secs(2), EaseFunction::QuadraticInOut)
+///     .wait(secs(2))
+///     .then_move(pos3, secs(2), AnimationCurve::Linear)
+///     .repeat(Repeat::Mirrored)
+///     .build();
+/// ```
+pub struct SequenceAnimatorBuilder<TLens: AnimationLens> {
+    seq: Vec<AnimationStep<TLens>>,
+    repeat: Repeat,
+}
+
+impl<TLens: AnimationLens> Default for SequenceAnimatorBuilder<TLens> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TLens: AnimationLens> SequenceAnimatorBuilder<TLens> {
+    pub fn new() -> Self {
+        Self {
+            seq: Vec::new(),
+            repeat: Repeat::Once,
+        }
+    }
+
+    pub fn then(mut self, animation: Animation, lens: TLens) -> Self {
+        self.seq.push(AnimationStep::Animation(animation, lens));
+        self
+    }
+
+    pub fn wait(mut self, duration: Duration) -> Self {
+        self.seq.push(AnimationStep::Delay(Delay { duration }));
+        self
+    }
+
+    pub fn wait_for(mut self, signal: u32) -> Self {
+        self.seq
+            .push(AnimationStep::WaitFor(WaitCondition::Signal(signal)));
+        self
+    }
+
+    pub fn repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn build(self) -> SequenceAnimator<TLens> {
+        SequenceAnimator::new(self.seq, self.repeat)
+    }
+
+    pub fn step(mut self, step: AnimationStep<TLens>) -> Self {
+        self.seq.push(step);
+        self
+    }
+
+    /// The steps pushed so far as a single step, to be nested in another sequence
+    pub fn into_step(self) -> AnimationStep<TLens> {
+        AnimationStep::Sequence(self.seq)
+    }
+
+    /// Wraps the last pushed step so it plays `count` times
+    pub fn times(mut self, count: u32) -> Self {
+        if let Some(step) = self.seq.pop() {
+            self.seq.push(step.repeated(count));
+        }
+        self
+    }
+
+    /// Lens of the last animation step pushed so far, delays are skipped
+    fn last_lens(&self) -> Option<&TLens> {
+        self.seq.iter().rev().find_map(AnimationStep::last_lens)
+    }
+}
+
+impl SequenceAnimatorBuilder<TranslationLens> {
+    pub fn move_to(
+        self,
+        from: Vec3,
+        to: Vec3,
+        duration: Duration,
+        curve: impl Into<AnimationCurve>,
+    ) -> Self {
+        self.then(
+            Animation {
+                duration,
+                curve: curve.into(),
+            },
+            TranslationLens {
+                start: from,
+                end: to,
+            },
+        )
+    }
+
+    /// Moves from where the previous move ended
+    ///
+    /// # Panics
+    /// If no move was pushed before
+    pub fn then_move(self, to: Vec3, duration: Duration, curve: impl Into<AnimationCurve>) -> Self {
+        let from = self
+            .last_lens()
+            .expect("then_move requires a previous move to start from")
+            .end;
+        self.move_to(from, to, duration, curve)
+    }
+}
+
+impl SequenceAnimatorBuilder<ScaleLens> {
+    pub fn scale_to(
+        self,
+        from: Vec3,
+        to: Vec3,
+        duration: Duration,
+        curve: impl Into<AnimationCurve>,
+    ) -> Self {
+        self.then(
+            Animation {
+                duration,
+                curve: curve.into(),
+            },
+            ScaleLens {
+                start: from,
+                end: to,
+            },
+        )
+    }
+
+    /// Scales from where the previous scale ended
+    ///
+    /// # Panics
+    /// If no scale was pushed before
+    pub fn then_scale(
+        self,
+        to: Vec3,
+        duration: Duration,
+        curve: impl Into<AnimationCurve>,
+    ) -> Self {
+        let from = self
+            .last_lens()
+            .expect("then_scale requires a previous scale to start from")
+            .end;
+        self.scale_to(from, to, duration, curve)
+    }
+}
+
+/// Emitted while advancing an animator, see [`AnimationCompleted`] for the fields
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationEvent {
+    pub animator_id: Option<u32>,
+    pub animation_id: usize,
+    pub direction: AnimationDirection,
+    pub final_progress: f32,
+    pub last_step: bool,
+    pub repetition: u32,
+}
+
+impl AnimationEvent {
+    pub fn with_entity(self, entity: Entity) -> AnimationCompleted {
+        AnimationCompleted {
+            entity,
+            animator_id: self.animator_id,
+            animation_id: self.animation_id,
+            direction: self.direction,
+            final_progress: self.final_progress,
+            last_step: self.last_step,
+            repetition: self.repetition,
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct AnimationCompleted {
+    pub entity: Entity,
     pub animator_id: Option<u32>,
     pub animation_id: usize,
+    /// Direction the animation was travelling in when it completed
+    pub direction: AnimationDirection,
+    /// 1.0 when it finished at the end value, 0.0 when at the start value
+    pub final_progress: f32,
+    /// Whether this was the final step of a sequence in its direction of travel,
+    /// always true for [`Animator`]
+    pub last_step: bool,
+    /// Repetition index within a [`AnimationStep::Repeated`] step, 0 otherwise
+    pub repetition: u32,
+}
+
+/// Applies newly added animators at their starting value, so nothing renders
+/// with the value the target had before
+pub fn animation_start_system<TComponent, TLens>(
+    mut entities: Query<(&mut TComponent, &mut Animator<TLens>), Added<Animator<TLens>>>,
+) where
+    TComponent: Component,
+    TLens: AnimationLens<C = TComponent>,
+{
+    for (mut component, mut animator) in entities.iter_mut() {
+        animator.apply_start(&mut component);
+    }
+}
+
+/// [`animation_start_system`] for [`SequenceAnimator`]
+pub fn animation_sequence_start_system<TComponent, TLens>(
+    mut entities: Query<
+        (&mut TComponent, &mut SequenceAnimator<TLens>),
+        Added<SequenceAnimator<TLens>>,
+    >,
+) where
+    TComponent: Component,
+    TLens: AnimationLens<C = TComponent>,
+{
+    for (mut component, mut animator) in entities.iter_mut() {
+        animator.apply_start(&mut component);
+    }
 }
 
 pub fn animation_tick_system<TComponent, TLens>(
-    time: Res<Time>,
-    mut entities: Query<(Entity, &mut TComponent, &mut Animator<TLens>)>,
+    time: AnimationTime,
+    mut entities: Query<(
+        Entity,
+        &mut TComponent,
+        &mut Animator<TLens>,
+        Option<&AnimationGroup>,
+    )>,
     mut events: ResMut<Events<AnimationCompleted>>,
 ) where
     TComponent: Component,
     TLens: AnimationLens<C = TComponent>,
 {
-    for (entity, mut component, mut animator) in entities.iter_mut() {
-        animator.tick(&mut component, time.delta_seconds(), entity, &mut events);
+    for (entity, mut component, mut animator, group) in entities.iter_mut() {
+        let time_elapsed = time.delta_seconds(&animator.clock, group);
+        let emitted = animator.advance(&mut component, time_elapsed);
+        events.extend(emitted.into_iter().map(|event| event.with_entity(entity)));
     }
 }
 
 pub fn animation_sequence_tick_system<TComponent, TLens>(
-    time: Res<Time>,
-    mut entities: Query<(Entity, &mut TComponent, &mut SequenceAnimator<TLens>)>,
+    time: AnimationTime,
+    mut entities: Query<(
+        Entity,
+        &mut TComponent,
+        &mut SequenceAnimator<TLens>,
+        Option<&AnimationGroup>,
+    )>,
+    mut events: ResMut<Events<AnimationCompleted>>,
+    signals: Res<AnimationSignals>,
+) where
+    TComponent: Component,
+    TLens: AnimationLens<C = TComponent>,
+{
+    for (entity, mut component, mut animator, group) in entities.iter_mut() {
+        let time_elapsed = time.delta_seconds(&animator.clock, group);
+        let emitted = animator.advance(&mut component, time_elapsed, &signals);
+        events.extend(emitted.into_iter().map(|event| event.with_entity(entity)));
+    }
+}
+
+pub fn animation_queue_tick_system<TComponent, TLens>(
+    mut commands: Commands,
+    time: AnimationTime,
+    mut entities: Query<(
+        Entity,
+        &mut TComponent,
+        &mut AnimationQueue<TLens>,
+        Option<&AnimationGroup>,
+    )>,
     mut events: ResMut<Events<AnimationCompleted>>,
 ) where
     TComponent: Component,
     TLens: AnimationLens<C = TComponent>,
 {
-    for (entity, mut component, mut animator) in entities.iter_mut() {
-        animator.tick(&mut component, time.delta_seconds(), entity, &mut events);
+    for (entity, mut component, mut queue, group) in entities.iter_mut() {
+        let time_elapsed = time.delta_seconds(&queue.clock, group);
+        let emitted = queue.advance(&mut component, time_elapsed);
+        events.extend(emitted.into_iter().map(|event| event.with_entity(entity)));
+        if queue.is_empty() && queue.on_empty == QueueEmpty::Remove {
+            commands.entity(entity).remove::<AnimationQueue<TLens>>();
+        }
     }
 }
 
@@ -550,25 +1524,1155 @@ impl AnimationLens for ScaleLens {
     }
 }
 
-pub struct AnimationPlugin;
+/// Grows the x and y scale from `base` to `base * peak` at half way and back to `base`
+pub struct ScalePulseLens {
+    pub base: Vec3,
+    pub peak: f32,
+}
+impl AnimationLens for ScalePulseLens {
+    type C = Transform;
+
+    fn lerp(&self, target: &mut Self::C, progress: f32) {
+        let rise = 1.0 - (2.0 * progress.clamp(0.0, 1.0) - 1.0).abs();
+        let factor = 1.0 + (self.peak - 1.0) * rise;
+        target.scale = (self.base.truncate() * factor).extend(self.base.z);
+    }
+}
+
+/// Moves towards the current position of another entity, re-read every frame
+///
+/// Ticked by [`track_translation_tick_system`] for [`Animator`]s only,
+/// the target position is its [`GlobalTransform`] translation
+pub struct TrackTranslationLens {
+    pub target: Entity,
+    start: Option<Vec3>,
+    /// Last known position of the target
+    end: Option<Vec3>,
+}
+
+impl TrackTranslationLens {
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            start: None,
+            end: None,
+        }
+    }
+}
+
+impl AnimationLens for TrackTranslationLens {
+    type C = Transform;
+
+    fn begin(&mut self, target: &Self::C) {
+        self.start.get_or_insert(target.translation);
+    }
+
+    fn lerp(&self, target: &mut Self::C, progress: f32) {
+        let start = self.start.unwrap_or(target.translation);
+        let end = self.end.unwrap_or(start);
+        target.translation = start + (end - start) * progress;
+    }
+}
+
+/// Completes immediately at the last known position if the tracked entity is gone
+pub fn track_translation_tick_system(
+    time: AnimationTime,
+    mut entities: Query<(
+        Entity,
+        &mut Transform,
+        &mut Animator<TrackTranslationLens>,
+        Option<&AnimationGroup>,
+    )>,
+    targets: Query<&GlobalTransform>,
+    mut events: ResMut<Events<AnimationCompleted>>,
+) {
+    for (entity, mut transform, mut animator, group) in entities.iter_mut() {
+        let Ok(target) = targets.get(animator.lens.target) else {
+            animator.lens.begin(&transform);
+            let emitted = animator.finish(&mut transform);
+            events.extend(emitted.into_iter().map(|event| event.with_entity(entity)));
+            continue;
+        };
+        animator.lens.end = Some(target.translation());
+        let time_elapsed = time.delta_seconds(&animator.clock, group);
+        let emitted = animator.advance(&mut transform, time_elapsed);
+        events.extend(emitted.into_iter().map(|event| event.with_entity(entity)));
+    }
+}
+
+/// Flies `entity` from where it is to the current position of `target`
+pub fn fly_to(
+    commands: &mut Commands,
+    entity: Entity,
+    target: Entity,
+    duration: Duration,
+    curve: impl Into<AnimationCurve>,
+) {
+    commands.entity(entity).insert(Animator::new(
+        Animation {
+            duration,
+            curve: curve.into(),
+        },
+        Repeat::Once,
+        TrackTranslationLens::new(target),
+    ));
+}
+
+/// Shakes around the translation the entity had when the animation started,
+/// the displacement decays to exactly the base translation at progress 1.0
+pub struct TranslationShakeLens {
+    pub amplitude: Vec2,
+    /// Noise samples over the whole animation
+    pub frequency: f32,
+    pub seed: u32,
+    base: Option<Vec3>,
+}
+
+impl TranslationShakeLens {
+    pub fn new(amplitude: Vec2, frequency: f32, seed: u32) -> Self {
+        Self {
+            amplitude,
+            frequency,
+            seed,
+            base: None,
+        }
+    }
+
+    fn offset(&self, progress: f32) -> Vec2 {
+        let t = progress * self.frequency;
+        let noise = Vec2::new(
+            value_noise(self.seed, t),
+            value_noise(self.seed ^ 0x5bd1e995, t),
+        );
+        self.amplitude * noise * (1.0 - progress)
+    }
+}
+
+impl AnimationLens for TranslationShakeLens {
+    type C = Transform;
+
+    fn begin(&mut self, target: &Self::C) {
+        self.base = Some(target.translation);
+    }
+
+    fn lerp(&self, target: &mut Self::C, progress: f32) {
+        let base = self.base.unwrap_or(target.translation);
+        target.translation = base + self.offset(progress).extend(0.0);
+    }
+}
+
+/// Smoothly interpolated pseudo-random value in [-1, 1]
+fn value_noise(seed: u32, t: f32) -> f32 {
+    let t = t.max(0.0);
+    let i = t.floor();
+    let f = t - i;
+    let f = f * f * (3.0 - 2.0 * f);
+    let a = hash_unit(seed, i as u32);
+    let b = hash_unit(seed, i as u32 + 1);
+    a + (b - a) * f
+}
+
+fn hash_unit(seed: u32, i: u32) -> f32 {
+    let mut x = seed.wrapping_mul(0x9e3779b9) ^ i.wrapping_mul(0x85ebca6b);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// Noise samples per second of shake
+const SHAKE_RATE: f32 = 30.0;
+
+/// Shakes `entity` around its current translation for `duration`
+pub fn shake(commands: &mut Commands, entity: Entity, amplitude: Vec2, duration: Duration) {
+    let frequency = duration.as_secs_f32() * SHAKE_RATE;
+    commands.entity(entity).insert(Animator::new(
+        Animation {
+            duration,
+            curve: AnimationCurve::Linear,
+        },
+        Repeat::Once,
+        TranslationShakeLens::new(amplitude, frequency, entity.index()),
+    ));
+}
+
+/// Pulses the scale of `entity` from `base` to `base * peak` and back over `duration`,
+/// replacing a pulse still running on it
+pub fn pulse(commands: &mut Commands, entity: Entity, base: Vec3, peak: f32, duration: Duration) {
+    commands.entity(entity).insert(Animator::new(
+        Animation {
+            duration,
+            curve: AnimationCurve::Linear,
+        },
+        Repeat::Once,
+        ScalePulseLens { base, peak },
+    ));
+}
+
+/// Typewriter effect, shows the first `floor(progress * char_count)` characters
+/// of `full_text` in the given section
+pub struct TextRevealLens {
+    pub full_text: String,
+    pub section: usize,
+}
+
+impl AnimationLens for TextRevealLens {
+    type C = Text;
+
+    fn lerp(&self, target: &mut Self::C, progress: f32) {
+        let Some(section) = target.sections.get_mut(self.section) else {
+            return;
+        };
+        let char_count = self.full_text.chars().count();
+        let shown =
+            ((progress.clamp(0.0, 1.0) * char_count as f32).floor() as usize).min(char_count);
+        let end = self
+            .full_text
+            .char_indices()
+            .nth(shown)
+            .map_or(self.full_text.len(), |(index, _)| index);
+        if section.value != self.full_text[..end] {
+            section.value.clear();
+            section.value.push_str(&self.full_text[..end]);
+        }
+    }
+}
+
+/// Opt-in gizmo drawing of translation animation paths
+#[derive(Resource, Default)]
+pub struct AnimationDebug {
+    pub draw_paths: bool,
+}
+
+const PATH_SAMPLES: usize = 32;
+const PATH_COLORS: [Color; 4] = [
+    Color::YELLOW,
+    Color::CYAN,
+    Color::FUCHSIA,
+    Color::LIME_GREEN,
+];
+
+enum PathPiece {
+    Move {
+        start: Vec3,
+        end: Vec3,
+        curve: AnimationCurve,
+        points: Vec<Vec3>,
+    },
+    Dwell(Vec3),
+}
+
+/// Sampled path of a translation animator, pieces are only resampled when they change
+#[derive(Default)]
+struct DebugPath {
+    pieces: Vec<PathPiece>,
+}
+
+impl DebugPath {
+    fn sync_piece(&mut self, index: usize, piece: impl FnOnce() -> PathPiece, unchanged: bool) {
+        if unchanged {
+            return;
+        }
+        match self.pieces.get_mut(index) {
+            Some(current) => *current = piece(),
+            None => self.pieces.push(piece()),
+        }
+    }
+
+    fn sync_move(&mut self, index: usize, animation: &Animation, lens: &TranslationLens) {
+        let (start, end) = (lens.start, lens.end);
+        let unchanged = matches!(
+            self.pieces.get(index),
+            Some(PathPiece::Move { start: s, end: e, curve, .. })
+                if *s == start && *e == end && *curve == animation.curve
+        );
+        let piece = || PathPiece::Move {
+            start,
+            end,
+            curve: animation.curve.clone(),
+            points: (0..=PATH_SAMPLES)
+                .map(|i| start.lerp(end, animation.curve.eval(i as f32 / PATH_SAMPLES as f32)))
+                .collect(),
+        };
+        self.sync_piece(index, piece, unchanged);
+    }
+
+    fn sync_dwell(&mut self, index: usize, at: Vec3) {
+        let unchanged = matches!(self.pieces.get(index), Some(PathPiece::Dwell(p)) if *p == at);
+        self.sync_piece(index, || PathPiece::Dwell(at), unchanged);
+    }
+
+    fn sync_animator(&mut self, animator: &Animator<TranslationLens>) {
+        self.sync_move(0, &animator.animation, &animator.lens);
+        self.pieces.truncate(1);
+    }
+
+    /// Repeated steps are drawn once, delays and waits dwell where the previous move ended
+    fn sync_sequence(&mut self, seq: &[AnimationStep<TranslationLens>]) {
+        let mut index = 0;
+        let mut last = None;
+        let mut pending_dwell = false;
+        self.sync_steps(seq, &mut index, &mut last, &mut pending_dwell);
+        self.pieces.truncate(index);
+    }
+
+    fn sync_steps(
+        &mut self,
+        steps: &[AnimationStep<TranslationLens>],
+        index: &mut usize,
+        last: &mut Option<Vec3>,
+        pending_dwell: &mut bool,
+    ) {
+        for step in steps {
+            match step {
+                AnimationStep::Animation(animation, lens) => {
+                    if std::mem::take(pending_dwell) {
+                        self.sync_dwell(*index, lens.start);
+                        *index += 1;
+                    }
+                    self.sync_move(*index, animation, lens);
+                    *index += 1;
+                    *last = Some(lens.end);
+                }
+                AnimationStep::Delay(_) | AnimationStep::WaitFor(_) => match last {
+                    Some(at) => {
+                        self.sync_dwell(*index, *at);
+                        *index += 1;
+                    }
+                    None => *pending_dwell = true,
+                },
+                AnimationStep::Repeated(inner, _) => {
+                    self.sync_steps(std::slice::from_ref(inner), index, last, pending_dwell)
+                }
+                AnimationStep::Sequence(steps) => {
+                    self.sync_steps(steps, index, last, pending_dwell)
+                }
+            }
+        }
+    }
+
+    fn draw(&self, gizmos: &mut Gizmos, current: Vec3) {
+        let mut moves = 0;
+        for piece in &self.pieces {
+            match piece {
+                PathPiece::Move {
+                    start, end, points, ..
+                } => {
+                    let color = PATH_COLORS[moves % PATH_COLORS.len()];
+                    moves += 1;
+                    gizmos.linestrip(points.iter().copied(), color);
+                    gizmos.circle(*start, Vec3::Z, 4.0, color);
+                    gizmos.circle(*end, Vec3::Z, 4.0, color);
+                }
+                PathPiece::Dwell(at) => {
+                    gizmos.rect(*at, Quat::IDENTITY, Vec2::splat(10.0), Color::WHITE);
+                }
+            }
+        }
+        gizmos.circle(current, Vec3::Z, 6.0, Color::RED);
+    }
+}
+
+/// Draws the paths of translation animators while [`AnimationDebug::draw_paths`] is set
+fn animation_debug_path_system(
+    mut gizmos: Gizmos,
+    mut animator_paths: Local<HashMap<Entity, DebugPath>>,
+    mut sequence_paths: Local<HashMap<Entity, DebugPath>>,
+    animators: Query<(Entity, &Transform, &Animator<TranslationLens>)>,
+    sequences: Query<(Entity, &Transform, &SequenceAnimator<TranslationLens>)>,
+) {
+    animator_paths.retain(|entity, _| animators.contains(*entity));
+    sequence_paths.retain(|entity, _| sequences.contains(*entity));
+
+    for (entity, transform, animator) in animators.iter() {
+        let path = animator_paths.entry(entity).or_default();
+        path.sync_animator(animator);
+        path.draw(&mut gizmos, transform.translation);
+    }
+    for (entity, transform, animator) in sequences.iter() {
+        let path = sequence_paths.entry(entity).or_default();
+        path.sync_sequence(&animator.seq);
+        path.draw(&mut gizmos, transform.translation);
+    }
+}
+
+/// Lens types whose tick systems are already added
+#[derive(Resource, Default)]
+struct RegisteredLenses(HashSet<TypeId>);
+
+/// Registers the tick systems of a lens type
+pub trait AnimationAppExt {
+    /// Adds the [`Animator`], [`SequenceAnimator`] and [`AnimationQueue`] tick systems
+    /// for `TLens` and [`Reversed<TLens>`], registering a lens more than once is a no-op
+    fn add_animation_lens<TLens: AnimationLens>(&mut self) -> &mut Self;
+}
+
+impl AnimationAppExt for App {
+    fn add_animation_lens<TLens: AnimationLens>(&mut self) -> &mut Self {
+        add_lens_systems::<TLens>(self);
+        add_lens_systems::<Reversed<TLens>>(self);
+        self
+    }
+}
+
+fn add_lens_systems<TLens: AnimationLens>(app: &mut App) {
+    let mut registered = app
+        .world
+        .get_resource_or_insert_with(RegisteredLenses::default);
+    if !registered.0.insert(TypeId::of::<TLens>()) {
+        return;
+    }
+    app.add_systems(
+        Update,
+        (
+            animation_tick_system::<TLens::C, TLens>,
+            animation_sequence_tick_system::<TLens::C, TLens>,
+            animation_queue_tick_system::<TLens::C, TLens>,
+        ),
+    )
+    .add_systems(
+        PostUpdate,
+        (
+            animation_start_system::<TLens::C, TLens>,
+            animation_sequence_start_system::<TLens::C, TLens>,
+        )
+            .before(TransformSystem::TransformPropagate),
+    );
+}
+
+#[derive(Default)]
+pub struct AnimationPlugin {
+    /// See [`AnimationSettings::max_delta`]
+    pub max_delta: Option<Duration>,
+}
 impl Plugin for AnimationPlugin {
-    fn build(&self, app: &mut bevy::prelude::App) {
+    fn build(&self, app: &mut App) {
         app.add_event::<AnimationCompleted>()
+            .insert_resource(AnimationSettings {
+                max_delta: self.max_delta,
+            })
+            .init_resource::<AnimationSignals>()
+            .init_resource::<AnimationGroups>()
+            .init_resource::<AnimationDebug>()
+            .add_animation_lens::<TranslationLens>()
+            .add_animation_lens::<ScaleLens>()
+            .add_animation_lens::<ScalePulseLens>()
+            .add_animation_lens::<TranslationShakeLens>()
+            .add_animation_lens::<TextRevealLens>()
+            .add_systems(Update, track_translation_tick_system)
             .add_systems(
-                Update,
-                animation_tick_system::<Transform, TranslationLens>,
-            )
-            .add_systems(
-                Update,
-                animation_sequence_tick_system::<Transform, TranslationLens>,
+                PostUpdate,
+                animation_debug_path_system.run_if(|debug: Res<AnimationDebug>| debug.draw_paths),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        prelude::{
+            App, Commands, Events, GlobalTransform, Local, Startup, Text, TextSection, Transform,
+            TransformBundle, Update, Vec2, Vec3, World,
+        },
+        time::Time,
+    };
+
+    use crate::animation_test_harness::AnimationHarness;
+
+    use super::{
+        fly_to, track_translation_tick_system, Animation, AnimationCompleted, AnimationCurve,
+        AnimationDirection, AnimationGroup, AnimationGroups, AnimationLens, AnimationPlugin,
+        AnimationQueue, AnimationSettings, AnimationSignals, AnimationStep, Animator, DebugPath,
+        Delay, PathPiece, Repeat, ScaleLens, ScalePulseLens, SequenceAnimator, TextRevealLens,
+        TimeSource, TranslationLens, TranslationShakeLens, PATH_SAMPLES,
+    };
+
+    fn step(end: f32) -> AnimationStep<TranslationLens> {
+        AnimationStep::Animation(
+            Animation {
+                duration: Duration::from_secs(1),
+                curve: AnimationCurve::Linear,
+            },
+            TranslationLens {
+                start: Vec3::ZERO,
+                end: Vec3::splat(end),
+            },
+        )
+    }
+
+    fn run(
+        animator: &mut SequenceAnimator<TranslationLens>,
+        ticks: &[f32],
+    ) -> Vec<(usize, u32, AnimationDirection, bool)> {
+        let mut target = Transform::default();
+        let signals = AnimationSignals::default();
+        ticks
+            .iter()
+            .flat_map(|dt| animator.advance(&mut target, *dt, &signals))
+            .map(|e| (e.animation_id, e.repetition, e.direction, e.last_step))
+            .collect()
+    }
+
+    #[test]
+    fn sampled_curve() {
+        assert!(AnimationCurve::from_samples(&[]).is_none());
+        assert!(AnimationCurve::from_samples(&[1.0]).is_none());
+
+        let triangle = AnimationCurve::from_samples(&[0.0, 0.5, 1.0, 0.5, 0.0]).unwrap();
+        let curve = triangle.clone();
+
+        let tests = [
+            (0.0, 0.0),
+            (0.125, 0.25),
+            (0.25, 0.5),
+            (0.5, 1.0),
+            (0.625, 0.75),
+            (1.0, 0.0),
+        ];
+        for (progress, value) in tests {
+            assert_eq!(curve.eval(progress), value);
+        }
+
+        let peak = (0..=100)
+            .map(|i| i as f32 / 100.0)
+            .max_by(|a, b| curve.eval(*a).total_cmp(&curve.eval(*b)))
+            .unwrap();
+        assert_eq!(peak, 0.5);
+    }
+
+    #[test]
+    fn repeated_step() {
+        use AnimationDirection::{Backward, Forward};
+
+        let mut animator =
+            SequenceAnimator::new(vec![step(1.0).repeated(3), step(2.0)], Repeat::Once);
+        let events = run(&mut animator, &[0.5; 10]);
+        assert_eq!(
+            events,
+            [
+                (0, 0, Forward, false),
+                (0, 1, Forward, false),
+                (0, 2, Forward, false),
+                (1, 0, Forward, true),
+            ]
+        );
+
+        let mut animator =
+            SequenceAnimator::new(vec![step(1.0).repeated(3), step(2.0)], Repeat::Mirrored);
+        let events = run(&mut animator, &[8.5]);
+        assert_eq!(
+            events,
+            [
+                (0, 0, Forward, false),
+                (0, 1, Forward, false),
+                (0, 2, Forward, false),
+                (1, 0, Forward, true),
+                (1, 0, Backward, false),
+                (0, 2, Backward, false),
+                (0, 1, Backward, false),
+                (0, 0, Backward, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_delay_overtime() {
+        let delay = AnimationStep::Delay(Delay {
+            duration: Duration::from_secs(1),
+        });
+        let mut animator = SequenceAnimator::new(vec![delay.repeated(2), step(1.0)], Repeat::Once);
+        let mut target = Transform::default();
+        let events = animator.advance(&mut target, 2.5, &Default::default());
+        assert_eq!(events.len(), 2);
+        assert_eq!(target.translation, Vec3::splat(0.5));
+    }
+
+    #[test]
+    fn nested_sequence() {
+        use AnimationDirection::{Backward, Forward};
+
+        let shake = AnimationStep::Sequence(vec![step(2.0), step(3.0)]);
+        let mut animator = SequenceAnimator::new(
+            vec![step(1.0), shake, AnimationStep::Sequence(vec![]), step(4.0)],
+            Repeat::Mirrored,
+        );
+        let mut target = Transform::default();
+        let events: Vec<_> = animator
+            .advance(&mut target, 6.5, &Default::default())
+            .into_iter()
+            .map(|e| (e.animation_id, e.direction, e.last_step))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (0, Forward, false),
+                (1, Forward, false),
+                (1, Forward, false),
+                (2, Forward, true),
+                (2, Backward, false),
+                (1, Backward, false),
+            ]
+        );
+        // Halfway back through the first step of the nested sequence
+        assert_eq!(target.translation, Vec3::splat(1.0));
+    }
+
+    #[test]
+    fn time_sources_diverge() {
+        let animator = |time_source| {
+            Animator::new(
+                Animation {
+                    duration: Duration::from_secs(10),
+                    curve: AnimationCurve::Linear,
+                },
+                Repeat::Once,
+                TranslationLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::splat(10.0),
+                },
             )
-            .add_systems(
-                Update,
-                animation_tick_system::<Transform, ScaleLens>,
+            .with_time_source(time_source)
+        };
+        let mut world_animator = animator(TimeSource::Virtual);
+        let mut ui_animator = animator(TimeSource::Real);
+        let mut world_target = Transform::default();
+        let mut ui_target = Transform::default();
+        let settings = AnimationSettings::default();
+
+        let mut time = Time::default();
+        let mut now = time.startup();
+        time.update_with_instant(now);
+        let mut step = |time: &mut Time| {
+            now += Duration::from_secs(1);
+            time.update_with_instant(now);
+            for (animator, target) in [
+                (&mut world_animator, &mut world_target),
+                (&mut ui_animator, &mut ui_target),
+            ] {
+                let time_elapsed = animator.clock.delta_seconds(time, &settings, 1.0);
+                animator.advance(target, time_elapsed);
+            }
+        };
+
+        step(&mut time);
+        time.set_relative_speed(0.5);
+        step(&mut time);
+        time.pause();
+        step(&mut time);
+
+        assert_eq!(world_target.translation, Vec3::splat(1.5));
+        assert_eq!(ui_target.translation, Vec3::splat(3.0));
+    }
+
+    #[test]
+    fn track_moving_target() {
+        let mut app = App::new();
+        app.add_event::<AnimationCompleted>()
+            .init_resource::<Time>()
+            .init_resource::<AnimationSettings>()
+            .init_resource::<AnimationGroups>()
+            .add_systems(Update, track_translation_tick_system);
+
+        let target = app
+            .world
+            .spawn(GlobalTransform::from_xyz(10.0, 0.0, 0.0))
+            .id();
+        let entity = app.world.spawn(TransformBundle::default()).id();
+        app.add_systems(Startup, move |mut commands: Commands| {
+            let duration = Duration::from_secs(4);
+            fly_to(
+                &mut commands,
+                entity,
+                target,
+                duration,
+                AnimationCurve::Linear,
+            );
+        });
+
+        let mut now = app.world.resource::<Time>().startup();
+        let mut step = |app: &mut App| {
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            now += Duration::from_secs(1);
+            app.update();
+        };
+        let translation = |world: &World| world.get::<Transform>(entity).unwrap().translation;
+
+        step(&mut app);
+        step(&mut app);
+        assert_eq!(translation(&app.world), Vec3::new(2.5, 0.0, 0.0));
+
+        // Target moved, the rest of the way is towards the new position
+        *app.world.get_mut::<GlobalTransform>(target).unwrap() =
+            GlobalTransform::from_xyz(20.0, 0.0, 0.0);
+        step(&mut app);
+        assert_eq!(translation(&app.world), Vec3::new(10.0, 0.0, 0.0));
+
+        // Despawned target, completes at the last known position
+        app.world.despawn(target);
+        step(&mut app);
+        assert_eq!(translation(&app.world), Vec3::new(20.0, 0.0, 0.0));
+        let events = app.world.resource::<Events<AnimationCompleted>>();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn wait_for_signal() {
+        let mut signals = AnimationSignals::default();
+        let mut target = Transform::default();
+        let mut tick = |animator: &mut SequenceAnimator<TranslationLens>, signals: &_| {
+            animator.advance(&mut target, 0.75, signals);
+            target.translation
+        };
+
+        let seq = || {
+            SequenceAnimator::builder()
+                .step(step(1.0))
+                .wait_for(7)
+                .step(step(4.0))
+                .build()
+        };
+
+        // Holds until the signal is raised, the wait consumes the frame
+        let mut animator = seq();
+        assert_eq!(tick(&mut animator, &signals), Vec3::splat(0.75));
+        assert_eq!(tick(&mut animator, &signals), Vec3::splat(1.0));
+        assert_eq!(tick(&mut animator, &signals), Vec3::splat(1.0));
+        signals.raise(7);
+        assert_eq!(tick(&mut animator, &signals), Vec3::splat(3.0));
+
+        // Already raised, passes straight through within the same tick
+        let mut animator = seq();
+        assert_eq!(tick(&mut animator, &signals), Vec3::splat(0.75));
+        assert_eq!(tick(&mut animator, &signals), Vec3::splat(2.0));
+    }
+
+    #[test]
+    fn queue_carries_overtime() {
+        let mut queue = AnimationQueue::new();
+        let mut target = Transform::default();
+        let mut events = Vec::new();
+        let mut tick = |queue: &mut AnimationQueue<_>, dt| {
+            events.extend(queue.advance(&mut target, dt));
+            target.translation
+        };
+        let walk = |end: f32| {
+            let AnimationStep::Animation(animation, lens) = step(end) else {
+                unreachable!()
+            };
+            (animation, lens)
+        };
+
+        let (animation, lens) = walk(1.0);
+        queue.push(animation, lens);
+        assert_eq!(tick(&mut queue, 0.5), Vec3::splat(0.5));
+
+        queue.push_delay(Delay {
+            duration: Duration::from_secs(1),
+        });
+        let (animation, lens) = walk(4.0);
+        queue.push(animation, lens);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(tick(&mut queue, 2.0), Vec3::splat(2.0));
+        assert_eq!(queue.len(), 1);
+
+        assert_eq!(tick(&mut queue, 1.0), Vec3::splat(4.0));
+        assert!(queue.is_empty());
+        // Idle on empty
+        assert_eq!(tick(&mut queue, 1.0), Vec3::splat(4.0));
+
+        let ids: Vec<_> = events
+            .into_iter()
+            .map(|e| (e.animation_id, e.last_step))
+            .collect();
+        assert_eq!(ids, [(0, false), (1, false), (2, true)]);
+    }
+
+    #[test]
+    fn max_delta_caps_progress() {
+        let mut time = Time::default();
+        let now = time.startup();
+        time.update_with_instant(now);
+        time.update_with_instant(now + Duration::from_secs(10));
+
+        let animator = || {
+            Animator::new(
+                Animation {
+                    duration: Duration::from_secs(2),
+                    curve: AnimationCurve::Linear,
+                },
+                Repeat::Mirrored,
+                TranslationLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::splat(2.0),
+                },
             )
-            .add_systems(
-                Update,
-                animation_sequence_tick_system::<Transform, ScaleLens>,
+        };
+        let capped = AnimationSettings {
+            max_delta: Some(Duration::from_secs_f32(0.1)),
+        };
+        let mut target = Transform::default();
+
+        // Global cap
+        let mut global = animator();
+        let time_elapsed = global.clock.delta_seconds(&time, &capped, 1.0);
+        global.advance(&mut target, time_elapsed);
+        assert_eq!(global.state.progress, 0.05);
+
+        // Per animator cap
+        let mut local = animator().with_max_delta(Duration::from_secs_f32(0.1));
+        let time_elapsed = local
+            .clock
+            .delta_seconds(&time, &AnimationSettings::default(), 1.0);
+        local.advance(&mut target, time_elapsed);
+        assert_eq!(local.state.progress, 0.05);
+    }
+
+    #[test]
+    fn reversed_lens() {
+        let lens = TranslationLens {
+            start: Vec3::ZERO,
+            end: Vec3::splat(4.0),
+        }
+        .reversed();
+        let mut target = Transform::default();
+        for (progress, value) in [(0.0, 4.0), (0.25, 3.0), (1.0, 0.0)] {
+            lens.lerp(&mut target, progress);
+            assert_eq!(target.translation, Vec3::splat(value));
+        }
+    }
+
+    #[test]
+    fn scale_pulse_peaks_half_way() {
+        let lens = ScalePulseLens {
+            base: Vec3::new(50.0, 50.0, 1.0),
+            peak: 1.2,
+        };
+        let mut target = Transform::default();
+        for (progress, scale) in [(0.0, 50.0), (0.25, 55.0), (0.5, 60.0), (1.0, 50.0)] {
+            lens.lerp(&mut target, progress);
+            assert!((target.scale.truncate() - Vec2::splat(scale)).length() < 1e-4);
+            assert_eq!(target.scale.z, 1.0);
+        }
+    }
+
+    #[test]
+    fn groups_advance_at_their_own_speed() {
+        let mut app = App::new();
+        app.add_plugins(AnimationPlugin::default())
+            .init_resource::<Time>();
+
+        let spawn = |app: &mut App, group: Option<AnimationGroup>| {
+            let animator = Animator::new(
+                Animation {
+                    duration: Duration::from_secs(10),
+                    curve: AnimationCurve::Linear,
+                },
+                Repeat::Once,
+                TranslationLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::splat(10.0),
+                },
             );
+            let mut entity = app.world.spawn((Transform::default(), animator));
+            if let Some(group) = group {
+                entity.insert(group);
+            }
+            entity.id()
+        };
+        let ui = spawn(&mut app, Some(AnimationGroup(1)));
+        let world = spawn(&mut app, Some(AnimationGroup(2)));
+        let ungrouped = spawn(&mut app, None);
+
+        let mut groups = app.world.resource_mut::<AnimationGroups>();
+        groups.set_speed(1, 2.0);
+        groups.set_speed(2, 0.5);
+
+        let mut now = app.world.resource::<Time>().startup();
+        let mut step = |app: &mut App| {
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            now += Duration::from_secs(1);
+            app.update();
+        };
+        step(&mut app);
+        step(&mut app);
+
+        app.world.resource_mut::<AnimationGroups>().pause(2);
+        step(&mut app);
+
+        let x = |app: &App, entity| app.world.get::<Transform>(entity).unwrap().translation.x;
+        assert_eq!(x(&app, ui), 4.0);
+        assert_eq!(x(&app, world), 0.5);
+        assert_eq!(x(&app, ungrouped), 2.0);
+    }
+
+    #[test]
+    fn shake_restores_base() {
+        let base = Vec3::new(3.0, -2.0, 1.0);
+        let mut target = Transform::from_translation(base);
+        let amplitude = Vec2::new(4.0, 2.0);
+        let mut animator = Animator::new(
+            Animation {
+                duration: Duration::from_secs(1),
+                curve: AnimationCurve::Linear,
+            },
+            Repeat::Once,
+            TranslationShakeLens::new(amplitude, 20.0, 7),
+        );
+
+        let mut moved = false;
+        for _ in 0..9 {
+            animator.advance(&mut target, 0.1);
+            let offset = target.translation - base;
+            assert!(offset.x.abs() <= amplitude.x && offset.y.abs() <= amplitude.y);
+            assert_eq!(offset.z, 0.0);
+            moved |= offset != Vec3::ZERO;
+        }
+        assert!(moved);
+
+        let events = animator.advance(&mut target, 0.5);
+        assert_eq!(events.len(), 1);
+        assert_eq!(target.translation, base);
+    }
+
+    #[test]
+    fn text_reveal_multibyte() {
+        let mut text = Text::from_sections([
+            TextSection::from_style(Default::default()),
+            TextSection::new("static", Default::default()),
+        ]);
+        let mut animator = Animator::new(
+            Animation {
+                duration: Duration::from_secs(4),
+                curve: AnimationCurve::Linear,
+            },
+            Repeat::Once,
+            TextRevealLens {
+                full_text: "héllo 🌳ü".to_string(),
+                section: 0,
+            },
+        );
+
+        let mut shown = Vec::new();
+        let mut completed = 0;
+        for _ in 0..9 {
+            completed += animator.advance(&mut text, 0.5).len();
+            shown.push(text.sections[0].value.clone());
+        }
+        assert_eq!(
+            shown,
+            [
+                "h",
+                "hé",
+                "hél",
+                "héll",
+                "héllo",
+                "héllo ",
+                "héllo 🌳",
+                "héllo 🌳ü",
+                "héllo 🌳ü"
+            ]
+        );
+        assert_eq!(completed, 1);
+        assert_eq!(text.sections[1].value, "static");
+    }
+
+    #[test]
+    fn loop_delay() {
+        let animator = |repeat| {
+            Animator::new(
+                Animation {
+                    duration: Duration::from_secs(1),
+                    curve: AnimationCurve::Linear,
+                },
+                repeat,
+                TranslationLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::splat(1.0),
+                },
+            )
+            .with_loop_delay(Duration::from_secs(1))
+        };
+        let run = |animator: &mut Animator<TranslationLens>, ticks: &[f32]| {
+            let mut target = Transform::default();
+            ticks
+                .iter()
+                .map(|dt| {
+                    animator.advance(&mut target, *dt);
+                    target.translation.x
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut always = animator(Repeat::Always);
+        assert_eq!(
+            run(&mut always, &[0.5, 1.0, 0.5, 0.5]),
+            [0.5, 1.0, 0.0, 0.5]
+        );
+
+        let mut mirrored = animator(Repeat::Mirrored);
+        assert_eq!(
+            run(&mut mirrored, &[0.5, 1.0, 0.75, 1.0]),
+            [0.5, 1.0, 0.75, 0.0]
+        );
+
+        // Overtime spanning several rests and legs in one tick
+        let mut mirrored = animator(Repeat::Mirrored);
+        assert_eq!(run(&mut mirrored, &[4.25]), [0.25]);
+        assert_eq!(mirrored.state.direction, AnimationDirection::Forward);
+    }
+
+    #[test]
+    fn backward_leg() {
+        let mut animator = Animator::new(
+            Animation {
+                duration: Duration::from_secs(1),
+                curve: AnimationCurve::Linear,
+            },
+            Repeat::Mirrored,
+            TranslationLens {
+                start: Vec3::ZERO,
+                end: Vec3::splat(1.0),
+            },
+        )
+        .with_backward_duration(Duration::from_secs(4))
+        .with_backward_curve(AnimationCurve::Custom(|x| x * x));
+        let mut target = Transform::default();
+        let mut tick = |animator: &mut Animator<TranslationLens>, dt| {
+            animator.advance(&mut target, dt);
+            (animator.state.progress, target.translation.x)
+        };
+
+        assert_eq!(tick(&mut animator, 0.5), (0.5, 0.5));
+        // 0.5s of overtime into the 4s backward leg
+        assert_eq!(tick(&mut animator, 1.0), (0.875, 0.765625));
+        assert_eq!(tick(&mut animator, 2.0), (0.375, 0.140625));
+        // 0.5s into the next forward leg
+        assert_eq!(tick(&mut animator, 2.0), (0.5, 0.5));
+    }
+
+    #[test]
+    fn start_value_applied_on_insert() {
+        let mut harness = AnimationHarness::default();
+        let grow = || {
+            (
+                Animation {
+                    duration: Duration::from_secs(1),
+                    curve: AnimationCurve::Linear,
+                },
+                ScaleLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::ONE,
+                },
+            )
+        };
+        harness.app.add_systems(
+            Update,
+            move |mut commands: Commands, mut spawned: Local<bool>| {
+                if std::mem::replace(&mut *spawned, true) {
+                    return;
+                }
+                let (animation, lens) = grow();
+                commands.spawn((
+                    Transform::default(),
+                    Animator::new(animation, Repeat::Once, lens),
+                ));
+                let (animation, lens) = grow();
+                commands.spawn((
+                    Transform::default(),
+                    SequenceAnimator::builder()
+                        .wait(Duration::from_secs(1))
+                        .then(animation, lens)
+                        .build(),
+                ));
+            },
+        );
+
+        // Spawned by commands after the tick systems ran this frame
+        harness.step(Duration::from_millis(500));
+        let mut scales = harness.world_mut().query::<&Transform>();
+        let scales: Vec<_> = scales.iter(harness.world()).map(|t| t.scale).collect();
+        assert_eq!(scales, [Vec3::ZERO, Vec3::ZERO]);
+
+        harness.step(Duration::from_millis(500));
+        let mut scales = harness.world_mut().query::<&Transform>();
+        let scales: Vec<_> = scales.iter(harness.world()).map(|t| t.scale).collect();
+        assert_eq!(scales, [Vec3::splat(0.5), Vec3::ZERO]);
+    }
+
+    #[test]
+    fn debug_path_cached() {
+        let mut seq = SequenceAnimator::builder()
+            .wait(Duration::from_secs(1))
+            .move_to(
+                Vec3::ZERO,
+                Vec3::X,
+                Duration::from_secs(1),
+                AnimationCurve::Linear,
+            )
+            .wait(Duration::from_secs(1))
+            .then_move(Vec3::Y, Duration::from_secs(1), AnimationCurve::Linear)
+            .build();
+        let mut path = DebugPath::default();
+        path.sync_sequence(&seq.seq);
+
+        let pieces: Vec<_> = path
+            .pieces
+            .iter()
+            .map(|piece| match piece {
+                PathPiece::Move {
+                    start, end, points, ..
+                } => {
+                    assert_eq!(points.len(), PATH_SAMPLES + 1);
+                    (Some((*start, *end)), None)
+                }
+                PathPiece::Dwell(at) => (None, Some(*at)),
+            })
+            .collect();
+        assert_eq!(
+            pieces,
+            [
+                (None, Some(Vec3::ZERO)),
+                (Some((Vec3::ZERO, Vec3::X)), None),
+                (None, Some(Vec3::X)),
+                (Some((Vec3::X, Vec3::Y)), None),
+            ]
+        );
+
+        let points = |path: &DebugPath, index| match &path.pieces[index] {
+            PathPiece::Move { points, .. } => points.as_ptr(),
+            PathPiece::Dwell(_) => unreachable!(),
+        };
+        let (first, second) = (points(&path, 1), points(&path, 3));
+
+        // Static sequence keeps the sampled points
+        path.sync_sequence(&seq.seq);
+        assert_eq!((points(&path, 1), points(&path, 3)), (first, second));
+
+        // Only the changed move is resampled
+        let AnimationStep::Animation(_, lens) = &mut seq.seq[3] else {
+            unreachable!()
+        };
+        lens.end = Vec3::Z;
+        path.sync_sequence(&seq.seq);
+        assert_eq!(points(&path, 1), first);
+        assert!(matches!(path.pieces[3], PathPiece::Move { end, .. } if end == Vec3::Z));
+
+        // And so is a move whose curve changed
+        let second = points(&path, 3);
+        let AnimationStep::Animation(animation, _) = &mut seq.seq[3] else {
+            unreachable!()
+        };
+        animation.curve = AnimationCurve::EaseFunction(interpolation::EaseFunction::QuadraticIn);
+        path.sync_sequence(&seq.seq);
+        assert_eq!(points(&path, 1), first);
+        assert_ne!(points(&path, 3), second);
+        let PathPiece::Move { points, .. } = &path.pieces[3] else {
+            unreachable!()
+        };
+        assert_eq!(points[PATH_SAMPLES / 2], Vec3::X.lerp(Vec3::Z, 0.25));
     }
 }
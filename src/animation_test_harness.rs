@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    ecs::event::ManualEventReader,
+    prelude::{App, Events, World},
+    time::Time,
+};
+
+use crate::animation::{AnimationCompleted, AnimationPlugin};
+
+/// Minimal headless [`App`] with [`AnimationPlugin`] and a manually stepped [`Time`]
+pub struct AnimationHarness {
+    pub app: App,
+    now: Instant,
+    reader: ManualEventReader<AnimationCompleted>,
+}
+
+impl Default for AnimationHarness {
+    fn default() -> Self {
+        Self::new(AnimationPlugin::default())
+    }
+}
+
+impl AnimationHarness {
+    pub fn new(plugin: AnimationPlugin) -> Self {
+        let mut app = App::new();
+        app.add_plugins(plugin).init_resource::<Time>();
+
+        // The first update only sets the reference point, frames start with a zero delta otherwise
+        let mut time = app.world.resource_mut::<Time>();
+        let now = time.startup();
+        time.update_with_instant(now);
+
+        Self {
+            app,
+            now,
+            reader: ManualEventReader::default(),
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    /// Runs a single frame `dt` after the previous one
+    pub fn step(&mut self, dt: Duration) -> Vec<AnimationCompleted> {
+        self.now += dt;
+        self.app
+            .world
+            .resource_mut::<Time>()
+            .update_with_instant(self.now);
+        self.app.update();
+
+        let events = self.app.world.resource::<Events<AnimationCompleted>>();
+        self.reader.iter(events).copied().collect()
+    }
+
+    /// Runs `frames` frames of a fixed `dt`, returns the events of all of them in order
+    pub fn step_frames(&mut self, frames: usize, dt: Duration) -> Vec<AnimationCompleted> {
+        (0..frames).flat_map(|_| self.step(dt)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::{Transform, Vec3};
+
+    use super::AnimationHarness;
+    use crate::animation::{
+        Animation, AnimationCurve, AnimationDirection, Animator, Repeat, TranslationLens,
+    };
+
+    #[test]
+    fn systems_match_manual_advance() {
+        let animator = || {
+            Animator::new(
+                Animation {
+                    duration: Duration::from_secs(1),
+                    curve: AnimationCurve::Linear,
+                },
+                Repeat::Once,
+                TranslationLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::splat(1.0),
+                },
+            )
+            .with_id(3)
+        };
+
+        let mut harness = AnimationHarness::default();
+        let entity = harness
+            .world_mut()
+            .spawn((Transform::default(), animator()))
+            .id();
+        let events = harness.step_frames(6, Duration::from_millis(250));
+
+        let mut manual = animator();
+        let mut target = Transform::default();
+        let manual_events: Vec<_> = (0..6)
+            .flat_map(|_| manual.advance(&mut target, 0.25))
+            .map(|event| event.with_entity(entity))
+            .collect();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events, manual_events);
+        assert_eq!(events[0].direction, AnimationDirection::Forward);
+        assert_eq!(harness.world().get::<Transform>(entity), Some(&target));
+        assert_eq!(target.translation, Vec3::splat(1.0));
+    }
+}
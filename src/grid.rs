@@ -1,32 +1,312 @@
-use bevy::prelude::{IVec2, Resource, UVec2, Vec2, Vec3};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque},
+    error::Error,
+    fmt,
+    ops::{Add, RangeInclusive, Sub},
+    str::FromStr,
+    time::Duration,
+};
+
+use bevy::{
+    ecs::{entity::Entities, system::SystemParam},
+    gizmos::GizmoConfig,
+    input::InputSystem,
+    log::warn,
+    prelude::{
+        resource_exists, Added, App, Bundle, Camera, Camera2d, Changed, Color, Commands, Component,
+        DetectChanges, DetectChangesMut, Entity, Event, EventReader, EventWriter, Gizmos,
+        GlobalTransform, IVec2, Input, IntoSystemConfigs, KeyCode, MouseButton, Plugin, PostUpdate,
+        PreUpdate, Query, Rect, Ref, Res, ResMut, Resource, Sprite, SpriteBundle, Transform, UVec2,
+        Update, Vec2, Vec3, Vec4, Visibility, With, Without,
+    },
+    sprite::Anchor,
+    text::{Text, Text2dBundle, TextStyle},
+    transform::TransformSystem,
+    window::{PrimaryWindow, Window},
+};
+
+use crate::animation::{
+    Animation, AnimationCurve, Animator, Repeat, SequenceAnimator, TranslationLens,
+    TranslationShakeLens,
+};
 
 #[derive(Resource)]
 pub struct GridSettings {
-    pub size: u32,
+    /// Side length of a cell in world units
+    pub size: f32,
+    /// Where placed items snap to
+    pub snap_mode: SnapMode,
+    pub topology: GridTopology,
+    /// Sub-cells along each axis for items placed at sub-cell resolution, 1 is whole cells
+    pub snap_divisions: u32,
 }
-// TODO: handle negative
-// Grid index
-#[derive(Debug)]
-pub struct GridCoord {
-    coord: UVec2,
-    quad: IVec2, // (x: +-1, y: +-1)
+
+impl GridSettings {
+    pub fn from_u32(size: u32) -> Self {
+        Self {
+            size: size as f32,
+            snap_mode: SnapMode::default(),
+            topology: GridTopology::default(),
+            snap_divisions: 1,
+        }
+    }
+
+    /// Cell containing `world_pos`, in the canonical range of the topology
+    pub fn cell_at(&self, world_pos: Vec2) -> GridCoord {
+        self.topology.wrap(world_pos.as_grid_coord(self.size))
+    }
+
+    /// Cell and [`SubCell`] containing `world_pos`, split by [`snap_divisions`](Self::snap_divisions)
+    pub fn sub_cell_at(&self, world_pos: Vec2) -> (GridCoord, SubCell) {
+        let divisions = self.snap_divisions.max(1) as i32;
+        let fine = world_pos.as_grid_coord(self.size / divisions as f32).0;
+        let coord = GridCoord::new(fine.x.div_euclid(divisions), fine.y.div_euclid(divisions));
+        let sub_cell = SubCell {
+            x: fine.x.rem_euclid(divisions) as u32,
+            y: fine.y.rem_euclid(divisions) as u32,
+        };
+        (self.topology.wrap(coord), sub_cell)
+    }
+
+    /// Center of the canonical cell for `coord`
+    pub fn cell_center(&self, coord: GridCoord) -> Vec2 {
+        self.topology.wrap(coord).translation(self.size)
+    }
+}
+
+/// How cells connect at the edges of the world
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GridTopology {
+    #[default]
+    Plane,
+    /// Wraps around, canonical cells are `[0, width) x [0, height)`
+    ///
+    /// A size of 0 leaves that axis unbounded, e.g. `height: 0` is a cylinder.
+    Torus { width: u32, height: u32 },
+}
+
+/// `v` wrapped into `[0, len)`, unchanged on an unbounded axis
+fn wrap_axis(v: i32, len: u32) -> i32 {
+    match len {
+        0 => v,
+        len => v.rem_euclid(len as i32),
+    }
+}
+
+impl GridTopology {
+    /// Canonical cell for `coord`
+    pub fn wrap(&self, coord: GridCoord) -> GridCoord {
+        match *self {
+            GridTopology::Plane => coord,
+            GridTopology::Torus { width, height } => {
+                GridCoord::new(wrap_axis(coord.0.x, width), wrap_axis(coord.0.y, height))
+            }
+        }
+    }
+
+    /// Shortest offset from `from` to `to`, going the positive way around on ties
+    pub fn delta(&self, from: GridCoord, to: GridCoord) -> IVec2 {
+        let d = to.0 - from.0;
+        match *self {
+            GridTopology::Plane => d,
+            GridTopology::Torus { width, height } => {
+                let shortest = |d: i32, len: u32| {
+                    let d = wrap_axis(d, len);
+                    if len > 0 && d > len as i32 / 2 {
+                        d - len as i32
+                    } else {
+                        d
+                    }
+                };
+                IVec2::new(shortest(d.x, width), shortest(d.y, height))
+            }
+        }
+    }
+
+    /// [`GridCoord::neighbors4`] wrapped into the canonical range
+    pub fn neighbors4(&self, coord: GridCoord) -> [GridCoord; 4] {
+        coord.neighbors4().map(|neighbor| self.wrap(neighbor))
+    }
+
+    /// [`GridCoord::neighbors8`] wrapped into the canonical range
+    pub fn neighbors8(&self, coord: GridCoord) -> [GridCoord; 8] {
+        coord.neighbors8().map(|neighbor| self.wrap(neighbor))
+    }
+
+    pub fn manhattan_distance(&self, from: GridCoord, to: GridCoord) -> u32 {
+        let d = self.delta(from, to).abs();
+        (d.x + d.y) as u32
+    }
+
+    pub fn chebyshev_distance(&self, from: GridCoord, to: GridCoord) -> u32 {
+        self.delta(from, to).abs().max_element() as u32
+    }
+
+    /// [`GridCoord::line_to`] along the shortest way around, cells in the canonical range
+    pub fn line(&self, from: GridCoord, to: GridCoord) -> Vec<GridCoord> {
+        from.line_to(from + self.delta(from, to))
+            .into_iter()
+            .map(|coord| self.wrap(coord))
+            .collect()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SnapMode {
+    #[default]
+    Center,
+    /// Bottom left corner of the cell
+    Corner,
+    /// Nearest grid line intersection, ties go up and to the right
+    Intersection,
+}
+
+pub fn snap(world_pos: Vec2, settings: &GridSettings, mode: SnapMode) -> Vec2 {
+    snap_to_size(world_pos, settings.size, mode)
+}
+
+/// [`snap`] with [`GridSettings::snap_mode`] on cells split `divisions` times along each axis
+pub fn snap_with_divisions(world_pos: Vec2, settings: &GridSettings, divisions: u32) -> Vec2 {
+    snap_to_size(
+        world_pos,
+        settings.size / divisions.max(1) as f32,
+        settings.snap_mode,
+    )
 }
 
+fn snap_to_size(world_pos: Vec2, grid_size: f32, mode: SnapMode) -> Vec2 {
+    match mode {
+        SnapMode::Center => world_pos.as_grid_coord(grid_size).translation(grid_size),
+        SnapMode::Corner => (world_pos / grid_size).floor() * grid_size,
+        SnapMode::Intersection => (world_pos / grid_size + 0.5).floor() * grid_size,
+    }
+}
+/// Signed grid index, cell `(x, y)` spans `[x * size, (x + 1) * size)` on each axis
+/// so every point belongs to exactly one cell
+///
+/// Migrating from the former `{ coord, quad }` pair: positive cells keep their index,
+/// negative cells are `-(coord + 1)`, as the duplicated cell at -0 no longer exists
+///
+/// Ordered row by row from the bottom, like [`GridRect::iter`], and displayed as `(x,y)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridCoord(pub IVec2);
+
 impl GridCoord {
-    /// Translation of the grid center in 2D space
     #[inline]
-    pub fn translation(&self, grid_size: u32) -> Vec2 {
-        Vec2 {
-            x: (self.quad.x * grid_size as i32 * self.coord.x as i32) as f32
-                + ((self.quad.x * grid_size as i32) as f32 / 2.0),
-            y: (self.quad.y * grid_size as i32 * self.coord.y as i32) as f32
-                + ((self.quad.y * grid_size as i32) as f32 / 2.0),
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self(IVec2::new(x, y))
+    }
+
+    #[inline]
+    pub fn to_ivec2(self) -> IVec2 {
+        self.0
+    }
+
+    #[inline]
+    pub fn offset(&self, dx: i32, dy: i32) -> GridCoord {
+        *self + IVec2::new(dx, dy)
+    }
+
+    /// Edge-adjacent cells, counterclockwise from +x
+    pub fn neighbors4(&self) -> [GridCoord; 4] {
+        [(1, 0), (0, 1), (-1, 0), (0, -1)].map(|(dx, dy)| self.offset(dx, dy))
+    }
+
+    /// Edge and corner-adjacent cells, counterclockwise from +x
+    pub fn neighbors8(&self) -> [GridCoord; 8] {
+        [
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+        ]
+        .map(|(dx, dy)| self.offset(dx, dy))
+    }
+
+    /// Distances along x and y, in u64 so cells at opposite ends of the i32 range do not
+    /// overflow
+    fn axis_distances(&self, other: GridCoord) -> (u64, u64) {
+        let axis = |a: i32, b: i32| (i64::from(a) - i64::from(b)).unsigned_abs();
+        (axis(self.0.x, other.0.x), axis(self.0.y, other.0.y))
+    }
+
+    pub fn manhattan_distance(&self, other: GridCoord) -> u64 {
+        let (dx, dy) = self.axis_distances(other);
+        dx + dy
+    }
+
+    pub fn chebyshev_distance(&self, other: GridCoord) -> u64 {
+        let (dx, dy) = self.axis_distances(other);
+        dx.max(dy)
+    }
+
+    /// Saturates at `u64::MAX`, past the square of any u32 range
+    pub fn euclidean_distance_sq(&self, other: GridCoord) -> u64 {
+        let (dx, dy) = self.axis_distances(other);
+        (dx * dx).saturating_add(dy * dy)
+    }
+
+    /// Whether `other` is at most `range` cells away, measured with `metric`
+    pub fn within_range(&self, other: GridCoord, range: u32, metric: GridMetric) -> bool {
+        let range = u64::from(range);
+        match metric {
+            GridMetric::Manhattan => self.manhattan_distance(other) <= range,
+            GridMetric::Chebyshev => self.chebyshev_distance(other) <= range,
+            GridMetric::Euclidean => self.euclidean_distance_sq(other) <= range * range,
         }
     }
 
+    /// Cells at exactly Chebyshev distance `radius`, counterclockwise from the bottom left corner
+    pub fn ring(&self, radius: u32) -> impl Iterator<Item = GridCoord> {
+        let center = *self;
+        let r = radius as i32;
+        // The single cell of radius 0 is the start of its only side
+        let side = (2 * r).max(1);
+        let count = if radius == 0 { 1 } else { 4 * side };
+        (0..count).map(move |i| {
+            let t = i % side;
+            let (dx, dy) = match i / side {
+                0 => (-r + t, -r),
+                1 => (r, -r + t),
+                2 => (r - t, r),
+                _ => (-r, r - t),
+            };
+            center.offset(dx, dy)
+        })
+    }
+
+    /// Self followed by the [`ring`](Self::ring)s of radius 1 through `max_radius`
+    pub fn spiral(&self, max_radius: u32) -> impl Iterator<Item = GridCoord> {
+        let center = *self;
+        (0..=max_radius).flat_map(move |radius| center.ring(radius))
+    }
+
+    /// Bresenham line to `other`, 8-connected with both ends included
+    pub fn line_to(&self, other: GridCoord) -> Vec<GridCoord> {
+        ordered_line(*self, other, bresenham_line)
+    }
+
+    /// Every cell the segment between the two cell centers passes through, 4-connected,
+    /// passing exactly through a corner takes the horizontal neighbor
+    pub fn supercover_line_to(&self, other: GridCoord) -> Vec<GridCoord> {
+        ordered_line(*self, other, supercover_line)
+    }
+
+    /// Translation of the grid center in 2D space
+    #[inline]
+    pub fn translation(&self, grid_size: f32) -> Vec2 {
+        (self.0.as_vec2() + 0.5) * grid_size
+    }
+
     /// Translation of the grid center in 3D space with z coordinate
     #[inline]
-    pub fn translation_with_z(&self, grid_size: u32, z: f32) -> Vec3 {
+    pub fn translation_with_z(&self, grid_size: f32, z: f32) -> Vec3 {
         let translation_xy = self.translation(grid_size);
         Vec3 {
             x: translation_xy.x,
@@ -34,31 +314,2515 @@ impl GridCoord {
             z,
         }
     }
+
+    /// Bottom left corner of the cell
+    #[inline]
+    pub fn min_corner(&self, grid_size: f32) -> Vec2 {
+        self.0.as_vec2() * grid_size
+    }
+
+    /// Top right corner of the cell, the first point of the next cells
+    #[inline]
+    pub fn max_corner(&self, grid_size: f32) -> Vec2 {
+        (self.0 + IVec2::ONE).as_vec2() * grid_size
+    }
+
+    /// Area covered by the cell
+    pub fn bounds(&self, settings: &GridSettings) -> Rect {
+        Rect::from_corners(
+            self.min_corner(settings.size),
+            self.max_corner(settings.size),
+        )
+    }
+
+    /// Whether `point` is in the cell, points on the top and right edges belong to the next cells
+    pub fn contains_point(&self, point: Vec2, settings: &GridSettings) -> bool {
+        let bounds = self.bounds(settings);
+        point.cmpge(bounds.min).all() && point.cmplt(bounds.max).all()
+    }
 }
 
-pub trait AsGridCoord {
-    fn as_grid_coord(&self, grid_size: u32) -> GridCoord;
+/// Draws lines from the lower endpoint so a line and its reverse cover the same cells
+fn ordered_line(
+    from: GridCoord,
+    to: GridCoord,
+    line: fn(IVec2, IVec2) -> Vec<GridCoord>,
+) -> Vec<GridCoord> {
+    if (from.0.x, from.0.y) <= (to.0.x, to.0.y) {
+        line(from.0, to.0)
+    } else {
+        let mut cells = line(to.0, from.0);
+        cells.reverse();
+        cells
+    }
 }
-impl AsGridCoord for Vec2 {
-    fn as_grid_coord(&self, grid_size: u32) -> GridCoord {
-        GridCoord {
-            coord: UVec2 {
-                x: (self.x.abs() as u32) / grid_size as u32,
-                y: (self.y.abs() as u32) / grid_size as u32,
-            },
-            quad: IVec2 {
-                x: self.x.signum() as i32,
-                y: self.y.signum() as i32,
-            },
+
+fn bresenham_line(from: IVec2, to: IVec2) -> Vec<GridCoord> {
+    let d = (to - from).abs();
+    let step = (to - from).signum();
+    let mut err = d.x - d.y;
+    let mut current = from;
+    let mut cells = vec![GridCoord(current)];
+    while current != to {
+        let e2 = 2 * err;
+        if e2 >= -d.y {
+            err -= d.y;
+            current.x += step.x;
+        }
+        if e2 <= d.x {
+            err += d.x;
+            current.y += step.y;
+        }
+        cells.push(GridCoord(current));
+    }
+    cells
+}
+
+fn supercover_line(from: IVec2, to: IVec2) -> Vec<GridCoord> {
+    let d = (to - from).abs();
+    let step = (to - from).signum();
+    let (mut ix, mut iy) = (0, 0);
+    let mut current = from;
+    let mut cells = vec![GridCoord(current)];
+    while ix < d.x || iy < d.y {
+        // Compares where the segment crosses the next vertical and horizontal cell edge
+        if (1 + 2 * ix) * d.y <= (1 + 2 * iy) * d.x {
+            current.x += step.x;
+            ix += 1;
+        } else {
+            current.y += step.y;
+            iy += 1;
+        }
+        cells.push(GridCoord(current));
+    }
+    cells
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridMetric {
+    /// Steps through edges only
+    Manhattan,
+    /// Steps through edges and corners
+    Chebyshev,
+    Euclidean,
+}
+
+impl PartialOrd for GridCoord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GridCoord {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.y, self.0.x).cmp(&(other.0.y, other.0.x))
+    }
+}
+
+impl fmt::Display for GridCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{})", self.0.x, self.0.y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseGridCoordError;
+
+impl fmt::Display for ParseGridCoordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected a grid coordinate like (x,y)")
+    }
+}
+
+impl Error for ParseGridCoordError {}
+
+/// Accepts the [`Display`](fmt::Display) format, with optional whitespace
+impl FromStr for GridCoord {
+    type Err = ParseGridCoordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = s
+            .trim()
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .ok_or(ParseGridCoordError)?;
+        let (x, y) = inner.split_once(',').ok_or(ParseGridCoordError)?;
+        let parse = |n: &str| n.trim().parse().map_err(|_| ParseGridCoordError);
+        Ok(GridCoord::new(parse(x)?, parse(y)?))
+    }
+}
+
+impl From<IVec2> for GridCoord {
+    fn from(value: IVec2) -> Self {
+        Self(value)
+    }
+}
+
+impl Add<IVec2> for GridCoord {
+    type Output = GridCoord;
+
+    fn add(self, rhs: IVec2) -> Self::Output {
+        GridCoord(self.0 + rhs)
+    }
+}
+
+impl Sub<IVec2> for GridCoord {
+    type Output = GridCoord;
+
+    fn sub(self, rhs: IVec2) -> Self::Output {
+        GridCoord(self.0 - rhs)
+    }
+}
+
+/// Rectangle of cells, inclusive on both ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GridRect {
+    pub min: GridCoord,
+    pub max: GridCoord,
+}
+
+impl GridRect {
+    /// Rectangle spanned by two opposite corners in any order
+    pub fn from_corners(corner: GridCoord, opposite: GridCoord) -> Self {
+        Self {
+            min: GridCoord(corner.0.min(opposite.0)),
+            max: GridCoord(corner.0.max(opposite.0)),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        (self.max.0.x as i64 - self.min.0.x as i64 + 1).clamp(0, u32::MAX as i64) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.max.0.y as i64 - self.min.0.y as i64 + 1).clamp(0, u32::MAX as i64) as u32
+    }
+
+    /// Number of cells, in u64 since rects spanning most of the coordinate range hold more
+    /// than [`u32::MAX`]
+    pub fn area(&self) -> u64 {
+        let len = |min: i32, max: i32| (max as i64 - min as i64 + 1).max(0) as u64;
+        len(self.min.0.x, self.max.0.x).saturating_mul(len(self.min.0.y, self.max.0.y))
+    }
+
+    pub fn contains(&self, coord: GridCoord) -> bool {
+        coord.0.cmpge(self.min.0).all() && coord.0.cmple(self.max.0).all()
+    }
+
+    pub fn intersection(&self, other: GridRect) -> Option<GridRect> {
+        let min = self.min.0.max(other.min.0);
+        let max = self.max.0.min(other.max.0);
+        min.cmple(max).all().then_some(GridRect {
+            min: GridCoord(min),
+            max: GridCoord(max),
+        })
+    }
+
+    /// Every cell, row by row from the bottom left
+    pub fn iter(&self) -> impl Iterator<Item = GridCoord> {
+        let (min, max) = (self.min.0, self.max.0);
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| GridCoord::new(x, y)))
+    }
+
+    /// Position of `coord` in [`iter`](Self::iter) order, None outside the rectangle
+    pub fn index_of(&self, coord: GridCoord) -> Option<usize> {
+        if !self.contains(coord) {
+            return None;
+        }
+        let offset = (coord.0 - self.min.0).as_uvec2();
+        Some(offset.y as usize * self.width() as usize + offset.x as usize)
+    }
+
+    /// Inverse of [`index_of`](Self::index_of), indices past the area continue into the rows above
+    pub fn coord_of(&self, index: usize) -> GridCoord {
+        let width = self.width().max(1) as usize;
+        self.min
+            .offset((index % width) as i32, (index / width) as i32)
+    }
+}
+
+/// Per-cell values of a fixed [`GridRect`] stored row by row, for data covering every cell
+#[derive(Resource, Debug, Clone)]
+pub struct DenseGridMap<T: Send + Sync + 'static> {
+    rect: GridRect,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Send + Sync + 'static> DenseGridMap<T> {
+    pub fn new(rect: GridRect, value: T) -> Self {
+        Self {
+            rect,
+            cells: vec![value; rect.area() as usize],
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+}
+
+impl<T: Send + Sync + 'static> DenseGridMap<T> {
+    pub fn rect(&self) -> GridRect {
+        self.rect
+    }
+
+    /// None outside [`rect`](Self::rect)
+    pub fn get(&self, coord: GridCoord) -> Option<&T> {
+        self.cells.get(self.rect.index_of(coord)?)
+    }
+
+    /// None outside [`rect`](Self::rect)
+    pub fn get_mut(&mut self, coord: GridCoord) -> Option<&mut T> {
+        self.cells.get_mut(self.rect.index_of(coord)?)
+    }
+
+    /// Every cell with its index, in [`GridRect::iter`] order
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, GridCoord, &T)> {
+        let rect = self.rect;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, value)| (index, rect.coord_of(index), value))
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+}
+
+/// Cells whose area intersects the world space rectangle, only touching a cell
+/// on its edge does not overlap it
+pub fn cells_overlapping(aabb_min: Vec2, aabb_max: Vec2, settings: &GridSettings) -> GridRect {
+    let grid_size = settings.size;
+    let min = (aabb_min.min(aabb_max) / grid_size).floor().as_ivec2();
+    let max = (aabb_min.max(aabb_max) / grid_size).ceil().as_ivec2() - IVec2::ONE;
+    // Degenerate rectangles lying on a line still touch the cell they start in
+    GridRect {
+        min: GridCoord(min),
+        max: GridCoord(max.max(min)),
+    }
+}
+
+/// Every cell the segment from `from` to `to` passes through in order, 4-connected,
+/// including the cells of both ends
+///
+/// Passing exactly through a corner takes the horizontal neighbor first, like
+/// [`GridCoord::supercover_line_to`].
+pub fn raycast_cells(
+    from: Vec2,
+    to: Vec2,
+    settings: &GridSettings,
+) -> impl Iterator<Item = GridCoord> {
+    let grid_size = settings.size;
+    let start = from.as_grid_coord(grid_size);
+    let end = to.as_grid_coord(grid_size);
+    let direction = to - from;
+    let step = (end.0 - start.0).signum();
+
+    // Ray parameter at which the next cell boundary is crossed, and between two crossings
+    let axis = |from: f32, direction: f32, cell: i32| {
+        if direction == 0.0 {
+            return (f32::INFINITY, f32::INFINITY);
+        }
+        let boundary = if direction > 0.0 { cell + 1 } else { cell } as f32 * grid_size;
+        ((boundary - from) / direction, grid_size / direction.abs())
+    };
+    let (mut t_max_x, t_delta_x) = axis(from.x, direction.x, start.0.x);
+    let (mut t_max_y, t_delta_y) = axis(from.y, direction.y, start.0.y);
+
+    // Exactly one step per row or column between the ends, so rounding can not overshoot
+    let steps = start.manhattan_distance(end);
+    let mut current = start;
+    std::iter::once(start).chain((0..steps).map(move |_| {
+        let x_done = current.0.x == end.0.x;
+        let y_done = current.0.y == end.0.y;
+        if y_done || (!x_done && t_max_x <= t_max_y) {
+            current = current.offset(step.x, 0);
+            t_max_x += t_delta_x;
+        } else {
+            current = current.offset(0, step.y);
+            t_max_y += t_delta_y;
+        }
+        current
+    }))
+}
+
+/// First cell along [`raycast_cells`] occupied in `grid_map`
+pub fn first_blocked_cell<T: Send + Sync + 'static>(
+    from: Vec2,
+    to: Vec2,
+    settings: &GridSettings,
+    grid_map: &GridMap<T>,
+) -> Option<GridCoord> {
+    raycast_cells(from, to, settings).find(|coord| grid_map.contains(*coord))
+}
+
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Octile distance in path cost units, Manhattan without diagonal moves
+fn path_heuristic(topology: GridTopology, from: GridCoord, to: GridCoord, diagonal: bool) -> u32 {
+    let d = topology.delta(from, to).abs();
+    let (dx, dy) = (d.x as u32, d.y as u32);
+    if diagonal {
+        STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+    } else {
+        STRAIGHT_COST * (dx + dy)
+    }
+}
+
+/// A* from `start` to `goal`, both included in the returned path
+///
+/// Diagonal moves never cut the corner of a blocked cell. None if the goal is unreachable
+/// or more than `max_expansions` cells had to be expanded.
+pub fn pathfind(
+    start: GridCoord,
+    goal: GridCoord,
+    is_blocked: impl Fn(GridCoord) -> bool,
+    diagonal: bool,
+    max_expansions: usize,
+) -> Option<Vec<GridCoord>> {
+    pathfind_in(
+        GridTopology::Plane,
+        start,
+        goal,
+        is_blocked,
+        diagonal,
+        max_expansions,
+    )
+}
+
+/// [`pathfind`] on `topology`, the path is in canonical cells and may cross the seams
+pub fn pathfind_in(
+    topology: GridTopology,
+    start: GridCoord,
+    goal: GridCoord,
+    is_blocked: impl Fn(GridCoord) -> bool,
+    diagonal: bool,
+    max_expansions: usize,
+) -> Option<Vec<GridCoord>> {
+    let (start, goal) = (topology.wrap(start), topology.wrap(goal));
+    let mut open = BinaryHeap::new();
+    let mut came_from = HashMap::new();
+    let mut cost = HashMap::from([(start, 0)]);
+    open.push(Reverse((
+        path_heuristic(topology, start, goal, diagonal),
+        0,
+        start.0.x,
+        start.0.y,
+    )));
+
+    let mut expansions = 0;
+    while let Some(Reverse((_, current_cost, x, y))) = open.pop() {
+        let current = GridCoord::new(x, y);
+        if current == goal {
+            let mut path = vec![goal];
+            while let Some(previous) = came_from.get(path.last().unwrap()) {
+                path.push(*previous);
+            }
+            path.reverse();
+            return Some(path);
         }
+        // Stale entry, a cheaper way here was already expanded
+        if cost.get(&current).is_some_and(|best| current_cost > *best) {
+            continue;
+        }
+        expansions += 1;
+        if expansions > max_expansions {
+            return None;
+        }
+
+        for (dx, dy) in [
+            (1, 0),
+            (0, 1),
+            (-1, 0),
+            (0, -1),
+            (1, 1),
+            (-1, 1),
+            (-1, -1),
+            (1, -1),
+        ] {
+            let is_diagonal = dx != 0 && dy != 0;
+            if is_diagonal
+                && (!diagonal
+                    || is_blocked(topology.wrap(current.offset(dx, 0)))
+                    || is_blocked(topology.wrap(current.offset(0, dy))))
+            {
+                continue;
+            }
+            let next = topology.wrap(current.offset(dx, dy));
+            if is_blocked(next) {
+                continue;
+            }
+            let step = if is_diagonal {
+                DIAGONAL_COST
+            } else {
+                STRAIGHT_COST
+            };
+            let next_cost = current_cost + step;
+            if cost.get(&next).is_some_and(|best| next_cost >= *best) {
+                continue;
+            }
+            cost.insert(next, next_cost);
+            came_from.insert(next, current);
+            let estimate = next_cost + path_heuristic(topology, next, goal, diagonal);
+            open.push(Reverse((estimate, next_cost, next.0.x, next.0.y)));
+        }
+    }
+    None
+}
+
+/// First cell along [`GridCoord::spiral`] matching `predicate`, nearest by Chebyshev distance
+pub fn find_nearest(
+    center: GridCoord,
+    max_radius: u32,
+    predicate: impl Fn(GridCoord) -> bool,
+) -> Option<GridCoord> {
+    center.spiral(max_radius).find(|coord| predicate(*coord))
+}
+
+/// 4-connected region of passable cells around `start`
+///
+/// Stops once `limit` cells are filled, empty if `start` itself is not passable.
+pub fn flood_fill(
+    start: GridCoord,
+    passable: impl Fn(GridCoord) -> bool,
+    limit: usize,
+) -> HashSet<GridCoord> {
+    let mut filled = HashSet::new();
+    if limit == 0 || !passable(start) {
+        return filled;
+    }
+    filled.insert(start);
+    let mut frontier = VecDeque::from([start]);
+    while let Some(current) = frontier.pop_front() {
+        for next in current.neighbors4() {
+            if filled.contains(&next) || !passable(next) {
+                continue;
+            }
+            filled.insert(next);
+            if filled.len() >= limit {
+                return filled;
+            }
+            frontier.push_back(next);
+        }
+    }
+    filled
+}
+
+/// [`flood_fill`] that never leaves `rect`
+pub fn flood_fill_in_rect(
+    start: GridCoord,
+    rect: GridRect,
+    passable: impl Fn(GridCoord) -> bool,
+    limit: usize,
+) -> HashSet<GridCoord> {
+    flood_fill(
+        start,
+        |coord| rect.contains(coord) && passable(coord),
+        limit,
+    )
+}
+
+/// Cells that can be built on, inclusive on both ends
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridBounds {
+    pub min: GridCoord,
+    pub max: GridCoord,
+}
+
+impl GridBounds {
+    pub fn rect(&self) -> GridRect {
+        GridRect {
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    pub fn contains(&self, coord: GridCoord) -> bool {
+        self.rect().contains(coord)
+    }
+
+    /// Nearest cell within bounds
+    pub fn clamp(&self, coord: GridCoord) -> GridCoord {
+        GridCoord(coord.0.clamp(self.min.0, self.max.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridMapChange {
+    Inserted,
+    Removed,
+    Replaced,
+}
+
+/// Sent for every cell of the [`LayeredGridMap<Entity>`] resource that changed during the frame
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridMapChanged {
+    pub layer: GridLayer,
+    pub coord: GridCoord,
+    pub change: GridMapChange,
+}
+
+/// Contents of grid cells, at most one value per cell
+#[derive(Resource, Debug, Clone)]
+pub struct GridMap<T: Send + Sync + 'static> {
+    cells: HashMap<GridCoord, T>,
+    /// None unless created with [`GridMap::tracked`]
+    changes: Option<Vec<(GridCoord, GridMapChange)>>,
+}
+
+impl<T: Send + Sync + 'static> Default for GridMap<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            changes: None,
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Extend<(GridCoord, T)> for GridMap<T> {
+    fn extend<I: IntoIterator<Item = (GridCoord, T)>>(&mut self, iter: I) {
+        for (coord, value) in iter {
+            self.insert(coord, value);
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> GridMap<T> {
+    /// Map that logs every insertion and removal until [`drain_changes`](Self::drain_changes)
+    ///
+    /// Values edited in place through [`get_mut`](Self::get_mut) are not logged.
+    pub fn tracked() -> Self {
+        Self {
+            cells: HashMap::new(),
+            changes: Some(Vec::new()),
+        }
+    }
+
+    pub fn is_tracked(&self) -> bool {
+        self.changes.is_some()
+    }
+
+    /// Logged changes in the order they happened, empty for untracked maps
+    pub fn drain_changes(&mut self) -> Vec<(GridCoord, GridMapChange)> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.changes
+            .as_ref()
+            .is_some_and(|changes| !changes.is_empty())
+    }
+
+    fn log(&mut self, coord: GridCoord, change: GridMapChange) {
+        if let Some(changes) = &mut self.changes {
+            changes.push((coord, change));
+        }
+    }
+
+    /// Returns the previous value of the cell
+    pub fn insert(&mut self, coord: GridCoord, value: T) -> Option<T> {
+        let previous = self.cells.insert(coord, value);
+        let change = match previous {
+            Some(_) => GridMapChange::Replaced,
+            None => GridMapChange::Inserted,
+        };
+        self.log(coord, change);
+        previous
+    }
+
+    pub fn get(&self, coord: GridCoord) -> Option<&T> {
+        self.cells.get(&coord)
+    }
+
+    pub fn get_mut(&mut self, coord: GridCoord) -> Option<&mut T> {
+        self.cells.get_mut(&coord)
+    }
+
+    pub fn remove(&mut self, coord: GridCoord) -> Option<T> {
+        let removed = self.cells.remove(&coord);
+        if removed.is_some() {
+            self.log(coord, GridMapChange::Removed);
+        }
+        removed
+    }
+
+    pub fn contains(&self, coord: GridCoord) -> bool {
+        self.cells.contains_key(&coord)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (GridCoord, &T)> {
+        self.cells.iter().map(|(coord, value)| (*coord, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Removed cells are logged in coordinate order
+    pub fn retain(&mut self, mut keep: impl FnMut(GridCoord, &mut T) -> bool) {
+        let mut removed = Vec::new();
+        self.cells.retain(|coord, value| {
+            let kept = keep(*coord, value);
+            if !kept {
+                removed.push(*coord);
+            }
+            kept
+        });
+        if self.is_tracked() {
+            removed.sort();
+            for coord in removed {
+                self.log(coord, GridMapChange::Removed);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+
+    /// Occupied cells within `rect`, in no particular order
+    pub fn entities_in_rect(
+        &self,
+        rect: GridRect,
+    ) -> Box<dyn Iterator<Item = (GridCoord, &T)> + '_> {
+        // Looks up every cell of small regions, scans the map for large ones
+        if rect.area() < self.cells.len() as u64 {
+            Box::new(
+                rect.iter()
+                    .filter_map(|coord| self.get(coord).map(|value| (coord, value))),
+            )
+        } else {
+            Box::new(self.iter().filter(move |(coord, _)| rect.contains(*coord)))
+        }
+    }
+
+    /// Occupied cells of [`GridCoord::ring`] in ring order
+    pub fn entities_in_ring(
+        &self,
+        center: GridCoord,
+        radius: u32,
+    ) -> impl Iterator<Item = (GridCoord, &T)> {
+        center
+            .ring(radius)
+            .filter_map(|coord| self.get(coord).map(|value| (coord, value)))
+    }
+}
+
+/// [`GridMap`] contents along with the cell size they were placed at
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SavedGridMap<T> {
+    pub grid_size: f32,
+    /// Sorted by coordinate
+    pub cells: Vec<(GridCoord, T)>,
+}
+
+#[derive(Debug)]
+pub enum GridMapFileError {
+    /// The map was saved for a grid of another cell size
+    SizeMismatch { saved: f32, expected: f32 },
+    #[cfg(feature = "ron")]
+    Serialize(ron::Error),
+    #[cfg(feature = "ron")]
+    Parse(ron::error::SpannedError),
+    #[cfg(feature = "std-io")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for GridMapFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SizeMismatch { saved, expected } => write!(
+                f,
+                "grid map saved with cell size {saved}, expected {expected}"
+            ),
+            #[cfg(feature = "ron")]
+            Self::Serialize(error) => write!(f, "failed to write grid map: {error}"),
+            #[cfg(feature = "ron")]
+            Self::Parse(error) => write!(f, "failed to read grid map: {error}"),
+            #[cfg(feature = "std-io")]
+            Self::Io(error) => write!(f, "grid map file: {error}"),
+        }
+    }
+}
+
+impl Error for GridMapFileError {}
+
+#[cfg(feature = "serde")]
+impl<T: Send + Sync + 'static> GridMap<T> {
+    pub fn to_saved(&self, settings: &GridSettings) -> SavedGridMap<&T> {
+        let mut cells: Vec<_> = self.iter().collect();
+        cells.sort_by_key(|(coord, _)| *coord);
+        SavedGridMap {
+            grid_size: settings.size,
+            cells,
+        }
+    }
+
+    /// Fails if `saved` is for another cell size than `settings`
+    pub fn from_saved(
+        saved: SavedGridMap<T>,
+        settings: &GridSettings,
+    ) -> Result<Self, GridMapFileError> {
+        if saved.grid_size != settings.size {
+            return Err(GridMapFileError::SizeMismatch {
+                saved: saved.grid_size,
+                expected: settings.size,
+            });
+        }
+        let mut grid_map = Self::default();
+        grid_map.extend(saved.cells);
+        Ok(grid_map)
+    }
+}
+
+#[cfg(feature = "ron")]
+impl<T: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static> GridMap<T> {
+    pub fn to_ron(&self, settings: &GridSettings) -> Result<String, GridMapFileError> {
+        ron::ser::to_string_pretty(&self.to_saved(settings), Default::default())
+            .map_err(GridMapFileError::Serialize)
+    }
+
+    pub fn from_ron(ron: &str, settings: &GridSettings) -> Result<Self, GridMapFileError> {
+        let saved = ron::from_str(ron).map_err(GridMapFileError::Parse)?;
+        Self::from_saved(saved, settings)
+    }
+
+    #[cfg(feature = "std-io")]
+    pub fn save_to_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        settings: &GridSettings,
+    ) -> Result<(), GridMapFileError> {
+        std::fs::write(path, self.to_ron(settings)?).map_err(GridMapFileError::Io)
+    }
+
+    #[cfg(feature = "std-io")]
+    pub fn load_from_file(
+        path: impl AsRef<std::path::Path>,
+        settings: &GridSettings,
+    ) -> Result<Self, GridMapFileError> {
+        let ron = std::fs::read_to_string(path).map_err(GridMapFileError::Io)?;
+        Self::from_ron(&ron, settings)
+    }
+}
+
+/// Z distance between consecutive [`GridLayer`]s
+pub const GRID_LAYER_Z_STEP: f32 = 1.0;
+
+/// Independent occupancy layer of the grid, higher layers draw on top
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridLayer(pub u8);
+
+impl GridLayer {
+    pub const FLOOR: Self = Self(0);
+    pub const OBJECT: Self = Self(1);
+    pub const OVERLAY: Self = Self(2);
+
+    /// Added to the z translation of sprites spawned on this layer
+    pub fn z_offset(&self) -> f32 {
+        self.0 as f32 * GRID_LAYER_Z_STEP
+    }
+}
+
+/// One [`GridMap`] per [`GridLayer`], a cell can hold a value on each layer
+#[derive(Resource, Debug, Clone)]
+pub struct LayeredGridMap<T: Send + Sync + 'static> {
+    layers: BTreeMap<GridLayer, GridMap<T>>,
+    /// None unless created with [`LayeredGridMap::tracked`]
+    changes: Option<Vec<(GridLayer, GridCoord, GridMapChange)>>,
+}
+
+impl<T: Send + Sync + 'static> Default for LayeredGridMap<T> {
+    fn default() -> Self {
+        Self {
+            layers: BTreeMap::new(),
+            changes: None,
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> LayeredGridMap<T> {
+    /// Map that logs every insertion and removal on any layer until
+    /// [`drain_changes`](Self::drain_changes)
+    pub fn tracked() -> Self {
+        Self {
+            layers: BTreeMap::new(),
+            changes: Some(Vec::new()),
+        }
+    }
+
+    /// Logged changes in the order they happened, empty for untracked maps
+    pub fn drain_changes(&mut self) -> Vec<(GridLayer, GridCoord, GridMapChange)> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.changes
+            .as_ref()
+            .is_some_and(|changes| !changes.is_empty())
+    }
+
+    /// Moves the changes `layer` just logged into the log of the whole map
+    fn log(&mut self, layer: GridLayer) {
+        let (Some(changes), Some(grid_map)) = (&mut self.changes, self.layers.get_mut(&layer))
+        else {
+            return;
+        };
+        changes.extend(
+            grid_map
+                .drain_changes()
+                .into_iter()
+                .map(|(coord, change)| (layer, coord, change)),
+        );
+    }
+
+    /// Returns the previous value of the cell on `layer`
+    pub fn insert(&mut self, layer: GridLayer, coord: GridCoord, value: T) -> Option<T> {
+        let tracked = self.changes.is_some();
+        let previous = self
+            .layers
+            .entry(layer)
+            .or_insert_with(|| match tracked {
+                true => GridMap::tracked(),
+                false => GridMap::default(),
+            })
+            .insert(coord, value);
+        self.log(layer);
+        previous
+    }
+
+    pub fn get(&self, layer: GridLayer, coord: GridCoord) -> Option<&T> {
+        self.layers.get(&layer)?.get(coord)
+    }
+
+    pub fn get_mut(&mut self, layer: GridLayer, coord: GridCoord) -> Option<&mut T> {
+        self.layers.get_mut(&layer)?.get_mut(coord)
+    }
+
+    pub fn remove(&mut self, layer: GridLayer, coord: GridCoord) -> Option<T> {
+        let removed = self.layers.get_mut(&layer)?.remove(coord);
+        self.log(layer);
+        removed
+    }
+
+    pub fn contains(&self, layer: GridLayer, coord: GridCoord) -> bool {
+        self.get(layer, coord).is_some()
+    }
+
+    pub fn layer(&self, layer: GridLayer) -> Option<&GridMap<T>> {
+        self.layers.get(&layer)
+    }
+
+    pub fn iter_layer(&self, layer: GridLayer) -> impl Iterator<Item = (GridCoord, &T)> {
+        self.layers.get(&layer).into_iter().flat_map(GridMap::iter)
+    }
+
+    /// All occupied cells, layer by layer from the bottom
+    pub fn iter(&self) -> impl Iterator<Item = (GridLayer, GridCoord, &T)> {
+        self.layers.iter().flat_map(|(layer, grid_map)| {
+            grid_map
+                .iter()
+                .map(move |(coord, value)| (*layer, coord, value))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.values().map(GridMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.values().all(GridMap::is_empty)
+    }
+
+    /// Removed cells are logged layer by layer from the bottom
+    pub fn retain(&mut self, mut keep: impl FnMut(GridLayer, GridCoord, &mut T) -> bool) {
+        let layers: Vec<_> = self.layers.keys().copied().collect();
+        for layer in layers {
+            if let Some(grid_map) = self.layers.get_mut(&layer) {
+                grid_map.retain(|coord, value| keep(layer, coord, value));
+            }
+            self.log(layer);
+        }
+    }
+}
+
+/// Position inside a cell counted from the bottom left, see [`GridSettings::sub_cell_at`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubCell {
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Contents of sub-cells, for items placed at [`GridSettings::snap_divisions`]
+///
+/// Keys are not remapped when the divisions change.
+#[derive(Resource, Debug, Clone)]
+pub struct SubCellMap<T: Send + Sync + 'static> {
+    cells: HashMap<(GridCoord, SubCell), T>,
+}
+
+impl<T: Send + Sync + 'static> Default for SubCellMap<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> SubCellMap<T> {
+    /// Returns the previous value of the sub-cell
+    pub fn insert(&mut self, coord: GridCoord, sub_cell: SubCell, value: T) -> Option<T> {
+        self.cells.insert((coord, sub_cell), value)
+    }
+
+    pub fn get(&self, coord: GridCoord, sub_cell: SubCell) -> Option<&T> {
+        self.cells.get(&(coord, sub_cell))
+    }
+
+    pub fn remove(&mut self, coord: GridCoord, sub_cell: SubCell) -> Option<T> {
+        self.cells.remove(&(coord, sub_cell))
+    }
+
+    pub fn contains(&self, coord: GridCoord, sub_cell: SubCell) -> bool {
+        self.cells.contains_key(&(coord, sub_cell))
+    }
+
+    /// Occupied sub-cells of `coord`, in no particular order
+    pub fn iter_cell(&self, coord: GridCoord) -> impl Iterator<Item = (SubCell, &T)> {
+        self.cells
+            .iter()
+            .filter(move |((cell, _), _)| *cell == coord)
+            .map(|((_, sub_cell), value)| (*sub_cell, value))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (GridCoord, SubCell, &T)> {
+        self.cells
+            .iter()
+            .map(|((coord, sub_cell), value)| (*coord, *sub_cell, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(GridCoord, SubCell, &mut T) -> bool) {
+        self.cells
+            .retain(|(coord, sub_cell), value| keep(*coord, *sub_cell, value));
+    }
+}
+
+/// Grid used instead of [`GridSettings`] by the [`ItemPreview`](crate::items::ItemPreview)
+/// carrying it, placed items keep the world area of their cell in a [`RectMap`]
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct GridOverride {
+    /// Cell width and height in world units, non-zero
+    pub size: UVec2,
+    /// Bottom left corner of cell `(0, 0)`, the world origin when None
+    pub origin: Option<Vec2>,
+}
+
+impl GridOverride {
+    fn origin(&self) -> Vec2 {
+        self.origin.unwrap_or_default()
+    }
+
+    /// Cell of this grid containing `world_pos`
+    pub fn cell_at(&self, world_pos: Vec2) -> GridCoord {
+        GridCoord(
+            ((world_pos - self.origin()) / self.size.as_vec2())
+                .floor()
+                .as_ivec2(),
+        )
+    }
+
+    /// Area covered by `coord` on this grid
+    pub fn cell_bounds(&self, coord: GridCoord) -> Rect {
+        let min = self.origin() + coord.0.as_vec2() * self.size.as_vec2();
+        Rect::from_corners(min, min + self.size.as_vec2())
+    }
+
+    /// [`snap`] on this grid
+    pub fn snap(&self, world_pos: Vec2, mode: SnapMode) -> Vec2 {
+        let size = self.size.as_vec2();
+        let local = (world_pos - self.origin()) / size;
+        let snapped = match mode {
+            SnapMode::Center => local.floor() + 0.5,
+            SnapMode::Corner => local.floor(),
+            SnapMode::Intersection => (local + 0.5).floor(),
+        };
+        self.origin() + snapped * size
+    }
+}
+
+/// Values covering world space rectangles, for items placed on a [`GridOverride`] grid
+///
+/// Rectangles only touching on an edge do not overlap.
+#[derive(Resource, Debug, Clone)]
+pub struct RectMap<T: Send + Sync + 'static> {
+    entries: Vec<(GridLayer, Rect, T)>,
+}
+
+impl<T: Send + Sync + 'static> Default for RectMap<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> RectMap<T> {
+    pub fn insert(&mut self, layer: GridLayer, rect: Rect, value: T) {
+        self.entries.push((layer, rect, value));
+    }
+
+    /// Values on `layer` whose rectangle overlaps `rect`
+    pub fn overlapping(&self, layer: GridLayer, rect: Rect) -> impl Iterator<Item = (Rect, &T)> {
+        self.entries
+            .iter()
+            .filter(move |(entry_layer, entry_rect, _)| {
+                *entry_layer == layer && !entry_rect.intersect(rect).is_empty()
+            })
+            .map(|(_, rect, value)| (*rect, value))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (GridLayer, Rect, &T)> {
+        self.entries
+            .iter()
+            .map(|(layer, rect, value)| (*layer, *rect, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(GridLayer, Rect, &mut T) -> bool) {
+        self.entries
+            .retain_mut(|(layer, rect, value)| keep(*layer, *rect, value));
+    }
+}
+
+/// Placed entities looked up by world position
+///
+/// Entities despawned without being removed from the map are skipped when `alive` is given.
+#[derive(SystemParam)]
+pub struct GridQuery<'w> {
+    pub grid_map: Res<'w, LayeredGridMap<Entity>>,
+    pub settings: Res<'w, GridSettings>,
+}
+
+impl<'w> GridQuery<'w> {
+    pub fn entity_at(
+        &self,
+        layer: GridLayer,
+        world_pos: Vec2,
+        alive: Option<&Query<()>>,
+    ) -> Option<Entity> {
+        let coord = self.settings.cell_at(world_pos);
+        self.grid_map
+            .get(layer, coord)
+            .copied()
+            .filter(|entity| is_alive(alive, *entity))
+    }
+
+    /// Entities on the cells overlapping the world space rectangle, see [`cells_overlapping`]
+    pub fn entities_in_area(
+        &self,
+        layer: GridLayer,
+        aabb_min: Vec2,
+        aabb_max: Vec2,
+        alive: Option<&Query<()>>,
+    ) -> Vec<(GridCoord, Entity)> {
+        let rect = cells_overlapping(aabb_min, aabb_max, &self.settings);
+        let Some(grid_map) = self.grid_map.layer(layer) else {
+            return Vec::new();
+        };
+        grid_map
+            .entities_in_rect(rect)
+            .map(|(coord, entity)| (coord, *entity))
+            .filter(|(_, entity)| is_alive(alive, *entity))
+            .collect()
+    }
+
+    /// Entities `radius` cells away from the cell at `world_pos`, see [`GridCoord::ring`]
+    pub fn entities_in_ring(
+        &self,
+        layer: GridLayer,
+        world_pos: Vec2,
+        radius: u32,
+        alive: Option<&Query<()>>,
+    ) -> Vec<(GridCoord, Entity)> {
+        let center = self.settings.cell_at(world_pos);
+        let Some(grid_map) = self.grid_map.layer(layer) else {
+            return Vec::new();
+        };
+        grid_map
+            .entities_in_ring(center, radius)
+            .map(|(coord, entity)| (coord, *entity))
+            .filter(|(_, entity)| is_alive(alive, *entity))
+            .collect()
+    }
+}
+
+fn is_alive(alive: Option<&Query<()>>, entity: Entity) -> bool {
+    alive.is_none_or(|alive| alive.contains(entity))
+}
+
+/// Sends a [`GridMapChanged`] for every change logged since the last frame
+pub fn grid_map_changed_system(
+    mut grid_map: ResMut<LayeredGridMap<Entity>>,
+    mut changed: EventWriter<GridMapChanged>,
+) {
+    if !grid_map.has_changes() {
+        return;
+    }
+    // Draining the log is not a change of the map itself
+    let changes = grid_map.bypass_change_detection().drain_changes();
+    changed.send_batch(
+        changes
+            .into_iter()
+            .map(|(layer, coord, change)| GridMapChanged {
+                layer,
+                coord,
+                change,
+            }),
+    );
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Bits N = 1, E = 2, S = 4, W = 8
+    #[default]
+    Four,
+    /// Bits NW = 1, N = 2, NE = 4, W = 8, E = 16, SW = 32, S = 64, SE = 128
+    Eight,
+}
+
+/// Which neighbors of `coord` are of the same kind, as a bitmask laid out per [`Connectivity`]
+///
+/// North is +y. Corners are reported as is, see [`blob47_index`] to drop the ones
+/// that do not change the tile.
+pub fn autotile_mask(
+    coord: GridCoord,
+    is_same: impl Fn(GridCoord) -> bool,
+    connectivity: Connectivity,
+) -> u8 {
+    let offsets: &[(i32, i32)] = match connectivity {
+        Connectivity::Four => &[(0, 1), (1, 0), (0, -1), (-1, 0)],
+        Connectivity::Eight => &[
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+            (-1, 0),
+            (1, 0),
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+        ],
+    };
+    offsets
+        .iter()
+        .enumerate()
+        .filter(|(_, (dx, dy))| is_same(coord.offset(*dx, *dy)))
+        .fold(0, |mask, (bit, _)| mask | 1 << bit)
+}
+
+/// Clears corner bits of an 8-connected mask whose two adjacent edges are not both set
+const fn blob_reduce(mask: u8) -> u8 {
+    const CORNERS: [(u8, u8, u8); 4] = [(1, 2, 8), (4, 2, 16), (32, 64, 8), (128, 64, 16)];
+    let mut reduced = mask;
+    let mut i = 0;
+    while i < CORNERS.len() {
+        let (corner, edge_a, edge_b) = CORNERS[i];
+        if mask & edge_a == 0 || mask & edge_b == 0 {
+            reduced &= !corner;
+        }
+        i += 1;
+    }
+    reduced
+}
+
+/// Raw 8-connected mask to tile index, indices follow the reduced masks in ascending order
+const BLOB47: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let reduced = blob_reduce(mask as u8);
+        // Count the distinct reduced masks below this one
+        let mut index = 0;
+        let mut other = 0;
+        while other < reduced as usize {
+            if blob_reduce(other as u8) == other as u8 {
+                index += 1;
+            }
+            other += 1;
+        }
+        table[mask] = index;
+        mask += 1;
+    }
+    table
+};
+
+/// Standard 47 tile blob set index, 0 for an isolated tile and 46 for a surrounded one
+pub fn blob47_index(mask: u8) -> u8 {
+    BLOB47[mask as usize]
+}
+
+/// Entity stored in the [`LayeredGridMap<Entity>`] whose [`TileVariant`] follows its neighbors
+///
+/// Neighbors count as the same kind when they are on the same layer with the same `group`.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Autotile {
+    pub group: u32,
+    pub connectivity: Connectivity,
+}
+
+/// Mask from [`autotile_mask`], kept up to date for [`Autotile`] entities
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TileVariant(pub u8);
+
+/// Recomputes [`TileVariant`] for changed cells and their neighbors
+pub fn autotile_system(
+    mut commands: Commands,
+    mut changed: EventReader<GridMapChanged>,
+    grid_map: Res<LayeredGridMap<Entity>>,
+    autotiles: Query<&Autotile>,
+    mut variants: Query<&mut TileVariant>,
+) {
+    let mut dirty = HashSet::new();
+    for event in changed.iter() {
+        dirty.insert((event.layer, event.coord));
+        dirty.extend(
+            event
+                .coord
+                .neighbors8()
+                .map(|neighbor| (event.layer, neighbor)),
+        );
+    }
+
+    for (layer, coord) in dirty {
+        let Some(&entity) = grid_map.get(layer, coord) else {
+            continue;
+        };
+        let Ok(autotile) = autotiles.get(entity) else {
+            continue;
+        };
+        let is_same = |neighbor| {
+            grid_map
+                .get(layer, neighbor)
+                .and_then(|neighbor| autotiles.get(*neighbor).ok())
+                .is_some_and(|neighbor| neighbor.group == autotile.group)
+        };
+        let variant = TileVariant(autotile_mask(coord, is_same, autotile.connectivity));
+        match variants.get_mut(entity) {
+            Ok(mut current) => {
+                if *current != variant {
+                    *current = variant;
+                }
+            }
+            Err(_) => {
+                commands.entity(entity).insert(variant);
+            }
+        }
+    }
+}
+
+/// Clears cells whose entity no longer exists
+pub fn grid_map_despawn_system(
+    mut grid_map: ResMut<LayeredGridMap<Entity>>,
+    sub_cell_map: Option<ResMut<SubCellMap<Entity>>>,
+    rect_map: Option<ResMut<RectMap<Entity>>>,
+    entities: &Entities,
+) {
+    if !grid_map.is_empty() {
+        grid_map.retain(|_, _, entity| entities.contains(*entity));
+    }
+    if let Some(mut sub_cell_map) = sub_cell_map.filter(|map| !map.is_empty()) {
+        sub_cell_map.retain(|_, _, entity| entities.contains(*entity));
+    }
+    if let Some(mut rect_map) = rect_map.filter(|map| !map.is_empty()) {
+        rect_map.retain(|_, _, entity| entities.contains(*entity));
+    }
+}
+
+/// Entity kept aligned to the grid whenever its [`Transform`] or [`GridSettings`] change
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct SnapToGrid {
+    pub mode: SnapMode,
+    /// Overrides the z translation when set
+    pub layer_z: Option<f32>,
+    /// Registered in the [`LayeredGridMap<Entity>`] on its [`GridLayer`] once added
+    pub register: bool,
+}
+
+impl SnapToGrid {
+    /// Snapped translation of `translation`
+    pub fn apply(&self, translation: Vec3, settings: &GridSettings) -> Vec3 {
+        snap(translation.truncate(), settings, self.mode)
+            .extend(self.layer_z.unwrap_or(translation.z))
+    }
+}
+
+/// Ids handed out by [`move_to_cell`], starting high above typical hand-picked ids
+#[derive(Resource, Debug)]
+pub struct MoveIds {
+    next: u32,
+}
+
+impl Default for MoveIds {
+    fn default() -> Self {
+        Self { next: 1 << 31 }
+    }
+}
+
+impl MoveIds {
+    pub fn next_id(&mut self) -> u32 {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        id
+    }
+}
+
+/// Animates `entity` from `current` to the [`GridSettings::cell_center`] of `target`, keeping
+/// its z
+///
+/// Replaces the translation [`Animator`] of an entity that is still moving. Returns the
+/// animator id taken from `move_ids`, reported in the
+/// [`AnimationCompleted`](crate::animation::AnimationCompleted) event.
+#[allow(clippy::too_many_arguments)]
+pub fn move_to_cell(
+    commands: &mut Commands,
+    move_ids: &mut MoveIds,
+    entity: Entity,
+    current: Vec3,
+    target: GridCoord,
+    settings: &GridSettings,
+    duration: Duration,
+    curve: AnimationCurve,
+) -> u32 {
+    let id = move_ids.next_id();
+    commands.entity(entity).insert(
+        Animator::new(
+            Animation { duration, curve },
+            Repeat::Once,
+            TranslationLens {
+                start: current,
+                end: settings.cell_center(target).extend(current.z),
+            },
+        )
+        .with_id(id),
+    );
+    id
+}
+
+/// Pauses [`SnapToGrid`] while present, e.g. while dragging an entity around
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct SnapSuspended;
+
+/// Marks a [`SnapToGrid`] entity left in place as its cell was taken, see
+/// [`SnapConflictPolicy::KeepInPlace`]
+///
+/// It is retried when its transform or the [`GridSettings`] change and loses the marker once
+/// it snaps.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct SnapConflicted;
+
+/// Cell a registered [`SnapToGrid`] entity holds in the [`LayeredGridMap<Entity>`] on its
+/// [`GridLayer`], kept by the snapping systems so a moved entity finds its old cell directly
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridCell(pub GridCoord);
+
+/// Whether a translation animator is still moving the entity
+fn animating_translation(
+    animator: Option<&Animator<TranslationLens>>,
+    shake: Option<&Animator<TranslationShakeLens>>,
+    sequence: Option<&SequenceAnimator<TranslationLens>>,
+) -> bool {
+    animator.is_some_and(|animator| !animator.is_completed())
+        || shake.is_some_and(|shake| !shake.is_completed())
+        || sequence.is_some_and(|sequence| !sequence.is_completed())
+}
+
+/// Snaps [`SnapToGrid`] entities whose transform changed,
+/// skipping [`SnapSuspended`] ones and ones with an unfinished translation animator
+///
+/// Registered entities move their [`LayeredGridMap`] cell along, a taken cell is handled by
+/// the [`SnapConflictPolicy`]. Entities that just got their [`SnapToGrid`] are left to
+/// [`snap_added_system`].
+#[allow(clippy::type_complexity)]
+pub fn snap_to_grid_changed_system(
+    mut commands: Commands,
+    grid_settings: Res<GridSettings>,
+    conflict_policy: Option<Res<SnapConflictPolicy>>,
+    mut grid_map: ResMut<LayeredGridMap<Entity>>,
+    mut conflicts: EventWriter<GridPlacementConflict>,
+    mut snapped: Query<
+        (
+            Entity,
+            Ref<SnapToGrid>,
+            &mut Transform,
+            Option<&GridLayer>,
+            Option<&GridCell>,
+            Option<&SnapConflicted>,
+            Option<&Animator<TranslationLens>>,
+            Option<&Animator<TranslationShakeLens>>,
+            Option<&SequenceAnimator<TranslationLens>>,
+        ),
+        (Changed<Transform>, Without<SnapSuspended>),
+    >,
+) {
+    let conflict_policy = conflict_policy.map(|policy| *policy).unwrap_or_default();
+    for (
+        entity,
+        snap_to_grid,
+        mut transform,
+        layer,
+        grid_cell,
+        conflicted,
+        animator,
+        shake,
+        sequence,
+    ) in snapped.iter_mut()
+    {
+        if snap_to_grid.is_added() || animating_translation(animator, shake, sequence) {
+            continue;
+        }
+        let translation = snap_to_grid.apply(transform.translation, &grid_settings);
+        let layer = layer.copied().unwrap_or_default();
+        // Ignored when the map was edited behind the snapping systems' back
+        let registered = grid_cell
+            .map(|cell| cell.0)
+            .filter(|coord| grid_map.get(layer, *coord) == Some(&entity));
+        let coord = grid_settings.cell_at(translation.truncate());
+        if (snap_to_grid.register || registered.is_some()) && registered != Some(coord) {
+            if let Some(registered) = registered {
+                grid_map.remove(layer, registered);
+            }
+            if !register_snapped(
+                &mut commands,
+                &mut grid_map,
+                &mut conflicts,
+                conflict_policy,
+                entity,
+                layer,
+                coord,
+            ) {
+                continue;
+            }
+        }
+        if conflicted.is_some() {
+            commands.entity(entity).remove::<SnapConflicted>();
+        }
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
+
+/// What to do with a [`SnapToGrid`] entity whose new cell is already taken
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SnapConflictPolicy {
+    /// Leave it at its old translation, off the [`LayeredGridMap`], and warn
+    ///
+    /// It gets a [`SnapConflicted`] until it snaps into a free cell.
+    #[default]
+    KeepInPlace,
+    Despawn,
+}
+
+/// Settings changed after being inserted, new entities are snapped by [`snap_added_system`]
+fn grid_settings_changed(grid_settings: Res<GridSettings>) -> bool {
+    grid_settings.is_changed() && !grid_settings.is_added()
+}
+
+/// Sent when a [`SnapToGrid`] entity can not be registered as its cell is taken
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridPlacementConflict {
+    pub entity: Entity,
+    pub layer: GridLayer,
+    pub coord: GridCoord,
+    pub occupant: Entity,
+}
+
+/// Registers `entity` and gives it its [`GridCell`] unless another entity holds the cell, the
+/// conflict is reported and [`SnapConflictPolicy`] applied otherwise
+fn register_snapped(
+    commands: &mut Commands,
+    grid_map: &mut LayeredGridMap<Entity>,
+    conflicts: &mut EventWriter<GridPlacementConflict>,
+    conflict_policy: SnapConflictPolicy,
+    entity: Entity,
+    layer: GridLayer,
+    coord: GridCoord,
+) -> bool {
+    match grid_map.get(layer, coord) {
+        Some(occupant) if *occupant == entity => {
+            commands.entity(entity).insert(GridCell(coord));
+            true
+        }
+        Some(occupant) => {
+            conflicts.send(GridPlacementConflict {
+                entity,
+                layer,
+                coord,
+                occupant: *occupant,
+            });
+            match conflict_policy {
+                SnapConflictPolicy::KeepInPlace => {
+                    warn!("{entity:?} left in place, {coord} is taken by {occupant:?}");
+                    commands
+                        .entity(entity)
+                        .insert(SnapConflicted)
+                        .remove::<GridCell>();
+                }
+                SnapConflictPolicy::Despawn => commands.entity(entity).despawn(),
+            }
+            false
+        }
+        None => {
+            grid_map.insert(layer, coord, entity);
+            commands.entity(entity).insert(GridCell(coord));
+            true
+        }
+    }
+}
+
+/// Snaps every entity that got a [`SnapToGrid`] this frame in one pass and registers the
+/// ones with [`SnapToGrid::register`] set, in spawn order so the earliest one wins a cell
+///
+/// [`SnapSuspended`] and animating entities are registered at their snapped cell but not moved.
+#[allow(clippy::type_complexity)]
+pub fn snap_added_system(
+    mut commands: Commands,
+    grid_settings: Res<GridSettings>,
+    conflict_policy: Option<Res<SnapConflictPolicy>>,
+    mut grid_map: ResMut<LayeredGridMap<Entity>>,
+    mut conflicts: EventWriter<GridPlacementConflict>,
+    mut added: Query<
+        (
+            Entity,
+            &mut Transform,
+            &SnapToGrid,
+            Option<&GridLayer>,
+            Option<&SnapSuspended>,
+            Option<&Animator<TranslationLens>>,
+            Option<&Animator<TranslationShakeLens>>,
+            Option<&SequenceAnimator<TranslationLens>>,
+        ),
+        Added<SnapToGrid>,
+    >,
+) {
+    let conflict_policy = conflict_policy.map(|policy| *policy).unwrap_or_default();
+    let mut added: Vec<_> = added.iter_mut().collect();
+    added.sort_by_key(|(entity, ..)| *entity);
+
+    for (entity, transform, snap_to_grid, layer, suspended, animator, shake, sequence) in
+        added.iter_mut()
+    {
+        let translation = snap_to_grid.apply(transform.translation, &grid_settings);
+        if snap_to_grid.register {
+            let layer = layer.copied().unwrap_or_default();
+            let coord = grid_settings.cell_at(translation.truncate());
+            if !register_snapped(
+                &mut commands,
+                &mut grid_map,
+                &mut conflicts,
+                conflict_policy,
+                *entity,
+                layer,
+                coord,
+            ) {
+                continue;
+            }
+        }
+        if suspended.is_none()
+            && !animating_translation(*animator, *shake, *sequence)
+            && transform.translation != translation
+        {
+            transform.translation = translation;
+        }
+    }
+}
+
+/// Re-snaps every [`SnapToGrid`] entity that is not [`SnapSuspended`] and moves the
+/// [`LayeredGridMap`] cell of registered ones along
+///
+/// Entities are handled in spawn order, so the earliest placed one wins a contested cell.
+/// Entities without a [`GridLayer`] are on [`GridLayer::FLOOR`].
+#[allow(clippy::type_complexity)]
+pub fn snap_to_grid_system(
+    mut commands: Commands,
+    grid_settings: Res<GridSettings>,
+    conflict_policy: Option<Res<SnapConflictPolicy>>,
+    mut grid_map: ResMut<LayeredGridMap<Entity>>,
+    mut conflicts: EventWriter<GridPlacementConflict>,
+    mut snapped: Query<
+        (
+            Entity,
+            &mut Transform,
+            &SnapToGrid,
+            Option<&GridLayer>,
+            Option<&SnapConflicted>,
+        ),
+        Without<SnapSuspended>,
+    >,
+) {
+    let conflict_policy = conflict_policy.map(|policy| *policy).unwrap_or_default();
+    let mut snapped: Vec<_> = snapped
+        .iter_mut()
+        .map(|(entity, transform, snap_to_grid, layer, conflicted)| {
+            let translation = snap_to_grid.apply(transform.translation, &grid_settings);
+            let cell = (
+                layer.copied().unwrap_or_default(),
+                grid_settings.cell_at(translation.truncate()),
+            );
+            let register = (snap_to_grid.register, conflicted.is_some());
+            (entity, transform, register, cell, translation)
+        })
+        .collect();
+    snapped.sort_by_key(|(entity, ..)| *entity);
+    let registered: HashMap<_, _> = grid_map
+        .iter()
+        .filter(|(_, _, entity)| {
+            snapped
+                .binary_search_by_key(*entity, |(snapped, ..)| *snapped)
+                .is_ok()
+        })
+        .map(|(layer, coord, entity)| (*entity, (layer, coord)))
+        .collect();
+
+    // Only cells that actually change are touched, and all of them are vacated
+    // before any is taken so entities can move into each other's old cells
+    for (entity, .., cell, _) in snapped.iter() {
+        if let Some(&(layer, coord)) = registered.get(entity) {
+            if (layer, coord) != *cell {
+                grid_map.remove(layer, coord);
+            }
+        }
+    }
+
+    for (entity, transform, (register, conflicted), (layer, coord), translation) in
+        snapped.iter_mut()
+    {
+        let previous = registered.get(entity).copied();
+        let moves_cell = (*register || previous.is_some()) && previous != Some((*layer, *coord));
+        if moves_cell
+            && !register_snapped(
+                &mut commands,
+                &mut grid_map,
+                &mut conflicts,
+                conflict_policy,
+                *entity,
+                *layer,
+                *coord,
+            )
+        {
+            continue;
+        }
+        if *conflicted {
+            commands.entity(*entity).remove::<SnapConflicted>();
+        }
+        transform.translation = *translation;
+    }
+}
+
+/// Grid line overlay, toggled with G
+#[derive(Resource)]
+pub struct GridDebug {
+    pub show_lines: bool,
+    pub color: Color,
+    /// Cell indices on every visible cell and the cursor cell in the top left corner
+    pub show_labels: bool,
+    /// Visible cells of the [`DenseGridMap<f32>`] resource tinted by value,
+    /// see [`GridHeatmapSettings`]
+    pub show_heatmap: bool,
+}
+
+impl Default for GridDebug {
+    fn default() -> Self {
+        Self {
+            show_lines: false,
+            color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+            show_labels: false,
+            show_heatmap: false,
+        }
+    }
+}
+
+pub fn grid_debug_toggle_system(key: Res<Input<KeyCode>>, mut grid_debug: ResMut<GridDebug>) {
+    if key.just_pressed(KeyCode::G) {
+        grid_debug.show_lines = !grid_debug.show_lines;
+    }
+}
+
+/// Indices of the grid lines inside `[min, max]`, line `i` lies at `i * grid_size`
+fn visible_lines(
+    min: Vec2,
+    max: Vec2,
+    grid_size: f32,
+) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+    let first = (min / grid_size).ceil().as_ivec2();
+    let last = (max / grid_size).floor().as_ivec2();
+    (first.x..=last.x, first.y..=last.y)
+}
+
+/// Cells intersecting the world area shown by `camera`, zoom included
+///
+/// The viewport is the whole `window` unless the camera sets its own.
+/// None before the camera projection is computed.
+pub fn visible_cells(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    window: &Window,
+    settings: &GridSettings,
+) -> Option<GridRect> {
+    let size = match camera.viewport {
+        Some(_) => camera.logical_viewport_size()?,
+        None => Vec2::new(window.width(), window.height()),
+    };
+    let top_left = camera.viewport_to_world_2d(camera_transform, Vec2::ZERO)?;
+    let bottom_right = camera.viewport_to_world_2d(camera_transform, size)?;
+    Some(cells_overlapping(top_left, bottom_right, settings))
+}
+
+/// [`visible_cells`] of the first active 2d camera within [`GridBounds`]
+fn debug_cells(
+    cameras: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    primary_window: &Query<&Window, With<PrimaryWindow>>,
+    grid_settings: &GridSettings,
+    grid_bounds: Option<&GridBounds>,
+) -> Option<GridRect> {
+    let (camera, camera_transform) = cameras.iter().find(|(camera, _)| camera.is_active)?;
+    let window = primary_window.get_single().ok()?;
+    let cells = visible_cells(camera, camera_transform, window, grid_settings)?;
+    match grid_bounds {
+        Some(grid_bounds) => cells.intersection(grid_bounds.rect()),
+        None => Some(cells),
+    }
+}
+
+/// Draws only the lines around the visible cells
+pub fn grid_debug_lines_system(
+    grid_debug: Res<GridDebug>,
+    grid_settings: Res<GridSettings>,
+    grid_bounds: Option<Res<GridBounds>>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut gizmos: Gizmos,
+) {
+    if !grid_debug.show_lines {
+        return;
+    }
+    let Some(cells) = debug_cells(
+        &cameras,
+        &primary_window,
+        &grid_settings,
+        grid_bounds.as_deref(),
+    ) else {
+        return;
+    };
+    let grid_size = grid_settings.size;
+    let min = cells.min.min_corner(grid_size);
+    let max = cells.max.max_corner(grid_size);
+
+    let (columns, rows) = visible_lines(min, max, grid_size);
+    for x in columns {
+        let x = x as f32 * grid_size;
+        gizmos.line_2d(Vec2::new(x, min.y), Vec2::new(x, max.y), grid_debug.color);
+    }
+    for y in rows {
+        let y = y as f32 * grid_size;
+        gizmos.line_2d(Vec2::new(min.x, y), Vec2::new(max.x, y), grid_debug.color);
+    }
+}
+
+/// Pooled cell index label, see [`GridDebug::show_labels`]
+#[derive(Component)]
+pub struct GridLabel;
+
+/// Label showing [`CursorGridCoord`]
+#[derive(Component)]
+pub struct GridCursorLabel;
+
+/// More visible cells than this and only the cursor label is shown
+const MAX_GRID_LABELS: u64 = 1024;
+const GRID_LABEL_Z: f32 = 100.0;
+const GRID_LABEL_FONT_SIZE: f32 = 14.0;
+
+/// World space corners of the camera view
+fn camera_view(camera: &Camera, camera_transform: &GlobalTransform) -> Option<(Vec2, Vec2)> {
+    let size = camera.logical_viewport_size()?;
+    let top_left = camera.viewport_to_world_2d(camera_transform, Vec2::ZERO)?;
+    let bottom_right = camera.viewport_to_world_2d(camera_transform, size)?;
+    Some((top_left.min(bottom_right), top_left.max(bottom_right)))
+}
+
+fn set_label(text: &mut Text, value: String) {
+    // Only touch the text when it differs, changing it triggers a new layout
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+fn grid_label(value: String, translation: Vec3, anchor: Anchor) -> Text2dBundle {
+    Text2dBundle {
+        text: Text::from_section(
+            value,
+            TextStyle {
+                font_size: GRID_LABEL_FONT_SIZE,
+                ..Default::default()
+            },
+        ),
+        text_anchor: anchor,
+        transform: Transform::from_translation(translation),
+        ..Default::default()
+    }
+}
+
+/// Keeps one [`GridLabel`] per visible cell, reusing them as the view moves,
+/// and despawns all labels once [`GridDebug::show_labels`] is off
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn grid_debug_labels_system(
+    mut commands: Commands,
+    grid_debug: Res<GridDebug>,
+    grid_settings: Res<GridSettings>,
+    grid_bounds: Option<Res<GridBounds>>,
+    cursor_grid_coord: Res<CursorGridCoord>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut labels: Query<
+        (Entity, &mut Text, &mut Transform, &mut Visibility),
+        (With<GridLabel>, Without<GridCursorLabel>),
+    >,
+    mut cursor_labels: Query<(Entity, &mut Text, &mut Transform), With<GridCursorLabel>>,
+) {
+    if !grid_debug.show_labels {
+        for (entity, ..) in labels.iter() {
+            commands.entity(entity).despawn();
+        }
+        for (entity, ..) in cursor_labels.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    let Some((view_min, view_max)) = cameras
+        .iter()
+        .find(|(camera, _)| camera.is_active)
+        .and_then(|(camera, camera_transform)| camera_view(camera, camera_transform))
+    else {
+        return;
+    };
+
+    let cursor_text = match cursor_grid_coord.0 {
+        Some(coord) => format!("cursor {coord}"),
+        None => "cursor -".to_string(),
+    };
+    let corner = Vec2::new(view_min.x, view_max.y).extend(GRID_LABEL_Z);
+    match cursor_labels.get_single_mut() {
+        Ok((_, mut text, mut transform)) => {
+            set_label(&mut text, cursor_text);
+            transform.translation = corner;
+        }
+        Err(_) => {
+            commands.spawn((
+                grid_label(cursor_text, corner, Anchor::TopLeft),
+                GridCursorLabel,
+            ));
+        }
+    }
+
+    let cells = debug_cells(
+        &cameras,
+        &primary_window,
+        &grid_settings,
+        grid_bounds.as_deref(),
+    );
+    let mut cells = cells
+        .filter(|cells| cells.area() <= MAX_GRID_LABELS)
+        .into_iter()
+        .flat_map(|cells| cells.iter());
+    for (_, mut text, mut transform, mut visibility) in labels.iter_mut() {
+        match cells.next() {
+            Some(coord) => {
+                set_label(&mut text, coord.to_string());
+                transform.translation = coord.translation_with_z(grid_settings.size, GRID_LABEL_Z);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+    for coord in cells {
+        let translation = coord.translation_with_z(grid_settings.size, GRID_LABEL_Z);
+        commands.spawn((
+            grid_label(coord.to_string(), translation, Anchor::Center),
+            GridLabel,
+        ));
+    }
+}
+
+/// Values mapped onto the ends of [`GridHeatmapSettings::gradient`]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum HeatmapRange {
+    /// Lowest and highest finite value in the map
+    #[default]
+    Auto,
+    Fixed {
+        min: f32,
+        max: f32,
+    },
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct GridHeatmapSettings {
+    /// Evenly spaced color stops from the lowest to the highest value
+    pub gradient: Vec<Color>,
+    pub range: HeatmapRange,
+    pub z: f32,
+}
+
+impl Default for GridHeatmapSettings {
+    fn default() -> Self {
+        Self {
+            gradient: vec![
+                Color::rgba(0.0, 0.0, 1.0, 0.4),
+                Color::rgba(0.0, 1.0, 0.0, 0.4),
+                Color::rgba(1.0, 0.0, 0.0, 0.4),
+            ],
+            range: HeatmapRange::Auto,
+            z: 50.0,
+        }
+    }
+}
+
+/// Pooled heatmap quad, see [`GridDebug::show_heatmap`]
+#[derive(Component)]
+pub struct GridHeatmapCell;
+
+/// More visible cells than this and the heatmap is not drawn
+const MAX_HEATMAP_CELLS: u64 = 4096;
+
+/// Color at `t` in `[0, 1]` along evenly spaced `gradient` stops
+fn gradient_color(gradient: &[Color], t: f32) -> Color {
+    let (Some(first), Some(last)) = (gradient.first(), gradient.last()) else {
+        return Color::NONE;
+    };
+    if gradient.len() == 1 || t <= 0.0 {
+        return *first;
+    }
+    if t >= 1.0 {
+        return *last;
+    }
+    let position = t * (gradient.len() - 1) as f32;
+    let index = position as usize;
+    let s = position - index as f32;
+    let from = Vec4::from(gradient[index].as_rgba_f32());
+    let to = Vec4::from(gradient[index + 1].as_rgba_f32());
+    Color::from(from.lerp(to, s))
+}
+
+/// Keeps one [`GridHeatmapCell`] per visible cell of the [`DenseGridMap<f32>`] resource,
+/// reusing them as the view or the values change, and despawns all of them once
+/// [`GridDebug::show_heatmap`] is off or the values are removed
+///
+/// Cells with a non-finite value are not drawn.
+#[allow(clippy::too_many_arguments)]
+pub fn grid_debug_heatmap_system(
+    mut commands: Commands,
+    grid_debug: Res<GridDebug>,
+    grid_settings: Res<GridSettings>,
+    grid_bounds: Option<Res<GridBounds>>,
+    heatmap_settings: Res<GridHeatmapSettings>,
+    values: Option<Res<DenseGridMap<f32>>>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut heatmap_cells: Query<
+        (Entity, &mut Sprite, &mut Transform, &mut Visibility),
+        With<GridHeatmapCell>,
+    >,
+) {
+    let values = match values {
+        Some(values) if grid_debug.show_heatmap => values,
+        _ => {
+            for (entity, ..) in heatmap_cells.iter() {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+    };
+
+    let (min, max) = match heatmap_settings.range {
+        HeatmapRange::Fixed { min, max } => (min, max),
+        HeatmapRange::Auto => values
+            .as_slice()
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+                (min.min(*value), max.max(*value))
+            }),
+    };
+    let color = |value: f32| {
+        let t = if max > min {
+            (value - min) / (max - min)
+        } else {
+            0.0
+        };
+        gradient_color(&heatmap_settings.gradient, t)
+    };
+
+    let cells = debug_cells(
+        &cameras,
+        &primary_window,
+        &grid_settings,
+        grid_bounds.as_deref(),
+    )
+    .and_then(|cells| cells.intersection(values.rect()))
+    .filter(|cells| cells.area() <= MAX_HEATMAP_CELLS);
+    let mut cells = cells
+        .into_iter()
+        .flat_map(|cells| cells.iter())
+        .filter_map(|coord| {
+            let value = *values.get(coord)?;
+            value.is_finite().then_some((coord, value))
+        });
+    let size = Some(Vec2::splat(grid_settings.size));
+    for (_, mut sprite, mut transform, mut visibility) in heatmap_cells.iter_mut() {
+        match cells.next() {
+            Some((coord, value)) => {
+                sprite.color = color(value);
+                sprite.custom_size = size;
+                transform.translation =
+                    coord.translation_with_z(grid_settings.size, heatmap_settings.z);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+    for (coord, value) in cells {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: color(value),
+                    custom_size: size,
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(
+                    coord.translation_with_z(grid_settings.size, heatmap_settings.z),
+                ),
+                ..Default::default()
+            },
+            GridHeatmapCell,
+        ));
+    }
+}
+
+/// Cell under the viewport position `cursor` as seen through `camera`
+///
+/// None when the camera can not unproject the position, e.g. before its viewport size is known.
+pub fn cursor_grid_coord(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor: Vec2,
+    settings: &GridSettings,
+) -> Option<GridCoord> {
+    camera
+        .viewport_to_world_2d(camera_transform, cursor)
+        .map(|position| settings.cell_at(position))
+}
+
+/// Cell under the cursor, None while the cursor is outside the primary window
+///
+/// Only marked changed when the cell changes.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CursorGridCoord(pub Option<GridCoord>);
+
+/// World position of the cursor, updated along with [`CursorGridCoord`]
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct CursorWorldPosition(pub Option<Vec2>);
+
+/// Unprojects the cursor through the first active 2d camera
+pub fn cursor_grid_coord_system(
+    grid_settings: Res<GridSettings>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut cursor_coord: ResMut<CursorGridCoord>,
+    mut cursor_world_position: ResMut<CursorWorldPosition>,
+) {
+    let camera = cameras.iter().find(|(camera, _)| camera.is_active);
+    let cursor = primary_window
+        .get_single()
+        .ok()
+        .and_then(|primary_window| primary_window.cursor_position());
+    let (position, coord) = match (camera, cursor) {
+        (Some((camera, camera_transform)), Some(cursor)) => (
+            camera.viewport_to_world_2d(camera_transform, cursor),
+            cursor_grid_coord(camera, camera_transform, cursor, &grid_settings),
+        ),
+        _ => (None, None),
+    };
+    if cursor_coord.0 != coord {
+        cursor_coord.0 = coord;
+    }
+    if cursor_world_position.0 != position {
+        cursor_world_position.0 = position;
+    }
+}
+
+/// Entity kept on the cell under the cursor, see [`spawn_grid_cursor`]
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GridCursor {
+    /// None while hidden
+    pub coord: Option<GridCoord>,
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GridCursorStyle {
+    pub visible: bool,
+    pub z: f32,
+    pub color: Color,
+}
+
+impl Default for GridCursorStyle {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            z: 10.0,
+            color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct GridCursorBundle {
+    pub cursor: GridCursor,
+    pub sprite: SpriteBundle,
+}
+
+impl GridCursorBundle {
+    pub fn new(settings: &GridSettings, style: &GridCursorStyle) -> Self {
+        Self {
+            cursor: GridCursor::default(),
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    color: style.color,
+                    custom_size: Some(Vec2::splat(settings.size)),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, style.z),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Unit sprite scaled to its size, e.g. a UI panel, that hides the [`GridCursor`] while
+/// hovered unless [`Visibility::Hidden`]
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct GridCursorBlocker;
+
+pub fn spawn_grid_cursor(
+    commands: &mut Commands,
+    settings: &GridSettings,
+    style: &GridCursorStyle,
+) -> Entity {
+    commands.spawn(GridCursorBundle::new(settings, style)).id()
+}
+
+/// Moves [`GridCursor`]s onto [`CursorGridCoord`], hidden off-window and over a
+/// [`GridCursorBlocker`]
+#[allow(clippy::type_complexity)]
+pub fn grid_cursor_system(
+    grid_settings: Res<GridSettings>,
+    style: Res<GridCursorStyle>,
+    cursor_coord: Res<CursorGridCoord>,
+    cursor_world_position: Res<CursorWorldPosition>,
+    blockers: Query<
+        (&GlobalTransform, Option<&Visibility>),
+        (With<GridCursorBlocker>, Without<GridCursor>),
+    >,
+    mut cursors: Query<(
+        &mut GridCursor,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+) {
+    let blocked = cursor_world_position.0.is_some_and(|position| {
+        blockers.iter().any(|(blocker, visibility)| {
+            if visibility == Some(&Visibility::Hidden) {
+                return false;
+            }
+            let (scale, _, translation) = blocker.to_scale_rotation_translation();
+            let half = scale.truncate().abs() / 2.0;
+            (position - translation.truncate()).abs().cmple(half).all()
+        })
+    });
+    let coord = cursor_coord.0.filter(|_| !blocked);
+    for (mut cursor, mut transform, mut sprite, mut visibility) in cursors.iter_mut() {
+        cursor.set_if_neq(GridCursor { coord });
+        if let Some(coord) = coord {
+            transform.translation = grid_settings.cell_center(coord).extend(style.z);
+        }
+        let size = Some(Vec2::splat(grid_settings.size));
+        if sprite.color != style.color || sprite.custom_size != size {
+            sprite.color = style.color;
+            sprite.custom_size = size;
+        }
+        visibility.set_if_neq(if style.visible && coord.is_some() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+/// Sent when a drag selection ends with the mouse released
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridRegionSelected {
+    pub rect: GridRect,
+}
+
+/// In-progress drag selection, empty while not dragging
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GridSelection {
+    /// Cell the drag started at
+    pub anchor: Option<GridCoord>,
+    /// Last cell the cursor was over while dragging
+    pub current: Option<GridCoord>,
+}
+
+impl GridSelection {
+    pub fn is_active(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    pub fn rect(&self) -> Option<GridRect> {
+        Some(GridRect::from_corners(self.anchor?, self.current?))
+    }
+
+    pub fn cancel(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GridSelectionSettings {
+    /// Key that has to be held when the left button is pressed to start a selection,
+    /// None starts one on every left click
+    pub modifier: Option<KeyCode>,
+    pub color: Color,
+}
+
+impl Default for GridSelectionSettings {
+    fn default() -> Self {
+        Self {
+            modifier: Some(KeyCode::ShiftLeft),
+            color: Color::rgba(1.0, 1.0, 0.0, 0.8),
+        }
+    }
+}
+
+/// Drags a [`GridSelection`] with the left button, cancelled by Escape or the right button
+pub fn grid_selection_system(
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    cursor_grid_coord: Res<CursorGridCoord>,
+    settings: Res<GridSelectionSettings>,
+    mut selection: ResMut<GridSelection>,
+    mut selected: EventWriter<GridRegionSelected>,
+) {
+    if !selection.is_active() {
+        let modifier_held = settings.modifier.is_none_or(|key| keys.pressed(key));
+        if mouse.just_pressed(MouseButton::Left) && modifier_held {
+            if let Some(coord) = cursor_grid_coord.0 {
+                selection.anchor = Some(coord);
+                selection.current = Some(coord);
+            }
+        }
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Escape) || mouse.just_pressed(MouseButton::Right) {
+        selection.cancel();
+        return;
+    }
+    if let Some(coord) = cursor_grid_coord.0 {
+        if selection.current != Some(coord) {
+            selection.current = Some(coord);
+        }
+    }
+    if mouse.just_released(MouseButton::Left) {
+        if let Some(rect) = selection.rect() {
+            selected.send(GridRegionSelected { rect });
+        }
+        selection.cancel();
+    }
+}
+
+pub fn grid_selection_highlight_system(
+    mut gizmos: Gizmos,
+    grid_settings: Res<GridSettings>,
+    settings: Res<GridSelectionSettings>,
+    selection: Res<GridSelection>,
+) {
+    let Some(rect) = selection.rect() else {
+        return;
+    };
+    let grid_size = grid_settings.size;
+    let min = rect.min.min_corner(grid_size);
+    let max = rect.max.max_corner(grid_size);
+    gizmos.rect_2d((min + max) / 2.0, 0.0, max - min, settings.color);
+}
+
+pub struct GridPlugin {
+    /// See [`GridSettings::size`]
+    pub size: f32,
+    /// See [`GridSettings::snap_mode`]
+    pub snap_mode: SnapMode,
+    /// See [`GridSettings::topology`]
+    pub topology: GridTopology,
+    /// See [`GridSettings::snap_divisions`]
+    pub snap_divisions: u32,
+}
+
+impl Default for GridPlugin {
+    fn default() -> Self {
+        Self {
+            size: 100.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        }
+    }
+}
+
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GridSettings {
+            size: self.size,
+            snap_mode: self.snap_mode,
+            topology: self.topology,
+            snap_divisions: self.snap_divisions,
+        })
+        .init_resource::<CursorGridCoord>()
+        .init_resource::<CursorWorldPosition>()
+        .insert_resource(LayeredGridMap::<Entity>::tracked())
+        .init_resource::<SubCellMap<Entity>>()
+        .init_resource::<RectMap<Entity>>()
+        .init_resource::<GridDebug>()
+        .init_resource::<GridSelection>()
+        .init_resource::<GridSelectionSettings>()
+        .init_resource::<GridCursorStyle>()
+        .init_resource::<GridHeatmapSettings>()
+        .init_resource::<MoveIds>()
+        .add_event::<GridRegionSelected>()
+        .add_event::<GridMapChanged>()
+        .add_event::<GridPlacementConflict>()
+        .add_systems(
+            PreUpdate,
+            (
+                cursor_grid_coord_system,
+                // Runs before Update so placement can tell a drag is starting this frame
+                grid_selection_system
+                    .after(InputSystem)
+                    .run_if(resource_exists::<Input<KeyCode>>())
+                    .run_if(resource_exists::<Input<MouseButton>>()),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                grid_debug_toggle_system.run_if(resource_exists::<Input<KeyCode>>()),
+                grid_debug_lines_system.run_if(|grid_debug: Res<GridDebug>| grid_debug.show_lines),
+                grid_debug_labels_system,
+                grid_debug_heatmap_system,
+                grid_cursor_system,
+                grid_selection_highlight_system
+                    .run_if(resource_exists::<GizmoConfig>())
+                    .run_if(|selection: Res<GridSelection>| selection.is_active()),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                (
+                    grid_map_despawn_system,
+                    snap_to_grid_system.run_if(grid_settings_changed),
+                    snap_added_system,
+                    grid_map_changed_system,
+                    autotile_system,
+                )
+                    .chain(),
+                snap_to_grid_changed_system
+                    .after(snap_added_system)
+                    .before(TransformSystem::TransformPropagate),
+            ),
+        );
+    }
+}
+
+pub trait AsGridCoord {
+    /// Points on a cell edge belong to the cell above or to the right of it
+    fn as_grid_coord(&self, grid_size: f32) -> GridCoord;
+}
+impl AsGridCoord for Vec2 {
+    fn as_grid_coord(&self, grid_size: f32) -> GridCoord {
+        GridCoord((*self / grid_size).floor().as_ivec2())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use bevy::prelude::Vec2;
+    use std::{
+        collections::{BTreeMap, HashSet},
+        time::Duration,
+    };
 
-    use super::AsGridCoord;
+    use bevy::{
+        ecs::system::{CommandQueue, SystemState},
+        prelude::{
+            App, Color, Commands, Entity, Events, IVec2, Input, KeyCode, MouseButton, Query, Rect,
+            Transform, UVec2, Update, Vec2, Vec3, World,
+        },
+    };
+
+    use super::{
+        autotile_mask, blob47_index, cells_overlapping, find_nearest, first_blocked_cell,
+        flood_fill, flood_fill_in_rect, gradient_color, grid_selection_system, move_to_cell,
+        pathfind, pathfind_in, raycast_cells, snap, snap_with_divisions, visible_lines,
+        AsGridCoord, Autotile, Connectivity, CursorGridCoord, DenseGridMap, GridBounds, GridCell,
+        GridCoord, GridLayer, GridMap, GridMapChange, GridMapChanged, GridMetric, GridOverride,
+        GridPlacementConflict, GridPlugin, GridQuery, GridRect, GridRegionSelected, GridSelection,
+        GridSelectionSettings, GridSettings, GridTopology, LayeredGridMap, MoveIds,
+        ParseGridCoordError, RectMap, SnapConflictPolicy, SnapConflicted, SnapMode, SnapSuspended,
+        SnapToGrid, SubCell, SubCellMap, TileVariant,
+    };
+    use crate::{
+        animation::{Animation, AnimationCurve, Animator, Repeat, TranslationLens},
+        animation_test_harness::AnimationHarness,
+    };
 
     struct TestPair {
         pub translation: Vec2,
@@ -67,7 +2831,7 @@ mod tests {
 
     #[test]
     fn find_grid_coord() {
-        let grid_size = 10;
+        let grid_size = 10.0;
 
         let tests = [
             TestPair {
@@ -120,4 +2884,1860 @@ mod tests {
             assert_eq!(grid_translation_found, grid_translation);
         }
     }
+
+    #[test]
+    fn cell_boundaries() {
+        let grid_size = 10.0;
+
+        let tests = [
+            (Vec2::new(10.0, 0.0), IVec2::new(1, 0), Vec2::new(15.0, 5.0)),
+            (Vec2::new(9.999, 0.0), IVec2::new(0, 0), Vec2::new(5.0, 5.0)),
+            (
+                Vec2::new(-10.0, 0.0),
+                IVec2::new(-1, 0),
+                Vec2::new(-5.0, 5.0),
+            ),
+            (
+                Vec2::new(-10.001, 0.0),
+                IVec2::new(-2, 0),
+                Vec2::new(-15.0, 5.0),
+            ),
+            (
+                Vec2::new(0.0, -20.0),
+                IVec2::new(0, -2),
+                Vec2::new(5.0, -15.0),
+            ),
+            (Vec2::new(-0.0, -0.0), IVec2::new(0, 0), Vec2::new(5.0, 5.0)),
+        ];
+
+        for (translation, coord, grid_translation) in tests {
+            let grid_coord = translation.as_grid_coord(grid_size);
+            assert_eq!(grid_coord, GridCoord(coord), "{translation}");
+            assert_eq!(grid_coord.translation(grid_size), grid_translation);
+        }
+    }
+
+    #[test]
+    fn neighbors_across_axes() {
+        let grid_size = 10.0;
+        let cell = Vec2::new(-0.5, 0.5).as_grid_coord(grid_size);
+        assert_eq!(cell, GridCoord(IVec2::new(-1, 0)));
+
+        // Neighbors are the cells one grid size away in world space
+        let center = cell.translation(grid_size);
+        let expected = [(1, 0), (0, 1), (-1, 0), (0, -1)].map(|(dx, dy)| {
+            (center + Vec2::new(dx as f32, dy as f32) * grid_size).as_grid_coord(grid_size)
+        });
+        assert_eq!(cell.neighbors4(), expected);
+        assert_eq!(
+            cell.neighbors4(),
+            [(0, 0), (-1, 1), (-2, 0), (-1, -1)].map(|(x, y)| GridCoord(IVec2::new(x, y)))
+        );
+
+        let neighbors = GridCoord(IVec2::ZERO).neighbors8();
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&GridCoord(IVec2::new(-1, -1))));
+        assert!(!neighbors.contains(&GridCoord(IVec2::ZERO)));
+        assert_eq!(
+            GridCoord(IVec2::ZERO).offset(-1, 2),
+            GridCoord(IVec2::new(-1, 2))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for grid_size in [1.0, 10.0, 32.0, 37.5] {
+            for (x, y) in [(0, 0), (3, 7), (-1, 0), (0, -1), (-4, -9), (12, -3)] {
+                let coord = GridCoord::new(x, y);
+                let translation = coord.translation(grid_size);
+                assert_eq!(translation.as_grid_coord(grid_size), coord);
+            }
+        }
+
+        let coord = GridCoord::from(IVec2::new(-2, 5));
+        assert_eq!(coord + IVec2::new(3, -5), GridCoord::new(1, 0));
+        assert_eq!(coord - IVec2::ONE, GridCoord::new(-3, 4));
+        assert_eq!(coord.to_ivec2(), IVec2::new(-2, 5));
+    }
+
+    #[test]
+    fn grid_map() {
+        let mut map = GridMap::default();
+        assert!(map.is_empty());
+
+        assert_eq!(map.insert(GridCoord::new(-1, -1), 'a'), None);
+        assert_eq!(map.insert(GridCoord::new(0, 0), 'b'), None);
+        // Overwrite returns the previous value
+        assert_eq!(map.insert(GridCoord::new(-1, -1), 'c'), Some('a'));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(GridCoord::new(-1, -1)), Some(&'c'));
+
+        // The cells on either side of zero are distinct
+        let grid_size = 10.0;
+        assert!(map.contains(Vec2::new(-0.5, -0.5).as_grid_coord(grid_size)));
+        assert!(map.contains(Vec2::new(0.5, 0.5).as_grid_coord(grid_size)));
+        assert!(!map.contains(Vec2::new(-0.5, 0.5).as_grid_coord(grid_size)));
+
+        map.insert(GridCoord::new(3, -2), 'd');
+        let mut found: Vec<_> = map
+            .entities_in_rect(GridRect::from_corners(
+                GridCoord::new(0, 0),
+                GridCoord::new(-2, -2),
+            ))
+            .collect();
+        found.sort_by_key(|(coord, _)| (coord.0.x, coord.0.y));
+        assert_eq!(
+            found,
+            [(GridCoord::new(-1, -1), &'c'), (GridCoord::new(0, 0), &'b')]
+        );
+
+        assert_eq!(map.remove(GridCoord::new(-1, -1)), Some('c'));
+        assert_eq!(map.remove(GridCoord::new(-1, -1)), None);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn lines_within_window() {
+        let (columns, rows) =
+            visible_lines(Vec2::new(-750.0, -375.0), Vec2::new(750.0, 375.0), 100.0);
+        assert_eq!(columns, -7..=7);
+        assert_eq!(rows, -3..=3);
+
+        // Lines exactly on the edge are drawn
+        let (columns, _) = visible_lines(Vec2::new(-200.0, 0.0), Vec2::new(200.0, 0.0), 100.0);
+        assert_eq!(columns, -2..=2);
+    }
+
+    #[test]
+    fn bounds_inclusive() {
+        let bounds = GridBounds {
+            min: GridCoord::new(-3, -2),
+            max: GridCoord::new(4, 0),
+        };
+
+        for coord in [(-3, -2), (4, 0), (-3, 0), (4, -2), (0, -1)] {
+            let coord = GridCoord::new(coord.0, coord.1);
+            assert!(bounds.contains(coord), "{coord:?}");
+            assert_eq!(bounds.clamp(coord), coord);
+        }
+        for coord in [(-4, -2), (5, 0), (0, 1), (0, -3)] {
+            assert!(
+                !bounds.contains(GridCoord::new(coord.0, coord.1)),
+                "{coord:?}"
+            );
+        }
+
+        assert_eq!(bounds.clamp(GridCoord::new(-10, 7)), GridCoord::new(-3, 0));
+        assert_eq!(bounds.clamp(GridCoord::new(9, -9)), GridCoord::new(4, -2));
+    }
+
+    #[test]
+    fn rect_straddling_origin() {
+        let rect = GridRect::from_corners(GridCoord::new(2, -1), GridCoord::new(-2, 1));
+        assert_eq!(rect.min, GridCoord::new(-2, -1));
+        assert_eq!(rect.max, GridCoord::new(2, 1));
+        assert_eq!((rect.width(), rect.height(), rect.area()), (5, 3, 15));
+
+        let cells: Vec<_> = rect.iter().collect();
+        let mut expected = Vec::new();
+        for y in -1..=1 {
+            for x in -2..=2 {
+                expected.push(GridCoord::new(x, y));
+            }
+        }
+        assert_eq!(cells, expected);
+
+        for x in -4..=4 {
+            for y in -3..=3 {
+                let inside = (-2..=2).contains(&x) && (-1..=1).contains(&y);
+                assert_eq!(rect.contains(GridCoord::new(x, y)), inside, "({x}, {y})");
+            }
+        }
+
+        let single = GridRect::from_corners(GridCoord::new(-1, -1), GridCoord::new(-1, -1));
+        assert_eq!(single.iter().collect::<Vec<_>>(), [GridCoord::new(-1, -1)]);
+        assert_eq!(single.area(), 1);
+    }
+
+    #[test]
+    fn rect_intersection() {
+        let a = GridRect::from_corners(GridCoord::new(-3, -3), GridCoord::new(0, 0));
+        let b = GridRect::from_corners(GridCoord::new(-1, -1), GridCoord::new(2, 2));
+        assert_eq!(
+            a.intersection(b),
+            Some(GridRect::from_corners(
+                GridCoord::new(-1, -1),
+                GridCoord::new(0, 0)
+            ))
+        );
+        assert_eq!(a.intersection(b), b.intersection(a));
+
+        // Sharing only an edge row still overlaps, one past does not
+        let c = GridRect::from_corners(GridCoord::new(0, -3), GridCoord::new(3, 3));
+        assert_eq!(a.intersection(c).map(|r| r.area()), Some(4));
+        let d = GridRect::from_corners(GridCoord::new(1, -3), GridCoord::new(3, 3));
+        assert_eq!(a.intersection(d), None);
+    }
+
+    #[test]
+    fn overlapping_cells() {
+        let settings = GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        };
+        let overlapping = |center: Vec2, size: f32| {
+            let half = Vec2::splat(size / 2.0);
+            cells_overlapping(center - half, center + half, &settings)
+        };
+        let rect = |min: (i32, i32), max: (i32, i32)| GridRect {
+            min: GridCoord::new(min.0, min.1),
+            max: GridCoord::new(max.0, max.1),
+        };
+
+        // Smaller than a cell
+        assert_eq!(
+            overlapping(Vec2::new(-5.0, 5.0), 4.0),
+            rect((-1, 0), (-1, 0))
+        );
+        assert_eq!(
+            overlapping(Vec2::new(0.0, 0.0), 4.0),
+            rect((-1, -1), (0, 0))
+        );
+        // Exactly one cell, edges touch the neighbors only
+        for center in [Vec2::new(5.0, 5.0), Vec2::new(-15.0, -5.0)] {
+            let cell = center.as_grid_coord(settings.size);
+            assert_eq!(
+                overlapping(center, 10.0),
+                GridRect {
+                    min: cell,
+                    max: cell
+                }
+            );
+        }
+        // Larger than a cell
+        assert_eq!(
+            overlapping(Vec2::new(5.0, 5.0), 12.0),
+            rect((-1, -1), (1, 1))
+        );
+        assert_eq!(
+            overlapping(Vec2::new(0.0, 0.0), 20.0),
+            rect((-1, -1), (0, 0))
+        );
+        // Corners in any order
+        assert_eq!(
+            cells_overlapping(Vec2::new(25.0, -1.0), Vec2::new(-1.0, 25.0), &settings),
+            rect((-1, -1), (2, 2))
+        );
+    }
+
+    #[test]
+    fn pathfinding() {
+        let open = |_| false;
+
+        // Straight corridor
+        let path = pathfind(GridCoord::new(0, 0), GridCoord::new(3, 0), open, false, 100).unwrap();
+        assert_eq!(
+            path,
+            (0..=3).map(|x| GridCoord::new(x, 0)).collect::<Vec<_>>()
+        );
+
+        // Detour around a wall at x = 0 from y = -2 to 2
+        let wall = |coord: GridCoord| coord.0.x == 0 && (-2..=2).contains(&coord.0.y);
+        let (start, goal) = (GridCoord::new(-2, 0), GridCoord::new(2, 0));
+        let path = pathfind(start, goal, wall, false, 1000).unwrap();
+        assert_eq!((path[0], *path.last().unwrap()), (start, goal));
+        assert_eq!(path.len(), 11);
+        assert!(path.iter().all(|coord| !wall(*coord)));
+        assert!(path
+            .windows(2)
+            .all(|pair| pair[0].neighbors4().contains(&pair[1])));
+
+        // Through negative cells with diagonals
+        let path = pathfind(
+            GridCoord::new(-3, -3),
+            GridCoord::new(2, 1),
+            open,
+            true,
+            1000,
+        )
+        .unwrap();
+        assert_eq!(path.len(), 6);
+        assert!(path
+            .windows(2)
+            .all(|pair| pair[0].neighbors8().contains(&pair[1])));
+
+        // Goal enclosed
+        let ring = |coord: GridCoord| {
+            coord != GridCoord::new(5, 5) && (coord.0 - IVec2::new(5, 5)).abs().max_element() == 1
+        };
+        assert_eq!(
+            pathfind(
+                GridCoord::new(0, 0),
+                GridCoord::new(5, 5),
+                ring,
+                true,
+                10_000
+            ),
+            None
+        );
+        // Out of budget
+        assert_eq!(
+            pathfind(GridCoord::new(0, 0), GridCoord::new(50, 0), open, false, 10),
+            None
+        );
+        // Same cell
+        assert_eq!(
+            pathfind(GridCoord::new(-1, 4), GridCoord::new(-1, 4), open, false, 0),
+            Some(vec![GridCoord::new(-1, 4)])
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let a = GridCoord::new(-2, 1);
+        assert_eq!(a.line_to(a), [a]);
+        assert_eq!(a.supercover_line_to(a), [a]);
+
+        assert_eq!(
+            GridCoord::new(0, 0).line_to(GridCoord::new(3, 1)),
+            [(0, 0), (1, 0), (2, 1), (3, 1)].map(|(x, y)| GridCoord::new(x, y))
+        );
+        assert_eq!(
+            GridCoord::new(0, 0).supercover_line_to(GridCoord::new(2, 2)),
+            [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)].map(|(x, y)| GridCoord::new(x, y))
+        );
+
+        let cells: Vec<_> = GridRect::from_corners(GridCoord::new(-4, -4), GridCoord::new(4, 4))
+            .iter()
+            .collect();
+        for from in &cells {
+            for to in &cells {
+                // Steps are 8-adjacent for Bresenham, 4-adjacent for supercover
+                let chebyshev = |d: IVec2| d.abs().max_element();
+                let manhattan = |d: IVec2| d.abs().x + d.abs().y;
+                for (line, distance) in [
+                    (
+                        GridCoord::line_to as fn(&GridCoord, GridCoord) -> Vec<GridCoord>,
+                        &chebyshev as &dyn Fn(IVec2) -> i32,
+                    ),
+                    (GridCoord::supercover_line_to, &manhattan),
+                ] {
+                    let forward = line(from, *to);
+                    let mut backward = line(to, *from);
+                    backward.reverse();
+                    assert_eq!(forward, backward, "{from:?} -> {to:?}");
+                    assert_eq!((forward[0], *forward.last().unwrap()), (*from, *to));
+                    assert!(forward
+                        .windows(2)
+                        .all(|pair| distance(pair[1].0 - pair[0].0) == 1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn snap_modes() {
+        let settings = GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        };
+        let snapped = |x: f32, y: f32, mode| snap(Vec2::new(x, y), &settings, mode);
+
+        // Cell spanning [-10, 0) on both axes
+        let near_corners = [
+            ((-9.0, -9.0), (-10.0, -10.0)),
+            ((-1.0, -9.0), (0.0, -10.0)),
+            ((-9.0, -1.0), (-10.0, 0.0)),
+            ((-1.0, -1.0), (0.0, 0.0)),
+        ];
+        for ((x, y), corner) in near_corners {
+            assert_eq!(snapped(x, y, SnapMode::Intersection), corner.into());
+            assert_eq!(snapped(x, y, SnapMode::Corner), Vec2::new(-10.0, -10.0));
+            assert_eq!(snapped(x, y, SnapMode::Center), Vec2::new(-5.0, -5.0));
+        }
+
+        // Exactly at the center
+        assert_eq!(snapped(-5.0, -5.0, SnapMode::Center), Vec2::new(-5.0, -5.0));
+        assert_eq!(
+            snapped(-5.0, -5.0, SnapMode::Corner),
+            Vec2::new(-10.0, -10.0)
+        );
+        assert_eq!(
+            snapped(-5.0, -5.0, SnapMode::Intersection),
+            Vec2::new(0.0, 0.0)
+        );
+        assert_eq!(
+            snapped(15.0, 25.0, SnapMode::Intersection),
+            Vec2::new(20.0, 30.0)
+        );
+    }
+
+    #[test]
+    fn fractional_size() {
+        let grid_size = 37.5;
+        let tests = [
+            (
+                Vec2::new(0.0, 0.0),
+                GridCoord::new(0, 0),
+                Vec2::new(18.75, 18.75),
+            ),
+            (
+                Vec2::new(37.4, -0.1),
+                GridCoord::new(0, -1),
+                Vec2::new(18.75, -18.75),
+            ),
+            // On the edge, belongs to the cell to the right and above
+            (
+                Vec2::new(75.0, -37.5),
+                GridCoord::new(2, -1),
+                Vec2::new(93.75, -18.75),
+            ),
+            (
+                Vec2::new(-75.0, 112.5),
+                GridCoord::new(-2, 3),
+                Vec2::new(-56.25, 131.25),
+            ),
+        ];
+        for (translation, coord, center) in tests {
+            let grid_coord = translation.as_grid_coord(grid_size);
+            assert_eq!(grid_coord, coord, "{translation}");
+            assert_eq!(grid_coord.translation(grid_size), center);
+        }
+
+        let settings = GridSettings::from_u32(10);
+        assert_eq!(settings.size, 10.0);
+        assert_eq!(settings.snap_mode, SnapMode::Center);
+    }
+
+    #[test]
+    fn ordering_and_text() {
+        let mut map = BTreeMap::new();
+        for (x, y) in [(1, 0), (-1, 1), (0, 0), (-2, 0), (0, -1)] {
+            map.insert(GridCoord::new(x, y), ());
+        }
+        let ordered: Vec<_> = map.into_keys().collect();
+        assert_eq!(
+            ordered,
+            [(0, -1), (-2, 0), (0, 0), (1, 0), (-1, 1)].map(|(x, y)| GridCoord::new(x, y))
+        );
+
+        let coord = GridCoord::new(-3, 12);
+        assert_eq!(coord.to_string(), "(-3,12)");
+        assert_eq!(coord.to_string().parse(), Ok(coord));
+        assert_eq!(" ( -3 , 12 ) ".parse(), Ok(coord));
+        for invalid in ["", "(1,2", "1,2", "(1)", "(1,2,3)", "(a,2)"] {
+            assert_eq!(
+                invalid.parse::<GridCoord>(),
+                Err(ParseGridCoordError),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        for coord in [GridCoord::new(0, 0), GridCoord::new(-7, 42)] {
+            let text = ron::to_string(&coord).unwrap();
+            assert_eq!(ron::from_str::<GridCoord>(&text).unwrap(), coord);
+        }
+    }
+
+    #[test]
+    fn distances() {
+        let (a, b) = (GridCoord::new(-1, 0), GridCoord::new(1, 0));
+        assert_eq!(a.manhattan_distance(b), 2);
+        assert_eq!(a.chebyshev_distance(b), 2);
+        assert_eq!(a.euclidean_distance_sq(b), 4);
+
+        let (a, b) = (GridCoord::new(-2, -3), GridCoord::new(1, 1));
+        assert_eq!(a.manhattan_distance(b), 7);
+        assert_eq!(a.chebyshev_distance(b), 4);
+        assert_eq!(a.euclidean_distance_sq(b), 25);
+        assert!(a.within_range(b, 5, GridMetric::Euclidean));
+        assert!(!a.within_range(b, 4, GridMetric::Euclidean));
+        assert!(a.within_range(b, 4, GridMetric::Chebyshev));
+        assert!(!a.within_range(b, 6, GridMetric::Manhattan));
+
+        let cells: Vec<_> = GridRect::from_corners(GridCoord::new(-3, -3), GridCoord::new(3, 3))
+            .iter()
+            .collect();
+        for a in &cells {
+            for b in &cells {
+                for distance in [
+                    GridCoord::manhattan_distance,
+                    GridCoord::chebyshev_distance,
+                    GridCoord::euclidean_distance_sq,
+                ] {
+                    assert_eq!(distance(a, *b), distance(b, *a));
+                    assert_eq!(distance(a, *b) == 0, a == b);
+                }
+            }
+        }
+
+        // Opposite ends of the coordinate range
+        let (a, b) = (
+            GridCoord::new(i32::MIN, i32::MIN),
+            GridCoord::new(i32::MAX, i32::MAX),
+        );
+        let span = u64::from(u32::MAX);
+        assert_eq!(a.manhattan_distance(b), 2 * span);
+        assert_eq!(a.chebyshev_distance(b), span);
+        assert_eq!(a.euclidean_distance_sq(b), u64::MAX);
+        assert!(!a.within_range(b, u32::MAX, GridMetric::Euclidean));
+        assert!(a.within_range(b, u32::MAX, GridMetric::Chebyshev));
+        let (a, b) = (GridCoord::new(0, 0), GridCoord::new(50_000, 0));
+        assert_eq!(a.euclidean_distance_sq(b), 2_500_000_000);
+        assert!(a.within_range(b, 70_000, GridMetric::Euclidean));
+        assert!(!a.within_range(b, 49_999, GridMetric::Euclidean));
+    }
+
+    #[test]
+    fn flood_fill_regions() {
+        // 5x5 room with walls on its border and a gap-free interior
+        let room = GridRect::from_corners(GridCoord::new(-2, -2), GridCoord::new(2, 2));
+        let mut walls = GridMap::default();
+        for coord in room.iter() {
+            if coord.0.x.abs() == 2 || coord.0.y.abs() == 2 {
+                walls.insert(coord, ());
+            }
+        }
+        let filled = flood_fill(GridCoord::new(0, 0), |c| !walls.contains(c), 100);
+        assert_eq!(filled.len(), 9);
+        assert!(filled
+            .iter()
+            .all(|c| c.chebyshev_distance(GridCoord::new(0, 0)) <= 1));
+
+        // Open on one side, the budget stops the fill
+        walls.remove(GridCoord::new(2, 0));
+        let filled = flood_fill(GridCoord::new(0, 0), |c| !walls.contains(c), 50);
+        assert_eq!(filled.len(), 50);
+
+        // The rect closes the gap again
+        let filled = flood_fill_in_rect(
+            GridCoord::new(0, 0),
+            GridRect::from_corners(GridCoord::new(-1, -1), GridCoord::new(2, 1)),
+            |c| !walls.contains(c),
+            100,
+        );
+        assert_eq!(filled.len(), 10);
+        assert!(filled.contains(&GridCoord::new(2, 0)));
+
+        assert!(flood_fill(GridCoord::new(2, 2), |c| !walls.contains(c), 100).is_empty());
+        assert!(flood_fill(GridCoord::new(0, 0), |_| true, 0).is_empty());
+    }
+
+    #[test]
+    fn resnap_on_size_change() {
+        let mut app = App::new();
+        app.add_plugins(GridPlugin {
+            size: 50.0,
+            ..Default::default()
+        });
+        let place = |app: &mut App, coord: GridCoord| {
+            app.world
+                .spawn((
+                    Transform::from_translation(coord.translation_with_z(50.0, 1.0)),
+                    SnapToGrid {
+                        register: true,
+                        ..Default::default()
+                    },
+                ))
+                .id()
+        };
+        let a = place(&mut app, GridCoord::new(1, 1));
+        let b = place(&mut app, GridCoord::new(0, 1));
+        let c = place(&mut app, GridCoord::new(-1, 2));
+        // Dragged around, the settings do not pull it back into the grid
+        let dragged = app
+            .world
+            .spawn((
+                Transform::from_xyz(130.0, -20.0, 1.0),
+                SnapToGrid::default(),
+                SnapSuspended,
+            ))
+            .id();
+        app.update();
+
+        let translation =
+            |app: &App, entity| app.world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(&app, a), Vec3::new(75.0, 75.0, 1.0));
+
+        app.world.resource_mut::<GridSettings>().size = 100.0;
+        app.update();
+        // a and b now share cell (0, 0), b stays where it was
+        assert_eq!(translation(&app, a), Vec3::new(50.0, 50.0, 1.0));
+        assert_eq!(translation(&app, b), Vec3::new(25.0, 75.0, 1.0));
+        assert_eq!(translation(&app, c), Vec3::new(-50.0, 150.0, 1.0));
+        assert_eq!(translation(&app, dragged), Vec3::new(130.0, -20.0, 1.0));
+        assert!(app.world.get::<SnapConflicted>(b).is_some());
+        let grid_map = app.world.resource::<LayeredGridMap<Entity>>();
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(0, 0)),
+            Some(&a)
+        );
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(-1, 1)),
+            Some(&c)
+        );
+        assert_eq!(grid_map.len(), 2);
+
+        // b is retried and gets its old cell back
+        app.world.resource_mut::<GridSettings>().size = 50.0;
+        app.insert_resource(SnapConflictPolicy::Despawn);
+        app.update();
+        assert_eq!(translation(&app, a), Vec3::new(75.0, 75.0, 1.0));
+        assert_eq!(translation(&app, b), Vec3::new(25.0, 75.0, 1.0));
+        assert_eq!(translation(&app, c), Vec3::new(-25.0, 175.0, 1.0));
+        assert!(app.world.get::<SnapConflicted>(b).is_none());
+        assert_eq!(
+            app.world
+                .resource::<LayeredGridMap<Entity>>()
+                .get(GridLayer::FLOOR, GridCoord::new(0, 1)),
+            Some(&b)
+        );
+
+        // Contested again at 100, b loses its cell and is despawned
+        app.world.resource_mut::<GridSettings>().size = 100.0;
+        app.update();
+        assert!(app.world.get_entity(b).is_none());
+        assert_eq!(app.world.resource::<LayeredGridMap<Entity>>().len(), 2);
+        assert_eq!(translation(&app, dragged), Vec3::new(130.0, -20.0, 1.0));
+    }
+
+    #[test]
+    fn resnap_only_reports_moved_cells() {
+        let mut app = App::new();
+        app.add_plugins(GridPlugin {
+            size: 50.0,
+            ..Default::default()
+        });
+        let place = |app: &mut App, coord: GridCoord| {
+            app.world
+                .spawn((
+                    Transform::from_translation(coord.translation_with_z(50.0, 1.0)),
+                    SnapToGrid {
+                        register: true,
+                        ..Default::default()
+                    },
+                ))
+                .id()
+        };
+        let a = place(&mut app, GridCoord::new(0, 0));
+        let b = place(&mut app, GridCoord::new(3, 0));
+        app.update();
+        app.world.resource_mut::<Events<GridMapChanged>>().clear();
+
+        // a keeps (0, 0), b moves from (3, 0) to (1, 0)
+        app.world.resource_mut::<GridSettings>().size = 100.0;
+        app.update();
+        let changes: Vec<_> = app
+            .world
+            .resource_mut::<Events<GridMapChanged>>()
+            .drain()
+            .map(|event| (event.coord, event.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (GridCoord::new(3, 0), GridMapChange::Removed),
+                (GridCoord::new(1, 0), GridMapChange::Inserted),
+            ]
+        );
+        let grid_map = app.world.resource::<LayeredGridMap<Entity>>();
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(0, 0)),
+            Some(&a)
+        );
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(1, 0)),
+            Some(&b)
+        );
+    }
+
+    #[test]
+    fn layered_grid_map() {
+        let mut grid_map = LayeredGridMap::default();
+        let coord = GridCoord::new(-1, 2);
+        assert_eq!(grid_map.insert(GridLayer::FLOOR, coord, "floor"), None);
+        assert_eq!(grid_map.insert(GridLayer::OBJECT, coord, "machine"), None);
+        assert_eq!(grid_map.get(GridLayer::FLOOR, coord), Some(&"floor"));
+        assert_eq!(grid_map.get(GridLayer::OBJECT, coord), Some(&"machine"));
+        assert!(!grid_map.contains(GridLayer::OVERLAY, coord));
+        assert_eq!(grid_map.len(), 2);
+
+        assert_eq!(
+            grid_map.insert(GridLayer::OBJECT, coord, "crate"),
+            Some("machine")
+        );
+        grid_map.insert(GridLayer::OBJECT, GridCoord::new(0, 0), "belt");
+        let mut objects: Vec<_> = grid_map.iter_layer(GridLayer::OBJECT).collect();
+        objects.sort();
+        assert_eq!(
+            objects,
+            [(GridCoord::new(0, 0), &"belt"), (coord, &"crate")]
+        );
+        assert_eq!(grid_map.iter_layer(GridLayer::OVERLAY).count(), 0);
+
+        assert_eq!(grid_map.remove(GridLayer::FLOOR, coord), Some("floor"));
+        assert_eq!(grid_map.get(GridLayer::OBJECT, coord), Some(&"crate"));
+        grid_map.retain(|layer, _, _| layer != GridLayer::OBJECT);
+        assert!(grid_map.is_empty());
+
+        assert_eq!(GridLayer::FLOOR.z_offset(), 0.0);
+        assert!(GridLayer::OVERLAY.z_offset() > GridLayer::OBJECT.z_offset());
+    }
+
+    #[test]
+    fn rings_and_spiral() {
+        let center = GridCoord::new(-3, 2);
+        assert_eq!(center.ring(0).collect::<Vec<_>>(), [center]);
+        assert_eq!(
+            center.ring(1).collect::<Vec<_>>(),
+            [
+                GridCoord::new(-4, 1),
+                GridCoord::new(-3, 1),
+                GridCoord::new(-2, 1),
+                GridCoord::new(-2, 2),
+                GridCoord::new(-2, 3),
+                GridCoord::new(-3, 3),
+                GridCoord::new(-4, 3),
+                GridCoord::new(-4, 2),
+            ]
+        );
+        for radius in 0..5 {
+            let ring: Vec<_> = center.ring(radius).collect();
+            assert_eq!(ring.len(), (8 * radius).max(1) as usize);
+            assert!(ring
+                .iter()
+                .all(|coord| coord.chebyshev_distance(center) == u64::from(radius)));
+        }
+
+        let spiral: Vec<_> = center.spiral(3).collect();
+        let mut square: Vec<_> = GridRect::from_corners(center.offset(-3, -3), center.offset(3, 3))
+            .iter()
+            .collect();
+        assert_eq!(spiral.len(), square.len());
+        let mut sorted = spiral.clone();
+        sorted.sort();
+        square.sort();
+        assert_eq!(sorted, square);
+        assert!(spiral
+            .windows(2)
+            .all(|pair| pair[0].chebyshev_distance(center) <= pair[1].chebyshev_distance(center)));
+
+        assert_eq!(find_nearest(center, 3, |_| true), Some(center));
+        let mut occupied = GridMap::default();
+        for coord in center.spiral(1) {
+            occupied.insert(coord, ());
+        }
+        let free = find_nearest(center, 3, |coord| !occupied.contains(coord)).unwrap();
+        assert_eq!(free.chebyshev_distance(center), 2);
+        assert_eq!(
+            find_nearest(center, 1, |coord| !occupied.contains(coord)),
+            None
+        );
+    }
+
+    #[test]
+    fn raycast() {
+        let settings = GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        };
+        let cast = |from: (f32, f32), to: (f32, f32)| {
+            raycast_cells(from.into(), to.into(), &settings)
+                .map(|coord| (coord.0.x, coord.0.y))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(cast((5.0, 5.0), (5.0, 5.0)), [(0, 0)]);
+        assert_eq!(
+            cast((5.0, 5.0), (35.0, 5.0)),
+            [(0, 0), (1, 0), (2, 0), (3, 0)]
+        );
+        assert_eq!(
+            cast((-5.0, -5.0), (-5.0, -28.0)),
+            [(-1, -1), (-1, -2), (-1, -3)]
+        );
+        assert_eq!(
+            cast((5.0, 5.0), (25.0, 15.0)),
+            [(0, 0), (1, 0), (1, 1), (2, 1)]
+        );
+        assert_eq!(
+            cast((-5.0, -5.0), (-25.0, -15.0)),
+            [(-1, -1), (-2, -1), (-2, -2), (-3, -2)]
+        );
+
+        // Through the corners
+        assert_eq!(
+            cast((5.0, 5.0), (25.0, 25.0)),
+            [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)]
+        );
+
+        // Along a grid line, the cells above it
+        assert_eq!(
+            cast((0.0, 10.0), (30.0, 10.0)),
+            [(0, 1), (1, 1), (2, 1), (3, 1)]
+        );
+        assert_eq!(
+            cast((30.0, 10.0), (0.0, 10.0)),
+            [(3, 1), (2, 1), (1, 1), (0, 1)]
+        );
+        assert_eq!(
+            cast((-10.0, 15.0), (-10.0, -5.0)),
+            [(-1, 1), (-1, 0), (-1, -1)]
+        );
+
+        let mut walls = GridMap::default();
+        walls.insert(GridCoord::new(2, 1), ());
+        walls.insert(GridCoord::new(3, 0), ());
+        let blocked =
+            |to: (f32, f32)| first_blocked_cell(Vec2::new(5.0, 5.0), to.into(), &settings, &walls);
+        assert_eq!(blocked((25.0, 15.0)), Some(GridCoord::new(2, 1)));
+        assert_eq!(blocked((45.0, 5.0)), Some(GridCoord::new(3, 0)));
+        assert_eq!(blocked((5.0, 45.0)), None);
+    }
+
+    #[test]
+    fn snap_changed_transforms() {
+        let mut app = App::new();
+        app.add_plugins(GridPlugin {
+            size: 10.0,
+            ..Default::default()
+        });
+        let snap_to_grid = SnapToGrid {
+            mode: SnapMode::Center,
+            layer_z: Some(2.0),
+            register: false,
+        };
+        let snapped = app
+            .world
+            .spawn((Transform::from_xyz(3.0, 4.0, 0.0), snap_to_grid))
+            .id();
+        let corner = app
+            .world
+            .spawn((
+                Transform::from_xyz(3.0, -4.0, 7.0),
+                SnapToGrid {
+                    mode: SnapMode::Corner,
+                    layer_z: None,
+                    register: false,
+                },
+            ))
+            .id();
+        let suspended = app
+            .world
+            .spawn((
+                Transform::from_xyz(3.0, 4.0, 0.0),
+                snap_to_grid,
+                SnapSuspended,
+            ))
+            .id();
+        let animated = app
+            .world
+            .spawn((
+                Transform::from_xyz(3.0, 4.0, 0.0),
+                snap_to_grid,
+                Animator::new(
+                    Animation {
+                        duration: Duration::from_secs(1),
+                        curve: AnimationCurve::Linear,
+                    },
+                    Repeat::Once,
+                    TranslationLens {
+                        start: Vec3::ZERO,
+                        end: Vec3::X,
+                    },
+                ),
+            ))
+            .id();
+        app.update();
+
+        let translation =
+            |app: &App, entity| app.world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(&app, snapped), Vec3::new(5.0, 5.0, 2.0));
+        assert_eq!(translation(&app, corner), Vec3::new(0.0, -10.0, 7.0));
+        assert_eq!(translation(&app, suspended), Vec3::new(3.0, 4.0, 0.0));
+        assert_eq!(translation(&app, animated), Vec3::new(3.0, 4.0, 0.0));
+
+        app.world.get_mut::<Transform>(snapped).unwrap().translation = Vec3::new(17.0, -3.0, 0.0);
+        app.update();
+        assert_eq!(translation(&app, snapped), Vec3::new(15.0, -5.0, 2.0));
+
+        // Picked up again once no longer suspended and moved
+        app.world.entity_mut(suspended).remove::<SnapSuspended>();
+        app.world
+            .get_mut::<Transform>(suspended)
+            .unwrap()
+            .translation
+            .x = -1.0;
+        app.update();
+        assert_eq!(translation(&app, suspended), Vec3::new(-5.0, 5.0, 2.0));
+    }
+
+    #[test]
+    fn dense_indices() {
+        let rect = GridRect::from_corners(GridCoord::new(-3, -2), GridCoord::new(2, 1));
+        for (index, coord) in rect.iter().enumerate() {
+            assert_eq!(rect.index_of(coord), Some(index));
+            assert_eq!(rect.coord_of(index), coord);
+        }
+        assert_eq!(rect.index_of(GridCoord::new(3, 0)), None);
+        assert_eq!(rect.index_of(GridCoord::new(0, -3)), None);
+
+        let mut costs = DenseGridMap::new(rect, 1);
+        assert_eq!(costs.as_slice().len(), 24);
+        *costs.get_mut(GridCoord::new(0, 0)).unwrap() = 5;
+        assert_eq!(costs.get(GridCoord::new(0, 0)), Some(&5));
+        assert_eq!(costs.get(GridCoord::new(-3, -2)), Some(&1));
+        assert_eq!(costs.get(GridCoord::new(-4, -2)), None);
+        assert!(costs.get_mut(GridCoord::new(0, 2)).is_none());
+        assert_eq!(
+            costs.iter_indexed().find(|(_, _, cost)| **cost == 5),
+            Some((
+                rect.index_of(GridCoord::new(0, 0)).unwrap(),
+                GridCoord::new(0, 0),
+                &5
+            ))
+        );
+
+        costs.fill(0);
+        assert!(costs.iter_indexed().all(|(_, _, cost)| *cost == 0));
+    }
+
+    #[test]
+    fn drag_selection() {
+        let mut app = App::new();
+        app.init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<CursorGridCoord>()
+            .init_resource::<GridSelection>()
+            .init_resource::<GridSelectionSettings>()
+            .add_event::<GridRegionSelected>()
+            .add_systems(Update, grid_selection_system);
+
+        let frame = |app: &mut App, cursor: (i32, i32), input: &dyn Fn(&mut App)| {
+            app.world.resource_mut::<Input<KeyCode>>().clear();
+            app.world.resource_mut::<Input<MouseButton>>().clear();
+            app.world.resource_mut::<CursorGridCoord>().0 =
+                Some(GridCoord::new(cursor.0, cursor.1));
+            input(app);
+            app.update();
+            let selected: Vec<_> = app
+                .world
+                .resource_mut::<Events<GridRegionSelected>>()
+                .drain()
+                .map(|event| event.rect)
+                .collect();
+            (app.world.resource::<GridSelection>().rect(), selected)
+        };
+        let press = |button| {
+            move |app: &mut App| app.world.resource_mut::<Input<MouseButton>>().press(button)
+        };
+        let release = |button| {
+            move |app: &mut App| {
+                app.world
+                    .resource_mut::<Input<MouseButton>>()
+                    .release(button)
+            }
+        };
+        let key = |key| move |app: &mut App| app.world.resource_mut::<Input<KeyCode>>().press(key);
+        let idle = |_: &mut App| {};
+
+        // Without the modifier a click is left to placement
+        assert_eq!(
+            frame(&mut app, (0, 0), &press(MouseButton::Left)),
+            (None, vec![])
+        );
+        frame(&mut app, (0, 0), &release(MouseButton::Left));
+
+        frame(&mut app, (0, 0), &key(KeyCode::ShiftLeft));
+        let started = frame(&mut app, (1, -1), &press(MouseButton::Left));
+        assert_eq!(
+            started.0,
+            Some(GridRect::from_corners(
+                GridCoord::new(1, -1),
+                GridCoord::new(1, -1)
+            ))
+        );
+        let dragged = GridRect::from_corners(GridCoord::new(1, -1), GridCoord::new(-2, 3));
+        assert_eq!(frame(&mut app, (-2, 3), &idle), (Some(dragged), vec![]));
+        assert_eq!(
+            frame(&mut app, (-2, 3), &release(MouseButton::Left)),
+            (None, vec![dragged])
+        );
+
+        // Cancelled mid-drag
+        frame(&mut app, (0, 0), &press(MouseButton::Left));
+        frame(&mut app, (4, 4), &press(MouseButton::Right));
+        assert_eq!(
+            frame(&mut app, (4, 4), &release(MouseButton::Left)),
+            (None, vec![])
+        );
+        frame(&mut app, (0, 0), &press(MouseButton::Left));
+        frame(&mut app, (4, 4), &key(KeyCode::Escape));
+        assert_eq!(
+            frame(&mut app, (4, 4), &release(MouseButton::Left)),
+            (None, vec![])
+        );
+    }
+
+    #[cfg(feature = "ron")]
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    enum Tile {
+        Floor,
+        Machine { name: String, rotation: u8 },
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn grid_map_ron_round_trip() {
+        let settings = GridSettings {
+            size: 100.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        };
+        let mut grid_map = GridMap::default();
+        grid_map.insert(GridCoord::new(-3, 2), Tile::Floor);
+        grid_map.insert(GridCoord::new(0, -1), Tile::Floor);
+        grid_map.insert(
+            GridCoord::new(-1, -7),
+            Tile::Machine {
+                name: "press".to_string(),
+                rotation: 3,
+            },
+        );
+
+        let ron = grid_map.to_ron(&settings).unwrap();
+        let loaded = GridMap::<Tile>::from_ron(&ron, &settings).unwrap();
+        assert_eq!(loaded.len(), 3);
+        for (coord, tile) in grid_map.iter() {
+            assert_eq!(loaded.get(coord), Some(tile));
+        }
+        // Sorted cells keep the output stable
+        assert_eq!(loaded.to_ron(&settings).unwrap(), ron);
+
+        let smaller = GridSettings {
+            size: 50.0,
+            ..settings
+        };
+        assert!(matches!(
+            GridMap::<Tile>::from_ron(&ron, &smaller),
+            Err(super::GridMapFileError::SizeMismatch {
+                saved,
+                expected,
+            }) if saved == 100.0 && expected == 50.0
+        ));
+        assert!(matches!(
+            GridMap::<Tile>::from_ron("(grid_size: 100.0)", &settings),
+            Err(super::GridMapFileError::Parse(_))
+        ));
+    }
+
+    #[cfg(feature = "std-io")]
+    #[test]
+    fn grid_map_file_round_trip() {
+        let settings = GridSettings {
+            size: 100.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        };
+        let mut grid_map = GridMap::default();
+        grid_map.insert(GridCoord::new(-2, -2), Tile::Floor);
+        let path = std::env::temp_dir().join(format!("grid_map_{}.ron", std::process::id()));
+        grid_map.save_to_file(&path, &settings).unwrap();
+        let loaded = GridMap::<Tile>::load_from_file(&path, &settings);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.unwrap().get(GridCoord::new(-2, -2)),
+            Some(&Tile::Floor)
+        );
+        assert!(matches!(
+            GridMap::<Tile>::load_from_file(&path, &settings),
+            Err(super::GridMapFileError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn grid_map_change_log() {
+        let (a, b, c) = (
+            GridCoord::new(-1, 0),
+            GridCoord::new(2, 3),
+            GridCoord::new(0, -4),
+        );
+        let mut untracked = GridMap::default();
+        untracked.insert(a, 1);
+        assert!(untracked.drain_changes().is_empty());
+
+        let mut grid_map = GridMap::tracked();
+        grid_map.insert(a, 1);
+        grid_map.insert(b, 2);
+        grid_map.insert(a, 3);
+        grid_map.remove(b);
+        grid_map.remove(c);
+        grid_map.extend([(c, 4), (b, 5)]);
+        grid_map.retain(|_, value| *value != 4);
+        grid_map.clear();
+        assert_eq!(
+            grid_map.drain_changes(),
+            [
+                (a, GridMapChange::Inserted),
+                (b, GridMapChange::Inserted),
+                (a, GridMapChange::Replaced),
+                (b, GridMapChange::Removed),
+                (c, GridMapChange::Inserted),
+                (b, GridMapChange::Inserted),
+                (c, GridMapChange::Removed),
+                // Cleared cells in coordinate order
+                (a, GridMapChange::Removed),
+                (b, GridMapChange::Removed),
+            ]
+        );
+        assert!(grid_map.drain_changes().is_empty());
+    }
+
+    #[test]
+    fn grid_map_changed_events() {
+        let mut app = App::new();
+        app.add_plugins(GridPlugin::default());
+        let entity = app.world.spawn_empty().id();
+        let coord = GridCoord::new(1, -1);
+        let mut grid_map = app.world.resource_mut::<LayeredGridMap<Entity>>();
+        grid_map.insert(GridLayer::OBJECT, coord, entity);
+        grid_map.insert(GridLayer::FLOOR, coord, entity);
+
+        let events = |app: &mut App| {
+            app.update();
+            app.world
+                .resource_mut::<Events<GridMapChanged>>()
+                .drain()
+                .map(|event| (event.layer, event.coord, event.change))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            events(&mut app),
+            [
+                (GridLayer::OBJECT, coord, GridMapChange::Inserted),
+                (GridLayer::FLOOR, coord, GridMapChange::Inserted),
+            ]
+        );
+        assert_eq!(events(&mut app), []);
+
+        // In the order they happened across layers
+        let mut grid_map = app.world.resource_mut::<LayeredGridMap<Entity>>();
+        grid_map.remove(GridLayer::OBJECT, coord);
+        grid_map.insert(GridLayer::FLOOR, coord, entity);
+        grid_map.insert(GridLayer::OBJECT, coord, entity);
+        assert_eq!(
+            events(&mut app),
+            [
+                (GridLayer::OBJECT, coord, GridMapChange::Removed),
+                (GridLayer::FLOOR, coord, GridMapChange::Replaced),
+                (GridLayer::OBJECT, coord, GridMapChange::Inserted),
+            ]
+        );
+
+        // Cells of despawned entities are cleared and reported in the same frame
+        app.world.despawn(entity);
+        assert_eq!(
+            events(&mut app),
+            [
+                (GridLayer::FLOOR, coord, GridMapChange::Removed),
+                (GridLayer::OBJECT, coord, GridMapChange::Removed),
+            ]
+        );
+    }
+
+    #[test]
+    fn autotile_masks() {
+        // . # .
+        // # x .
+        // # # .
+        let walls =
+            [(0, 1), (-1, 0), (-1, -1), (0, -1), (0, 0)].map(|(x, y)| GridCoord::new(x + 5, y - 5));
+        let is_wall = |coord| walls.contains(&coord);
+        let center = GridCoord::new(5, -5);
+        assert_eq!(autotile_mask(center, is_wall, Connectivity::Four), 0b1101);
+        assert_eq!(
+            autotile_mask(center, is_wall, Connectivity::Eight),
+            2 | 8 | 32 | 64
+        );
+        assert_eq!(autotile_mask(center, |_| false, Connectivity::Eight), 0);
+        assert_eq!(autotile_mask(center, |_| true, Connectivity::Eight), 255);
+
+        let indices: HashSet<_> = (0..=255).map(blob47_index).collect();
+        assert_eq!(indices.len(), 47);
+        assert_eq!(blob47_index(0), 0);
+        assert_eq!(blob47_index(255), 46);
+        // Corners without both of their edges do not change the tile
+        assert_eq!(blob47_index(1 | 4 | 32 | 128), 0);
+        assert_eq!(blob47_index(2 | 8 | 1), blob47_index(2 | 8 | 1 | 128));
+        assert_ne!(blob47_index(2 | 8 | 1), blob47_index(2 | 8));
+    }
+
+    #[test]
+    fn autotile_variants() {
+        let mut app = App::new();
+        app.add_plugins(GridPlugin::default());
+        let wall = Autotile {
+            group: 1,
+            connectivity: Connectivity::Four,
+        };
+        let place = |app: &mut App, x, autotile| {
+            let entity = app.world.spawn(autotile).id();
+            app.world.resource_mut::<LayeredGridMap<Entity>>().insert(
+                GridLayer::OBJECT,
+                GridCoord::new(x, 0),
+                entity,
+            );
+            entity
+        };
+        let left = place(&mut app, -1, wall);
+        let middle = place(&mut app, 0, wall);
+        let other = place(&mut app, 1, Autotile { group: 2, ..wall });
+        app.update();
+        let variant = |app: &App, entity| app.world.get::<TileVariant>(entity).copied();
+        assert_eq!(variant(&app, left), Some(TileVariant(0b0010)));
+        assert_eq!(variant(&app, middle), Some(TileVariant(0b1000)));
+        assert_eq!(variant(&app, other), Some(TileVariant(0)));
+
+        let right = place(&mut app, 2, Autotile { group: 2, ..wall });
+        app.world.despawn(left);
+        app.update();
+        assert_eq!(variant(&app, middle), Some(TileVariant(0)));
+        assert_eq!(variant(&app, other), Some(TileVariant(0b0010)));
+        assert_eq!(variant(&app, right), Some(TileVariant(0b1000)));
+    }
+
+    #[test]
+    fn move_to_cell_replaces_in_flight_move() {
+        let mut harness = AnimationHarness::default();
+        let settings = GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        };
+        let start = Vec3::new(5.0, 5.0, 3.0);
+        let entity = harness
+            .world_mut()
+            .spawn(Transform::from_translation(start))
+            .id();
+        let mut move_ids = MoveIds::default();
+        let mut move_entity = |harness: &mut AnimationHarness, target| {
+            let current = harness
+                .world()
+                .get::<Transform>(entity)
+                .unwrap()
+                .translation;
+            let mut queue = CommandQueue::default();
+            let mut commands = Commands::new(&mut queue, harness.world());
+            let id = move_to_cell(
+                &mut commands,
+                &mut move_ids,
+                entity,
+                current,
+                target,
+                &settings,
+                Duration::from_secs(1),
+                AnimationCurve::Linear,
+            );
+            queue.apply(harness.world_mut());
+            id
+        };
+
+        let first = move_entity(&mut harness, GridCoord::new(4, 0));
+        assert!(harness
+            .step_frames(2, Duration::from_millis(250))
+            .is_empty());
+        let halfway = harness
+            .world()
+            .get::<Transform>(entity)
+            .unwrap()
+            .translation;
+        assert_eq!(halfway, Vec3::new(25.0, 5.0, 3.0));
+
+        let second = move_entity(&mut harness, GridCoord::new(-3, -2));
+        assert_ne!(first, second);
+        let events = harness.step_frames(5, Duration::from_millis(250));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].animator_id, Some(second));
+        assert_eq!(
+            harness
+                .world()
+                .get::<Transform>(entity)
+                .unwrap()
+                .translation,
+            Vec3::new(-25.0, -15.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn move_to_cell_ends_on_the_wrapped_cell() {
+        let mut harness = AnimationHarness::default();
+        let settings = GridSettings {
+            size: 8.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Torus {
+                width: 10,
+                height: 6,
+            },
+            snap_divisions: 1,
+        };
+        let entity = harness
+            .world_mut()
+            .spawn(Transform::from_xyz(4.0, 4.0, 1.0))
+            .id();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, harness.world());
+        move_to_cell(
+            &mut commands,
+            &mut MoveIds::default(),
+            entity,
+            Vec3::new(4.0, 4.0, 1.0),
+            GridCoord::new(-1, 7),
+            &settings,
+            Duration::from_secs(1),
+            AnimationCurve::Linear,
+        );
+        queue.apply(harness.world_mut());
+
+        assert_eq!(harness.step_frames(5, Duration::from_millis(250)).len(), 1);
+        let translation = harness
+            .world()
+            .get::<Transform>(entity)
+            .unwrap()
+            .translation;
+        assert_eq!(translation, Vec3::new(76.0, 12.0, 1.0));
+        assert_eq!(
+            settings.cell_at(translation.truncate()),
+            GridCoord::new(9, 1)
+        );
+    }
+
+    #[test]
+    fn torus_topology() {
+        let torus = GridTopology::Torus {
+            width: 10,
+            height: 6,
+        };
+        let c = GridCoord::new;
+
+        assert_eq!(torus.wrap(c(-1, 6)), c(9, 0));
+        assert_eq!(torus.wrap(c(23, -13)), c(3, 5));
+        assert_eq!(torus.manhattan_distance(c(0, 0), c(9, 0)), 1);
+        assert_eq!(torus.chebyshev_distance(c(0, 0), c(9, 5)), 1);
+        assert_eq!(torus.manhattan_distance(c(1, 1), c(6, 4)), 8);
+        assert_eq!(GridTopology::Plane.manhattan_distance(c(0, 0), c(9, 0)), 9);
+        assert!(torus.neighbors4(c(0, 5)).contains(&c(9, 5)));
+        assert!(torus.neighbors4(c(0, 5)).contains(&c(0, 0)));
+        assert_eq!(
+            torus.line(c(8, 0), c(1, 0)),
+            [c(8, 0), c(9, 0), c(0, 0), c(1, 0)]
+        );
+
+        let settings = GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: torus,
+            snap_divisions: 1,
+        };
+        assert_eq!(settings.cell_at(Vec2::new(-1.0, 61.0)), c(9, 0));
+        assert_eq!(settings.cell_center(c(-1, 6)), Vec2::new(95.0, 5.0));
+
+        // Zero sized axes do not wrap
+        let cylinder = GridTopology::Torus {
+            width: 10,
+            height: 0,
+        };
+        assert_eq!(cylinder.wrap(c(-1, -7)), c(9, -7));
+        assert_eq!(cylinder.manhattan_distance(c(0, -3), c(9, 40)), 44);
+        let unbounded = GridTopology::Torus {
+            width: 0,
+            height: 0,
+        };
+        assert_eq!(unbounded.wrap(c(-12, 30)), c(-12, 30));
+        assert_eq!(unbounded.delta(c(-12, 30), c(8, 0)), IVec2::new(20, -30));
+    }
+
+    #[test]
+    fn torus_path_crosses_seam() {
+        let torus = GridTopology::Torus {
+            width: 10,
+            height: 6,
+        };
+        let c = GridCoord::new;
+        let wall = |coord: GridCoord| coord.0.x == 5;
+
+        let path = pathfind_in(torus, c(3, 2), c(7, 2), wall, false, 1000).unwrap();
+        assert_eq!(path, [3, 2, 1, 0, 9, 8, 7].map(|x| c(x, 2)),);
+        // Goals outside the canonical range are wrapped
+        let path = pathfind_in(torus, c(0, 0), c(-1, -1), |_| false, true, 1000).unwrap();
+        assert_eq!(path, [c(0, 0), c(9, 5)]);
+        assert_eq!(pathfind(c(3, 2), c(7, 2), wall, false, 1000), None);
+    }
+
+    #[test]
+    fn cell_bounds() {
+        let settings = GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        };
+        for coord in GridRect::from_corners(GridCoord::new(-7, -5), GridCoord::new(6, 8)).iter() {
+            let bounds = coord.bounds(&settings);
+            assert_eq!(bounds.center(), coord.translation(settings.size));
+            assert_eq!(bounds.size(), Vec2::splat(10.0));
+            assert!(coord.contains_point(bounds.center(), &settings));
+            assert!(coord.contains_point(bounds.min, &settings));
+            assert!(!coord.contains_point(bounds.max, &settings));
+            assert_eq!(bounds.min.as_grid_coord(settings.size), coord);
+        }
+
+        let cell = GridCoord::new(-1, -2);
+        assert_eq!(cell.min_corner(10.0), Vec2::new(-10.0, -20.0));
+        assert_eq!(cell.max_corner(10.0), Vec2::new(0.0, -10.0));
+        assert!(cell.contains_point(Vec2::new(-0.001, -10.001), &settings));
+        assert!(!cell.contains_point(Vec2::new(0.0, -15.0), &settings));
+        assert!(!cell.contains_point(Vec2::new(-5.0, -10.0), &settings));
+    }
+
+    #[test]
+    fn spatial_queries() {
+        let c = GridCoord::new;
+        let mut map = GridMap::default();
+        for x in -5..5 {
+            for y in -5..5 {
+                if (x + y) % 3 == 0 {
+                    map.insert(c(x, y), (x, y));
+                }
+            }
+        }
+        let sorted = |mut found: Vec<GridCoord>| {
+            found.sort();
+            found
+        };
+
+        // Small regions look cells up, large ones scan the map, both agree
+        let rect = GridRect::from_corners(c(-1, -1), c(1, 1));
+        let found: Vec<_> = map.entities_in_rect(rect).map(|(coord, _)| coord).collect();
+        assert_eq!(sorted(found), [c(1, -1), c(0, 0), c(-1, 1)]);
+        let large = GridRect::from_corners(c(-20, -20), c(20, 20));
+        assert_eq!(map.entities_in_rect(large).count(), map.len());
+        let empty = GridRect::from_corners(c(10, 10), c(12, 12));
+        assert_eq!(map.entities_in_rect(empty).count(), 0);
+        // Areas past u32::MAX scan the map instead of overflowing
+        let huge = GridRect::from_corners(c(i32::MIN, i32::MIN), c(i32::MAX, i32::MAX));
+        assert_eq!(huge.area(), u64::MAX);
+        assert_eq!(huge.width(), u32::MAX);
+        assert_eq!(map.entities_in_rect(huge).count(), map.len());
+        let wide = GridRect::from_corners(c(-70_000, -70_000), c(70_000, 70_000));
+        assert_eq!(wide.area(), 140_001 * 140_001);
+        assert_eq!(map.entities_in_rect(wide).count(), map.len());
+
+        let ring: Vec<_> = map
+            .entities_in_ring(c(0, 0), 1)
+            .map(|(coord, value)| {
+                assert_eq!(*value, (coord.0.x, coord.0.y));
+                coord
+            })
+            .collect();
+        assert_eq!(ring, [c(1, -1), c(-1, 1)]);
+        assert_eq!(map.entities_in_ring(c(30, 30), 2).count(), 0);
+    }
+
+    #[test]
+    fn grid_query() {
+        let mut world = World::new();
+        world.insert_resource(GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 1,
+        });
+        let mut grid_map = LayeredGridMap::default();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        let dead = world.spawn_empty().id();
+        grid_map.insert(GridLayer::OBJECT, GridCoord::new(0, 0), a);
+        grid_map.insert(GridLayer::OBJECT, GridCoord::new(2, 1), b);
+        grid_map.insert(GridLayer::OBJECT, GridCoord::new(-1, 0), dead);
+        world.insert_resource(grid_map);
+        world.despawn(dead);
+
+        let mut state: SystemState<(GridQuery, Query<()>)> = SystemState::new(&mut world);
+        let (grid_query, alive) = state.get(&world);
+        let layer = GridLayer::OBJECT;
+
+        assert_eq!(
+            grid_query.entity_at(layer, Vec2::new(3.0, 9.0), None),
+            Some(a)
+        );
+        assert_eq!(
+            grid_query.entity_at(GridLayer::FLOOR, Vec2::new(3.0, 9.0), None),
+            None
+        );
+        assert_eq!(
+            grid_query.entity_at(layer, Vec2::new(-3.0, 1.0), None),
+            Some(dead)
+        );
+        assert_eq!(
+            grid_query.entity_at(layer, Vec2::new(-3.0, 1.0), Some(&alive)),
+            None
+        );
+
+        let mut found = grid_query.entities_in_area(
+            layer,
+            Vec2::new(-5.0, -5.0),
+            Vec2::new(25.0, 15.0),
+            Some(&alive),
+        );
+        found.sort();
+        assert_eq!(
+            found,
+            [(GridCoord::new(0, 0), a), (GridCoord::new(2, 1), b)]
+        );
+        assert_eq!(
+            grid_query
+                .entities_in_area(
+                    layer,
+                    Vec2::new(100.0, 100.0),
+                    Vec2::new(150.0, 150.0),
+                    None
+                )
+                .len(),
+            0
+        );
+
+        let ring = grid_query.entities_in_ring(layer, Vec2::new(5.0, 5.0), 1, None);
+        assert_eq!(ring, [(GridCoord::new(-1, 0), dead)]);
+        assert!(grid_query
+            .entities_in_ring(layer, Vec2::new(5.0, 5.0), 1, Some(&alive))
+            .is_empty());
+    }
+
+    #[test]
+    fn sub_cell_snapping() {
+        let mut settings = GridSettings {
+            size: 10.0,
+            snap_mode: SnapMode::Center,
+            topology: GridTopology::Plane,
+            snap_divisions: 2,
+        };
+        let samples = |coord: GridCoord| {
+            (0..10).flat_map(move |i| {
+                (0..10).map(move |j| coord.min_corner(10.0) + Vec2::new(i as f32, j as f32) + 0.5)
+            })
+        };
+
+        for coord in [GridCoord::new(0, 0), GridCoord::new(-1, -3)] {
+            for mode in [SnapMode::Center, SnapMode::Corner] {
+                settings.snap_mode = mode;
+                let mut positions: Vec<_> = samples(coord)
+                    .map(|point| snap_with_divisions(point, &settings, 2))
+                    .map(|position| (position.x as i32, position.y as i32))
+                    .collect();
+                positions.sort();
+                positions.dedup();
+                assert_eq!(positions.len(), 4, "{coord} {mode:?}");
+                assert!(positions.iter().all(|(x, y)| {
+                    let position = Vec2::new(*x as f32, *y as f32);
+                    position.as_grid_coord(10.0) == coord
+                }));
+            }
+
+            let mut sub_cells: Vec<_> = samples(coord)
+                .map(|point| settings.sub_cell_at(point))
+                .collect();
+            sub_cells.sort();
+            sub_cells.dedup();
+            assert_eq!(
+                sub_cells,
+                [(0, 0), (0, 1), (1, 0), (1, 1)].map(|(x, y)| (coord, SubCell { x, y }))
+            );
+        }
+
+        settings.snap_mode = SnapMode::Corner;
+        assert_eq!(
+            snap_with_divisions(Vec2::new(-6.0, 3.0), &settings, 2),
+            Vec2::new(-10.0, 0.0)
+        );
+        assert_eq!(
+            snap_with_divisions(Vec2::new(-4.0, 7.0), &settings, 2),
+            Vec2::new(-5.0, 5.0)
+        );
+        // One division is whole cells
+        let point = Vec2::new(-4.0, 7.0);
+        assert_eq!(
+            snap_with_divisions(point, &settings, 1),
+            snap(point, &settings, SnapMode::Corner)
+        );
+
+        let mut map = SubCellMap::default();
+        let (coord, sub_cell) = settings.sub_cell_at(Vec2::new(-4.0, 7.0));
+        assert_eq!(
+            (coord, sub_cell),
+            (GridCoord::new(-1, 0), SubCell { x: 1, y: 1 })
+        );
+        assert_eq!(map.insert(coord, sub_cell, 'a'), None);
+        map.insert(coord, SubCell { x: 0, y: 1 }, 'b');
+        assert!(!map.contains(coord, SubCell::default()));
+        assert_eq!(map.iter_cell(coord).count(), 2);
+        assert_eq!(map.iter_cell(GridCoord::new(0, 0)).count(), 0);
+        assert_eq!(map.remove(coord, sub_cell), Some('a'));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn snap_added_batch() {
+        let mut app = App::new();
+        app.add_plugins(GridPlugin {
+            size: 10.0,
+            ..Default::default()
+        });
+        let snap_to_grid = |register| SnapToGrid {
+            register,
+            ..Default::default()
+        };
+        let spawn = |app: &mut App, x: f32, y: f32, register, layer: GridLayer| {
+            app.world
+                .spawn((
+                    Transform::from_xyz(x, y, 0.0),
+                    snap_to_grid(register),
+                    layer,
+                ))
+                .id()
+        };
+        let a = spawn(&mut app, 3.0, 4.0, true, GridLayer::FLOOR);
+        let b = spawn(&mut app, 6.0, 8.0, true, GridLayer::FLOOR);
+        let c = spawn(&mut app, -3.0, 4.0, false, GridLayer::FLOOR);
+        let d = spawn(&mut app, 3.0, 4.0, true, GridLayer::OBJECT);
+        app.update();
+
+        let translation =
+            |app: &App, entity| app.world.get::<Transform>(entity).unwrap().translation;
+        assert_eq!(translation(&app, a), Vec3::new(5.0, 5.0, 0.0));
+        // Lost the cell to a, left where it was spawned
+        assert_eq!(translation(&app, b), Vec3::new(6.0, 8.0, 0.0));
+        assert_eq!(translation(&app, c), Vec3::new(-5.0, 5.0, 0.0));
+        assert_eq!(translation(&app, d), Vec3::new(5.0, 5.0, 0.0));
+
+        let grid_map = app.world.resource::<LayeredGridMap<Entity>>();
+        let origin = GridCoord::new(0, 0);
+        assert_eq!(grid_map.get(GridLayer::FLOOR, origin), Some(&a));
+        assert_eq!(grid_map.get(GridLayer::OBJECT, origin), Some(&d));
+        assert_eq!(grid_map.len(), 2);
+        let conflicts: Vec<_> = app
+            .world
+            .resource_mut::<Events<GridPlacementConflict>>()
+            .drain()
+            .collect();
+        assert_eq!(
+            conflicts,
+            [GridPlacementConflict {
+                entity: b,
+                layer: GridLayer::FLOOR,
+                coord: origin,
+                occupant: a,
+            }]
+        );
+
+        // Moving the loser does not snap it onto the taken cell
+        assert!(app.world.get::<SnapConflicted>(b).is_some());
+        app.world.get_mut::<Transform>(b).unwrap().translation.x = 7.0;
+        app.update();
+        assert_eq!(translation(&app, b), Vec3::new(7.0, 8.0, 0.0));
+        assert!(app.world.get::<SnapConflicted>(b).is_some());
+        app.world
+            .resource_mut::<Events<GridPlacementConflict>>()
+            .clear();
+
+        // Into a free cell it snaps again
+        app.world.get_mut::<Transform>(b).unwrap().translation.x = 16.0;
+        app.update();
+        assert_eq!(translation(&app, b), Vec3::new(15.0, 5.0, 0.0));
+        assert!(app.world.get::<SnapConflicted>(b).is_none());
+
+        // Later frames only handle the new entities
+        app.insert_resource(SnapConflictPolicy::Despawn);
+        let e = spawn(&mut app, 1.0, 1.0, true, GridLayer::OBJECT);
+        let f = spawn(&mut app, 11.0, 1.0, true, GridLayer::OBJECT);
+        app.update();
+        assert!(app.world.get_entity(e).is_none());
+        assert_eq!(translation(&app, f), Vec3::new(15.0, 5.0, 0.0));
+        assert_eq!(translation(&app, b), Vec3::new(15.0, 5.0, 0.0));
+        assert_eq!(app.world.resource::<LayeredGridMap<Entity>>().len(), 4);
+        assert_eq!(
+            app.world.resource::<Events<GridPlacementConflict>>().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn moved_entities_move_their_cell() {
+        let mut app = App::new();
+        app.add_plugins(GridPlugin {
+            size: 10.0,
+            ..Default::default()
+        });
+        let spawn = |app: &mut App, x: f32| {
+            app.world
+                .spawn((
+                    Transform::from_xyz(x, 5.0, 0.0),
+                    SnapToGrid {
+                        register: true,
+                        ..Default::default()
+                    },
+                ))
+                .id()
+        };
+        let a = spawn(&mut app, 5.0);
+        let b = spawn(&mut app, 15.0);
+        app.update();
+
+        app.world.get_mut::<Transform>(a).unwrap().translation.x = 23.0;
+        app.update();
+        let grid_map = app.world.resource::<LayeredGridMap<Entity>>();
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(2, 0)),
+            Some(&a)
+        );
+        assert!(!grid_map.contains(GridLayer::FLOOR, GridCoord::new(0, 0)));
+        assert_eq!(grid_map.len(), 2);
+        let translation = app.world.get::<Transform>(a).unwrap().translation;
+        assert_eq!(translation, Vec3::new(25.0, 5.0, 0.0));
+        assert_eq!(
+            app.world.get::<GridCell>(a),
+            Some(&GridCell(GridCoord::new(2, 0)))
+        );
+
+        // Onto a taken cell, the conflict policy applies
+        app.world.get_mut::<Transform>(a).unwrap().translation.x = 12.0;
+        app.update();
+        let grid_map = app.world.resource::<LayeredGridMap<Entity>>();
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(1, 0)),
+            Some(&b)
+        );
+        assert_eq!(grid_map.len(), 1);
+        let translation = app.world.get::<Transform>(a).unwrap().translation;
+        assert_eq!(translation, Vec3::new(12.0, 5.0, 0.0));
+        assert!(app.world.get::<SnapConflicted>(a).is_some());
+        assert!(app.world.get::<GridCell>(a).is_none());
+        assert_eq!(
+            app.world.resource::<Events<GridPlacementConflict>>().len(),
+            1
+        );
+
+        // A cell removed from the map behind its back is not vacated again
+        app.world
+            .resource_mut::<LayeredGridMap<Entity>>()
+            .remove(GridLayer::FLOOR, GridCoord::new(1, 0));
+        app.world.resource_mut::<LayeredGridMap<Entity>>().insert(
+            GridLayer::FLOOR,
+            GridCoord::new(1, 0),
+            a,
+        );
+        app.world.get_mut::<Transform>(b).unwrap().translation.x = 33.0;
+        app.update();
+        let grid_map = app.world.resource::<LayeredGridMap<Entity>>();
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(1, 0)),
+            Some(&a)
+        );
+        assert_eq!(
+            grid_map.get(GridLayer::FLOOR, GridCoord::new(3, 0)),
+            Some(&b)
+        );
+    }
+
+    #[test]
+    fn heatmap_gradient() {
+        let gradient = [
+            Color::rgba(0.0, 0.0, 0.0, 1.0),
+            Color::rgba(1.0, 0.0, 0.0, 1.0),
+            Color::rgba(1.0, 1.0, 0.0, 0.0),
+        ];
+        assert_eq!(gradient_color(&gradient, -1.0), gradient[0]);
+        assert_eq!(
+            gradient_color(&gradient, 0.25),
+            Color::rgba(0.5, 0.0, 0.0, 1.0)
+        );
+        assert_eq!(gradient_color(&gradient, 0.5), gradient[1]);
+        assert_eq!(
+            gradient_color(&gradient, 0.75),
+            Color::rgba(1.0, 0.5, 0.0, 0.5)
+        );
+        assert_eq!(gradient_color(&gradient, 2.0), gradient[2]);
+        assert_eq!(gradient_color(&gradient[..1], 0.7), gradient[0]);
+        assert_eq!(gradient_color(&[], 0.7), Color::NONE);
+    }
+
+    #[test]
+    fn grid_overrides() {
+        let fine = GridOverride {
+            size: UVec2::splat(25),
+            origin: None,
+        };
+        let wide = GridOverride {
+            size: UVec2::new(40, 20),
+            origin: Some(Vec2::new(10.0, 0.0)),
+        };
+        let cursor = Vec2::new(-30.0, 47.0);
+        assert_eq!(fine.snap(cursor, SnapMode::Center), Vec2::new(-37.5, 37.5));
+        assert_eq!(wide.snap(cursor, SnapMode::Center), Vec2::new(-10.0, 50.0));
+        assert_eq!(fine.snap(cursor, SnapMode::Corner), Vec2::new(-50.0, 25.0));
+        assert_eq!(wide.snap(cursor, SnapMode::Corner), Vec2::new(-30.0, 40.0));
+        assert_eq!(
+            wide.snap(cursor, SnapMode::Intersection),
+            Vec2::new(-30.0, 40.0)
+        );
+        assert_eq!(
+            fine.snap(cursor, SnapMode::Intersection),
+            Vec2::new(-25.0, 50.0)
+        );
+
+        assert_eq!(fine.cell_at(cursor), GridCoord::new(-2, 1));
+        // Offset by the origin, the cursor is on the left edge of its cell
+        assert_eq!(wide.cell_at(cursor), GridCoord::new(-1, 2));
+        let bounds = wide.cell_bounds(wide.cell_at(cursor));
+        assert_eq!(bounds, Rect::new(-30.0, 40.0, 10.0, 60.0));
+        assert_eq!(bounds.center(), wide.snap(cursor, SnapMode::Center));
+
+        let mut rects = RectMap::default();
+        rects.insert(GridLayer::OBJECT, bounds, 'w');
+        let fine_bounds = |x, y| fine.cell_bounds(GridCoord::new(x, y));
+        // The fine cell shares the area from (-30, 40) to (-25, 50), the one below is clear
+        assert_eq!(
+            rects
+                .overlapping(GridLayer::OBJECT, fine_bounds(-2, 1))
+                .map(|(_, value)| *value)
+                .collect::<Vec<_>>(),
+            ['w']
+        );
+        assert_eq!(
+            rects
+                .overlapping(GridLayer::OBJECT, fine_bounds(-2, 0))
+                .count(),
+            0
+        );
+        assert_eq!(
+            rects
+                .overlapping(GridLayer::FLOOR, fine_bounds(-2, 1))
+                .count(),
+            0
+        );
+        rects.retain(|_, _, value| *value != 'w');
+        assert!(rects.is_empty());
+    }
 }
@@ -0,0 +1,618 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Duration,
+};
+
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::{
+    animation::{shake, AnimationCompleted, Animator, TranslationShakeLens},
+    grid::{
+        cells_overlapping, snap, snap_with_divisions, AsGridCoord, CursorGridCoord,
+        CursorWorldPosition, GridBounds, GridOverride, GridSelection, GridSettings, LayeredGridMap,
+        RectMap, SnapToGrid, SubCellMap,
+    },
+    inventory::{
+        is_dragging, panel_at, ActiveInventoryQuery, ActiveInventoryQueryMut, BaseInventory,
+        ClickConsumed, Container, DraggedItem, DynInventory, InventoryPanel, InventoryUiState,
+        SlotCooldowns,
+    },
+    items::{ContainerItem, DroppedItem, ItemCode, ItemCollector, ItemDropped, ItemPreview},
+};
+
+const NUMERIC_KEY_CODES: &[(KeyCode, usize)] = &[
+    (KeyCode::Key0, 0),
+    (KeyCode::Key1, 1),
+    (KeyCode::Key2, 2),
+    (KeyCode::Key3, 3),
+    (KeyCode::Key4, 4),
+    (KeyCode::Key5, 5),
+    (KeyCode::Key6, 6),
+    (KeyCode::Key7, 7),
+    (KeyCode::Key8, 8),
+    (KeyCode::Key9, 9),
+];
+
+const NUMPAD_KEY_CODES: &[(KeyCode, usize)] = &[
+    (KeyCode::Numpad0, 0),
+    (KeyCode::Numpad1, 1),
+    (KeyCode::Numpad2, 2),
+    (KeyCode::Numpad3, 3),
+    (KeyCode::Numpad4, 4),
+    (KeyCode::Numpad5, 5),
+    (KeyCode::Numpad6, 6),
+    (KeyCode::Numpad7, 7),
+    (KeyCode::Numpad8, 8),
+    (KeyCode::Numpad9, 9),
+];
+
+/// Slot a key selects to clear the selection, like [`DynInventory::select_item`] with 0
+pub const CLEAR_SELECTION_SLOT: usize = 0;
+
+/// Keys selecting inventory slots, see [`CLEAR_SELECTION_SLOT`]
+///
+/// Defaults to the number row with Key0 clearing the selection. A key selects one slot and a
+/// slot has at most one key.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct InventoryKeyBindings {
+    keys: BTreeMap<KeyCode, usize>,
+    /// Numpad digits select the slot of their digit whatever the slot is bound to, Numpad0
+    /// clears the selection, a bound numpad key keeps its binding
+    pub numpad: bool,
+    /// Key clearing the selection, see [`clear_selection_on_input`]
+    pub clear_key: Option<KeyCode>,
+    /// Mouse button clearing the selection, right click by default
+    pub clear_button: Option<MouseButton>,
+    /// Key dropping one of the selected item, see [`drop_selected_item`]
+    pub drop_key: Option<KeyCode>,
+    /// See [`sort_inventory_on_key`](crate::inventory::sort_inventory_on_key)
+    pub sort_key: Option<KeyCode>,
+}
+
+impl Default for InventoryKeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: NUMERIC_KEY_CODES.iter().copied().collect(),
+            numpad: true,
+            // Escape exits the game in main.rs, so no key by default
+            clear_key: None,
+            clear_button: Some(MouseButton::Right),
+            drop_key: Some(KeyCode::Q),
+            sort_key: Some(KeyCode::R),
+        }
+    }
+}
+
+impl InventoryKeyBindings {
+    /// Binds `key` to `slot`, the old slot of the key and the old key of the slot are unbound
+    pub fn rebind(&mut self, slot: usize, key: KeyCode) {
+        self.keys.retain(|_, bound| *bound != slot);
+        self.keys.insert(key, slot);
+    }
+
+    /// Removes the key of `slot`, returns it
+    pub fn unbind(&mut self, slot: usize) -> Option<KeyCode> {
+        let key = self.binding_for(slot)?;
+        self.keys.remove(&key);
+        Some(key)
+    }
+
+    pub fn binding_for(&self, slot: usize) -> Option<KeyCode> {
+        self.keys
+            .iter()
+            .find(|(_, bound)| **bound == slot)
+            .map(|(key, _)| *key)
+    }
+
+    pub fn slot_for(&self, key: KeyCode) -> Option<usize> {
+        self.keys.get(&key).copied()
+    }
+
+    /// Bound keys with their slots
+    pub fn iter(&self) -> impl Iterator<Item = (KeyCode, usize)> + '_ {
+        self.keys.iter().map(|(key, slot)| (*key, *slot))
+    }
+
+    /// Bound keys with their slots, then the unbound numpad digits with the slot of their
+    /// digit if [`Self::numpad`] is set
+    pub fn slot_keys(&self) -> impl Iterator<Item = (KeyCode, usize)> + '_ {
+        let numpad = NUMPAD_KEY_CODES
+            .iter()
+            .copied()
+            .filter(move |(key, _)| self.numpad && !self.keys.contains_key(key));
+        self.iter().chain(numpad)
+    }
+}
+
+/// Bound keys select in the panel under the cursor, in the active inventory otherwise,
+/// see [`InventoryKeyBindings`]
+///
+/// Only a key pressed this frame selects, holding it changes nothing afterwards. Of keys pressed
+/// in the same frame the lowest slot wins, so the clear key wins over all others.
+pub fn select_item(
+    key: Res<Input<KeyCode>>,
+    bindings: Res<InventoryKeyBindings>,
+    cursor_world_position: Option<Res<CursorWorldPosition>>,
+    dragged_item: Option<Res<DraggedItem>>,
+    panels: Query<(&InventoryPanel, &Transform)>,
+    mut inventory: ActiveInventoryQueryMut,
+) {
+    if is_dragging(dragged_item.as_deref()) {
+        return;
+    }
+    let cursor = cursor_world_position.and_then(|cursor| cursor.0);
+    let panel = panel_at(cursor, &panels);
+    let Some(mut inventory) = inventory.for_panel_mut(&panel) else {
+        return;
+    };
+    // Only keys of existing unlocked slots, CLEAR_SELECTION_SLOT clears the selection
+    let slot_count = inventory.slot_count();
+    let Some(num) = bindings
+        .slot_keys()
+        .filter(|(keycode, num)| {
+            *num <= slot_count && !inventory.is_locked(*num) && key.just_pressed(*keycode)
+        })
+        .map(|(_, num)| num)
+        .min()
+    else {
+        return;
+    };
+    // Leaves change detection alone when nothing changes
+    if inventory.selected_slot().unwrap_or(CLEAR_SELECTION_SLOT) != num {
+        inventory
+            .select_item(num)
+            .expect("keys are only mapped to existing unlocked slots");
+    }
+}
+
+/// Clears the selection of the active inventory with [`InventoryKeyBindings::clear_key`]
+/// or [`InventoryKeyBindings::clear_button`], hiding the preview right away
+///
+/// Ignored while dragging and while a grid selection is active, right click cancels those.
+pub fn clear_selection_on_input(
+    key: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    bindings: Res<InventoryKeyBindings>,
+    dragged_item: Option<Res<DraggedItem>>,
+    grid_selection: Option<Res<GridSelection>>,
+    mut inventory: ActiveInventoryQueryMut,
+    mut preview_items: Query<&mut Visibility, With<ItemPreview>>,
+) {
+    let pressed = bindings
+        .clear_key
+        .is_some_and(|clear| key.just_pressed(clear))
+        || bindings
+            .clear_button
+            .is_some_and(|clear| mouse.just_pressed(clear));
+    if !pressed
+        || is_dragging(dragged_item.as_deref())
+        || grid_selection.is_some_and(|selection| selection.is_active())
+    {
+        return;
+    }
+    let Some(mut inventory) = inventory.get_mut() else {
+        return;
+    };
+    if inventory.selected_slot().is_none() {
+        return;
+    }
+    inventory.clear_selection();
+    for mut visibility in preview_items.iter_mut() {
+        visibility.set_if_neq(Visibility::Hidden);
+    }
+}
+
+#[derive(Resource)]
+pub struct HotbarScrollSettings {
+    /// Scrolling down selects the next slot unless inverted
+    pub invert: bool,
+    /// Stops on the no selection state between the last and the first slot
+    pub include_none: bool,
+    /// Pixel delta of touchpads that counts as one wheel notch
+    pub pixels_per_notch: f32,
+}
+
+impl Default for HotbarScrollSettings {
+    fn default() -> Self {
+        Self {
+            invert: false,
+            include_none: false,
+            pixels_per_notch: 50.0,
+        }
+    }
+}
+
+/// Cycles the selection of the active inventory with the mouse wheel, one slot per notch
+///
+/// Runs after [`select_item`], a held selection key wins over scrolling in the same frame.
+pub fn scroll_select_item(
+    key: Res<Input<KeyCode>>,
+    bindings: Res<InventoryKeyBindings>,
+    settings: Res<HotbarScrollSettings>,
+    dragged_item: Option<Res<DraggedItem>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut accumulated: Local<f32>,
+    mut inventory: ActiveInventoryQueryMut,
+) {
+    let delta: f32 = wheel
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / settings.pixels_per_notch,
+        })
+        .sum();
+    if is_dragging(dragged_item.as_deref())
+        || bindings
+            .slot_keys()
+            .any(|(keycode, _)| key.pressed(keycode))
+    {
+        *accumulated = 0.0;
+        return;
+    }
+    *accumulated += if settings.invert { delta } else { -delta };
+    let steps = accumulated.trunc();
+    *accumulated -= steps;
+    if steps != 0.0 {
+        if let Some(mut inventory) = inventory.get_mut() {
+            inventory.cycle_selection(steps as i32, settings.include_none);
+        }
+    }
+}
+
+pub fn show_selected_item(
+    cursor_world_position: Res<CursorWorldPosition>,
+    grid_settings: Res<GridSettings>,
+    grid_bounds: Option<Res<GridBounds>>,
+    inventory: ActiveInventoryQuery,
+    mut preview_items: Query<
+        (
+            &ItemCode,
+            &mut Transform,
+            &mut Visibility,
+            Option<&GridOverride>,
+        ),
+        With<ItemPreview>,
+    >,
+) {
+    let grid_size = grid_settings.size;
+
+    let selected_item = inventory
+        .get()
+        .and_then(|inventory| inventory.selected_item());
+    for (item_code, mut transform, mut visibility, grid_override) in preview_items.iter_mut() {
+        *visibility = Visibility::Hidden;
+        if let Some(selected_item) = selected_item {
+            if item_code.eq(&selected_item.code) {
+                *visibility = Visibility::Visible;
+                if let Some(mut cursor) = cursor_world_position.0 {
+                    if let Some(grid_bounds) = &grid_bounds {
+                        let grid_coord = cursor.as_grid_coord(grid_size);
+                        let clamped = grid_bounds.clamp(grid_coord);
+                        if clamped != grid_coord {
+                            cursor = clamped.translation(grid_size);
+                        }
+                    }
+                    let grid_translation = if let Some(grid_override) = grid_override {
+                        grid_override.snap(cursor, grid_settings.snap_mode)
+                    } else if selected_item.sub_cell {
+                        snap_with_divisions(cursor, &grid_settings, grid_settings.snap_divisions)
+                    } else {
+                        snap(cursor, &grid_settings, grid_settings.snap_mode)
+                    };
+                    transform.translation.x = grid_translation.x;
+                    transform.translation.y = grid_translation.y;
+                }
+            }
+        }
+    }
+}
+
+/// Creative mode, placing items leaves the inventory as it is
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct InfinitePlacement(pub bool);
+
+/// Shakes newly placed items, off by default
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PlacementShake(pub bool);
+
+/// Places the selected item at the cursor, taking one off its stack
+///
+/// The selection is cleared once the slot runs out. Disabled while a container is open and
+/// for slots on cooldown.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn place_selected_item(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    click_consumed: Res<ClickConsumed>,
+    infinite_placement: Res<InfinitePlacement>,
+    placement_shake: Option<Res<PlacementShake>>,
+    inventory_ui: InventoryUiState,
+    cooldowns: Option<Res<SlotCooldowns>>,
+    mut inventory: ActiveInventoryQueryMut,
+    cursor_grid_coord: Res<CursorGridCoord>,
+    grid_settings: Res<GridSettings>,
+    grid_bounds: Option<Res<GridBounds>>,
+    grid_selection: Res<GridSelection>,
+    grid_map: Res<LayeredGridMap<Entity>>,
+    mut sub_cell_map: ResMut<SubCellMap<Entity>>,
+    mut rect_map: ResMut<RectMap<Entity>>,
+    preview_items: Query<
+        (
+            &ItemCode,
+            &Sprite,
+            &Transform,
+            Option<&GridOverride>,
+            Option<&ContainerItem>,
+        ),
+        With<ItemPreview>,
+    >,
+) {
+    if !(mouse.just_pressed(MouseButton::Left)) || **click_consumed || grid_selection.is_active() {
+        return;
+    }
+    if inventory_ui.is_busy() {
+        return;
+    }
+    let Some(coord) = cursor_grid_coord.0 else {
+        return;
+    };
+    if let Some(grid_bounds) = &grid_bounds {
+        if !grid_bounds.contains(coord) {
+            return;
+        }
+    }
+    let inventory_entity = inventory.entity();
+    let Some(mut inventory) = inventory.get_mut() else {
+        return;
+    };
+    let (Some(slot), Some(selected_item)) = (inventory.selected_slot(), inventory.selected_item())
+    else {
+        return;
+    };
+    if cooldowns.is_some_and(|cooldowns| cooldowns.is_cooling_down(inventory_entity, slot)) {
+        return;
+    }
+    let Some((_, sprite, transform, grid_override, container_item)) = preview_items
+        .iter()
+        .find(|(item_code, ..)| **item_code == selected_item.code)
+    else {
+        return;
+    };
+    let layer = selected_item.layer;
+    let position = transform.translation.truncate();
+    // Items on an overridden grid are compared by the world area of their cell
+    let override_rect = grid_override
+        .map(|grid_override| grid_override.cell_bounds(grid_override.cell_at(position)));
+    let sub_cell = (override_rect.is_none() && selected_item.sub_cell)
+        .then(|| grid_settings.sub_cell_at(position));
+    let occupied = match (override_rect, sub_cell) {
+        (Some(rect), _) => {
+            rect_map.overlapping(layer, rect).next().is_some()
+                || cells_overlapping(rect.min, rect.max, &grid_settings)
+                    .iter()
+                    .any(|coord| grid_map.contains(layer, coord))
+        }
+        // Taken by another sub-cell item, a whole cell item or an overridden grid item
+        (None, Some((coord, sub_cell))) => {
+            let sub_size = grid_settings.size / grid_settings.snap_divisions.max(1) as f32;
+            let min = coord.min_corner(grid_settings.size)
+                + Vec2::new(sub_cell.x as f32, sub_cell.y as f32) * sub_size;
+            sub_cell_map.contains(coord, sub_cell)
+                || grid_map.contains(layer, coord)
+                || rect_map
+                    .overlapping(layer, Rect::from_corners(min, min + sub_size))
+                    .next()
+                    .is_some()
+        }
+        (None, None) => {
+            grid_map.contains(layer, coord)
+                || rect_map
+                    .overlapping(layer, coord.bounds(&grid_settings))
+                    .next()
+                    .is_some()
+        }
+    };
+    if occupied {
+        return;
+    }
+    let mut transform = *transform;
+    transform.translation.z += layer.z_offset();
+    let mut placed = commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: sprite.color.with_a(1.0),
+                ..Default::default()
+            },
+            transform,
+            visibility: Visibility::Visible,
+            ..Default::default()
+        },
+        layer,
+    ));
+    match (override_rect, sub_cell) {
+        (Some(rect), _) => rect_map.insert(layer, rect, placed.id()),
+        (None, Some((coord, sub_cell))) => {
+            sub_cell_map.insert(coord, sub_cell, placed.id());
+        }
+        (None, None) => {
+            // Whole cell snapping would move sub-cell items off their sub-cell,
+            // registered in the grid map along with other snapped entities
+            placed.insert(SnapToGrid {
+                mode: grid_settings.snap_mode,
+                layer_z: None,
+                register: true,
+            });
+        }
+    }
+    if let Some(container_item) = container_item {
+        placed.insert((Container, DynInventory::with_capacity(container_item.slots)));
+    }
+    let placed = placed.id();
+    if !**infinite_placement
+        && inventory.take_one(slot).is_ok()
+        && inventory.get_item(slot).is_none()
+    {
+        inventory.clear_selection();
+    }
+    if placement_shake.is_some_and(|placement_shake| **placement_shake) {
+        shake(
+            &mut commands,
+            placed,
+            Vec2::splat(4.0),
+            Duration::from_millis(200),
+        );
+    }
+}
+
+/// Removes finished shakes so placed items do not keep their animators
+pub fn remove_finished_shakes(
+    mut commands: Commands,
+    mut completed: EventReader<AnimationCompleted>,
+    shakes: Query<&Animator<TranslationShakeLens>>,
+) {
+    for event in completed.iter() {
+        if shakes
+            .get(event.entity)
+            .is_ok_and(|animator| animator.is_completed())
+        {
+            commands
+                .entity(event.entity)
+                .remove::<Animator<TranslationShakeLens>>();
+        }
+    }
+}
+
+/// Scale of a [`DroppedItem`] relative to the preview of its item
+const DROPPED_ITEM_SCALE: f32 = 0.5;
+const DROPPED_ITEM_Z: f32 = 5.0;
+
+/// Drops one of the selected item of the active inventory with
+/// [`InventoryKeyBindings::drop_key`], as a [`DroppedItem`] looking like its preview
+///
+/// The item lands on the grid cell under the cursor, without a cursor a cell in front of the
+/// first [`ItemCollector`]. Ignored while dragging.
+#[allow(clippy::too_many_arguments)]
+pub fn drop_selected_item(
+    mut commands: Commands,
+    key: Res<Input<KeyCode>>,
+    bindings: Res<InventoryKeyBindings>,
+    dragged_item: Option<Res<DraggedItem>>,
+    cursor_grid_coord: Res<CursorGridCoord>,
+    grid_settings: Res<GridSettings>,
+    collectors: Query<&Transform, With<ItemCollector>>,
+    preview_items: Query<(&ItemCode, &Sprite, &Transform), With<ItemPreview>>,
+    mut inventory: ActiveInventoryQueryMut,
+    mut dropped: EventWriter<ItemDropped>,
+) {
+    if !bindings.drop_key.is_some_and(|drop| key.just_pressed(drop))
+        || is_dragging(dragged_item.as_deref())
+    {
+        return;
+    }
+    let Some(mut inventory) = inventory.get_mut() else {
+        return;
+    };
+    let Some(slot) = inventory.selected_slot() else {
+        return;
+    };
+    let Some((_, sprite, preview)) = inventory.get_item(slot).and_then(|item| {
+        preview_items
+            .iter()
+            .find(|(item_code, ..)| **item_code == item.code)
+    }) else {
+        return;
+    };
+    let size = preview.scale.truncate() * DROPPED_ITEM_SCALE;
+    let position = match (cursor_grid_coord.0, collectors.iter().next()) {
+        (Some(coord), _) => coord.translation(grid_settings.size),
+        // Clear of the collector so it is not picked up right away
+        (None, Some(collector)) => {
+            collector.translation.truncate()
+                + Vec2::X * (collector.scale.x / 2.0 + grid_settings.size + size.x / 2.0)
+        }
+        (None, None) => return,
+    };
+    let Ok(item) = inventory.take_one(slot) else {
+        return;
+    };
+    if inventory.get_item(slot).is_none() {
+        inventory.clear_selection();
+    }
+    let entity = commands
+        .spawn((
+            DroppedItem { item: item.clone() },
+            SpriteBundle {
+                sprite: sprite.clone(),
+                transform: Transform::from_translation(position.extend(DROPPED_ITEM_Z))
+                    .with_scale(size.extend(1.0)),
+                ..Default::default()
+            },
+        ))
+        .id();
+    dropped.send(ItemDropped {
+        entity,
+        item,
+        position,
+    });
+}
+
+/// Moves the [`DroppedItem`]s an [`ItemCollector`] overlaps into the [`BaseInventory`]
+///
+/// What does not fit stays dropped, only the count left is updated. A drop under several
+/// collectors is picked up once.
+pub fn pick_up_dropped_items(
+    mut commands: Commands,
+    collectors: Query<&Transform, With<ItemCollector>>,
+    mut drops: Query<(Entity, &mut DroppedItem, &Transform), Without<ItemCollector>>,
+    mut inventory: ResMut<BaseInventory>,
+) {
+    let bounds = |transform: &Transform| {
+        Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+    };
+    // Despawned at the end of the frame, still in the query for the next collectors
+    let mut picked_up = HashSet::new();
+    for collector in collectors.iter().map(bounds) {
+        for (entity, mut dropped, transform) in drops.iter_mut() {
+            if picked_up.contains(&entity) || collector.intersect(bounds(transform)).is_empty() {
+                continue;
+            }
+            // A full inventory is only marked changed once something fits
+            match inventory
+                .bypass_change_detection()
+                .add_item(dropped.item.clone())
+            {
+                Ok(_) => {
+                    inventory.set_changed();
+                    commands.entity(entity).despawn_recursive();
+                    picked_up.insert(entity);
+                }
+                Err(left) if left.count != dropped.item.count => {
+                    inventory.set_changed();
+                    dropped.item = left;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+pub fn log_selected_item(
+    inventory: ActiveInventoryQuery,
+    preview_items: Query<(&ItemCode, &Visibility), With<ItemPreview>>,
+) {
+    if let Some(item) = inventory
+        .get()
+        .and_then(|inventory| inventory.selected_item())
+    {
+        let visible = preview_items
+            .iter()
+            .find(|(item_code, _)| **item_code == item.code)
+            .map(|(_, v)| v);
+        info!("Selected: {} - {:?}", item.code.0, visible);
+    }
+}
@@ -1,222 +1,4740 @@
-use std::array;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    error::Error,
+    fmt,
+    ops::{Deref, RangeInclusive},
+    time::Duration,
+};
 
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::{
+    ecs::{schedule::BoxedScheduleLabel, system::SystemParam},
+    prelude::*,
+    sprite::Anchor,
+    window::PrimaryWindow,
+};
 
-use crate::items::{Item, ItemCode, ItemImage, ItemPreview};
+use crate::{
+    animation::{
+        Animation, AnimationCompleted, AnimationCurve, AnimationSettings, Animator, Repeat,
+        ScalePulseLens,
+    },
+    grid::{CursorWorldPosition, GridCursorBlocker},
+    hotbar::{
+        clear_selection_on_input, drop_selected_item, pick_up_dropped_items, place_selected_item,
+        remove_finished_shakes, scroll_select_item, select_item, show_selected_item,
+        HotbarScrollSettings, InfinitePlacement, InventoryKeyBindings, PlacementShake,
+    },
+    inventory_ui::InventoryUiPlugin,
+    items::{Item, ItemCategory, ItemCode, ItemDropped, ItemImage, ItemPreview, ItemRegistry},
+    nine_slice::{spawn_nine_slice, tint_nine_slice_pieces, NineSlice, NineSlicePiece},
+};
 
-#[derive(Resource, Deref, DerefMut, Default)]
-pub struct BaseInventory(pub Inventory<9>);
+/// Slots of the default [`BaseInventory`]
+pub const BASE_INVENTORY_SLOTS: usize = 9;
 
-#[derive(Resource)]
-pub struct Inventory<const N: usize> {
-    items: [Option<Item>; N], // use 1-indexed
-    selected: usize,          // 0: no selection
-}
+/// Hotbar of the player, [`BASE_INVENTORY_SLOTS`] slots unless grown or shrunk at runtime
+///
+/// The bar spawned by [`spawn_base_inventory`] has as many slots as the inventory,
+/// [`resize_base_inventory_bar`] respawns it when the inventory grows or shrinks.
+#[derive(Resource, Deref, DerefMut)]
+pub struct BaseInventory(pub DynInventory);
 
-impl<const N: usize> Default for Inventory<N> {
+impl Default for BaseInventory {
     fn default() -> Self {
-        Self {
-            items: array::from_fn(|_i| None),
-            selected: 0,
-        }
+        Self(DynInventory::with_capacity(BASE_INVENTORY_SLOTS))
     }
 }
 
-// TODO: perform more bound checks (upper-bound)
-impl<const N: usize> Inventory<N> {
-    pub fn selected_slot(&self) -> Option<usize> {
-        if self.selected == 0 {
-            return None;
+/// Entity whose [`DynInventory`] component the hotbar shows and the selection and
+/// placement systems work on, None for the [`BaseInventory`] resource
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Deref, DerefMut)]
+pub struct ActiveInventory(pub Option<Entity>);
+
+/// Read access to the inventory picked by [`ActiveInventory`]
+///
+/// Apps without the [`ActiveInventory`] resource get the [`BaseInventory`].
+#[derive(SystemParam)]
+pub struct ActiveInventoryQuery<'w, 's> {
+    active: Option<Res<'w, ActiveInventory>>,
+    base: Option<Res<'w, BaseInventory>>,
+    inventories: Query<'w, 's, Ref<'static, DynInventory>>,
+}
+
+impl ActiveInventoryQuery<'_, '_> {
+    /// Entity of the active inventory, None for the [`BaseInventory`]
+    pub fn entity(&self) -> Option<Entity> {
+        self.active.as_deref().and_then(|active| active.0)
+    }
+
+    pub fn get(&self) -> Option<&DynInventory> {
+        match self.active.as_deref().and_then(|active| active.0) {
+            Some(entity) => self.inventories.get(entity).ok().map(Ref::into_inner),
+            None => self.base.as_deref().map(|base| &base.0),
         }
-        Some(self.selected)
     }
 
-    pub fn clear_selection(&mut self) {
-        self.selected = 0;
+    /// Inventory shown in `panel`
+    pub fn for_panel(&self, panel: &InventoryPanel) -> Option<&DynInventory> {
+        match panel.inventory {
+            Some(entity) => self.inventories.get(entity).ok().map(Ref::into_inner),
+            None => self.get(),
+        }
     }
 
-    pub fn select_item(&mut self, selection: usize) {
-        self.selected = selection;
+    /// Whether the inventory shown in `panel` changed, or `panel` switched to another one,
+    /// since the system last ran
+    ///
+    /// True when the inventory is gone, there is no telling what the panel showed.
+    pub fn panel_changed(&self, panel: &InventoryPanel) -> bool {
+        let entity = match panel.inventory {
+            Some(entity) => Some(entity),
+            None if self
+                .active
+                .as_ref()
+                .is_some_and(|active| active.is_changed()) =>
+            {
+                return true;
+            }
+            None => self.active.as_deref().and_then(|active| active.0),
+        };
+        match entity {
+            Some(entity) => self
+                .inventories
+                .get(entity)
+                .map_or(true, |inventory| inventory.is_changed()),
+            None => self.base.as_ref().is_none_or(|base| base.is_changed()),
+        }
     }
+}
 
-    pub fn selected_item(&self) -> Option<&Item> {
-        if self.selected == 0 {
-            return None;
+/// Write access to the inventory picked by [`ActiveInventory`]
+#[derive(SystemParam)]
+pub struct ActiveInventoryQueryMut<'w, 's> {
+    active: Option<Res<'w, ActiveInventory>>,
+    base: Option<ResMut<'w, BaseInventory>>,
+    inventories: Query<'w, 's, &'static mut DynInventory>,
+}
+
+impl ActiveInventoryQueryMut<'_, '_> {
+    /// Entity of the active inventory, None for the [`BaseInventory`]
+    pub fn entity(&self) -> Option<Entity> {
+        self.active.as_deref().and_then(|active| active.0)
+    }
+
+    pub fn get(&self) -> Option<&DynInventory> {
+        match self.active.as_deref().and_then(|active| active.0) {
+            Some(entity) => self.inventories.get(entity).ok(),
+            None => self.base.as_deref().map(|base| &base.0),
         }
-        self.items[self.selected - 1].as_ref()
     }
 
-    /// slot: 1-indexed
-    pub fn get_item(&self, slot: usize) -> Option<&Item> {
-        if slot == 0 {
-            return None;
+    pub fn get_mut(&mut self) -> Option<Mut<'_, DynInventory>> {
+        match self.active.as_deref().and_then(|active| active.0) {
+            Some(entity) => self.inventories.get_mut(entity).ok(),
+            None => self
+                .base
+                .as_mut()
+                .map(|base| base.reborrow().map_unchanged(|base| &mut base.0)),
         }
-        self.items[slot - 1].as_ref()
     }
 
-    /// slot: 1-indexed
-    pub fn put_item(&mut self, slot: usize, item: Item) {
-        self.items[slot - 1] = Some(item);
+    /// Inventory shown in `panel`
+    pub fn for_panel(&self, panel: &InventoryPanel) -> Option<&DynInventory> {
+        match panel.inventory {
+            Some(entity) => self.inventories.get(entity).ok(),
+            None => self.get(),
+        }
     }
 
-    /// slot: 1-indexed
-    pub fn remove_item(&mut self, slot: usize) -> Option<Item> {
-        let item = self.items[slot - 1].clone();
-        self.items[slot - 1] = None;
-        item
+    /// Inventory shown in `panel`
+    pub fn for_panel_mut(&mut self, panel: &InventoryPanel) -> Option<Mut<'_, DynInventory>> {
+        match panel.inventory {
+            Some(entity) => self.inventories.get_mut(entity).ok(),
+            None => self.get_mut(),
+        }
     }
 }
 
-#[derive(Component)]
-pub struct BaseInventoryBackground;
+/// Sent when the selected slot of an inventory changes, 0 is no selection
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionChanged {
+    /// Entity of the [`DynInventory`] component, None for the [`BaseInventory`]
+    pub inventory: Option<Entity>,
+    pub previous: usize,
+    pub current: usize,
+}
 
-#[derive(Component)]
-pub struct InventorySlotBackground {
-    pub base: Entity,
-    pub slot: usize,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotChange {
+    /// An item was put into the slot, replacing what was there
+    Put,
+    Removed,
+    /// The stack in the slot grew or shrank
+    CountChanged,
+    Locked,
+    Unlocked,
 }
 
-#[derive(Component)]
-pub struct InventorySlot {
-    pub base: Entity,
+/// Sent for every slot of an inventory a mutation touched
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryChanged {
+    /// Entity of the [`DynInventory`] component, None for the [`BaseInventory`]
+    pub inventory: Option<Entity>,
     pub slot: usize,
+    pub change: SlotChange,
+}
+
+/// Slots numbered from 1, slot 0 stands for no selection
+///
+/// Mutations are buffered as events, see [`send_inventory_events`] and
+/// [`MAX_PENDING_INVENTORY_EVENTS`].
+/// A component for entities with their own inventory, see [`ActiveInventory`].
+/// Serialized with its slots, locks and selection, pending events, slot filters and the weight
+/// limit are not saved.
+#[derive(Resource, Component, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DynInventory {
+    items: Vec<Option<Item>>, // use 1-indexed
+    selected: usize,          // 0: no selection
+    #[cfg_attr(feature = "serde", serde(skip))]
+    filters: BTreeMap<usize, SlotFilter>, // 1-indexed slot
+    #[cfg_attr(feature = "serde", serde(default))]
+    locked: BTreeSet<usize>, // 1-indexed slot
+    #[cfg_attr(feature = "serde", serde(skip))]
+    selection_events: VecDeque<SelectionChanged>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    slot_events: VecDeque<InventoryChanged>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    max_weight: Option<f32>,
+    /// Total weight of the items, None when it has to be summed again
+    #[cfg_attr(feature = "serde", serde(skip))]
+    weight: Option<f32>,
+}
+
+/// Events a [`DynInventory`] buffers of each kind until they are drained, past it the oldest
+/// are dropped
+///
+/// [`send_inventory_events`] drains the [`BaseInventory`] and the [`DynInventory`] components
+/// every frame. Other inventories, like an [`Inventory`] resource, only keep the latest ones.
+pub const MAX_PENDING_INVENTORY_EVENTS: usize = 1024;
+
+/// Equal up to the rounding of summing in another order
+fn same_weight(a: f32, b: f32) -> bool {
+    a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= 1e-4 * a.abs().max(b.abs())
+}
+
+fn push_pending<T>(events: &mut VecDeque<T>, event: T) {
+    if events.len() == MAX_PENDING_INVENTORY_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(event);
 }
 
+/// [`DynInventory`] starting with `N` slots
 #[derive(Resource, Deref, DerefMut)]
-pub struct BaseInventorySettings(pub InventorySettings);
+pub struct Inventory<const N: usize>(pub DynInventory);
 
-pub struct InventorySettings {
-    pub w_padding: f32,
-    pub w_mid_step: f32,
-    pub h_padding: f32,
-    // pub h_mid_step: f32,
-    pub slot_margin: f32,
-    pub slot_size: f32,
+impl<const N: usize> Default for Inventory<N> {
+    fn default() -> Self {
+        Self(DynInventory::with_capacity(N))
+    }
 }
 
-pub fn spawn_base_inventory(
-    mut commands: Commands,
-    settings: Res<BaseInventorySettings>,
-    primary_window: Query<&Window, With<PrimaryWindow>>,
-) {
-    let primary_window = primary_window.single();
-    let window_h = primary_window.height();
-    let _window_w = primary_window.width();
-    let window_padding = 40.0;
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for Inventory<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
 
-    let n_slots = 9;
-    let InventorySettings {
-        w_padding,
-        w_mid_step,
-        h_padding,
-        // h_mid_step,
-        slot_margin,
-        slot_size,
-    } = settings.0;
+/// Fails unless the saved inventory has `N` slots
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for Inventory<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let inventory = DynInventory::deserialize(deserializer)?;
+        if inventory.slot_count() != N {
+            return Err(serde::de::Error::custom(
+                InventoryFileError::SlotCountMismatch {
+                    saved: inventory.slot_count(),
+                    expected: N,
+                },
+            ));
+        }
+        Ok(Self(inventory))
+    }
+}
 
-    let pos = Vec2::new(0.0, -(window_h / 2.0) + window_padding);
+/// What loading does with items whose code is not known to the game
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownItemPolicy {
+    #[default]
+    Keep,
+    /// Leaves their slots empty
+    Drop,
+}
 
-    let w_total =
-        (2.0 * w_padding) + (n_slots as f32 * slot_size) + ((n_slots - 1) as f32 * w_mid_step);
+#[derive(Debug)]
+pub enum InventoryFileError {
+    /// The inventory was saved with another number of slots
+    SlotCountMismatch { saved: usize, expected: usize },
+    #[cfg(feature = "ron")]
+    Serialize(ron::Error),
+    #[cfg(feature = "ron")]
+    Parse(ron::error::SpannedError),
+    #[cfg(feature = "std-io")]
+    Io(std::io::Error),
+}
 
-    let h_total = (2.0 * h_padding) + slot_size;
+impl fmt::Display for InventoryFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SlotCountMismatch { saved, expected } => {
+                write!(f, "inventory saved with {saved} slots, expected {expected}")
+            }
+            #[cfg(feature = "ron")]
+            Self::Serialize(error) => write!(f, "failed to write inventory: {error}"),
+            #[cfg(feature = "ron")]
+            Self::Parse(error) => write!(f, "failed to read inventory: {error}"),
+            #[cfg(feature = "std-io")]
+            Self::Io(error) => write!(f, "inventory file: {error}"),
+        }
+    }
+}
 
-    let inventory_background = commands
-        .spawn((
-            BaseInventoryBackground,
-            SpriteBundle {
-                sprite: Sprite {
-                    color: Color::GRAY,
-                    ..Default::default()
-                },
-                transform: Transform::from_translation(Vec3::new(pos.x, pos.y, 42.0))
-                    .with_scale(Vec3::new(w_total, h_total, 1.0)),
-                visibility: Visibility::Visible,
-                ..Default::default()
-            },
-        ))
-        .id();
+impl Error for InventoryFileError {}
 
-    trace!("{w_total}-{h_total}");
-    trace!("---");
-    let x_start = pos.x - (w_total / 2.0) + w_padding + (slot_size / 2.0);
-    for i in 0..n_slots {
-        let x = x_start + (i as f32 * (slot_size + w_mid_step));
-        let y = pos.y;
-        trace!("{x}-{y}");
+#[cfg(feature = "serde")]
+impl DynInventory {
+    /// Replaces the slots, the locks and the selection with those of `saved`, which must have
+    /// as many slots, recording a change for every slot
+    ///
+    /// Items `is_known` rejects are handled by `unknown_items`. An out of range saved
+    /// selection is cleared.
+    pub fn restore(
+        &mut self,
+        saved: DynInventory,
+        unknown_items: UnknownItemPolicy,
+        is_known: impl Fn(ItemCode) -> bool,
+    ) -> Result<(), InventoryFileError> {
+        if saved.slot_count() != self.slot_count() {
+            return Err(InventoryFileError::SlotCountMismatch {
+                saved: saved.slot_count(),
+                expected: self.slot_count(),
+            });
+        }
+        for (i, item) in saved.items.into_iter().enumerate() {
+            let item =
+                item.filter(|item| unknown_items == UnknownItemPolicy::Keep || is_known(item.code));
+            let was_empty = self.items[i].is_none();
+            self.items[i] = item;
+            if !(was_empty && self.items[i].is_none()) {
+                self.record_content(i + 1);
+            }
+        }
+        self.locked = saved.locked;
+        // Every slot was replaced
+        self.weight = Some(self.sum_weight());
+        let selected = if saved.selected <= self.slot_count() && !self.is_locked(saved.selected) {
+            saved.selected
+        } else {
+            0
+        };
+        self.set_selected(selected);
+        Ok(())
+    }
+}
 
-        commands.spawn((
-            InventorySlotBackground {
-                base: inventory_background,
-                slot: i + 1,
-            },
-            SpriteBundle {
-                sprite: Sprite {
-                    color: Color::rgba(0.9, 0.9, 0.9, 1.0),
-                    ..Default::default()
-                },
-                transform: Transform::from_translation(Vec3::new(x, y, 43.0))
-                    .with_scale(Vec3::new(slot_size, slot_size, 1.0)),
-                visibility: Visibility::Visible,
-                ..Default::default()
-            },
-        ));
+#[cfg(feature = "ron")]
+impl DynInventory {
+    pub fn to_ron(&self) -> Result<String, InventoryFileError> {
+        ron::ser::to_string_pretty(self, Default::default()).map_err(InventoryFileError::Serialize)
+    }
 
-        commands.spawn((
-            InventorySlot {
-                base: inventory_background,
-                slot: i + 1,
-            },
-            SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(slot_size - slot_margin, slot_size - slot_margin)),
-                    ..Default::default()
-                },
-                transform: Transform::from_translation(Vec3::new(x, y, 44.0)),
-                visibility: Visibility::Hidden,
-                ..Default::default()
-            },
-        ));
+    /// See [`DynInventory::restore`]
+    pub fn restore_ron(
+        &mut self,
+        ron: &str,
+        unknown_items: UnknownItemPolicy,
+        is_known: impl Fn(ItemCode) -> bool,
+    ) -> Result<(), InventoryFileError> {
+        let saved = ron::from_str(ron).map_err(InventoryFileError::Parse)?;
+        self.restore(saved, unknown_items, is_known)
+    }
+
+    #[cfg(feature = "std-io")]
+    pub fn save_ron(&self, path: impl AsRef<std::path::Path>) -> Result<(), InventoryFileError> {
+        std::fs::write(path, self.to_ron()?).map_err(InventoryFileError::Io)
+    }
+
+    /// See [`DynInventory::restore`]
+    #[cfg(feature = "std-io")]
+    pub fn load_ron(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        unknown_items: UnknownItemPolicy,
+        is_known: impl Fn(ItemCode) -> bool,
+    ) -> Result<(), InventoryFileError> {
+        let ron = std::fs::read_to_string(path).map_err(InventoryFileError::Io)?;
+        self.restore_ron(&ron, unknown_items, is_known)
     }
-    // inventory_background.with_children(|cb| {
-    //     for i in 0..n_slots {
-    //         let x = x_start + (i as f32 * (slot_size + w_mid_step));
-    //         let y = pos.y;
-    //         trace!("{x}-{y}");
-    //         cb.spawn((
-    //             InventorySlot,
-    //             SpriteBundle {
-    //                 sprite: Sprite {
-    //                     color: Color::rgba(0.9, 0.9, 0.9, 1.0),
-    //                     ..Default::default()
-    //                 },
-    //                 transform: Transform::from_translation(Vec3::new(x, y, 11.0))
-    //                     * Transform::from_scale(Vec3::new(slot_size, slot_size, 1.0)),
-    //                 visibility: Visibility::Visible,
-    //                 ..Default::default()
-    //             },
-    //         ));
-    //     }
-    // });
 }
 
-pub fn render_items_in_base_inventory(
+/// Saves the [`BaseInventory`] to `path`, see [`save_inventory_system`]
+#[cfg(feature = "std-io")]
+#[derive(Event, Debug, Clone)]
+pub struct SaveInventoryRequest {
+    pub path: std::path::PathBuf,
+}
+
+/// Loads the [`BaseInventory`] from `path`, see [`load_inventory_system`]
+///
+/// Item codes without an [`ItemPreview`] prototype are unknown.
+#[cfg(feature = "std-io")]
+#[derive(Event, Debug, Clone)]
+pub struct LoadInventoryRequest {
+    pub path: std::path::PathBuf,
+    pub unknown_items: UnknownItemPolicy,
+}
+
+#[cfg(feature = "std-io")]
+pub fn save_inventory_system(
     inventory: Res<BaseInventory>,
-    // images: Res<Assets<Image>>,
-    preview_items: Query<(&ItemCode, &ItemImage), With<ItemPreview>>,
-    mut slot_items: Query<(&InventorySlot, &mut Handle<Image>, &mut Visibility)>,
+    mut requests: EventReader<SaveInventoryRequest>,
 ) {
-    for (slot, mut slot_image, mut visibility) in slot_items.iter_mut() {
-        if let Some(item) = &inventory.get_item(slot.slot) {
-            let Some((_, item_image)) = preview_items
-                .iter()
-                .find(|(item_code, _)| **item_code == item.code)
-            else {
-                continue;
+    for request in requests.iter() {
+        if let Err(error) = inventory.save_ron(&request.path) {
+            error!("Saving inventory to {:?}: {error}", request.path);
+        }
+    }
+}
+
+/// Failed loads leave the inventory as it was
+#[cfg(feature = "std-io")]
+pub fn load_inventory_system(
+    mut inventory: ResMut<BaseInventory>,
+    preview_items: Query<&ItemCode, With<ItemPreview>>,
+    mut requests: EventReader<LoadInventoryRequest>,
+) {
+    for request in requests.iter() {
+        let is_known = |code| preview_items.iter().any(|item_code| *item_code == code);
+        if let Err(error) = inventory.load_ron(&request.path, request.unknown_items, is_known) {
+            error!("Loading inventory from {:?}: {error}", request.path);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryError {
+    /// Slot is 0 or above the slot count
+    SlotOutOfRange(usize),
+    SlotOccupied(usize),
+    SlotEmpty(usize),
+    /// The [`SlotFilter`] of the slot does not accept the item
+    SlotRejectsItem(usize),
+    /// See [`DynInventory::lock_slot`]
+    SlotLocked(usize),
+    /// The item in the slot does not stack, see [`DynInventory::split_stack`]
+    NotStackable(usize),
+    /// A split or take of no items
+    ZeroAmount,
+    /// See [`InventoryTransaction::take`]
+    NotEnoughItems(ItemCode),
+    /// See [`InventoryTransaction::give`]
+    NoRoom(ItemCode),
+    /// See [`DynInventory::set_max_weight`]
+    OverWeight,
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SlotOutOfRange(slot) => write!(f, "inventory slot {slot} out of range"),
+            Self::SlotOccupied(slot) => write!(f, "inventory slot {slot} is occupied"),
+            Self::SlotEmpty(slot) => write!(f, "inventory slot {slot} is empty"),
+            Self::SlotRejectsItem(slot) => write!(f, "inventory slot {slot} rejects the item"),
+            Self::SlotLocked(slot) => write!(f, "inventory slot {slot} is locked"),
+            Self::NotStackable(slot) => write!(f, "item in inventory slot {slot} does not stack"),
+            Self::ZeroAmount => write!(f, "split or take of zero items"),
+            Self::NotEnoughItems(code) => write!(f, "not enough items of code {}", code.0),
+            Self::NoRoom(code) => write!(f, "no room for items of code {}", code.0),
+            Self::OverWeight => write!(f, "item goes over the inventory weight limit"),
+        }
+    }
+}
+
+impl Error for InventoryError {}
+
+/// Limits the items a slot takes, see [`DynInventory::set_filter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlotFilter {
+    Category(ItemCategory),
+    /// Item codes in the range
+    Codes(RangeInclusive<usize>),
+}
+
+impl SlotFilter {
+    pub fn accepts(&self, item: &Item) -> bool {
+        match self {
+            Self::Category(category) => item.category == Some(*category),
+            Self::Codes(codes) => codes.contains(&item.code.0),
+        }
+    }
+}
+
+/// Order [`DynInventory::sort`] leaves the items in, partial stacks of a code are merged first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortStrategy {
+    /// Items keep their order, only the gaps between them are closed
+    CompactOnly,
+    #[default]
+    ByCode,
+    /// By category, items without one last, then by code
+    ByCategory,
+}
+
+/// What [`DynInventory::move_item`] does when the target slot holds an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MoveConflict {
+    #[default]
+    Fail,
+    Swap,
+}
+
+impl DynInventory {
+    /// `slots` empty slots
+    pub fn with_capacity(slots: usize) -> Self {
+        Self {
+            items: vec![None; slots],
+            ..Default::default()
+        }
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Adds `slots` empty slots after the last one
+    pub fn grow(&mut self, slots: usize) {
+        self.items.resize(self.items.len() + slots, None);
+    }
+
+    /// Drops the last `slots` slots, returns the items they held in slot order
+    ///
+    /// A selection on a dropped slot is cleared.
+    pub fn shrink(&mut self, slots: usize) -> Vec<Item> {
+        let len = self.items.len().saturating_sub(slots);
+        let evicted: Vec<_> = self.items.drain(len..).collect();
+        let mut items = Vec::new();
+        for (i, item) in evicted.into_iter().enumerate() {
+            if let Some(item) = item {
+                self.record(len + i + 1, SlotChange::Removed);
+                items.push(item);
+            }
+        }
+        self.shift_weight(-items.iter().map(Item::stack_weight).sum::<f32>());
+        if self.selected > len {
+            self.clear_selection();
+        }
+        self.filters.retain(|slot, _| *slot <= len);
+        self.locked.retain(|slot| *slot <= len);
+        items
+    }
+
+    /// slot: 1-indexed, None lets the slot take any item
+    ///
+    /// Items already in the slot stay.
+    pub fn set_filter(
+        &mut self,
+        slot: usize,
+        filter: Option<SlotFilter>,
+    ) -> Result<(), InventoryError> {
+        self.slot_index(slot)?;
+        match filter {
+            Some(filter) => self.filters.insert(slot, filter),
+            None => self.filters.remove(&slot),
+        };
+        Ok(())
+    }
+
+    pub fn filter(&self, slot: usize) -> Option<&SlotFilter> {
+        self.filters.get(&slot)
+    }
+
+    /// Whether the filter of `slot` lets `item` in, bounds are not checked
+    ///
+    /// Locked slots take nothing.
+    pub fn accepts(&self, slot: usize, item: &Item) -> bool {
+        !self.is_locked(slot)
+            && self
+                .filters
+                .get(&slot)
+                .is_none_or(|filter| filter.accepts(item))
+    }
+
+    /// Locks `slot`, it can not be selected and takes no items, items already in it stay
+    ///
+    /// A selection on the slot is cleared. slot: 1-indexed
+    pub fn lock_slot(&mut self, slot: usize) -> Result<(), InventoryError> {
+        self.slot_index(slot)?;
+        if self.locked.insert(slot) {
+            self.record(slot, SlotChange::Locked);
+            if self.selected == slot {
+                self.clear_selection();
+            }
+        }
+        Ok(())
+    }
+
+    /// slot: 1-indexed
+    pub fn unlock_slot(&mut self, slot: usize) -> Result<(), InventoryError> {
+        self.slot_index(slot)?;
+        if self.locked.remove(&slot) {
+            self.record(slot, SlotChange::Unlocked);
+        }
+        Ok(())
+    }
+
+    /// slot: 1-indexed, false out of range
+    pub fn is_locked(&self, slot: usize) -> bool {
+        self.locked.contains(&slot)
+    }
+
+    pub fn selected_slot(&self) -> Option<usize> {
+        if self.selected == 0 {
+            return None;
+        }
+        Some(self.selected)
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.set_selected(0);
+    }
+
+    /// selection: 1-indexed, 0 clears the selection
+    pub fn select_item(&mut self, selection: usize) -> Result<(), InventoryError> {
+        if selection > self.items.len() {
+            return Err(InventoryError::SlotOutOfRange(selection));
+        }
+        if self.is_locked(selection) {
+            return Err(InventoryError::SlotLocked(selection));
+        }
+        self.set_selected(selection);
+        Ok(())
+    }
+
+    fn set_selected(&mut self, selection: usize) {
+        if self.selected != selection {
+            push_pending(
+                &mut self.selection_events,
+                SelectionChanged {
+                    inventory: None,
+                    previous: self.selected,
+                    current: selection,
+                },
+            );
+            self.selected = selection;
+        }
+    }
+
+    /// slot: 1-indexed
+    fn record(&mut self, slot: usize, change: SlotChange) {
+        push_pending(
+            &mut self.slot_events,
+            InventoryChanged {
+                inventory: None,
+                slot,
+                change,
+            },
+        );
+    }
+
+    /// Records `Put` or `Removed` depending on what the slot holds now
+    fn record_content(&mut self, slot: usize) {
+        let change = match self.items[slot - 1] {
+            Some(_) => SlotChange::Put,
+            None => SlotChange::Removed,
+        };
+        self.record(slot, change);
+    }
+
+    pub fn drain_selection_events(&mut self) -> impl Iterator<Item = SelectionChanged> + '_ {
+        self.selection_events.drain(..)
+    }
+
+    pub fn drain_slot_events(&mut self) -> impl Iterator<Item = InventoryChanged> + '_ {
+        self.slot_events.drain(..)
+    }
+
+    pub fn has_pending_events(&self) -> bool {
+        !self.selection_events.is_empty() || !self.slot_events.is_empty()
+    }
+
+    /// Moves the selection `steps` slots forward, backward when negative, wrapping around
+    ///
+    /// With `include_none` the no selection state sits between slot N and slot 1,
+    /// otherwise it is skipped and cycling from it starts at slot 1 or slot N.
+    /// Locked slots are skipped, nothing changes when every slot is locked.
+    pub fn cycle_selection(&mut self, steps: i32, include_none: bool) {
+        if steps == 0 || self.items.is_empty() {
+            return;
+        }
+        let direction = steps.signum() as i64;
+        let n = self.items.len() as i64;
+        let next = |selected: usize| {
+            if include_none {
+                return (selected as i64 + direction).rem_euclid(n + 1) as usize;
+            }
+            let current = match selected {
+                0 if direction > 0 => 0,
+                0 => 1,
+                selected => selected as i64,
             };
-            // trace!("Item in slot {}, code: {}", slot.slot, item.code.0);
-            *slot_image = item_image.0.clone();
-            *visibility = Visibility::Visible;
+            ((current - 1 + direction).rem_euclid(n) + 1) as usize
+        };
+        let mut selected = self.selected;
+        for _ in 0..steps.unsigned_abs() {
+            selected = next(selected);
+            // At most a full round to get past the locked slots
+            for _ in 0..n {
+                if !self.is_locked(selected) {
+                    break;
+                }
+                selected = next(selected);
+            }
         }
+        if !self.is_locked(selected) {
+            self.set_selected(selected);
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&Item> {
+        self.get_item(self.selected)
+    }
+
+    /// slot: 1-indexed
+    pub fn get_item(&self, slot: usize) -> Option<&Item> {
+        self.items.get(slot.checked_sub(1)?)?.as_ref()
+    }
+
+    /// All slots in order, index 0 is slot 1
+    pub fn slots(&self) -> &[Option<Item>] {
+        &self.items
+    }
+
+    /// Every slot with its 1-indexed number, empty ones included
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Option<&Item>)> + '_ {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (i + 1, item.as_ref()))
+    }
+
+    /// Filled slots with their 1-indexed number
+    ///
+    /// ```
+    /// # use bevy_toolbox::{inventory::DynInventory, items::{Item, ItemCode}};
+    /// let mut inventory = DynInventory::with_capacity(4);
+    /// let item = Item::new(ItemCode(1), 3, 10);
+    /// inventory.put_item(2, item.clone()).unwrap();
+    /// inventory.put_item(4, item).unwrap();
+    ///
+    /// let slots: Vec<_> = inventory.iter_items().map(|(slot, _)| slot).collect();
+    /// assert_eq!(slots, [2, 4]);
+    /// ```
+    pub fn iter_items(&self) -> impl Iterator<Item = (usize, &Item)> + '_ {
+        self.iter().filter_map(|(slot, item)| Some((slot, item?)))
+    }
+
+    /// Filled slots with their 1-indexed number, for edits in place
+    ///
+    /// Edits are not recorded as [`InventoryChanged`] events. Emptying a slot needs
+    /// [`DynInventory::remove_item`].
+    ///
+    /// ```
+    /// # use bevy_toolbox::{inventory::DynInventory, items::{Item, ItemCode}};
+    /// # let mut inventory = DynInventory::with_capacity(2);
+    /// # let item = Item::new(ItemCode(1), 3, 10);
+    /// # inventory.put_item(1, item).unwrap();
+    /// // Food goes bad one item at a time
+    /// for (_, item) in inventory.iter_mut() {
+    ///     item.count = item.count.saturating_sub(1).max(1);
+    /// }
+    /// assert_eq!(inventory.get_item(1).unwrap().count, 2);
+    /// ```
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut Item)> + '_ {
+        // Counts may change
+        self.weight = None;
+        self.items
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, item)| Some((i + 1, item.as_mut()?)))
+    }
+
+    /// slot: 1-indexed
+    pub fn contains_slot(&self, slot: usize) -> bool {
+        self.slot_index(slot).is_ok()
+    }
+
+    /// slot: 1-indexed, must be empty and unlocked
+    pub fn put_item(&mut self, slot: usize, item: Item) -> Result<(), InventoryError> {
+        let i = self.slot_index(slot)?;
+        if self.is_locked(slot) {
+            return Err(InventoryError::SlotLocked(slot));
+        }
+        if self.items[i].is_some() {
+            return Err(InventoryError::SlotOccupied(slot));
+        }
+        if !self.accepts(slot, &item) {
+            return Err(InventoryError::SlotRejectsItem(slot));
+        }
+        if !self.fits_weight(item.stack_weight()) {
+            return Err(InventoryError::OverWeight);
+        }
+        self.put_item_unchecked(slot, item);
+        Ok(())
+    }
+
+    /// Replaces whatever `slot` holds, ignoring its filter and the weight limit, panics when
+    /// `slot` is out of range
+    ///
+    /// slot: 1-indexed
+    pub fn put_item_unchecked(&mut self, slot: usize, item: Item) {
+        let weight = item.stack_weight();
+        let old = self.items[slot - 1].replace(item);
+        self.shift_weight(weight - old.map_or(0.0, |old| old.stack_weight()));
+        self.record(slot, SlotChange::Put);
+    }
+
+    /// slot: 1-indexed
+    pub fn remove_item(&mut self, slot: usize) -> Result<Item, InventoryError> {
+        let i = self.slot_index(slot)?;
+        if self.items[i].is_none() {
+            return Err(InventoryError::SlotEmpty(slot));
+        }
+        Ok(self
+            .remove_item_unchecked(slot)
+            .expect("slot holds an item"))
+    }
+
+    /// Panics when `slot` is out of range
+    ///
+    /// slot: 1-indexed
+    pub fn remove_item_unchecked(&mut self, slot: usize) -> Option<Item> {
+        let item = self.items[slot - 1].take();
+        if let Some(item) = &item {
+            self.shift_weight(-item.stack_weight());
+            self.record(slot, SlotChange::Removed);
+        }
+        item
+    }
+
+    /// Takes a single item off the stack in `slot`, emptying the slot with the last one
+    ///
+    /// slot: 1-indexed
+    pub fn take_one(&mut self, slot: usize) -> Result<Item, InventoryError> {
+        self.take(slot, 1)
+    }
+
+    /// Takes up to `count` items off the stack in `slot`, emptying the slot when none are left
+    ///
+    /// slot: 1-indexed
+    pub fn take(&mut self, slot: usize, count: u32) -> Result<Item, InventoryError> {
+        let i = self.slot_index(slot)?;
+        let stack = self.items[i]
+            .as_mut()
+            .ok_or(InventoryError::SlotEmpty(slot))?;
+        let mut item = stack.clone();
+        item.count = count.min(stack.count);
+        if stack.count > item.count {
+            stack.count -= item.count;
+            self.record(slot, SlotChange::CountChanged);
+        } else {
+            self.items[i] = None;
+            self.record(slot, SlotChange::Removed);
+        }
+        self.shift_weight(-item.stack_weight());
+        Ok(item)
+    }
+
+    /// Splits `min(amount, count)` items off the stack in `slot`, emptying the slot when none
+    /// are left
+    ///
+    /// Fails for items that do not stack and for an `amount` of 0. slot: 1-indexed
+    pub fn split_stack(&mut self, slot: usize, amount: u32) -> Result<Item, InventoryError> {
+        let i = self.slot_index(slot)?;
+        let stack = self.items[i]
+            .as_ref()
+            .ok_or(InventoryError::SlotEmpty(slot))?;
+        if stack.max_stack <= 1 {
+            return Err(InventoryError::NotStackable(slot));
+        }
+        if amount == 0 {
+            return Err(InventoryError::ZeroAmount);
+        }
+        self.take(slot, amount)
+    }
+
+    /// Splits off the larger half of the stack in `slot`, the whole of a single item
+    ///
+    /// slot: 1-indexed
+    pub fn take_half(&mut self, slot: usize) -> Result<Item, InventoryError> {
+        let count = self
+            .get_item(slot)
+            .map_or(0, |stack| stack.count.div_ceil(2));
+        self.split_stack(slot, count)
+    }
+
+    /// 1-indexed, None when every slot holds an item
+    pub fn first_empty_slot(&self) -> Option<usize> {
+        self.iter()
+            .find_map(|(slot, item)| item.is_none().then_some(slot))
+    }
+
+    /// Every slot holds an item, stacks may still have room, see [`DynInventory::add_item`]
+    pub fn is_full(&self) -> bool {
+        self.first_empty_slot().is_none()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter_items().next().is_none()
+    }
+
+    /// Slots holding an item
+    pub fn occupied_count(&self) -> usize {
+        self.iter_items().count()
+    }
+
+    /// Same as [`DynInventory::slot_count`]
+    pub fn capacity(&self) -> usize {
+        self.slot_count()
+    }
+
+    /// Items of `code` summed over all stacks
+    pub fn count_of(&self, code: ItemCode) -> u32 {
+        self.iter_items()
+            .filter(|(_, item)| item.code == code)
+            .map(|(_, item)| item.count)
+            .sum()
+    }
+
+    pub fn has(&self, code: ItemCode) -> bool {
+        self.iter_items().any(|(_, item)| item.code == code)
+    }
+
+    /// How many of `item` [`DynInventory::add_item`] takes, `u32::MAX` when an empty slot
+    /// accepts it and the weight limit does not get in the way
+    pub fn room_for(&self, item: &Item) -> u32 {
+        let mut room = 0u32;
+        for (slot, stack) in self.iter() {
+            if !self.accepts(slot, item) {
+                continue;
+            }
+            match stack {
+                None => {
+                    room = u32::MAX;
+                    break;
+                }
+                Some(stack) if stack.code == item.code => {
+                    room = room.saturating_add(stack.max_stack.saturating_sub(stack.count));
+                }
+                Some(_) => {}
+            }
+        }
+        room.min(self.weight_room_for(item))
+    }
+
+    /// None for no limit
+    pub fn max_weight(&self) -> Option<f32> {
+        self.max_weight
+    }
+
+    /// Limits the total weight of the items, see [`Item::weight`]
+    ///
+    /// Items already in the inventory stay, even when they weigh more.
+    pub fn set_max_weight(&mut self, max_weight: Option<f32>) {
+        self.max_weight = max_weight;
+    }
+
+    /// Total weight of the items
+    pub fn current_weight(&self) -> f32 {
+        match self.weight {
+            Some(weight) => {
+                debug_assert!(
+                    same_weight(weight, self.sum_weight()),
+                    "stale inventory weight"
+                );
+                weight
+            }
+            None => self.sum_weight(),
+        }
+    }
+
+    /// How many of `item` fit under the weight limit, `u32::MAX` without a limit or for
+    /// weightless items
+    pub fn weight_room_for(&self, item: &Item) -> u32 {
+        let Some(max_weight) = self.max_weight.filter(|_| item.weight > 0.0) else {
+            return u32::MAX;
+        };
+        let left = max_weight - self.current_weight();
+        if left < 0.0 {
+            return 0;
+        }
+        // Corrected by one for rounding
+        let mut count = (left / item.weight) as u32;
+        if count > 0 && !self.fits_weight(item.weight * count as f32) {
+            count -= 1;
+        } else if self.fits_weight(item.weight * count.saturating_add(1) as f32) {
+            count = count.saturating_add(1);
+        }
+        count
+    }
+
+    /// Whether `weight` more stays under the weight limit, exactly reaching it fits
+    fn fits_weight(&self, weight: f32) -> bool {
+        self.max_weight
+            .is_none_or(|max_weight| self.current_weight() + weight <= max_weight)
+    }
+
+    /// Whether trading `leaving` weight for `arriving` weight stays under the weight limit,
+    /// trades that do not add weight always fit
+    fn fits_weight_trade(&self, leaving: f32, arriving: f32) -> bool {
+        arriving <= leaving || self.fits_weight(arriving - leaving)
+    }
+
+    fn sum_weight(&self) -> f32 {
+        self.iter_items().map(|(_, item)| item.stack_weight()).sum()
+    }
+
+    /// Adds `delta` to the cached weight, call after changing `items`
+    ///
+    /// Sums the slots again when nothing is cached or the weight stopped being finite.
+    fn shift_weight(&mut self, delta: f32) {
+        let weight = self
+            .weight
+            .map(|weight| weight + delta)
+            .filter(|weight| weight.is_finite());
+        self.weight = Some(weight.unwrap_or_else(|| self.sum_weight()));
+    }
+
+    /// Moves as much of the stack in `from` into `other` as it takes, like
+    /// [`DynInventory::add_item`], returns how many moved
+    ///
+    /// The rest stays in `from`. from: 1-indexed
+    pub fn quick_transfer(
+        &mut self,
+        from: usize,
+        other: &mut DynInventory,
+    ) -> Result<u32, InventoryError> {
+        let i = self.slot_index(from)?;
+        let item = self.items[i]
+            .as_ref()
+            .ok_or(InventoryError::SlotEmpty(from))?;
+        let moved = other.room_for(item).min(item.count);
+        if moved > 0 {
+            let item = self.take(from, moved)?;
+            other.add_item(item).expect("room was checked");
+        }
+        Ok(moved)
+    }
+
+    /// Tops up stacks of the same code first, in slot order, then puts the rest into the
+    /// first empty slot, skipping locked slots and slots whose filter rejects the item
+    ///
+    /// Returns the 1-indexed slot holding the last of the item, or the part that did
+    /// not fit when the inventory is full or the rest would go over the weight limit.
+    pub fn add_item(&mut self, mut item: Item) -> Result<usize, Item> {
+        let over_weight = item.count.saturating_sub(self.weight_room_for(&item));
+        if over_weight == 0 {
+            return self.add_item_unweighed(item);
+        }
+        if over_weight == item.count {
+            return Err(item);
+        }
+        let rest = Item {
+            count: over_weight,
+            ..item.clone()
+        };
+        item.count -= over_weight;
+        match self.add_item_unweighed(item) {
+            Ok(_) => Err(rest),
+            Err(mut left) => {
+                left.count += rest.count;
+                Err(left)
+            }
+        }
+    }
+
+    /// [`DynInventory::add_item`] without the weight limit
+    fn add_item_unweighed(&mut self, mut item: Item) -> Result<usize, Item> {
+        for (i, stack) in self.items.iter_mut().enumerate() {
+            let Some(stack) = stack else {
+                continue;
+            };
+            if stack.code != item.code
+                || stack.count >= stack.max_stack
+                || self.locked.contains(&(i + 1))
+                || self
+                    .filters
+                    .get(&(i + 1))
+                    .is_some_and(|filter| !filter.accepts(&item))
+            {
+                continue;
+            }
+            let moved = item.count.min(stack.max_stack - stack.count);
+            stack.count += moved;
+            item.count -= moved;
+            if let Some(weight) = &mut self.weight {
+                *weight += item.weight * moved as f32;
+            }
+            push_pending(
+                &mut self.slot_events,
+                InventoryChanged {
+                    inventory: None,
+                    slot: i + 1,
+                    change: SlotChange::CountChanged,
+                },
+            );
+            if item.count == 0 {
+                return Ok(i + 1);
+            }
+        }
+        let slot = self.iter().find_map(|(slot, stack)| {
+            (stack.is_none() && self.accepts(slot, &item)).then_some(slot)
+        });
+        match slot {
+            Some(slot) => {
+                self.put_item_unchecked(slot, item);
+                Ok(slot)
+            }
+            None => Err(item),
+        }
+    }
+
+    /// slot: 1-indexed, returns the index into `items`
+    fn slot_index(&self, slot: usize) -> Result<usize, InventoryError> {
+        if (1..=self.items.len()).contains(&slot) {
+            Ok(slot - 1)
+        } else {
+            Err(InventoryError::SlotOutOfRange(slot))
+        }
+    }
+
+    /// Fails when `slot` rejects the item at index `i` of `items`, an empty index passes
+    fn check_accepts(&self, slot: usize, i: usize) -> Result<(), InventoryError> {
+        match &self.items[i] {
+            Some(_) if self.is_locked(slot) => Err(InventoryError::SlotLocked(slot)),
+            Some(item) if !self.accepts(slot, item) => Err(InventoryError::SlotRejectsItem(slot)),
+            _ => Ok(()),
+        }
+    }
+
+    // Selection stays on its slot for all moves below, it follows the slot and not the
+    // item, like a hotbar: after a move the selected item is whatever landed in the slot.
+
+    /// a, b: 1-indexed, either may be empty, swapping a slot with itself does nothing
+    pub fn swap_slots(&mut self, a: usize, b: usize) -> Result<(), InventoryError> {
+        let (i, j) = (self.slot_index(a)?, self.slot_index(b)?);
+        if i == j {
+            return Ok(());
+        }
+        self.check_accepts(b, i)?;
+        self.check_accepts(a, j)?;
+        if self.items[i].is_some() || self.items[j].is_some() {
+            self.items.swap(i, j);
+            self.record_content(a);
+            self.record_content(b);
+        }
+        Ok(())
+    }
+
+    /// from, to: 1-indexed, `conflict` decides what happens when `to` is occupied
+    pub fn move_item(
+        &mut self,
+        from: usize,
+        to: usize,
+        conflict: MoveConflict,
+    ) -> Result<(), InventoryError> {
+        let (i, j) = (self.slot_index(from)?, self.slot_index(to)?);
+        if self.items[i].is_none() {
+            return Err(InventoryError::SlotEmpty(from));
+        }
+        // Back onto its own slot, even a locked or filtered one
+        if i == j {
+            return Ok(());
+        }
+        if self.items[j].is_some() && conflict == MoveConflict::Fail {
+            return Err(InventoryError::SlotOccupied(to));
+        }
+        self.check_accepts(to, i)?;
+        self.check_accepts(from, j)?;
+        self.items.swap(i, j);
+        self.record_content(from);
+        self.record(to, SlotChange::Put);
+        Ok(())
+    }
+
+    /// Moves the item in `from` into the empty slot `to` of `other`, both 1-indexed
+    ///
+    /// Nothing changes on error.
+    pub fn transfer_to(
+        &mut self,
+        other: &mut DynInventory,
+        from: usize,
+        to: usize,
+    ) -> Result<(), InventoryError> {
+        let i = self.slot_index(from)?;
+        let j = other.slot_index(to)?;
+        if other.items[j].is_some() {
+            return Err(InventoryError::SlotOccupied(to));
+        }
+        let item = self.items[i]
+            .as_ref()
+            .ok_or(InventoryError::SlotEmpty(from))?;
+        if other.is_locked(to) {
+            return Err(InventoryError::SlotLocked(to));
+        }
+        if !other.accepts(to, item) {
+            return Err(InventoryError::SlotRejectsItem(to));
+        }
+        if !other.fits_weight(item.stack_weight()) {
+            return Err(InventoryError::OverWeight);
+        }
+        let weight = item.stack_weight();
+        other.items[j] = self.items[i].take();
+        self.shift_weight(-weight);
+        other.shift_weight(weight);
+        self.record(from, SlotChange::Removed);
+        other.record(to, SlotChange::Put);
+        Ok(())
+    }
+
+    /// Merges partial stacks of the same code and moves the items to the front, in the order
+    /// of `strategy`, see [`DynInventory::sort_slots`]
+    pub fn sort(&mut self, strategy: SortStrategy) {
+        self.sort_slots(strategy, 1..=self.slot_count());
+    }
+
+    /// [`DynInventory::sort`] limited to `slots`, 1-indexed
+    ///
+    /// Locked slots and slots with a filter are left out, their items stay. The selection
+    /// follows the selected item, to the stack it merged into first. Records a change for
+    /// every slot whose content changed.
+    pub fn sort_slots(&mut self, strategy: SortStrategy, slots: RangeInclusive<usize>) {
+        let slots: Vec<usize> = slots
+            .filter(|slot| {
+                self.contains_slot(*slot)
+                    && !self.is_locked(*slot)
+                    && !self.filters.contains_key(slot)
+            })
+            .collect();
+        let mut stacks: Vec<Item> = Vec::new();
+        // Index into stacks of the first stack the selected item went to
+        let mut selected = None;
+        for &slot in &slots {
+            let Some(mut item) = self.items[slot - 1].clone() else {
+                continue;
+            };
+            let is_selected = slot == self.selected;
+            for (i, stack) in stacks.iter_mut().enumerate() {
+                if stack.code != item.code || stack.count >= stack.max_stack {
+                    continue;
+                }
+                let moved = item.count.min(stack.max_stack - stack.count);
+                stack.count += moved;
+                item.count -= moved;
+                if is_selected && selected.is_none() {
+                    selected = Some(i);
+                }
+                if item.count == 0 {
+                    break;
+                }
+            }
+            if item.count > 0 {
+                if is_selected && selected.is_none() {
+                    selected = Some(stacks.len());
+                }
+                stacks.push(item);
+            }
+        }
+        let mut order: Vec<usize> = (0..stacks.len()).collect();
+        match strategy {
+            SortStrategy::CompactOnly => {}
+            SortStrategy::ByCode => order.sort_by_key(|&i| stacks[i].code),
+            SortStrategy::ByCategory => order.sort_by_key(|&i| {
+                let item = &stacks[i];
+                (
+                    item.category.is_none(),
+                    item.category.map(|category| category.0),
+                    item.code,
+                )
+            }),
+        }
+        for (n, &slot) in slots.iter().enumerate() {
+            let item = order.get(n).map(|&i| stacks[i].clone());
+            self.replace_slot(slot, item);
+        }
+        if let Some(selected) = selected {
+            let n = order
+                .iter()
+                .position(|&i| i == selected)
+                .expect("every stack is ordered");
+            self.set_selected(slots[n]);
+        }
+    }
+
+    /// Puts `item` into `slot`, recording a change unless the slot already held it
+    ///
+    /// slot: 1-indexed
+    fn replace_slot(&mut self, slot: usize, item: Option<Item>) {
+        let old = &self.items[slot - 1];
+        if *old == item {
+            return;
+        }
+        let change = match (old, &item) {
+            (Some(old), Some(new)) if old.code == new.code => SlotChange::CountChanged,
+            (_, Some(_)) => SlotChange::Put,
+            (_, None) => SlotChange::Removed,
+        };
+        self.items[slot - 1] = item;
+        self.record(slot, change);
+    }
+
+    /// Runs `f` on a staged copy of the slots, the changes are applied only when it returns
+    /// Ok, with one recorded change per slot that ended up different
+    ///
+    /// ```
+    /// # use bevy_toolbox::{inventory::{DynInventory, InventoryError}, items::ItemCode};
+    /// # let mut inventory = DynInventory::with_capacity(4);
+    /// let crafted = inventory.transaction(|tx| {
+    ///     tx.take(ItemCode(1), 2)?;
+    ///     tx.take(ItemCode(2), 1)?;
+    ///     Ok::<_, InventoryError>(())
+    /// });
+    /// assert_eq!(crafted, Err(InventoryError::NotEnoughItems(ItemCode(1))));
+    /// ```
+    pub fn transaction<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut InventoryTransaction) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut tx = InventoryTransaction {
+            staged: DynInventory {
+                items: self.items.clone(),
+                selected: self.selected,
+                filters: self.filters.clone(),
+                locked: self.locked.clone(),
+                max_weight: self.max_weight,
+                ..Default::default()
+            },
+        };
+        let result = f(&mut tx)?;
+        let weight = tx.staged.current_weight();
+        for (i, item) in tx.staged.items.into_iter().enumerate() {
+            self.replace_slot(i + 1, item);
+        }
+        self.weight = Some(weight);
+        Ok(result)
+    }
+}
+
+/// Staged view of a [`DynInventory`] in [`DynInventory::transaction`], reads see the staged
+/// slots
+pub struct InventoryTransaction {
+    staged: DynInventory,
+}
+
+impl Deref for InventoryTransaction {
+    type Target = DynInventory;
+
+    fn deref(&self) -> &DynInventory {
+        &self.staged
+    }
+}
+
+impl InventoryTransaction {
+    /// Takes `count` items of `code` off the stacks in slot order, skipping locked slots
+    ///
+    /// Takes nothing unless there are enough. Returns them as one item.
+    pub fn take(&mut self, code: ItemCode, count: u32) -> Result<Item, InventoryError> {
+        if count == 0 {
+            return Err(InventoryError::ZeroAmount);
+        }
+        let stacks: Vec<usize> = self
+            .staged
+            .iter_items()
+            .filter(|(slot, item)| item.code == code && !self.staged.is_locked(*slot))
+            .map(|(slot, _)| slot)
+            .collect();
+        let available: u32 = stacks
+            .iter()
+            .filter_map(|slot| self.staged.get_item(*slot))
+            .map(|item| item.count)
+            .sum();
+        if available < count {
+            return Err(InventoryError::NotEnoughItems(code));
+        }
+        let mut taken: Option<Item> = None;
+        for slot in stacks {
+            let missing = count - taken.as_ref().map_or(0, |item| item.count);
+            if missing == 0 {
+                break;
+            }
+            let item = self.staged.take(slot, missing)?;
+            match &mut taken {
+                Some(taken) => taken.count += item.count,
+                None => taken = Some(item),
+            }
+        }
+        Ok(taken.expect("count is not 0"))
+    }
+
+    /// Adds `item` like [`DynInventory::add_item`], fails unless all of it fits
+    ///
+    /// Adds nothing when it fails, the transaction can go on without it.
+    pub fn give(&mut self, item: Item) -> Result<usize, InventoryError> {
+        let code = item.code;
+        if self.staged.room_for(&item) < item.count {
+            return Err(InventoryError::NoRoom(code));
+        }
+        self.staged
+            .add_item(item)
+            .map_err(|_| InventoryError::NoRoom(code))
+    }
+}
+
+#[derive(Component)]
+pub struct BaseInventoryBackground;
+
+/// Background of a grid of inventory slots, see [`spawn_inventory_panel`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryPanel {
+    /// Entity whose [`DynInventory`] the panel shows, None for the [`ActiveInventory`]
+    pub inventory: Option<Entity>,
+}
+
+/// Grid of an [`InventoryPanel`], see [`relayout_inventory_panels`]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryPanelLayout {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+/// Marks entities whose [`DynInventory`] opens in a panel above the hotbar when clicked
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Container;
+
+/// Container shown in the container panel, closed with Escape
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Deref, DerefMut)]
+pub struct OpenContainer(pub Option<Entity>);
+
+/// Background of the panel of the [`OpenContainer`]
+#[derive(Component)]
+pub struct ContainerPanel;
+
+/// Space between the hotbar and the container panel above it
+const CONTAINER_PANEL_GAP: f32 = 10.0;
+
+/// Marks the entity holding the backpack [`DynInventory`], see [`spawn_backpack`]
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Backpack;
+
+/// Whether the backpack panel is open, see [`toggle_backpack`]
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Deref, DerefMut)]
+pub struct BackpackOpen(pub bool);
+
+/// Background of the panel of the [`Backpack`]
+#[derive(Component)]
+pub struct BackpackPanel;
+
+/// Layout and keys of the [`Backpack`]
+#[derive(Resource, Debug, Clone)]
+pub struct BackpackSettings {
+    pub rows: usize,
+    pub columns: usize,
+    /// Keys opening and closing the backpack
+    pub toggle_keys: Vec<KeyCode>,
+}
+
+impl Default for BackpackSettings {
+    fn default() -> Self {
+        Self {
+            rows: 3,
+            columns: 9,
+            toggle_keys: vec![KeyCode::Tab, KeyCode::E],
+        }
+    }
+}
+
+/// How [`sort_inventory_on_key`] orders an inventory
+#[derive(Resource, Debug, Default, Clone)]
+pub struct SortSettings {
+    pub strategy: SortStrategy,
+    /// The first slots stay as they are, for a hotbar row at the start of an inventory
+    pub keep_slots: usize,
+}
+
+/// Open panels and drags that keep clicks from reaching the world
+#[derive(SystemParam)]
+pub struct InventoryUiState<'w> {
+    open_container: Option<Res<'w, OpenContainer>>,
+    backpack_open: Option<Res<'w, BackpackOpen>>,
+    dragged_item: Option<Res<'w, DraggedItem>>,
+}
+
+impl InventoryUiState<'_> {
+    /// Whether a container or the backpack is open or an item is being dragged
+    pub fn is_busy(&self) -> bool {
+        self.open_container
+            .as_ref()
+            .is_some_and(|open_container| open_container.is_some())
+            || self.backpack_open.as_ref().is_some_and(|open| ***open)
+            || is_dragging(self.dragged_item.as_deref())
+    }
+}
+
+/// Item being dragged out of an inventory slot with the mouse, None while not dragging
+///
+/// The item stays in its slot until dropped on another one.
+#[derive(Resource, Debug, Default, Clone, Deref, DerefMut)]
+pub struct DraggedItem(pub Option<ItemDrag>);
+
+#[derive(Debug, Clone)]
+pub struct ItemDrag {
+    /// Panel the drag started in
+    pub panel: InventoryPanel,
+    pub slot: usize,
+    pub item: Item,
+}
+
+/// Sprite following the cursor while dragging
+#[derive(Component)]
+pub struct DraggedItemSprite;
+
+/// Above every panel
+const DRAGGED_ITEM_Z: f32 = 100.0;
+
+#[derive(Component)]
+pub struct InventorySlotBackground {
+    pub base: Entity,
+    pub slot: usize,
+}
+
+#[derive(Component)]
+pub struct InventorySlot {
+    pub base: Entity,
+    pub slot: usize,
+}
+
+/// Count of the stack in its parent [`InventorySlot`], hidden below 2
+#[derive(Component)]
+pub struct InventoryStackCount;
+
+/// Border behind the slot background of the selected slot, hidden without a selection
+#[derive(Component)]
+pub struct InventorySlotHighlight {
+    pub base: Entity,
+}
+
+/// Icon over a locked slot, a child of its [`InventorySlotBackground`], see
+/// [`update_slot_backgrounds`]
+#[derive(Component)]
+pub struct InventorySlotLock;
+
+/// Size of the lock icon relative to the slot
+const LOCK_ICON_FRACTION: f32 = 0.4;
+
+/// Darkens the part of a hotbar slot still on cooldown, see [`SlotCooldowns`]
+#[derive(Component)]
+pub struct InventorySlotCooldown {
+    pub base: Entity,
+    pub slot: usize,
+}
+
+/// Use cooldowns of inventory slots, nothing is placed from a slot on cooldown
+///
+/// Slots are told apart by inventory, the entity of the [`DynInventory`] component or None
+/// for the [`BaseInventory`], so switching the [`ActiveInventory`] does not carry them over.
+#[derive(Resource, Debug, Default)]
+pub struct SlotCooldowns {
+    timers: HashMap<(Option<Entity>, usize), Timer>,
+}
+
+impl SlotCooldowns {
+    /// slot: 1-indexed, restarts a running cooldown
+    pub fn start_cooldown(&mut self, inventory: Option<Entity>, slot: usize, duration: Duration) {
+        self.timers
+            .insert((inventory, slot), Timer::new(duration, TimerMode::Once));
+    }
+
+    pub fn is_cooling_down(&self, inventory: Option<Entity>, slot: usize) -> bool {
+        self.timers.contains_key(&(inventory, slot))
+    }
+
+    pub fn remaining(&self, inventory: Option<Entity>, slot: usize) -> Option<Duration> {
+        self.timers.get(&(inventory, slot)).map(Timer::remaining)
+    }
+
+    /// 1.0 when the cooldown just started, 0.0 without one
+    pub fn remaining_fraction(&self, inventory: Option<Entity>, slot: usize) -> f32 {
+        self.timers
+            .get(&(inventory, slot))
+            .map_or(0.0, Timer::percent_left)
+    }
+
+    /// Drops the cooldowns that are over
+    pub fn tick(&mut self, delta: Duration) {
+        self.timers.retain(|_, timer| !timer.tick(delta).finished());
+    }
+}
+
+/// Name of the hovered item above its slot, the text is a child, see
+/// [`update_inventory_tooltip`]
+#[derive(Component)]
+pub struct InventoryTooltip;
+
+/// Name of the selected item under its slot of the hotbar, see [`update_selected_item_name`]
+#[derive(Component)]
+pub struct InventoryItemName {
+    pub base: Entity,
+}
+
+/// Number of the hotbar slot, the key that selects it, see [`update_slot_numbers`]
+#[derive(Component)]
+pub struct InventorySlotNumber {
+    pub base: Entity,
+    pub slot: usize,
+}
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct BaseInventorySettings(pub InventorySettings);
+
+/// Set when UI handled this frame's click, world interactions skip it then
+///
+/// Reset by [`reset_click_consumed`] at the start of each frame.
+#[derive(Resource, Default, Deref, DerefMut, PartialEq, Eq)]
+pub struct ClickConsumed(pub bool);
+
+/// Z of the parts of an inventory panel above [`InventorySettings::base_z`], where the panel
+/// background sits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InventoryZOffsets {
+    pub highlight: f32,
+    pub slot: f32,
+    pub item: f32,
+    /// See [`InventorySlotCooldown`]
+    pub cooldown: f32,
+    /// See [`InventorySlotNumber`]
+    pub number: f32,
+}
+
+impl Default for InventoryZOffsets {
+    fn default() -> Self {
+        Self {
+            highlight: 0.5,
+            slot: 1.0,
+            item: 2.0,
+            cooldown: 2.5,
+            number: 3.0,
+        }
+    }
+}
+
+/// Where an [`InventoryImage`] comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    Handle(Handle<Image>),
+    /// Loaded with the [`AssetServer`]
+    Path(String),
+}
+
+impl ImageSource {
+    /// None for a path without an `asset_server` to load it
+    pub fn handle(&self, asset_server: Option<&AssetServer>) -> Option<Handle<Image>> {
+        match self {
+            Self::Handle(handle) => Some(handle.clone()),
+            Self::Path(path) => asset_server.map(|asset_server| asset_server.load(path.as_str())),
+        }
+    }
+}
+
+impl From<Handle<Image>> for ImageSource {
+    fn from(handle: Handle<Image>) -> Self {
+        Self::Handle(handle)
+    }
+}
+
+impl From<&str> for ImageSource {
+    fn from(path: &str) -> Self {
+        Self::Path(path.to_string())
+    }
+}
+
+/// Texture of a part of the inventory panels, tinted by the color of the part
+#[derive(Debug, Clone, PartialEq)]
+pub struct InventoryImage {
+    pub source: ImageSource,
+    /// None stretches the whole image
+    pub slice: Option<NineSlice>,
+}
+
+/// Layout and look of the inventory panels, changes restyle the spawned panels
+#[derive(Debug, Clone)]
+pub struct InventorySettings {
+    pub w_padding: f32,
+    pub w_mid_step: f32,
+    pub h_padding: f32,
+    // pub h_mid_step: f32,
+    pub slot_margin: f32,
+    pub slot_size: f32,
+    pub highlight_color: Color,
+    /// Width of the highlight border around the selected slot
+    pub highlight_thickness: f32,
+    /// Font of the stack counts, sized from `slot_size`
+    pub count_font: Handle<Font>,
+    pub count_color: Color,
+    pub show_slot_numbers: bool,
+    pub number_font_size: f32,
+    pub number_color: Color,
+    /// Corner of the slot the number sits in
+    pub number_corner: Anchor,
+    /// Hover time before the tooltip shows
+    pub tooltip_delay: Duration,
+    /// Pulse the background of a newly selected slot, needs the
+    /// [`AnimationPlugin`](crate::animation::AnimationPlugin)
+    pub pulse_selected: bool,
+    /// Scale of the slot background at the height of the pulse
+    pub pulse_scale: f32,
+    pub pulse_duration: Duration,
+    /// Panel behind the slots
+    pub background_color: Color,
+    /// Drawn instead of the plain `background_color`, see [`apply_inventory_images`]
+    pub background_image: Option<InventoryImage>,
+    pub slot_color: Color,
+    /// Drawn instead of the plain slot colors
+    pub slot_image: Option<InventoryImage>,
+    pub selected_slot_color: Color,
+    /// Background of locked slots, see [`DynInventory::lock_slot`]
+    pub locked_slot_color: Color,
+    pub base_z: f32,
+    pub z_offsets: InventoryZOffsets,
+    pub lock_icon: Handle<Image>,
+    pub lock_icon_color: Color,
+    pub item_name_font_size: f32,
+    pub item_name_color: Color,
+    /// Names of the selected item with more characters are cut short with an ellipsis
+    ///
+    /// A character budget, the drawn width still depends on the glyphs of the font.
+    pub item_name_max_chars: usize,
+}
+
+impl Default for InventorySettings {
+    fn default() -> Self {
+        Self {
+            w_padding: 5.0,
+            w_mid_step: 4.0,
+            h_padding: 3.0,
+            slot_margin: 2.0,
+            slot_size: 50.0,
+            highlight_color: Color::ORANGE,
+            highlight_thickness: 3.0,
+            count_font: Handle::default(),
+            count_color: Color::WHITE,
+            show_slot_numbers: true,
+            number_font_size: 14.0,
+            number_color: Color::DARK_GRAY,
+            number_corner: Anchor::TopLeft,
+            tooltip_delay: Duration::from_millis(300),
+            pulse_selected: true,
+            pulse_scale: 1.15,
+            pulse_duration: Duration::from_millis(200),
+            background_color: Color::GRAY,
+            background_image: None,
+            slot_color: Color::rgba(0.9, 0.9, 0.9, 1.0),
+            slot_image: None,
+            selected_slot_color: Color::rgba(0.9, 0.9, 0.9, 1.0),
+            locked_slot_color: Color::rgba(0.45, 0.45, 0.45, 1.0),
+            base_z: 42.0,
+            z_offsets: InventoryZOffsets::default(),
+            lock_icon: Handle::default(),
+            lock_icon_color: Color::rgba(0.2, 0.2, 0.2, 1.0),
+            item_name_font_size: 14.0,
+            item_name_color: Color::WHITE,
+            item_name_max_chars: 19,
+        }
+    }
+}
+
+pub fn spawn_base_inventory(
+    mut commands: Commands,
+    inventory: Res<BaseInventory>,
+    settings: Res<BaseInventorySettings>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    // Headless apps and closed windows have nothing to lay out against
+    let Ok(primary_window) = primary_window.get_single() else {
+        return;
+    };
+    let window_h = primary_window.height();
+    let _window_w = primary_window.width();
+    let window_padding = 40.0;
+
+    let pos = Vec2::new(0.0, -(window_h / 2.0) + window_padding);
+    spawn_base_inventory_bar(&mut commands, &settings, pos, inventory.slot_count());
+
+    commands
+        .spawn((
+            InventoryTooltip,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.1, 0.1, 0.1, 0.85),
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ))
+        .with_children(|tooltip| {
+            tooltip.spawn(Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: settings.count_font.clone(),
+                        font_size: TOOLTIP_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                ),
+                transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                ..Default::default()
+            });
+        });
+}
+
+/// Spawns the hotbar panel with `slot_count` slots centered on `pos`, along with its item
+/// name, cooldown overlays and slot numbers
+fn spawn_base_inventory_bar(
+    commands: &mut Commands,
+    settings: &InventorySettings,
+    pos: Vec2,
+    slot_count: usize,
+) -> Entity {
+    let inventory_background = spawn_inventory_panel(commands, settings, pos, 1, slot_count, None);
+    commands
+        .entity(inventory_background)
+        .insert(BaseInventoryBackground);
+
+    // Placed and named by update_selected_item_name
+    commands.spawn((
+        InventoryItemName {
+            base: inventory_background,
+        },
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: settings.count_font.clone(),
+                    font_size: settings.item_name_font_size,
+                    color: settings.item_name_color,
+                },
+            ),
+            text_anchor: Anchor::TopCenter,
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+
+    // Placed by update_cooldown_overlays
+    for slot in 1..=slot_count {
+        commands.spawn((
+            InventorySlotCooldown {
+                base: inventory_background,
+                slot,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5),
+                    anchor: Anchor::BottomCenter,
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ));
+    }
+
+    // Placed and labelled by update_slot_numbers, hidden for slots without a key
+    for slot in 1..=slot_count {
+        commands.spawn((
+            InventorySlotNumber {
+                base: inventory_background,
+                slot,
+            },
+            Text2dBundle {
+                text: Text::from_section("", TextStyle::default()),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ));
+    }
+    inventory_background
+}
+
+/// Respawns the hotbar in place when the [`BaseInventory`] grows or shrinks, so the bar always
+/// has a slot per inventory slot
+#[allow(clippy::too_many_arguments)]
+pub fn resize_base_inventory_bar(
+    mut commands: Commands,
+    inventory: Res<BaseInventory>,
+    settings: Res<BaseInventorySettings>,
+    bars: Query<(Entity, &InventoryPanelLayout, &Transform), With<BaseInventoryBackground>>,
+    parts: InventoryPanelParts,
+    cooldowns: Query<(Entity, &InventorySlotCooldown)>,
+    numbers: Query<(Entity, &InventorySlotNumber)>,
+    names: Query<(Entity, &InventoryItemName)>,
+) {
+    if !inventory.is_changed() {
+        return;
+    }
+    for (bar, layout, transform) in bars.iter() {
+        if layout.rows * layout.cols == inventory.slot_count() {
+            continue;
+        }
+        parts.despawn(&mut commands, bar);
+        let labels = cooldowns
+            .iter()
+            .map(|(entity, cooldown)| (entity, cooldown.base))
+            .chain(numbers.iter().map(|(entity, number)| (entity, number.base)))
+            .chain(names.iter().map(|(entity, name)| (entity, name.base)));
+        for (entity, _) in labels.filter(|(_, base)| *base == bar) {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_base_inventory_bar(
+            &mut commands,
+            &settings,
+            transform.translation.truncate(),
+            inventory.slot_count(),
+        );
+    }
+}
+
+/// Size of a panel with `rows` rows of `cols` slots
+///
+/// Rows are spaced like the slots in a row, by `w_mid_step`.
+pub fn inventory_panel_size(settings: &InventorySettings, rows: usize, cols: usize) -> Vec2 {
+    let InventorySettings {
+        w_padding,
+        w_mid_step,
+        h_padding,
+        slot_size,
+        ..
+    } = *settings;
+    let w_total = (2.0 * w_padding)
+        + (cols as f32 * slot_size)
+        + (cols.saturating_sub(1) as f32 * w_mid_step);
+    let h_total = (2.0 * h_padding)
+        + (rows as f32 * slot_size)
+        + (rows.saturating_sub(1) as f32 * w_mid_step);
+    Vec2::new(w_total, h_total)
+}
+
+/// Centers of the slots of a panel of `rows` rows of `cols` slots centered at `pos`, row by
+/// row from the top left
+pub fn layout_slots(
+    settings: &InventorySettings,
+    rows: usize,
+    cols: usize,
+    pos: Vec2,
+) -> Vec<Vec2> {
+    let InventorySettings {
+        w_padding,
+        w_mid_step,
+        h_padding,
+        slot_size,
+        ..
+    } = *settings;
+    let Vec2 {
+        x: w_total,
+        y: h_total,
+    } = inventory_panel_size(settings, rows, cols);
+    let x_start = pos.x - (w_total / 2.0) + w_padding + (slot_size / 2.0);
+    let y_start = pos.y + (h_total / 2.0) - h_padding - (slot_size / 2.0);
+    (0..rows * cols)
+        .map(|i| {
+            let (row, col) = (i / cols, i % cols);
+            let x = x_start + (col as f32 * (slot_size + w_mid_step));
+            let y = y_start - (row as f32 * (slot_size + w_mid_step));
+            Vec2::new(x, y)
+        })
+        .collect()
+}
+
+/// Spawns `rows` rows of `cols` slots centered at `pos` showing `inventory`, see
+/// [`InventoryPanel`], returns the background
+///
+/// Slots are numbered row by row from the top left. The slots and the highlight are not
+/// children of the background, they point at it.
+pub fn spawn_inventory_panel(
+    commands: &mut Commands,
+    settings: &InventorySettings,
+    pos: Vec2,
+    rows: usize,
+    cols: usize,
+    inventory: Option<Entity>,
+) -> Entity {
+    let InventorySettings {
+        slot_margin,
+        slot_size,
+        highlight_color,
+        highlight_thickness,
+        base_z,
+        z_offsets,
+        ..
+    } = settings.clone();
+
+    let Vec2 {
+        x: w_total,
+        y: h_total,
+    } = inventory_panel_size(settings, rows, cols);
+
+    let inventory_background = commands
+        .spawn((
+            InventoryPanel { inventory },
+            InventoryPanelLayout { rows, cols },
+            GridCursorBlocker,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: settings.background_color,
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(pos.x, pos.y, base_z))
+                    .with_scale(Vec3::new(w_total, h_total, 1.0)),
+                visibility: Visibility::Visible,
+                ..Default::default()
+            },
+        ))
+        .id();
+
+    let highlight_size = slot_size + 2.0 * highlight_thickness;
+    commands.spawn((
+        InventorySlotHighlight {
+            base: inventory_background,
+        },
+        SpriteBundle {
+            sprite: Sprite {
+                color: highlight_color,
+                ..Default::default()
+            },
+            transform: Transform::from_translation(Vec3::new(
+                pos.x,
+                pos.y,
+                base_z + z_offsets.highlight,
+            ))
+            .with_scale(Vec3::new(highlight_size, highlight_size, 1.0)),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+
+    trace!("{w_total}-{h_total}");
+    trace!("---");
+    for (i, Vec2 { x, y }) in layout_slots(settings, rows, cols, pos)
+        .into_iter()
+        .enumerate()
+    {
+        trace!("{x}-{y}");
+
+        commands
+            .spawn((
+                InventorySlotBackground {
+                    base: inventory_background,
+                    slot: i + 1,
+                },
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: settings.slot_color,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(
+                        x,
+                        y,
+                        base_z + z_offsets.slot,
+                    ))
+                    .with_scale(Vec3::new(slot_size, slot_size, 1.0)),
+                    visibility: Visibility::Visible,
+                    ..Default::default()
+                },
+            ))
+            .with_children(|background| {
+                // Shown by update_slot_backgrounds, above the item and the cooldown overlay
+                background.spawn((
+                    InventorySlotLock,
+                    SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::splat(LOCK_ICON_FRACTION)),
+                            ..Default::default()
+                        },
+                        transform: Transform::from_xyz(0.0, 0.0, 1.6),
+                        visibility: Visibility::Hidden,
+                        ..Default::default()
+                    },
+                ));
+            });
+
+        let corner = (slot_size - slot_margin) / 2.0;
+        commands
+            .spawn((
+                InventorySlot {
+                    base: inventory_background,
+                    slot: i + 1,
+                },
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(
+                            slot_size - slot_margin,
+                            slot_size - slot_margin,
+                        )),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(
+                        x,
+                        y,
+                        base_z + z_offsets.item,
+                    )),
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+            ))
+            .with_children(|slot| {
+                slot.spawn((
+                    InventoryStackCount,
+                    Text2dBundle {
+                        text: Text::from_section(
+                            "",
+                            TextStyle {
+                                font: settings.count_font.clone(),
+                                font_size: slot_size * 0.4,
+                                color: settings.count_color,
+                            },
+                        ),
+                        text_anchor: Anchor::BottomRight,
+                        transform: Transform::from_translation(Vec3::new(corner, -corner, 1.0)),
+                        visibility: Visibility::Hidden,
+                        ..Default::default()
+                    },
+                ));
+            });
+    }
+    // inventory_background.with_children(|cb| {
+    //     for i in 0..n_slots {
+    //         let x = x_start + (i as f32 * (slot_size + w_mid_step));
+    //         let y = pos.y;
+    //         trace!("{x}-{y}");
+    //         cb.spawn((
+    //             InventorySlot,
+    //             SpriteBundle {
+    //                 sprite: Sprite {
+    //                     color: Color::rgba(0.9, 0.9, 0.9, 1.0),
+    //                     ..Default::default()
+    //                 },
+    //                 transform: Transform::from_translation(Vec3::new(x, y, 11.0))
+    //                     * Transform::from_scale(Vec3::new(slot_size, slot_size, 1.0)),
+    //                 visibility: Visibility::Visible,
+    //                 ..Default::default()
+    //             },
+    //         ));
+    //     }
+    // });
+    inventory_background
+}
+
+/// Lays the panels out again when the [`BaseInventorySettings`] change, the panels keep their
+/// centers and the container panel stays above the hotbar
+#[allow(clippy::type_complexity)]
+pub fn relayout_inventory_panels(
+    settings: Res<BaseInventorySettings>,
+    mut panels: Query<
+        (
+            Entity,
+            &InventoryPanelLayout,
+            &mut Transform,
+            Option<&BaseInventoryBackground>,
+            Option<&ContainerPanel>,
+        ),
+        (
+            Without<InventorySlotBackground>,
+            Without<InventorySlot>,
+            Without<InventoryStackCount>,
+        ),
+    >,
+    mut slot_backgrounds: Query<
+        (&InventorySlotBackground, &mut Transform),
+        (Without<InventorySlot>, Without<InventoryStackCount>),
+    >,
+    mut slots: Query<
+        (
+            &InventorySlot,
+            &mut Sprite,
+            &mut Transform,
+            Option<&Children>,
+        ),
+        Without<InventoryStackCount>,
+    >,
+    mut counts: Query<(&mut Text, &mut Transform), With<InventoryStackCount>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let hotbar =
+        panels
+            .iter()
+            .find(|(.., hotbar, _)| hotbar.is_some())
+            .map(|(_, layout, transform, ..)| {
+                let size = inventory_panel_size(&settings, layout.rows, layout.cols);
+                (transform.translation.truncate(), size)
+            });
+    let item_size = settings.slot_size - settings.slot_margin;
+    let corner = item_size / 2.0;
+    for (panel, layout, mut transform, _, container) in panels.iter_mut() {
+        let size = inventory_panel_size(&settings, layout.rows, layout.cols);
+        let pos = match (container, hotbar) {
+            (Some(_), Some((hotbar_pos, hotbar_size))) => {
+                hotbar_pos + Vec2::Y * (hotbar_size.y / 2.0 + CONTAINER_PANEL_GAP + size.y / 2.0)
+            }
+            _ => transform.translation.truncate(),
+        };
+        let z = transform.translation.z;
+        transform
+            .set_if_neq(Transform::from_translation(pos.extend(z)).with_scale(size.extend(1.0)));
+
+        let centers = layout_slots(&settings, layout.rows, layout.cols, pos);
+        let center = |slot: usize| centers.get(slot.wrapping_sub(1)).copied();
+        for (background, mut transform) in slot_backgrounds.iter_mut() {
+            let Some(center) = center(background.slot).filter(|_| background.base == panel) else {
+                continue;
+            };
+            let z = transform.translation.z;
+            transform.set_if_neq(
+                Transform::from_translation(center.extend(z)).with_scale(Vec3::new(
+                    settings.slot_size,
+                    settings.slot_size,
+                    1.0,
+                )),
+            );
+        }
+        for (slot, mut sprite, mut transform, children) in slots.iter_mut() {
+            let Some(center) = center(slot.slot).filter(|_| slot.base == panel) else {
+                continue;
+            };
+            let z = transform.translation.z;
+            transform.set_if_neq(Transform::from_translation(center.extend(z)));
+            if sprite.custom_size != Some(Vec2::splat(item_size)) {
+                sprite.custom_size = Some(Vec2::splat(item_size));
+            }
+            let mut children = counts.iter_many_mut(children.into_iter().flatten());
+            while let Some((mut text, mut transform)) = children.fetch_next() {
+                transform.set_if_neq(Transform::from_xyz(corner, -corner, 1.0));
+                for section in text.sections.iter_mut() {
+                    if section.style.font_size != settings.slot_size * 0.4 {
+                        section.style.font_size = settings.slot_size * 0.4;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Restyles the panel backgrounds and item sprites when the [`BaseInventorySettings`] change,
+/// the other parts follow the settings in the systems updating them
+pub fn apply_inventory_theme(
+    settings: Res<BaseInventorySettings>,
+    mut panels: Query<(&mut Sprite, &mut Transform), With<InventoryPanel>>,
+    mut slots: Query<&mut Transform, (With<InventorySlot>, Without<InventoryPanel>)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut sprite, mut transform) in panels.iter_mut() {
+        if sprite.color != settings.background_color {
+            sprite.color = settings.background_color;
+        }
+        if transform.translation.z != settings.base_z {
+            transform.translation.z = settings.base_z;
+        }
+    }
+    let z = settings.base_z + settings.z_offsets.item;
+    for mut transform in slots.iter_mut() {
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+    }
+}
+
+/// Textures the panel and slot backgrounds with the images of the [`BaseInventorySettings`],
+/// on spawn, when the settings change and when a nine-sliced image finishes loading
+///
+/// Nine-sliced images are stretched as a whole until they are loaded. The handles are only
+/// resolved again when the settings change.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn apply_inventory_images(
+    mut commands: Commands,
+    settings: Res<BaseInventorySettings>,
+    asset_server: Option<Res<AssetServer>>,
+    mut handles: Local<[Option<(Handle<Image>, Option<NineSlice>)>; 2]>,
+    images: Res<Assets<Image>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut panels: Query<
+        (
+            Entity,
+            Ref<InventoryPanel>,
+            &mut Sprite,
+            &mut Handle<Image>,
+            &Transform,
+            Option<&Children>,
+        ),
+        Without<InventorySlotBackground>,
+    >,
+    mut slot_backgrounds: Query<
+        (
+            Entity,
+            Ref<InventorySlotBackground>,
+            &mut Sprite,
+            &mut Handle<Image>,
+            &Transform,
+            Option<&Children>,
+        ),
+        Without<InventoryPanel>,
+    >,
+    pieces: Query<(), With<NineSlicePiece>>,
+) {
+    // Paths set before the asset server existed resolve once it is added
+    let resolve = settings.is_changed()
+        || asset_server
+            .as_ref()
+            .is_some_and(|server| server.is_added());
+    if resolve {
+        let asset_server = asset_server.as_deref();
+        *handles = [&settings.background_image, &settings.slot_image].map(|image| {
+            let image = image.as_ref()?;
+            Some((image.source.handle(asset_server)?, image.slice))
+        });
+    }
+    let [background, slot] = &*handles;
+    let loaded = image_events.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => [background, slot]
+            .into_iter()
+            .flatten()
+            .any(|(image, _)| image == handle),
+        AssetEvent::Removed { .. } => false,
+    });
+    let restyle = resolve || loaded;
+    let mut apply = |entity: Entity,
+                     image: &Option<(Handle<Image>, Option<NineSlice>)>,
+                     sprite: &mut Sprite,
+                     texture: &mut Handle<Image>,
+                     size: Vec2,
+                     children: Option<&Children>| {
+        for &child in children.into_iter().flatten() {
+            if pieces.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+        let Some((image, slice)) = image else {
+            *texture = Handle::default();
+            sprite.custom_size = None;
+            return;
+        };
+        match (slice, images.get(image)) {
+            (Some(slice), Some(loaded)) => {
+                *texture = Handle::default();
+                // The pieces draw it
+                sprite.custom_size = Some(Vec2::ZERO);
+                spawn_nine_slice(
+                    &mut commands,
+                    entity,
+                    image,
+                    loaded.size(),
+                    size,
+                    slice,
+                    sprite.color,
+                );
+            }
+            _ => {
+                *texture = image.clone();
+                // Stretched by the transform scale like the plain sprite
+                sprite.custom_size = Some(Vec2::ONE);
+            }
+        }
+    };
+    for (entity, panel, mut sprite, mut texture, transform, children) in panels.iter_mut() {
+        if restyle || panel.is_added() {
+            let size = transform.scale.truncate();
+            apply(
+                entity,
+                background,
+                &mut sprite,
+                &mut texture,
+                size,
+                children,
+            );
+        }
+    }
+    for (entity, background, mut sprite, mut texture, transform, children) in
+        slot_backgrounds.iter_mut()
+    {
+        if restyle || background.is_added() {
+            let size = transform.scale.truncate();
+            apply(entity, slot, &mut sprite, &mut texture, size, children);
+        }
+    }
+}
+
+/// Colors the slot backgrounds by whether the slot is locked or selected and shows the
+/// [`InventorySlotLock`] of locked slots
+#[allow(clippy::type_complexity)]
+pub fn update_slot_backgrounds(
+    inventory: ActiveInventoryQuery,
+    settings: Res<BaseInventorySettings>,
+    panels: Query<&InventoryPanel>,
+    mut slot_backgrounds: Query<(
+        Ref<InventorySlotBackground>,
+        &mut Sprite,
+        &mut Transform,
+        &Children,
+    )>,
+    mut lock_icons: Query<
+        (&mut Sprite, &mut Handle<Image>, &mut Visibility),
+        (With<InventorySlotLock>, Without<InventorySlotBackground>),
+    >,
+) {
+    for (background, mut sprite, mut transform, children) in slot_backgrounds.iter_mut() {
+        let panel = panels
+            .get(background.base)
+            .copied()
+            .unwrap_or(InventoryPanel { inventory: None });
+        if !background.is_added() && !settings.is_changed() && !inventory.panel_changed(&panel) {
+            continue;
+        }
+        let panel_inventory = inventory.for_panel(&panel);
+        let locked = panel_inventory.is_some_and(|inventory| inventory.is_locked(background.slot));
+        let selected = panel_inventory
+            .is_some_and(|inventory| inventory.selected_slot() == Some(background.slot));
+        let color = if locked {
+            settings.locked_slot_color
+        } else if selected {
+            settings.selected_slot_color
+        } else {
+            settings.slot_color
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+        let z = settings.base_z + settings.z_offsets.slot;
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+        let mut icons = lock_icons.iter_many_mut(children);
+        while let Some((mut icon, mut image, mut visibility)) = icons.fetch_next() {
+            if locked {
+                if icon.color != settings.lock_icon_color {
+                    icon.color = settings.lock_icon_color;
+                }
+                image.set_if_neq(settings.lock_icon.clone());
+                visibility.set_if_neq(Visibility::Inherited);
+            } else {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+}
+
+/// Shows the item images and stack counts of slots whose inventory changed, new slots and
+/// every slot when an item prototype changes
+#[allow(clippy::type_complexity)]
+pub fn render_items_in_base_inventory(
+    inventory: ActiveInventoryQuery,
+    // images: Res<Assets<Image>>,
+    panels: Query<&InventoryPanel>,
+    preview_items: Query<(&ItemCode, &ItemImage), With<ItemPreview>>,
+    changed_previews: Query<(), (With<ItemPreview>, Changed<ItemImage>)>,
+    mut slot_items: Query<(
+        Ref<InventorySlot>,
+        &mut Handle<Image>,
+        &mut Visibility,
+        Option<&Children>,
+    )>,
+    mut counts: Query<
+        (&mut Text, &mut Visibility),
+        (With<InventoryStackCount>, Without<InventorySlot>),
+    >,
+) {
+    let previews_changed = !changed_previews.is_empty();
+    for (slot, mut slot_image, mut visibility, children) in slot_items.iter_mut() {
+        let panel = panels
+            .get(slot.base)
+            .copied()
+            .unwrap_or(InventoryPanel { inventory: None });
+        if !previews_changed && !slot.is_changed() && !inventory.panel_changed(&panel) {
+            continue;
+        }
+        let item = inventory
+            .for_panel(&panel)
+            .and_then(|inventory| inventory.get_item(slot.slot));
+        let item_image = item.and_then(|item| {
+            preview_items
+                .iter()
+                .find(|(item_code, _)| **item_code == item.code)
+        });
+        match item_image {
+            Some((_, item_image)) => {
+                slot_image.set_if_neq(item_image.0.clone());
+                visibility.set_if_neq(Visibility::Visible);
+            }
+            // Empty, or no prototype to take the image from
+            None => {
+                slot_image.set_if_neq(Handle::default());
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+        let mut counts = counts.iter_many_mut(children.into_iter().flatten());
+        while let Some((mut text, mut visibility)) = counts.fetch_next() {
+            match item.filter(|item| item.count > 1) {
+                Some(item) => {
+                    // Only touch the text when it differs, changing it triggers a new layout
+                    let count = item.count.to_string();
+                    if text.sections[0].value != count {
+                        text.sections[0].value = count;
+                    }
+                    visibility.set_if_neq(Visibility::Inherited);
+                }
+                None => {
+                    visibility.set_if_neq(Visibility::Hidden);
+                }
+            }
+        }
+    }
+}
+
+pub fn reset_click_consumed(mut click_consumed: ResMut<ClickConsumed>) {
+    click_consumed.set_if_neq(ClickConsumed(false));
+}
+
+/// Panel under `cursor`, the one showing the [`ActiveInventory`] when there is none
+pub fn panel_at(
+    cursor: Option<Vec2>,
+    panels: &Query<(&InventoryPanel, &Transform)>,
+) -> InventoryPanel {
+    cursor
+        .and_then(|cursor| {
+            panels.iter().find(|(_, transform)| {
+                Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+                    .contains(cursor)
+            })
+        })
+        .map_or(InventoryPanel { inventory: None }, |(panel, _)| *panel)
+}
+
+/// Sorts the inventory of the panel under the cursor with [`InventoryKeyBindings::sort_key`],
+/// the active inventory otherwise, see [`SortSettings`]
+///
+/// Ignored while dragging.
+pub fn sort_inventory_on_key(
+    key: Res<Input<KeyCode>>,
+    bindings: Res<InventoryKeyBindings>,
+    settings: Res<SortSettings>,
+    cursor_world_position: Option<Res<CursorWorldPosition>>,
+    dragged_item: Option<Res<DraggedItem>>,
+    panels: Query<(&InventoryPanel, &Transform)>,
+    mut inventory: ActiveInventoryQueryMut,
+) {
+    if !bindings.sort_key.is_some_and(|sort| key.just_pressed(sort))
+        || is_dragging(dragged_item.as_deref())
+    {
+        return;
+    }
+    let cursor = cursor_world_position.and_then(|cursor| cursor.0);
+    if let Some(mut inventory) = inventory.for_panel_mut(&panel_at(cursor, &panels)) {
+        let slots = settings.keep_slots + 1..=inventory.slot_count();
+        inventory.sort_slots(settings.strategy, slots);
+    }
+}
+
+/// Panel and slot of the slot background under `cursor`
+fn slot_at(
+    cursor: Vec2,
+    slot_backgrounds: &Query<(&InventorySlotBackground, &Transform)>,
+    panels: &Query<&InventoryPanel>,
+) -> Option<(InventoryPanel, usize)> {
+    let (background, _) = slot_backgrounds.iter().find(|(_, transform)| {
+        Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+            .contains(cursor)
+    })?;
+    let panel = panels
+        .get(background.base)
+        .copied()
+        .unwrap_or(InventoryPanel { inventory: None });
+    Some((panel, background.slot))
+}
+
+/// Selects the slot under the cursor on a left click, empty slots included
+///
+/// Clicks anywhere on an [`InventoryPanel`] are consumed, the gaps between its slots
+/// included. Shift clicks are left to [`quick_transfer_on_shift_click`].
+#[allow(clippy::too_many_arguments)]
+pub fn click_select_slot(
+    mouse: Res<Input<MouseButton>>,
+    key: Res<Input<KeyCode>>,
+    cursor_world_position: Res<CursorWorldPosition>,
+    dragged_item: Option<Res<DraggedItem>>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform)>,
+    panels: Query<&InventoryPanel>,
+    panel_backgrounds: Query<&Transform, With<InventoryPanel>>,
+    mut inventory: ActiveInventoryQueryMut,
+    mut click_consumed: ResMut<ClickConsumed>,
+) {
+    if !mouse.just_pressed(MouseButton::Left)
+        || is_dragging(dragged_item.as_deref())
+        || is_shift_held(&key)
+    {
+        return;
+    }
+    let Some(cursor) = cursor_world_position.0 else {
+        return;
+    };
+    if panel_backgrounds.iter().any(|transform| {
+        Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+            .contains(cursor)
+    }) {
+        click_consumed.0 = true;
+    }
+    let Some((panel, slot)) = slot_at(cursor, &slot_backgrounds, &panels) else {
+        return;
+    };
+    if let Some(mut inventory) = inventory.for_panel_mut(&panel) {
+        // A panel left over from a shrunk inventory may show slots past the end
+        let _ = inventory.select_item(slot);
+        click_consumed.0 = true;
+    }
+}
+
+fn is_shift_held(key: &Input<KeyCode>) -> bool {
+    key.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+/// Panel a shift click in `panel` sends the stack to, the hotbar and an open container or
+/// the open backpack trade with each other
+fn quick_transfer_target(
+    panel: &InventoryPanel,
+    open_container: Option<Entity>,
+    open_backpack: Option<Entity>,
+) -> Option<InventoryPanel> {
+    match panel.inventory {
+        None => open_container
+            .or(open_backpack)
+            .map(|inventory| InventoryPanel {
+                inventory: Some(inventory),
+            }),
+        Some(_) => Some(InventoryPanel { inventory: None }),
+    }
+}
+
+/// Moves the stack under the cursor to the other open inventory on a shift left click,
+/// stacking onto matching items before filling empty slots
+///
+/// What does not fit stays in its slot. Selections stay on their slots.
+#[allow(clippy::too_many_arguments)]
+pub fn quick_transfer_on_shift_click(
+    mouse: Res<Input<MouseButton>>,
+    key: Res<Input<KeyCode>>,
+    cursor_world_position: Res<CursorWorldPosition>,
+    dragged_item: Option<Res<DraggedItem>>,
+    open_container: Option<Res<OpenContainer>>,
+    backpack_open: Option<Res<BackpackOpen>>,
+    backpacks: Query<Entity, With<Backpack>>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform)>,
+    panels: Query<&InventoryPanel>,
+    mut inventory: ActiveInventoryQueryMut,
+    mut click_consumed: ResMut<ClickConsumed>,
+) {
+    if !mouse.just_pressed(MouseButton::Left)
+        || !is_shift_held(&key)
+        || is_dragging(dragged_item.as_deref())
+    {
+        return;
+    }
+    let Some(cursor) = cursor_world_position.0 else {
+        return;
+    };
+    let Some((panel, slot)) = slot_at(cursor, &slot_backgrounds, &panels) else {
+        return;
+    };
+    click_consumed.0 = true;
+    let open_container = open_container.and_then(|open_container| open_container.0);
+    let open_backpack = backpacks
+        .iter()
+        .next()
+        .filter(|_| backpack_open.is_some_and(|open| **open));
+    let Some(target) = quick_transfer_target(&panel, open_container, open_backpack) else {
+        return;
+    };
+    // Both inventories can not be borrowed at once, the room is checked up front
+    let (Some(source), Some(destination)) =
+        (inventory.for_panel(&panel), inventory.for_panel(&target))
+    else {
+        return;
+    };
+    let Some(item) = source.get_item(slot) else {
+        return;
+    };
+    let moved = destination.room_for(item).min(item.count);
+    if moved == 0 {
+        return;
+    }
+    let item = inventory
+        .for_panel_mut(&panel)
+        .and_then(|mut source| source.take(slot, moved).ok());
+    if let (Some(item), Some(mut destination)) = (item, inventory.for_panel_mut(&target)) {
+        destination.add_item(item).expect("room was checked");
+    }
+}
+
+/// Whether an item is being dragged, false without the [`DraggedItem`] resource
+pub fn is_dragging(dragged_item: Option<&DraggedItem>) -> bool {
+    dragged_item.is_some_and(|dragged_item| dragged_item.is_some())
+}
+
+/// Starts dragging the item of the filled slot under the cursor on a left press, not on a
+/// shift click
+#[allow(clippy::too_many_arguments)]
+pub fn begin_item_drag(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    key: Res<Input<KeyCode>>,
+    cursor_world_position: Res<CursorWorldPosition>,
+    settings: Res<BaseInventorySettings>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform)>,
+    panels: Query<&InventoryPanel>,
+    preview_items: Query<(&ItemCode, &ItemImage), With<ItemPreview>>,
+    inventory: ActiveInventoryQuery,
+    mut dragged_item: ResMut<DraggedItem>,
+    mut click_consumed: ResMut<ClickConsumed>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || dragged_item.is_some() || is_shift_held(&key) {
+        return;
+    }
+    let Some(cursor) = cursor_world_position.0 else {
+        return;
+    };
+    let Some((panel, slot)) = slot_at(cursor, &slot_backgrounds, &panels) else {
+        return;
+    };
+    let Some(item) = inventory
+        .for_panel(&panel)
+        .and_then(|inventory| inventory.get_item(slot))
+    else {
+        return;
+    };
+    let texture = preview_items
+        .iter()
+        .find(|(item_code, _)| **item_code == item.code)
+        .map(|(_, item_image)| item_image.0.clone())
+        .unwrap_or_default();
+    let size = settings.slot_size - settings.slot_margin;
+    commands.spawn((
+        DraggedItemSprite,
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(size, size)),
+                ..Default::default()
+            },
+            texture,
+            transform: Transform::from_translation(cursor.extend(DRAGGED_ITEM_Z)),
+            ..Default::default()
+        },
+    ));
+    dragged_item.0 = Some(ItemDrag {
+        panel,
+        slot,
+        item: item.clone(),
+    });
+    click_consumed.0 = true;
+}
+
+pub fn update_item_drag(
+    cursor_world_position: Res<CursorWorldPosition>,
+    mut sprites: Query<&mut Transform, With<DraggedItemSprite>>,
+) {
+    let Some(cursor) = cursor_world_position.0 else {
+        return;
+    };
+    for mut transform in sprites.iter_mut() {
+        transform.translation = cursor.extend(DRAGGED_ITEM_Z);
+    }
+}
+
+/// Drops the dragged item on the slot under the cursor when the left button is released,
+/// swapping with what is there
+///
+/// Released anywhere else or cancelled with Escape, the item stays where it was.
+#[allow(clippy::too_many_arguments)]
+pub fn end_item_drag(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    key: Res<Input<KeyCode>>,
+    cursor_world_position: Res<CursorWorldPosition>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform)>,
+    panels: Query<&InventoryPanel>,
+    sprites: Query<Entity, With<DraggedItemSprite>>,
+    mut inventory: ActiveInventoryQueryMut,
+    mut dragged_item: ResMut<DraggedItem>,
+) {
+    let Some(drag) = dragged_item.0.clone() else {
+        return;
+    };
+    let cancelled = key.just_pressed(KeyCode::Escape);
+    if !cancelled && !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    let target = cursor_world_position
+        .0
+        .and_then(|cursor| slot_at(cursor, &slot_backgrounds, &panels))
+        .filter(|_| !cancelled);
+    if let Some((panel, slot)) = target {
+        if panel == drag.panel {
+            if let Some(mut inventory) = inventory.for_panel_mut(&panel) {
+                // Fails when the item left its slot during the drag
+                let _ = inventory.move_item(drag.slot, slot, MoveConflict::Swap);
+            }
+        } else if accepts_drop(&inventory, &drag, &panel, slot) {
+            // Fails when the item left its slot during the drag
+            let item = inventory
+                .for_panel_mut(&drag.panel)
+                .and_then(|mut source| source.remove_item(drag.slot).ok());
+            if let Some(item) = item {
+                let displaced = inventory.for_panel_mut(&panel).and_then(|mut target| {
+                    let displaced = target.remove_item(slot).ok();
+                    target.put_item_unchecked(slot, item);
+                    displaced
+                });
+                if let (Some(displaced), Some(mut source)) =
+                    (displaced, inventory.for_panel_mut(&drag.panel))
+                {
+                    source.put_item_unchecked(drag.slot, displaced);
+                }
+            }
+        }
+    }
+    for sprite in sprites.iter() {
+        commands.entity(sprite).despawn();
+    }
+    dragged_item.0 = None;
+}
+
+/// Whether `slot` of `panel` takes the dragged item and the slot it was dragged from takes
+/// the item it displaces, within the weight limits of both inventories
+fn accepts_drop(
+    inventory: &ActiveInventoryQueryMut,
+    drag: &ItemDrag,
+    panel: &InventoryPanel,
+    slot: usize,
+) -> bool {
+    let (Some(source), Some(target)) =
+        (inventory.for_panel(&drag.panel), inventory.for_panel(panel))
+    else {
+        return false;
+    };
+    let displaced = target.get_item(slot);
+    let dragged_weight = drag.item.stack_weight();
+    let displaced_weight = displaced.map_or(0.0, Item::stack_weight);
+    // Two panels of the same inventory trade nothing
+    let same = std::ptr::eq(source, target);
+    target.contains_slot(slot)
+        && target.accepts(slot, &drag.item)
+        && displaced.is_none_or(|displaced| source.accepts(drag.slot, displaced))
+        && (same
+            || target.fits_weight_trade(displaced_weight, dragged_weight)
+                && source.fits_weight_trade(dragged_weight, displaced_weight))
+}
+
+/// Sends the events buffered in the [`BaseInventory`] and every [`DynInventory`] component
+pub fn send_inventory_events(
+    base: Option<ResMut<BaseInventory>>,
+    mut inventories: Query<(Entity, &mut DynInventory)>,
+    mut selection_changed: EventWriter<SelectionChanged>,
+    mut inventory_changed: EventWriter<InventoryChanged>,
+) {
+    let base = base.map(|base| (None, base.map_unchanged(|base| &mut base.0)));
+    let owned = inventories
+        .iter_mut()
+        .map(|(entity, inventory)| (Some(entity), inventory));
+    for (owner, mut inventory) in base.into_iter().chain(owned) {
+        if !inventory.has_pending_events() {
+            continue;
+        }
+        // Draining leaves the inventory as it was, not worth a change
+        let inventory = inventory.bypass_change_detection();
+        selection_changed.send_batch(inventory.drain_selection_events().map(|event| {
+            SelectionChanged {
+                inventory: owner,
+                ..event
+            }
+        }));
+        inventory_changed.send_batch(inventory.drain_slot_events().map(|event| InventoryChanged {
+            inventory: owner,
+            ..event
+        }));
+    }
+}
+
+/// Opens the [`Container`] under the cursor on a left click not taken by a slot
+#[allow(clippy::type_complexity)]
+pub fn open_container_on_click(
+    mouse: Res<Input<MouseButton>>,
+    cursor_world_position: Res<CursorWorldPosition>,
+    containers: Query<(Entity, &Transform), (With<Container>, With<DynInventory>)>,
+    mut click_consumed: ResMut<ClickConsumed>,
+    mut open_container: ResMut<OpenContainer>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || **click_consumed {
+        return;
+    }
+    let Some(cursor) = cursor_world_position.0 else {
+        return;
+    };
+    let hit = containers.iter().find(|(_, transform)| {
+        Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+            .contains(cursor)
+    });
+    if let Some((container, _)) = hit {
+        open_container.set_if_neq(OpenContainer(Some(container)));
+        click_consumed.0 = true;
+    }
+}
+
+/// Escape cancels a drag before closing the container
+pub fn close_container_on_escape(
+    key: Res<Input<KeyCode>>,
+    dragged_item: Option<Res<DraggedItem>>,
+    mut open_container: ResMut<OpenContainer>,
+) {
+    if key.just_pressed(KeyCode::Escape) && !is_dragging(dragged_item.as_deref()) {
+        open_container.set_if_neq(OpenContainer(None));
+    }
+}
+
+/// Spawns the panel of the [`OpenContainer`] above the hotbar, despawns it once closed
+///
+/// A container that lost its inventory or was despawned is closed.
+#[allow(clippy::too_many_arguments)]
+pub fn sync_container_panel(
+    mut commands: Commands,
+    settings: Res<BaseInventorySettings>,
+    mut open_container: ResMut<OpenContainer>,
+    inventories: Query<&DynInventory, With<Container>>,
+    hotbars: Query<&Transform, With<BaseInventoryBackground>>,
+    panels: Query<(Entity, &InventoryPanel), With<ContainerPanel>>,
+    parts: InventoryPanelParts,
+) {
+    if open_container.is_some_and(|container| !inventories.contains(container)) {
+        open_container.0 = None;
+    }
+    let open = open_container.0;
+    let mut shown = false;
+    for (panel, InventoryPanel { inventory }) in panels.iter() {
+        if *inventory == open && !shown {
+            shown = true;
+            continue;
+        }
+        parts.despawn(&mut commands, panel);
+    }
+
+    let Some(container) = open.filter(|_| !shown) else {
+        return;
+    };
+    let n_slots = inventories
+        .get(container)
+        .map_or(0, DynInventory::slot_count);
+    let size = inventory_panel_size(&settings, 1, n_slots);
+    let pos = match hotbars.get_single() {
+        Ok(hotbar) => {
+            hotbar.translation.truncate()
+                + Vec2::Y * (hotbar.scale.y / 2.0 + CONTAINER_PANEL_GAP + size.y / 2.0)
+        }
+        Err(_) => Vec2::ZERO,
+    };
+    let panel = spawn_inventory_panel(&mut commands, &settings, pos, 1, n_slots, Some(container));
+    commands.entity(panel).insert(ContainerPanel);
+}
+
+/// Slots, slot backgrounds and highlights of the inventory panels
+#[derive(SystemParam)]
+pub struct InventoryPanelParts<'w, 's> {
+    slots: Query<'w, 's, (Entity, &'static InventorySlot)>,
+    slot_backgrounds: Query<'w, 's, (Entity, &'static InventorySlotBackground)>,
+    highlights: Query<'w, 's, (Entity, &'static InventorySlotHighlight)>,
+}
+
+impl InventoryPanelParts<'_, '_> {
+    /// Despawns the background of `panel` along with its slots, slot backgrounds and highlight
+    pub fn despawn(&self, commands: &mut Commands, panel: Entity) {
+        commands.entity(panel).despawn();
+        let parts = self
+            .slots
+            .iter()
+            .map(|(entity, slot)| (entity, slot.base))
+            .chain(
+                self.slot_backgrounds
+                    .iter()
+                    .map(|(entity, background)| (entity, background.base)),
+            )
+            .chain(
+                self.highlights
+                    .iter()
+                    .map(|(entity, highlight)| (entity, highlight.base)),
+            );
+        for (entity, _) in parts.filter(|(_, base)| *base == panel) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Spawns the [`Backpack`] inventory, it keeps its items while its panel is closed
+pub fn spawn_backpack(mut commands: Commands, settings: Res<BackpackSettings>) {
+    commands.spawn((
+        Backpack,
+        DynInventory::with_capacity(settings.rows * settings.columns),
+    ));
+}
+
+/// Opens and closes the backpack with its [`BackpackSettings::toggle_keys`], Escape closes it
+///
+/// Ignored while dragging, a drag can end on the hotbar or the backpack.
+pub fn toggle_backpack(
+    key: Res<Input<KeyCode>>,
+    settings: Res<BackpackSettings>,
+    dragged_item: Option<Res<DraggedItem>>,
+    mut backpack_open: ResMut<BackpackOpen>,
+) {
+    if is_dragging(dragged_item.as_deref()) {
+        return;
+    }
+    if key.any_just_pressed(settings.toggle_keys.iter().copied()) {
+        backpack_open.0 = !backpack_open.0;
+    } else if key.just_pressed(KeyCode::Escape) {
+        backpack_open.set_if_neq(BackpackOpen(false));
+    }
+}
+
+/// Spawns the panel of the [`Backpack`] while it is open, despawns it once closed
+///
+/// The panel sits above the hotbar, leaving a row for the container panel in between.
+#[allow(clippy::too_many_arguments)]
+pub fn sync_backpack_panel(
+    mut commands: Commands,
+    settings: Res<BaseInventorySettings>,
+    backpack_settings: Res<BackpackSettings>,
+    backpack_open: Res<BackpackOpen>,
+    backpacks: Query<Entity, (With<Backpack>, With<DynInventory>)>,
+    hotbars: Query<&Transform, With<BaseInventoryBackground>>,
+    panels: Query<(Entity, &InventoryPanel), With<BackpackPanel>>,
+    parts: InventoryPanelParts,
+) {
+    let open = backpacks.iter().next().filter(|_| **backpack_open);
+    let mut shown = false;
+    for (panel, InventoryPanel { inventory }) in panels.iter() {
+        if open.is_some() && *inventory == open && !shown {
+            shown = true;
+            continue;
+        }
+        parts.despawn(&mut commands, panel);
+    }
+
+    let Some(backpack) = open.filter(|_| !shown) else {
+        return;
+    };
+    let (rows, cols) = (backpack_settings.rows, backpack_settings.columns);
+    let size = inventory_panel_size(&settings, rows, cols);
+    let pos = match hotbars.get_single() {
+        Ok(hotbar) => {
+            hotbar.translation.truncate()
+                + Vec2::Y * (hotbar.scale.y * 1.5 + 2.0 * CONTAINER_PANEL_GAP + size.y / 2.0)
+        }
+        Err(_) => Vec2::ZERO,
+    };
+    let panel = spawn_inventory_panel(&mut commands, &settings, pos, rows, cols, Some(backpack));
+    commands.entity(panel).insert(BackpackPanel);
+}
+
+/// Moves the highlight onto the selected slot background, follows the slot if it moves
+#[allow(clippy::type_complexity)]
+pub fn highlight_selected_slot(
+    inventory: ActiveInventoryQuery,
+    settings: Res<BaseInventorySettings>,
+    panels: Query<&InventoryPanel>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform)>,
+    mut highlights: Query<
+        (
+            &InventorySlotHighlight,
+            &mut Transform,
+            &mut Visibility,
+            &mut Sprite,
+        ),
+        (
+            With<InventorySlotHighlight>,
+            Without<InventorySlotBackground>,
+        ),
+    >,
+) {
+    for (highlight, mut transform, mut visibility, mut sprite) in highlights.iter_mut() {
+        let panel_inventory = match panels.get(highlight.base) {
+            Ok(panel) => inventory.for_panel(panel),
+            Err(_) => inventory.get(),
+        };
+        let selected = panel_inventory
+            .and_then(DynInventory::selected_slot)
+            .and_then(|selected| {
+                slot_backgrounds.iter().find(|(background, _)| {
+                    background.base == highlight.base && background.slot == selected
+                })
+            });
+        let Some((_, background)) = selected else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Visible);
+        let size = settings.slot_size + 2.0 * settings.highlight_thickness;
+        let target = Vec3::new(
+            background.translation.x,
+            background.translation.y,
+            settings.base_z + settings.z_offsets.highlight,
+        );
+        if transform.translation != target || transform.scale.truncate() != Vec2::splat(size) {
+            transform.translation = target;
+            transform.scale = Vec3::new(size, size, 1.0);
+        }
+        if settings.is_changed() {
+            sprite.color = settings.highlight_color;
+        }
+    }
+}
+
+/// Slot backgrounds of the panels that show the [`DynInventory`] of `inventory`, see
+/// [`SelectionChanged::inventory`]
+#[derive(SystemParam)]
+pub struct ShownSlotBackgrounds<'w, 's> {
+    active: Option<Res<'w, ActiveInventory>>,
+    panels: Query<'w, 's, &'static InventoryPanel>,
+    slot_backgrounds: Query<'w, 's, (Entity, &'static InventorySlotBackground)>,
+}
+
+impl ShownSlotBackgrounds<'_, '_> {
+    /// Backgrounds of `slot` in every panel showing `inventory`
+    pub fn of(&self, inventory: Option<Entity>, slot: usize) -> impl Iterator<Item = Entity> + '_ {
+        let active = self.active.as_deref().and_then(|active| active.0);
+        self.slot_backgrounds
+            .iter()
+            .filter(move |(_, background)| {
+                let shown = match self.panels.get(background.base) {
+                    Ok(panel) => panel.inventory.or(active),
+                    Err(_) => active,
+                };
+                background.slot == slot && shown == inventory
+            })
+            .map(|(entity, _)| entity)
+    }
+}
+
+/// Id of the last pulse animator of a slot background
+///
+/// Each pulse of a background gets the next id, so a finished pulse is told apart from one
+/// restarting it and from other animators of the entity.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotPulse(pub u32);
+
+/// Like [`pulse`](crate::animation::pulse), following the [`SlotPulse`] of `entity`
+fn pulse_slot_background(
+    commands: &mut Commands,
+    settings: &InventorySettings,
+    pulses: &Query<&SlotPulse>,
+    entity: Entity,
+) {
+    let id = pulses
+        .get(entity)
+        .map_or(0, |pulse| pulse.0.wrapping_add(1));
+    commands.entity(entity).insert((
+        SlotPulse(id),
+        Animator::new(
+            Animation {
+                duration: settings.pulse_duration,
+                curve: AnimationCurve::Linear,
+            },
+            Repeat::Once,
+            ScalePulseLens {
+                base: Vec3::new(settings.slot_size, settings.slot_size, 1.0),
+                peak: settings.pulse_scale,
+            },
+        )
+        .with_id(id),
+    ));
+}
+
+/// Pulses the background of the newly selected slot in every panel showing the inventory
+pub fn pulse_selected_slot(
+    mut commands: Commands,
+    mut selection_changed: EventReader<SelectionChanged>,
+    settings: Res<BaseInventorySettings>,
+    pulses: Query<&SlotPulse>,
+    slot_backgrounds: ShownSlotBackgrounds,
+) {
+    if !settings.pulse_selected {
+        selection_changed.clear();
+        return;
+    }
+    for event in selection_changed.iter().filter(|event| event.current != 0) {
+        for entity in slot_backgrounds.of(event.inventory, event.current) {
+            pulse_slot_background(&mut commands, &settings, &pulses, entity);
+        }
+    }
+}
+
+/// Pulses the background of unlocked slots, see [`DynInventory::unlock_slot`]
+pub fn pulse_unlocked_slots(
+    mut commands: Commands,
+    mut inventory_changed: EventReader<InventoryChanged>,
+    settings: Res<BaseInventorySettings>,
+    pulses: Query<&SlotPulse>,
+    slot_backgrounds: ShownSlotBackgrounds,
+) {
+    for event in inventory_changed
+        .iter()
+        .filter(|event| event.change == SlotChange::Unlocked)
+    {
+        for entity in slot_backgrounds.of(event.inventory, event.slot) {
+            pulse_slot_background(&mut commands, &settings, &pulses, entity);
+        }
+    }
+}
+
+/// Removes finished pulses so slot backgrounds do not keep their animators
+///
+/// Runs before the pulsing systems, a pulse restarted in the frame the last one finished
+/// has a new [`SlotPulse`] and keeps running.
+pub fn remove_finished_pulses(
+    mut commands: Commands,
+    mut completed: EventReader<AnimationCompleted>,
+    pulses: Query<(&SlotPulse, &Animator<ScalePulseLens>)>,
+) {
+    for event in completed.iter() {
+        if pulses.get(event.entity).is_ok_and(|(pulse, animator)| {
+            event.animator_id == Some(pulse.0) && animator.id() == event.animator_id
+        }) {
+            commands
+                .entity(event.entity)
+                .remove::<Animator<ScalePulseLens>>();
+        }
+    }
+}
+
+/// Every system of [`InventoryPlugin`], in whichever schedule it runs
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InventorySet;
+
+/// Hotbar, container panels, drag and drop and placement of the selected item
+///
+/// Needs the [`GridPlugin`](crate::grid::GridPlugin) and bevy's input.
+pub struct InventoryPlugin {
+    /// Inserted as [`BaseInventorySettings`]
+    pub settings: InventorySettings,
+    /// Schedule [`spawn_base_inventory`] runs in, the primary window must exist by then
+    pub spawn_schedule: BoxedScheduleLabel,
+    pub backend: InventoryBackend,
+}
+
+/// How the hotbar is drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryBackend {
+    /// World space sprites, see [`spawn_base_inventory`]
+    #[default]
+    Sprite,
+    /// bevy_ui nodes, see [`InventoryUiPlugin`]
+    Ui,
+}
+
+impl Default for InventoryPlugin {
+    fn default() -> Self {
+        Self {
+            settings: InventorySettings::default(),
+            spawn_schedule: Box::new(PostStartup),
+            backend: InventoryBackend::default(),
+        }
+    }
+}
+
+impl InventoryPlugin {
+    pub fn with_settings(settings: InventorySettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+}
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BaseInventory>()
+            .init_resource::<ActiveInventory>()
+            .insert_resource(BaseInventorySettings(self.settings.clone()))
+            .init_resource::<HotbarScrollSettings>()
+            .init_resource::<InventoryKeyBindings>()
+            .init_resource::<ItemRegistry>()
+            .init_resource::<ClickConsumed>()
+            .init_resource::<InfinitePlacement>()
+            .init_resource::<PlacementShake>()
+            .init_resource::<OpenContainer>()
+            .init_resource::<DraggedItem>()
+            .init_resource::<SlotCooldowns>()
+            .init_resource::<BackpackSettings>()
+            .init_resource::<BackpackOpen>()
+            .init_resource::<SortSettings>()
+            .add_event::<SelectionChanged>()
+            .add_event::<InventoryChanged>()
+            .add_event::<ItemDropped>()
+            .add_systems(Startup, spawn_backpack.in_set(InventorySet))
+            .add_systems(First, reset_click_consumed.in_set(InventorySet))
+            .add_systems(
+                Update,
+                (
+                    select_item,
+                    scroll_select_item.after(select_item),
+                    clear_selection_on_input
+                        .after(scroll_select_item)
+                        .before(show_selected_item)
+                        .before(place_selected_item),
+                    drop_selected_item
+                        .after(clear_selection_on_input)
+                        .before(show_selected_item),
+                    (pick_up_dropped_items, sort_inventory_on_key),
+                    show_selected_item,
+                    quick_transfer_on_shift_click
+                        .after(scroll_select_item)
+                        .before(click_select_slot)
+                        .before(place_selected_item),
+                    click_select_slot
+                        .after(scroll_select_item)
+                        .before(place_selected_item),
+                    place_selected_item,
+                    (begin_item_drag, update_item_drag, end_item_drag)
+                        .chain()
+                        .after(click_select_slot)
+                        .before(open_container_on_click)
+                        .after(close_container_on_escape)
+                        .after(toggle_backpack),
+                    open_container_on_click
+                        .after(click_select_slot)
+                        .before(place_selected_item),
+                    close_container_on_escape,
+                    sync_container_panel,
+                    toggle_backpack,
+                    sync_backpack_panel.after(toggle_backpack),
+                    (
+                        render_items_in_base_inventory,
+                        update_slot_backgrounds,
+                        apply_inventory_theme,
+                        relayout_inventory_panels.before(apply_inventory_images),
+                        resize_base_inventory_bar.after(relayout_inventory_panels),
+                        apply_inventory_images
+                            .after(update_slot_backgrounds)
+                            .run_if(resource_exists::<Assets<Image>>()),
+                        tint_nine_slice_pieces
+                            .after(update_slot_backgrounds)
+                            .after(apply_inventory_images),
+                    ),
+                    highlight_selected_slot.after(scroll_select_item),
+                    update_slot_numbers,
+                    (update_inventory_tooltip, update_selected_item_name),
+                    (tick_slot_cooldowns, update_cooldown_overlays).chain(),
+                )
+                    .in_set(InventorySet),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    send_inventory_events,
+                    (
+                        pulse_selected_slot.after(send_inventory_events),
+                        pulse_unlocked_slots.after(send_inventory_events),
+                        remove_finished_pulses
+                            .before(pulse_selected_slot)
+                            .before(pulse_unlocked_slots),
+                        remove_finished_shakes,
+                    )
+                        .run_if(resource_exists::<AnimationSettings>()),
+                )
+                    .in_set(InventorySet),
+            );
+        match self.backend {
+            InventoryBackend::Sprite => {
+                app.add_systems(
+                    self.spawn_schedule.clone(),
+                    spawn_base_inventory.in_set(InventorySet),
+                );
+            }
+            InventoryBackend::Ui => {
+                app.add_plugins(InventoryUiPlugin);
+            }
+        }
+        #[cfg(feature = "std-io")]
+        app.add_event::<SaveInventoryRequest>()
+            .add_event::<LoadInventoryRequest>()
+            .add_systems(
+                Update,
+                (save_inventory_system, load_inventory_system).in_set(InventorySet),
+            );
+    }
+}
+
+/// Label of a key in the slot corner, the digit for number keys
+fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    // Number row keys are named Key0 to Key9
+    match name.strip_prefix("Key") {
+        Some(digit) => digit.to_string(),
+        None => name,
+    }
+}
+
+/// Keeps the slot numbers in the [`InventorySettings::number_corner`] of their slot,
+/// labelled with the key bound to the slot and styled by the settings
+#[allow(clippy::type_complexity)]
+pub fn update_slot_numbers(
+    settings: Res<BaseInventorySettings>,
+    bindings: Res<InventoryKeyBindings>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform), Without<InventorySlotNumber>>,
+    mut numbers: Query<(
+        Ref<InventorySlotNumber>,
+        &mut Transform,
+        &mut Text,
+        &mut Anchor,
+        &mut Visibility,
+    )>,
+) {
+    for (number, mut transform, mut text, mut anchor, mut visibility) in numbers.iter_mut() {
+        let background = slot_backgrounds.iter().find(|(background, _)| {
+            background.base == number.base && background.slot == number.slot
+        });
+        let key = bindings.binding_for(number.slot);
+        if bindings.is_changed() || number.is_added() {
+            text.sections[0].value = key.map(key_label).unwrap_or_default();
+        }
+        let shown = settings.show_slot_numbers && key.is_some();
+        let Some((_, background)) = background.filter(|_| shown) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Visible);
+        let corner = settings.number_corner.as_vec();
+        // Inset by the slot margin so the number stays inside the slot
+        let inset = background.scale.truncate() - Vec2::splat(2.0 * settings.slot_margin);
+        let target = (background.translation.truncate() + corner * inset)
+            .extend(settings.base_z + settings.z_offsets.number);
+        if transform.translation != target {
+            transform.translation = target;
+        }
+        if anchor.as_vec() != corner {
+            *anchor = settings.number_corner.clone();
+        }
+        if settings.is_changed() || number.is_added() {
+            for section in text.sections.iter_mut() {
+                section.style.font_size = settings.number_font_size;
+                section.style.color = settings.number_color;
+            }
+        }
+    }
+}
+
+pub fn tick_slot_cooldowns(time: Res<Time>, mut cooldowns: ResMut<SlotCooldowns>) {
+    if !cooldowns.timers.is_empty() {
+        cooldowns.tick(time.delta());
+    }
+}
+
+/// Fills each hotbar slot on cooldown from the bottom, as high as the part of the cooldown
+/// left in the [`ActiveInventory`]
+#[allow(clippy::type_complexity)]
+pub fn update_cooldown_overlays(
+    cooldowns: Res<SlotCooldowns>,
+    inventory: ActiveInventoryQuery,
+    settings: Res<BaseInventorySettings>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform), Without<InventorySlotCooldown>>,
+    mut overlays: Query<(&InventorySlotCooldown, &mut Transform, &mut Visibility)>,
+) {
+    for (overlay, mut transform, mut visibility) in overlays.iter_mut() {
+        let fraction = cooldowns.remaining_fraction(inventory.entity(), overlay.slot);
+        let background = slot_backgrounds.iter().find(|(background, _)| {
+            background.base == overlay.base && background.slot == overlay.slot
+        });
+        let Some((_, background)) = background.filter(|_| fraction > 0.0) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Visible);
+        let size = background.scale.truncate();
+        let target = Transform::from_translation(
+            (background.translation.truncate() - Vec2::Y * size.y / 2.0)
+                .extend(settings.base_z + settings.z_offsets.cooldown),
+        )
+        .with_scale(Vec3::new(size.x, size.y * fraction, 1.0));
+        if *transform != target {
+            *transform = target;
+        }
+    }
+}
+
+/// Width of a glyph relative to the font size, roughly
+const GLYPH_WIDTH: f32 = 0.6;
+
+/// Cuts `name` short with an ellipsis when it has more than `max_chars` characters
+fn fit_name(name: String, max_chars: usize) -> String {
+    if name.chars().count() <= max_chars {
+        return name;
+    }
+    let mut fitted: String = name.chars().take(max_chars.saturating_sub(1)).collect();
+    fitted.push('…');
+    fitted
+}
+
+/// Shows the [`ItemRegistry`] name of the selected item under its slot of the hotbar, hides it
+/// while nothing is selected or the selected slot is empty
+#[allow(clippy::type_complexity)]
+pub fn update_selected_item_name(
+    inventory: ActiveInventoryQuery,
+    settings: Res<BaseInventorySettings>,
+    registry: Res<ItemRegistry>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform), Without<InventoryItemName>>,
+    mut labels: Query<(
+        &InventoryItemName,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let inventory = inventory.get();
+    let selected = inventory.and_then(|inventory| {
+        let slot = inventory.selected_slot()?;
+        Some((slot, inventory.get_item(slot)?))
+    });
+    for (label, mut text, mut transform, mut visibility) in labels.iter_mut() {
+        // Hidden labels too, they show up styled once something is selected
+        if settings.is_changed() {
+            let style = &mut text.sections[0].style;
+            style.font = settings.count_font.clone();
+            style.font_size = settings.item_name_font_size;
+            style.color = settings.item_name_color;
+        }
+        let background = selected.and_then(|(slot, item)| {
+            slot_backgrounds
+                .iter()
+                .find(|(background, _)| background.base == label.base && background.slot == slot)
+                .map(|(_, background)| (background, item))
+        });
+        let Some((background, item)) = background else {
+            if !text.sections[0].value.is_empty() {
+                text.sections[0].value.clear();
+            }
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Visible);
+        let name = fit_name(
+            registry.display_name(item.code),
+            settings.item_name_max_chars,
+        );
+        let section = &mut text.sections[0];
+        if section.value != name {
+            section.value = name;
+        }
+        let target = (background.translation.truncate()
+            - Vec2::Y * (background.scale.y / 2.0 + settings.slot_margin))
+            .extend(TOOLTIP_Z);
+        if transform.translation != target {
+            transform.translation = target;
+        }
+    }
+}
+
+pub const TOOLTIP_FONT_SIZE: f32 = 16.0;
+/// Above the slot numbers and the stack counts
+pub const TOOLTIP_Z: f32 = 50.0;
+
+/// Shows the [`InventoryTooltip`] above the filled slot under the cursor once it was hovered
+/// for [`InventorySettings::tooltip_delay`], hides it otherwise
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_inventory_tooltip(
+    time: Res<Time>,
+    cursor_world_position: Res<CursorWorldPosition>,
+    settings: Res<BaseInventorySettings>,
+    registry: Res<ItemRegistry>,
+    slot_backgrounds: Query<(&InventorySlotBackground, &Transform), Without<InventoryTooltip>>,
+    panels: Query<&InventoryPanel>,
+    inventory: ActiveInventoryQuery,
+    mut hovered: Local<Option<(Entity, usize, Duration)>>,
+    mut tooltips: Query<
+        (&mut Transform, &mut Sprite, &mut Visibility, &Children),
+        With<InventoryTooltip>,
+    >,
+    mut texts: Query<&mut Text>,
+) {
+    let hit = cursor_world_position.0.and_then(|cursor| {
+        slot_backgrounds.iter().find(|(_, transform)| {
+            Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+                .contains(cursor)
+        })
+    });
+    let item = hit.and_then(|(background, _)| {
+        let panel = panels
+            .get(background.base)
+            .copied()
+            .unwrap_or(InventoryPanel { inventory: None });
+        inventory.for_panel(&panel)?.get_item(background.slot)
+    });
+    let shown = match (hit, item) {
+        (Some((background, transform)), Some(item)) => {
+            let elapsed = match *hovered {
+                Some((base, slot, elapsed))
+                    if base == background.base && slot == background.slot =>
+                {
+                    elapsed + time.delta()
+                }
+                _ => Duration::ZERO,
+            };
+            *hovered = Some((background.base, background.slot, elapsed));
+            (elapsed >= settings.tooltip_delay).then_some((transform, item))
+        }
+        _ => {
+            *hovered = None;
+            None
+        }
+    };
+
+    for (mut tooltip, mut sprite, mut visibility, children) in tooltips.iter_mut() {
+        let Some((slot, item)) = shown else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Visible);
+        let name = registry.display_name(item.code);
+        let size = Vec2::new(
+            name.chars().count() as f32 * TOOLTIP_FONT_SIZE * GLYPH_WIDTH,
+            TOOLTIP_FONT_SIZE * 1.5,
+        );
+        let target = (slot.translation.truncate()
+            + Vec2::Y * (slot.scale.y / 2.0 + settings.slot_margin + size.y / 2.0))
+            .extend(TOOLTIP_Z);
+        if tooltip.translation != target {
+            tooltip.translation = target;
+        }
+        if sprite.custom_size != Some(size) {
+            sprite.custom_size = Some(size);
+        }
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            if text.sections[0].value != name {
+                text.sections[0].value = name.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+
+    use super::{
+        inventory_panel_size, layout_slots, DynInventory, Inventory, InventoryChanged,
+        InventoryError, InventorySettings, MoveConflict, SelectionChanged, SlotChange, SlotFilter,
+        SortStrategy, MAX_PENDING_INVENTORY_EVENTS,
+    };
+    use crate::items::{Item, ItemCategory, ItemCode};
+
+    fn item(code: usize, count: u32, max_stack: u32) -> Item {
+        Item::new(ItemCode(code), count, max_stack)
+    }
+
+    #[test]
+    fn add_to_first_empty_slot() {
+        let mut inventory = Inventory::<3>::default();
+        assert_eq!(inventory.first_empty_slot(), Some(1));
+        inventory.put_item(1, item(1, 1, 1)).unwrap();
+        assert_eq!(inventory.add_item(item(1, 1, 1)), Ok(2));
+        inventory.remove_item(1).unwrap();
+        assert_eq!(inventory.add_item(item(2, 1, 1)), Ok(1));
+        assert_eq!(inventory.add_item(item(3, 1, 1)), Ok(3));
+
+        assert!(inventory.is_full());
+        assert_eq!(inventory.first_empty_slot(), None);
+        assert_eq!(inventory.add_item(item(4, 1, 1)), Err(item(4, 1, 1)));
+        assert_eq!(inventory.get_item(1), Some(&item(2, 1, 1)));
+    }
+
+    #[test]
+    fn add_merges_into_stacks() {
+        let mut inventory = Inventory::<4>::default();
+        inventory.put_item(2, item(7, 8, 10)).unwrap();
+        inventory.put_item(3, item(5, 3, 10)).unwrap();
+        inventory.put_item(4, item(7, 9, 10)).unwrap();
+
+        // Slot 1 is empty but the stacks of 7 are topped up first
+        assert_eq!(inventory.add_item(item(7, 2, 10)), Ok(2));
+        assert_eq!(inventory.get_item(2).unwrap().count, 10);
+        assert_eq!(inventory.get_item(1), None);
+
+        // Spills over into the next stack, then into the empty slot
+        assert_eq!(inventory.add_item(item(7, 4, 10)), Ok(1));
+        assert_eq!(inventory.get_item(4).unwrap().count, 10);
+        assert_eq!(inventory.get_item(1), Some(&item(7, 3, 10)));
+
+        // Full, only the part that did not fit comes back
+        assert_eq!(inventory.add_item(item(5, 9, 10)), Err(item(5, 2, 10)));
+        assert_eq!(inventory.get_item(3).unwrap().count, 10);
+    }
+
+    #[test]
+    fn take_one() {
+        let mut inventory = Inventory::<2>::default();
+        inventory.put_item(1, item(1, 2, 10)).unwrap();
+        assert_eq!(inventory.take_one(1), Ok(item(1, 1, 10)));
+        assert_eq!(inventory.get_item(1), Some(&item(1, 1, 10)));
+        assert_eq!(inventory.take_one(1), Ok(item(1, 1, 10)));
+        assert_eq!(inventory.get_item(1), None);
+        assert_eq!(inventory.take_one(1), Err(InventoryError::SlotEmpty(1)));
+        assert_eq!(
+            inventory.take_one(0),
+            Err(InventoryError::SlotOutOfRange(0))
+        );
+        assert_eq!(
+            inventory.take_one(3),
+            Err(InventoryError::SlotOutOfRange(3))
+        );
+    }
+
+    #[test]
+    fn bounds() {
+        use InventoryError::{SlotEmpty, SlotOccupied, SlotOutOfRange};
+
+        let mut inventory = Inventory::<3>::default();
+        let mut other = DynInventory::with_capacity(3);
+        for slot in [0, 4] {
+            assert_eq!(inventory.get_item(slot), None);
+            assert!(!inventory.contains_slot(slot));
+            assert_eq!(
+                inventory.put_item(slot, item(1, 1, 1)),
+                Err(SlotOutOfRange(slot))
+            );
+            assert_eq!(inventory.remove_item(slot), Err(SlotOutOfRange(slot)));
+            assert_eq!(inventory.take_one(slot), Err(SlotOutOfRange(slot)));
+            assert_eq!(inventory.swap_slots(slot, 3), Err(SlotOutOfRange(slot)));
+            assert_eq!(
+                inventory.move_item(3, slot, MoveConflict::Swap),
+                Err(SlotOutOfRange(slot))
+            );
+            assert_eq!(
+                inventory.transfer_to(&mut other, slot, 3),
+                Err(SlotOutOfRange(slot))
+            );
+        }
+        assert_eq!(inventory.select_item(4), Err(SlotOutOfRange(4)));
+        assert_eq!(inventory.selected_slot(), None);
+        assert!(!inventory.has_pending_events());
+
+        // Slot N is the last valid one, 0 clears the selection
+        assert!(inventory.contains_slot(3));
+        assert_eq!(inventory.get_item(3), None);
+        assert_eq!(inventory.remove_item(3), Err(SlotEmpty(3)));
+        assert_eq!(inventory.take_one(3), Err(SlotEmpty(3)));
+        inventory.put_item(3, item(1, 2, 10)).unwrap();
+        assert_eq!(inventory.put_item(3, item(2, 1, 1)), Err(SlotOccupied(3)));
+        assert_eq!(inventory.take_one(3), Ok(item(1, 1, 10)));
+        inventory.select_item(3).unwrap();
+        assert_eq!(inventory.selected_slot(), Some(3));
+        inventory.select_item(0).unwrap();
+        assert_eq!(inventory.selected_slot(), None);
+        inventory.swap_slots(3, 1).unwrap();
+        inventory.move_item(1, 3, MoveConflict::Fail).unwrap();
+        assert_eq!(
+            inventory.transfer_to(&mut other, 3, 4),
+            Err(SlotOutOfRange(4))
+        );
+        inventory.transfer_to(&mut other, 3, 3).unwrap();
+        assert_eq!(other.remove_item(3), Ok(item(1, 1, 10)));
+    }
+
+    #[test]
+    fn capacity_and_occupancy() {
+        let mut inventory = Inventory::<4>::default();
+        assert!(inventory.is_empty());
+        assert!(!inventory.is_full());
+        assert_eq!((inventory.occupied_count(), inventory.capacity()), (0, 4));
+        assert_eq!(inventory.count_of(ItemCode(7)), 0);
+        assert!(!inventory.has(ItemCode(7)));
+
+        inventory.put_item(1, item(7, 10, 10)).unwrap();
+        inventory.put_item(2, item(3, 1, 1)).unwrap();
+        inventory.put_item(4, item(7, 4, 10)).unwrap();
+        assert!(!inventory.is_empty());
+        assert!(!inventory.is_full());
+        assert_eq!(inventory.occupied_count(), 3);
+        assert_eq!(inventory.count_of(ItemCode(7)), 14);
+        assert_eq!(inventory.count_of(ItemCode(3)), 1);
+        assert!(inventory.has(ItemCode(3)));
+        assert!(!inventory.has(ItemCode(5)));
+
+        inventory.put_item(3, item(3, 1, 1)).unwrap();
+        assert!(inventory.is_full());
+        assert_eq!(inventory.count_of(ItemCode(3)), 2);
+    }
+
+    #[test]
+    fn iterate_slots() {
+        let mut inventory = Inventory::<3>::default();
+        inventory.put_item(1, item(4, 2, 10)).unwrap();
+        inventory.put_item(3, item(5, 1, 1)).unwrap();
+        assert_eq!(
+            inventory.iter().collect::<Vec<_>>(),
+            [
+                (1, Some(&item(4, 2, 10))),
+                (2, None),
+                (3, Some(&item(5, 1, 1)))
+            ]
+        );
+        assert_eq!(
+            inventory.iter_items().collect::<Vec<_>>(),
+            [(1, &item(4, 2, 10)), (3, &item(5, 1, 1))]
+        );
+        assert_eq!(inventory.slots().len(), 3);
+        assert_eq!(inventory.slots()[2], Some(item(5, 1, 1)));
+
+        for (slot, item) in inventory.iter_mut() {
+            item.count += slot as u32;
+        }
+        assert_eq!(inventory.count_of(ItemCode(4)), 3);
+        assert_eq!(inventory.count_of(ItemCode(5)), 4);
+    }
+
+    #[test]
+    fn filtered_slots() {
+        let tool = |code| Item {
+            category: Some(ItemCategory(1)),
+            ..item(code, 1, 1)
+        };
+        let mut inventory = Inventory::<3>::default();
+        inventory
+            .set_filter(1, Some(SlotFilter::Category(ItemCategory(1))))
+            .unwrap();
+        inventory
+            .set_filter(3, Some(SlotFilter::Codes(5..=6)))
+            .unwrap();
+        assert_eq!(
+            inventory.set_filter(4, None),
+            Err(InventoryError::SlotOutOfRange(4))
+        );
+
+        // add_item routes around slots that reject the item
+        assert_eq!(inventory.add_item(item(2, 1, 1)), Ok(2));
+        assert_eq!(inventory.add_item(item(2, 1, 1)), Err(item(2, 1, 1)));
+        assert_eq!(inventory.add_item(tool(5)), Ok(1));
+        assert_eq!(inventory.add_item(tool(6)), Ok(3));
+
+        assert_eq!(
+            inventory.put_item(1, item(2, 1, 1)),
+            Err(InventoryError::SlotOccupied(1))
+        );
+        inventory.remove_item(3).unwrap();
+        assert_eq!(
+            inventory.put_item(3, item(2, 1, 1)),
+            Err(InventoryError::SlotRejectsItem(3))
+        );
+        assert_eq!(
+            inventory.move_item(2, 3, MoveConflict::Fail),
+            Err(InventoryError::SlotRejectsItem(3))
+        );
+        // The swapped item has to fit the slot it lands in as well
+        assert_eq!(
+            inventory.move_item(1, 2, MoveConflict::Swap),
+            Err(InventoryError::SlotRejectsItem(1))
+        );
+        assert_eq!(
+            inventory.swap_slots(2, 1),
+            Err(InventoryError::SlotRejectsItem(1))
+        );
+        inventory.move_item(1, 3, MoveConflict::Fail).unwrap();
+        assert_eq!(inventory.get_item(3), Some(&tool(5)));
+
+        let mut other = DynInventory::with_capacity(1);
+        other.set_filter(1, Some(SlotFilter::Codes(5..=5))).unwrap();
+        assert_eq!(
+            inventory.transfer_to(&mut other, 2, 1),
+            Err(InventoryError::SlotRejectsItem(1))
+        );
+        assert_eq!(inventory.get_item(2), Some(&item(2, 1, 1)));
+        inventory.transfer_to(&mut other, 3, 1).unwrap();
+
+        // Shrinking drops the filters of the dropped slots
+        inventory.shrink(1);
+        inventory.grow(1);
+        assert_eq!(inventory.filter(3), None);
+        assert!(inventory.filter(1).is_some());
+    }
+
+    #[test]
+    fn undrained_events_are_capped() {
+        let mut inventory = Inventory::<2>::default();
+        for _ in 0..MAX_PENDING_INVENTORY_EVENTS {
+            inventory.put_item(1, item(1, 1, 1)).unwrap();
+            inventory.remove_item(1).unwrap();
+            inventory.select_item(2).unwrap();
+            inventory.select_item(0).unwrap();
+        }
+        inventory.put_item(2, item(2, 1, 1)).unwrap();
+
+        // The oldest are dropped, the latest kept
+        let slot_events: Vec<_> = inventory.drain_slot_events().collect();
+        assert_eq!(slot_events.len(), MAX_PENDING_INVENTORY_EVENTS);
+        assert_eq!(
+            slot_events.last(),
+            Some(&InventoryChanged {
+                inventory: None,
+                slot: 2,
+                change: SlotChange::Put,
+            })
+        );
+        let selection_events: Vec<_> = inventory.drain_selection_events().collect();
+        assert_eq!(selection_events.len(), MAX_PENDING_INVENTORY_EVENTS);
+        assert_eq!(selection_events.last().unwrap().current, 0);
+        assert!(!inventory.has_pending_events());
+    }
+
+    #[test]
+    fn grow_and_shrink() {
+        let mut inventory = DynInventory::with_capacity(2);
+        assert_eq!(inventory.slot_count(), 2);
+        assert!(inventory.add_item(item(1, 1, 1)).is_ok());
+        assert!(inventory.add_item(item(2, 1, 1)).is_ok());
+        assert!(inventory.is_full());
+
+        inventory.grow(3);
+        assert_eq!(inventory.slot_count(), 5);
+        assert_eq!(inventory.add_item(item(3, 1, 1)), Ok(3));
+        inventory.put_item(5, item(5, 1, 1)).unwrap();
+        inventory.select_item(4).unwrap();
+        inventory.drain_slot_events().for_each(drop);
+        inventory.drain_selection_events().for_each(drop);
+
+        assert_eq!(inventory.shrink(3), [item(3, 1, 1), item(5, 1, 1)]);
+        assert_eq!(inventory.slot_count(), 2);
+        assert_eq!(inventory.selected_slot(), None);
+        assert_eq!(inventory.get_item(3), None);
+        assert_eq!(
+            inventory.drain_slot_events().collect::<Vec<_>>(),
+            [3, 5].map(|slot| InventoryChanged {
+                inventory: None,
+                slot,
+                change: SlotChange::Removed
+            })
+        );
+        assert_eq!(inventory.drain_selection_events().count(), 1);
+
+        assert_eq!(inventory.shrink(5), [item(1, 1, 1), item(2, 1, 1)]);
+        assert_eq!(inventory.slot_count(), 0);
+        inventory.cycle_selection(1, false);
+        assert_eq!(inventory.selected_slot(), None);
+    }
+
+    #[test]
+    fn cycle_selection() {
+        let mut inventory = Inventory::<3>::default();
+        inventory.cycle_selection(1, false);
+        assert_eq!(inventory.selected_slot(), Some(1));
+        inventory.cycle_selection(2, false);
+        assert_eq!(inventory.selected_slot(), Some(3));
+        inventory.cycle_selection(1, false);
+        assert_eq!(inventory.selected_slot(), Some(1));
+        inventory.cycle_selection(-1, false);
+        assert_eq!(inventory.selected_slot(), Some(3));
+        inventory.cycle_selection(-7, false);
+        assert_eq!(inventory.selected_slot(), Some(2));
+
+        inventory.clear_selection();
+        inventory.cycle_selection(-1, false);
+        assert_eq!(inventory.selected_slot(), Some(3));
+
+        inventory.cycle_selection(1, true);
+        assert_eq!(inventory.selected_slot(), None);
+        inventory.cycle_selection(1, true);
+        assert_eq!(inventory.selected_slot(), Some(1));
+        inventory.cycle_selection(-2, true);
+        assert_eq!(inventory.selected_slot(), Some(3));
+    }
+
+    #[test]
+    fn sort_merges_and_orders_stacks() {
+        let mut inventory = Inventory::<6>::default();
+        inventory.put_item(1, item(3, 4, 10)).unwrap();
+        inventory.put_item(3, item(1, 1, 1)).unwrap();
+        inventory.put_item(4, item(3, 9, 10)).unwrap();
+        inventory.put_item(6, item(2, 5, 10)).unwrap();
+        inventory.select_item(4).unwrap();
+        inventory.drain_slot_events().for_each(drop);
+        inventory.drain_selection_events().for_each(drop);
+
+        inventory.sort(SortStrategy::CompactOnly);
+        let slots = |inventory: &DynInventory| -> Vec<_> {
+            inventory
+                .iter()
+                .map(|(_, item)| item.map(|item| (item.code.0, item.count)))
+                .collect()
+        };
+        assert_eq!(
+            slots(&inventory),
+            [
+                Some((3, 10)),
+                Some((1, 1)),
+                Some((3, 3)),
+                Some((2, 5)),
+                None,
+                None
+            ]
+        );
+        // The selected stack topped up slot 1 first
+        assert_eq!(inventory.selected_slot(), Some(1));
+        let changes: Vec<_> = inventory
+            .drain_slot_events()
+            .map(|event| (event.slot, event.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (1, SlotChange::CountChanged),
+                (2, SlotChange::Put),
+                (3, SlotChange::Put),
+                (4, SlotChange::Put),
+                (6, SlotChange::Removed),
+            ]
+        );
+
+        inventory.select_item(4).unwrap();
+        inventory.sort(SortStrategy::ByCode);
+        assert_eq!(
+            slots(&inventory),
+            [
+                Some((1, 1)),
+                Some((2, 5)),
+                Some((3, 10)),
+                Some((3, 3)),
+                None,
+                None
+            ]
+        );
+        assert_eq!(inventory.selected_slot(), Some(2));
+
+        // Sorted already
+        inventory.drain_slot_events().for_each(drop);
+        inventory.drain_selection_events().for_each(drop);
+        inventory.sort(SortStrategy::ByCode);
+        assert!(!inventory.has_pending_events());
+    }
+
+    #[test]
+    fn sort_leaves_locked_and_filtered_slots() {
+        let mut inventory = Inventory::<5>::default();
+        inventory.put_item(2, item(2, 1, 1)).unwrap();
+        inventory.put_item(3, item(1, 1, 1)).unwrap();
+        inventory.put_item(5, item(4, 1, 1)).unwrap();
+        let mut categorized = item(5, 1, 1);
+        categorized.category = Some(ItemCategory(0));
+        inventory.put_item(4, categorized).unwrap();
+        inventory.lock_slot(2).unwrap();
+        inventory
+            .set_filter(1, Some(SlotFilter::Codes(7..=7)))
+            .unwrap();
+
+        inventory.sort(SortStrategy::ByCategory);
+        let codes: Vec<_> = inventory
+            .iter()
+            .map(|(_, item)| item.map(|item| item.code.0))
+            .collect();
+        assert_eq!(codes, [None, Some(2), Some(5), Some(1), Some(4)]);
+
+        inventory.sort_slots(SortStrategy::ByCode, 4..=5);
+        assert_eq!(inventory.get_item(3).unwrap().code, ItemCode(5));
+        assert_eq!(inventory.get_item(4).unwrap().code, ItemCode(1));
+    }
+
+    #[test]
+    fn sort_keeps_item_counts() {
+        // Pseudo-random inventories, xorshift
+        let mut state = 0x2545f491u32;
+        let mut next = |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % bound
+        };
+        for _ in 0..200 {
+            let mut inventory = Inventory::<12>::default();
+            for slot in 1..=12 {
+                if next(3) > 0 {
+                    let max_stack = [1, 4, 16][next(3) as usize];
+                    let code = next(4) as usize;
+                    inventory
+                        .put_item(slot, item(code, next(max_stack) + 1, max_stack))
+                        .unwrap();
+                }
+            }
+            if next(2) == 0 {
+                inventory.lock_slot(next(12) as usize + 1).unwrap();
+            }
+            let counts = |inventory: &DynInventory| {
+                (0..4)
+                    .map(|code| inventory.count_of(ItemCode(code)))
+                    .collect::<Vec<_>>()
+            };
+            let before = counts(&inventory);
+            let strategy = [SortStrategy::CompactOnly, SortStrategy::ByCode][next(2) as usize];
+            inventory.sort(strategy);
+            assert_eq!(counts(&inventory), before);
+            assert!(inventory
+                .iter_items()
+                .all(|(_, item)| item.count > 0 && item.count <= item.max_stack));
+        }
+    }
+
+    #[test]
+    fn split_stacks() {
+        let mut inventory = Inventory::<3>::default();
+        inventory.put_item(1, item(1, 10, 16)).unwrap();
+        inventory.put_item(2, item(2, 1, 1)).unwrap();
+        inventory.drain_slot_events().for_each(drop);
+
+        assert_eq!(inventory.split_stack(1, 3), Ok(item(1, 3, 16)));
+        assert_eq!(inventory.get_item(1), Some(&item(1, 7, 16)));
+        assert_eq!(inventory.take_half(1), Ok(item(1, 4, 16)));
+        assert_eq!(inventory.get_item(1), Some(&item(1, 3, 16)));
+        // Asking for more takes the whole stack
+        assert_eq!(inventory.split_stack(1, 5), Ok(item(1, 3, 16)));
+        assert_eq!(inventory.get_item(1), None);
+        let changes: Vec<_> = inventory
+            .drain_slot_events()
+            .map(|event| (event.slot, event.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (1, SlotChange::CountChanged),
+                (1, SlotChange::CountChanged),
+                (1, SlotChange::Removed),
+            ]
+        );
+
+        assert_eq!(
+            inventory.split_stack(1, 1),
+            Err(InventoryError::SlotEmpty(1))
+        );
+        assert_eq!(inventory.take_half(1), Err(InventoryError::SlotEmpty(1)));
+        assert_eq!(
+            inventory.split_stack(2, 1),
+            Err(InventoryError::NotStackable(2))
+        );
+        assert_eq!(
+            inventory.split_stack(4, 1),
+            Err(InventoryError::SlotOutOfRange(4))
+        );
+        inventory.put_item(3, item(1, 1, 16)).unwrap();
+        assert_eq!(inventory.split_stack(3, 0), Err(InventoryError::ZeroAmount));
+        assert_eq!(inventory.take_half(3), Ok(item(1, 1, 16)));
+        assert!(!inventory.has(ItemCode(1)));
+    }
+
+    #[test]
+    fn add_item_skips_locked_slots() {
+        let mut inventory = Inventory::<4>::default();
+        inventory.put_item(1, item(1, 2, 5)).unwrap();
+        inventory.put_item(3, item(1, 1, 5)).unwrap();
+        inventory.lock_slot(1).unwrap();
+        inventory.lock_slot(2).unwrap();
+
+        // The locked stack in slot 1 keeps its count, slot 2 is passed over
+        assert_eq!(inventory.add_item(item(1, 6, 5)), Ok(4));
+        assert_eq!(inventory.get_item(1), Some(&item(1, 2, 5)));
+        assert_eq!(inventory.get_item(2), None);
+        assert_eq!(inventory.get_item(3), Some(&item(1, 5, 5)));
+        assert_eq!(inventory.get_item(4), Some(&item(1, 2, 5)));
+        assert_eq!(
+            inventory.put_item(2, item(2, 1, 1)),
+            Err(InventoryError::SlotLocked(2))
+        );
+        assert_eq!(
+            inventory.move_item(3, 2, MoveConflict::Fail),
+            Err(InventoryError::SlotLocked(2))
+        );
+        assert_eq!(inventory.add_item(item(2, 1, 1)), Err(item(2, 1, 1)));
+
+        inventory.unlock_slot(2).unwrap();
+        assert_eq!(inventory.add_item(item(2, 1, 1)), Ok(2));
+        assert_eq!(
+            inventory.lock_slot(5),
+            Err(InventoryError::SlotOutOfRange(5))
+        );
+    }
+
+    #[test]
+    fn locked_slots_can_not_be_selected() {
+        let mut inventory = Inventory::<4>::default();
+        inventory.select_item(2).unwrap();
+        inventory.lock_slot(2).unwrap();
+        inventory.lock_slot(3).unwrap();
+        assert_eq!(inventory.selected_slot(), None);
+        assert_eq!(inventory.select_item(3), Err(InventoryError::SlotLocked(3)));
+        assert_eq!(inventory.selected_slot(), None);
+
+        inventory.select_item(1).unwrap();
+        inventory.cycle_selection(1, false);
+        assert_eq!(inventory.selected_slot(), Some(4));
+        inventory.cycle_selection(-1, false);
+        assert_eq!(inventory.selected_slot(), Some(1));
+        inventory.cycle_selection(2, false);
+        assert_eq!(inventory.selected_slot(), Some(1));
+
+        // Nowhere to go
+        for slot in [1, 4] {
+            inventory.lock_slot(slot).unwrap();
+        }
+        inventory.cycle_selection(1, false);
+        assert_eq!(inventory.selected_slot(), None);
+        inventory.cycle_selection(1, true);
+        assert_eq!(inventory.selected_slot(), None);
+    }
+
+    #[test]
+    fn move_and_swap() {
+        let mut inventory = Inventory::<3>::default();
+        inventory.put_item(1, item(1, 1, 1)).unwrap();
+        inventory.put_item(2, item(2, 1, 1)).unwrap();
+
+        assert_eq!(
+            inventory.move_item(1, 2, MoveConflict::Fail),
+            Err(InventoryError::SlotOccupied(2))
+        );
+        assert_eq!(inventory.get_item(1), Some(&item(1, 1, 1)));
+        assert_eq!(inventory.move_item(1, 2, MoveConflict::Swap), Ok(()));
+        assert_eq!(inventory.get_item(1), Some(&item(2, 1, 1)));
+        assert_eq!(inventory.get_item(2), Some(&item(1, 1, 1)));
+
+        assert_eq!(inventory.move_item(2, 3, MoveConflict::Fail), Ok(()));
+        assert_eq!(inventory.get_item(2), None);
+        assert_eq!(inventory.get_item(3), Some(&item(1, 1, 1)));
+        assert_eq!(
+            inventory.move_item(2, 1, MoveConflict::Swap),
+            Err(InventoryError::SlotEmpty(2))
+        );
+
+        assert_eq!(inventory.swap_slots(2, 3), Ok(()));
+        assert_eq!(inventory.get_item(2), Some(&item(1, 1, 1)));
+        assert_eq!(inventory.get_item(3), None);
+        assert_eq!(
+            inventory.swap_slots(0, 1),
+            Err(InventoryError::SlotOutOfRange(0))
+        );
+        assert_eq!(
+            inventory.move_item(1, 4, MoveConflict::Swap),
+            Err(InventoryError::SlotOutOfRange(4))
+        );
+
+        // A slot onto itself is a no-op, locked and filtered ones included
+        inventory.lock_slot(2).unwrap();
+        inventory
+            .set_filter(1, Some(SlotFilter::Codes(7..=7)))
+            .unwrap();
+        inventory.drain_slot_events().for_each(drop);
+        for slot in [1, 2] {
+            assert_eq!(inventory.swap_slots(slot, slot), Ok(()));
+            assert_eq!(inventory.move_item(slot, slot, MoveConflict::Fail), Ok(()));
+        }
+        assert_eq!(inventory.get_item(1), Some(&item(2, 1, 1)));
+        assert_eq!(inventory.get_item(2), Some(&item(1, 1, 1)));
+        assert!(!inventory.has_pending_events());
+    }
+
+    #[test]
+    fn selection_stays_on_slot() {
+        let mut inventory = Inventory::<3>::default();
+        inventory.put_item(1, item(1, 1, 1)).unwrap();
+        inventory.put_item(2, item(2, 1, 1)).unwrap();
+        inventory.select_item(1).unwrap();
+
+        inventory.move_item(1, 2, MoveConflict::Swap).unwrap();
+        assert_eq!(inventory.selected_slot(), Some(1));
+        assert_eq!(inventory.selected_item(), Some(&item(2, 1, 1)));
+
+        inventory.move_item(1, 3, MoveConflict::Fail).unwrap();
+        assert_eq!(inventory.selected_slot(), Some(1));
+        assert_eq!(inventory.selected_item(), None);
+    }
+
+    #[test]
+    fn transfer_between_inventories() {
+        let mut hotbar = Inventory::<3>::default();
+        let mut chest = Inventory::<5>::default();
+        hotbar.put_item(1, item(1, 1, 1)).unwrap();
+        chest.put_item(5, item(2, 1, 1)).unwrap();
+        hotbar.select_item(1).unwrap();
+
+        assert_eq!(
+            hotbar.transfer_to(&mut chest, 1, 5),
+            Err(InventoryError::SlotOccupied(5))
+        );
+        assert_eq!(
+            hotbar.transfer_to(&mut chest, 1, 6),
+            Err(InventoryError::SlotOutOfRange(6))
+        );
+        assert_eq!(
+            hotbar.transfer_to(&mut chest, 2, 1),
+            Err(InventoryError::SlotEmpty(2))
+        );
+        assert_eq!(hotbar.get_item(1), Some(&item(1, 1, 1)));
+
+        assert_eq!(hotbar.transfer_to(&mut chest, 1, 4), Ok(()));
+        assert_eq!(hotbar.get_item(1), None);
+        assert_eq!(chest.get_item(4), Some(&item(1, 1, 1)));
+        assert_eq!(hotbar.selected_slot(), Some(1));
+        assert_eq!(hotbar.selected_item(), None);
+    }
+
+    #[test]
+    fn quick_transfer_stacks_first() {
+        let mut hotbar = Inventory::<3>::default();
+        let mut chest = Inventory::<2>::default();
+        hotbar.put_item(2, item(1, 30, 64)).unwrap();
+        chest.put_item(2, item(1, 60, 64)).unwrap();
+        assert_eq!(chest.room_for(&item(1, 1, 64)), u32::MAX);
+        assert_eq!(
+            hotbar.quick_transfer(1, &mut chest),
+            Err(InventoryError::SlotEmpty(1))
+        );
+
+        assert_eq!(hotbar.quick_transfer(2, &mut chest), Ok(30));
+        assert_eq!(hotbar.get_item(2), None);
+        assert_eq!(chest.get_item(2).unwrap().count, 64);
+        assert_eq!(chest.get_item(1).unwrap().count, 26);
+
+        // A full destination keeps the rest in place
+        chest.put_item_unchecked(1, item(1, 62, 64));
+        hotbar.put_item(3, item(1, 5, 64)).unwrap();
+        assert_eq!(chest.room_for(&item(1, 1, 64)), 2);
+        assert_eq!(chest.room_for(&item(2, 1, 64)), 0);
+        assert_eq!(hotbar.quick_transfer(3, &mut chest), Ok(2));
+        assert_eq!(hotbar.get_item(3).unwrap().count, 3);
+        assert_eq!(hotbar.quick_transfer(3, &mut chest), Ok(0));
+        assert_eq!(hotbar.get_item(3).unwrap().count, 3);
+    }
+
+    #[test]
+    fn one_event_per_change() {
+        let mut inventory = Inventory::<3>::default();
+        let changed = |slot, change| InventoryChanged {
+            inventory: None,
+            slot,
+            change,
+        };
+
+        inventory.select_item(2).unwrap();
+        inventory.select_item(2).unwrap();
+        inventory.cycle_selection(1, false);
+        inventory.clear_selection();
+        inventory.clear_selection();
+        assert_eq!(
+            inventory.drain_selection_events().collect::<Vec<_>>(),
+            [(0, 2), (2, 3), (3, 0)].map(|(previous, current)| SelectionChanged {
+                inventory: None,
+                previous,
+                current,
+            })
+        );
+
+        inventory.put_item(1, item(1, 5, 10)).unwrap();
+        inventory.add_item(item(1, 2, 10)).unwrap();
+        inventory.add_item(item(2, 1, 1)).unwrap();
+        assert_eq!(inventory.remove_item(3), Err(InventoryError::SlotEmpty(3)));
+        inventory.remove_item(2).unwrap();
+        inventory.swap_slots(2, 3).unwrap();
+        inventory.swap_slots(1, 3).unwrap();
+        inventory.move_item(3, 2, MoveConflict::Fail).unwrap();
+        assert_eq!(
+            inventory.drain_slot_events().collect::<Vec<_>>(),
+            [
+                changed(1, SlotChange::Put),
+                changed(1, SlotChange::CountChanged),
+                changed(2, SlotChange::Put),
+                changed(2, SlotChange::Removed),
+                changed(1, SlotChange::Removed),
+                changed(3, SlotChange::Put),
+                changed(3, SlotChange::Removed),
+                changed(2, SlotChange::Put),
+            ]
+        );
+
+        let mut chest = Inventory::<2>::default();
+        inventory.transfer_to(&mut chest, 2, 1).unwrap();
+        assert!(inventory.move_item(2, 1, MoveConflict::Swap).is_err());
+        assert_eq!(
+            inventory.drain_slot_events().collect::<Vec<_>>(),
+            [changed(2, SlotChange::Removed)]
+        );
+        assert_eq!(
+            chest.drain_slot_events().collect::<Vec<_>>(),
+            [changed(1, SlotChange::Put)]
+        );
+        assert!(!inventory.has_pending_events());
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn inventory_ron_round_trip() {
+        use super::{InventoryFileError, UnknownItemPolicy};
+        use crate::items::ItemCode;
+
+        let mut inventory = DynInventory::with_capacity(4);
+        inventory.put_item(2, item(1, 5, 10)).unwrap();
+        inventory.put_item(4, item(9, 1, 1)).unwrap();
+        inventory.select_item(3).unwrap();
+        let ron = inventory.to_ron().unwrap();
+
+        let mut loaded = DynInventory::with_capacity(4);
+        loaded.put_item(1, item(2, 1, 1)).unwrap();
+        loaded
+            .restore_ron(&ron, UnknownItemPolicy::Keep, |_| false)
+            .unwrap();
+        assert_eq!(loaded.get_item(1), None);
+        assert_eq!(loaded.get_item(2), Some(&item(1, 5, 10)));
+        assert_eq!(loaded.get_item(3), None);
+        assert_eq!(loaded.get_item(4), Some(&item(9, 1, 1)));
+        assert_eq!(loaded.selected_slot(), Some(3));
+        assert_eq!(loaded.to_ron().unwrap(), ron);
+        // Slot 3 stayed empty, everything else changed
+        let slots: Vec<_> = loaded.drain_slot_events().map(|event| event.slot).collect();
+        assert_eq!(slots, [1, 1, 2, 4]);
+
+        loaded
+            .restore_ron(&ron, UnknownItemPolicy::Drop, |code| code == ItemCode(1))
+            .unwrap();
+        assert_eq!(loaded.get_item(2), Some(&item(1, 5, 10)));
+        assert_eq!(loaded.get_item(4), None);
+
+        let mut larger = DynInventory::with_capacity(12);
+        assert!(matches!(
+            larger.restore_ron(&ron, UnknownItemPolicy::Keep, |_| true),
+            Err(InventoryFileError::SlotCountMismatch {
+                saved: 4,
+                expected: 12
+            })
+        ));
+        assert!(larger.get_item(2).is_none());
+        assert!(matches!(
+            larger.restore_ron("(items: [])", UnknownItemPolicy::Keep, |_| true),
+            Err(InventoryFileError::Parse(_))
+        ));
+
+        assert!(ron::from_str::<Inventory<4>>(&ron).is_ok());
+        assert!(ron::from_str::<Inventory<9>>(&ron).is_err());
+    }
+
+    #[cfg(feature = "std-io")]
+    #[test]
+    fn inventory_file_requests() {
+        use bevy::prelude::{App, Update};
+
+        use super::{
+            load_inventory_system, save_inventory_system, BaseInventory, LoadInventoryRequest,
+            SaveInventoryRequest, UnknownItemPolicy,
+        };
+
+        let mut app = App::new();
+        app.init_resource::<BaseInventory>()
+            .add_event::<SaveInventoryRequest>()
+            .add_event::<LoadInventoryRequest>()
+            .add_systems(Update, (save_inventory_system, load_inventory_system));
+        app.world
+            .resource_mut::<BaseInventory>()
+            .put_item(7, item(3, 2, 8))
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("inventory_{}.ron", std::process::id()));
+        app.world
+            .send_event(SaveInventoryRequest { path: path.clone() });
+        app.update();
+        app.world
+            .resource_mut::<BaseInventory>()
+            .remove_item(7)
+            .unwrap();
+        app.world.send_event(LoadInventoryRequest {
+            path: path.clone(),
+            unknown_items: UnknownItemPolicy::Keep,
+        });
+        app.update();
+        assert_eq!(
+            app.world.resource::<BaseInventory>().get_item(7),
+            Some(&item(3, 2, 8))
+        );
+
+        // Nothing is known without prototypes
+        app.world.send_event(LoadInventoryRequest {
+            path: path.clone(),
+            unknown_items: UnknownItemPolicy::Drop,
+        });
+        app.update();
+        assert_eq!(app.world.resource::<BaseInventory>().get_item(7), None);
+
+        // A missing file leaves the inventory as it was
+        std::fs::remove_file(&path).unwrap();
+        app.world
+            .resource_mut::<BaseInventory>()
+            .put_item(1, item(3, 2, 8))
+            .unwrap();
+        app.world.send_event(LoadInventoryRequest {
+            path,
+            unknown_items: UnknownItemPolicy::Keep,
+        });
+        app.update();
+        assert_eq!(
+            app.world.resource::<BaseInventory>().get_item(1),
+            Some(&item(3, 2, 8))
+        );
+    }
+
+    fn crafting_inventory() -> Inventory<4> {
+        let mut inventory = Inventory::<4>::default();
+        inventory.put_item(1, item(1, 3, 8)).unwrap();
+        inventory.put_item(2, item(2, 1, 8)).unwrap();
+        inventory.put_item(3, item(1, 2, 8)).unwrap();
+        inventory.drain_slot_events().for_each(drop);
+        inventory
+    }
+
+    #[test]
+    fn transactions_apply_all_steps() {
+        let mut inventory = crafting_inventory();
+        let taken = inventory.transaction(|tx| {
+            let planks = tx.take(ItemCode(1), 4)?;
+            tx.take(ItemCode(2), 1)?;
+            // Staged reads
+            assert_eq!(tx.count_of(ItemCode(1)), 1);
+            tx.give(item(5, 1, 1))?;
+            Ok::<_, InventoryError>(planks)
+        });
+        assert_eq!(taken, Ok(item(1, 4, 8)));
+        assert_eq!(
+            inventory.slots(),
+            [Some(item(5, 1, 1)), None, Some(item(1, 1, 8)), None]
+        );
+        // One change per slot
+        let changes: Vec<_> = inventory
+            .drain_slot_events()
+            .map(|event| (event.slot, event.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (1, SlotChange::Put),
+                (2, SlotChange::Removed),
+                (3, SlotChange::CountChanged),
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_transactions_change_nothing() {
+        let mut inventory = crafting_inventory();
+        let before = inventory.slots().to_vec();
+        let result = inventory.transaction(|tx| {
+            tx.take(ItemCode(1), 2)?;
+            tx.take(ItemCode(3), 1)?;
+            tx.give(item(5, 1, 1))
+        });
+        assert_eq!(result, Err(InventoryError::NotEnoughItems(ItemCode(3))));
+        assert_eq!(inventory.slots(), before);
+        assert!(!inventory.has_pending_events());
+
+        // Locked stacks can not be taken and filtered slots refuse what they reject
+        inventory.lock_slot(1).unwrap();
+        inventory.drain_slot_events().for_each(drop);
+        let result = inventory.transaction(|tx| tx.take(ItemCode(1), 4));
+        assert_eq!(result, Err(InventoryError::NotEnoughItems(ItemCode(1))));
+        inventory
+            .set_filter(4, Some(SlotFilter::Codes(1..=2)))
+            .unwrap();
+        let result = inventory.transaction(|tx| {
+            tx.take(ItemCode(1), 2)?;
+            tx.give(item(5, 1, 1))?;
+            tx.give(item(6, 1, 1))
+        });
+        assert_eq!(result, Err(InventoryError::NoRoom(ItemCode(6))));
+        assert_eq!(
+            inventory.transaction(|tx| tx.take(ItemCode(1), 0)),
+            Err(InventoryError::ZeroAmount)
+        );
+        assert_eq!(inventory.slots(), before);
+        assert!(!inventory.has_pending_events());
+    }
+
+    #[test]
+    fn failed_gives_add_nothing() {
+        let mut inventory = crafting_inventory();
+        inventory.put_item(4, item(3, 1, 1)).unwrap();
+        inventory.drain_slot_events().for_each(drop);
+        let before = inventory.slots().to_vec();
+        // The stacks would take 11 of them
+        let result = inventory.transaction(|tx| {
+            let given = tx.give(item(1, 20, 8));
+            assert_eq!(given, Err(InventoryError::NoRoom(ItemCode(1))));
+            assert_eq!(tx.count_of(ItemCode(1)), 5);
+            Ok::<_, InventoryError>(())
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(inventory.slots(), before);
+        assert!(!inventory.has_pending_events());
+
+        // Only 2 of them are light enough
+        inventory.remove_item(4).unwrap();
+        inventory.set_max_weight(Some(1.0));
+        let result = inventory.transaction(|tx| {
+            tx.give(weighted(5, 4, 8, 0.5)).ok();
+            tx.give(item(6, 1, 1))
+        });
+        assert_eq!(result, Ok(4));
+        assert_eq!(inventory.get_item(4), Some(&item(6, 1, 1)));
+        assert_eq!(inventory.slots()[..3], before[..3]);
+        assert_eq!(inventory.current_weight(), 0.0);
+    }
+
+    fn weighted(code: usize, count: u32, max_stack: u32, weight: f32) -> Item {
+        Item {
+            weight,
+            ..item(code, count, max_stack)
+        }
+    }
+
+    #[test]
+    fn weight_limit_takes_exactly_up_to_the_limit() {
+        let mut inventory = Inventory::<4>::default();
+        inventory.set_max_weight(Some(10.0));
+        inventory.put_item(1, weighted(1, 4, 16, 1.5)).unwrap();
+        assert_eq!(inventory.current_weight(), 6.0);
+        // Reaching the limit fits, going past it does not
+        assert_eq!(inventory.put_item(2, weighted(2, 1, 1, 4.0)), Ok(()));
+        assert_eq!(inventory.current_weight(), 10.0);
+        assert_eq!(
+            inventory.put_item(3, weighted(3, 1, 1, 0.5)),
+            Err(InventoryError::OverWeight)
+        );
+        assert_eq!(inventory.put_item(3, item(3, 1, 1)), Ok(()));
+        assert_eq!(inventory.room_for(&weighted(1, 1, 16, 1.5)), 0);
+        assert_eq!(inventory.room_for(&item(1, 1, 16)), u32::MAX);
+
+        let mut other = Inventory::<2>::default();
+        other.set_max_weight(Some(3.0));
+        assert_eq!(
+            inventory.transfer_to(&mut other, 2, 1),
+            Err(InventoryError::OverWeight)
+        );
+        assert_eq!(inventory.current_weight(), 10.0);
+        assert_eq!(other.current_weight(), 0.0);
+    }
+
+    #[test]
+    fn removals_free_weight() {
+        let mut inventory = Inventory::<4>::default();
+        inventory.set_max_weight(Some(10.0));
+        // Only what fits is added
+        assert_eq!(
+            inventory.add_item(weighted(1, 10, 16, 1.5)),
+            Err(weighted(1, 4, 16, 1.5))
+        );
+        assert_eq!(inventory.get_item(1), Some(&weighted(1, 6, 16, 1.5)));
+        assert_eq!(inventory.current_weight(), 9.0);
+        assert_eq!(
+            inventory.add_item(weighted(1, 1, 16, 1.5)),
+            Err(weighted(1, 1, 16, 1.5))
+        );
+
+        assert_eq!(inventory.take(1, 2), Ok(weighted(1, 2, 16, 1.5)));
+        assert_eq!(inventory.current_weight(), 6.0);
+        assert_eq!(inventory.room_for(&weighted(1, 1, 16, 1.5)), 2);
+        assert_eq!(inventory.add_item(weighted(1, 2, 16, 1.5)), Ok(1));
+        assert_eq!(inventory.current_weight(), 9.0);
+
+        inventory.remove_item(1).unwrap();
+        assert_eq!(inventory.current_weight(), 0.0);
+        assert_eq!(inventory.add_item(weighted(2, 1, 1, 10.0)), Ok(1));
+        for (_, item) in inventory.iter_mut() {
+            item.weight = 2.0;
+        }
+        assert_eq!(inventory.current_weight(), 2.0);
+        assert_eq!(
+            inventory.transaction(|tx| tx.give(weighted(3, 4, 8, 2.0))),
+            Ok(2)
+        );
+        assert_eq!(inventory.current_weight(), 10.0);
+    }
+
+    #[test]
+    fn cached_weight_tolerates_rounding_and_nan() {
+        let mut inventory = Inventory::<4>::default();
+        // Kept by adding and subtracting, which rounds differently than summing the slots
+        for _ in 0..50 {
+            inventory.add_item(weighted(1, 3, 64, 0.1)).unwrap();
+            inventory.put_item(2, weighted(2, 1, 1, 0.7)).unwrap();
+            inventory.take(1, 2).unwrap();
+            inventory.remove_item(2).unwrap();
+        }
+        assert!((inventory.current_weight() - 5.0).abs() < 1e-3);
+
+        inventory.put_item(3, weighted(3, 1, 1, f32::NAN)).unwrap();
+        assert!(inventory.current_weight().is_nan());
+        inventory.remove_item(3).unwrap();
+        assert!((inventory.current_weight() - 5.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn slots_are_laid_out_row_by_row() {
+        let settings = InventorySettings {
+            w_padding: 5.0,
+            w_mid_step: 4.0,
+            h_padding: 3.0,
+            slot_size: 50.0,
+            ..Default::default()
+        };
+        let pos = Vec2::new(10.0, 20.0);
+        assert_eq!(
+            layout_slots(&settings, 2, 3, pos),
+            [
+                Vec2::new(-44.0, 47.0),
+                Vec2::new(10.0, 47.0),
+                Vec2::new(64.0, 47.0),
+                Vec2::new(-44.0, -7.0),
+                Vec2::new(10.0, -7.0),
+                Vec2::new(64.0, -7.0),
+            ]
+        );
+        assert!(layout_slots(&settings, 1, 0, pos).is_empty());
+    }
+
+    #[test]
+    fn slots_keep_the_padding_to_the_panel_edges() {
+        let settings = InventorySettings::default();
+        let pos = Vec2::new(-3.0, 7.0);
+        let size = inventory_panel_size(&settings, 3, 4);
+        let slots = layout_slots(&settings, 3, 4, pos);
+        let half = settings.slot_size / 2.0;
+        let min = slots.iter().fold(Vec2::MAX, |min, slot| min.min(*slot)) - half;
+        let max = slots.iter().fold(Vec2::MIN, |max, slot| max.max(*slot)) + half;
+        let padding = Vec2::new(settings.w_padding, settings.h_padding);
+        assert_eq!(min, pos - size / 2.0 + padding);
+        assert_eq!(max, pos + size / 2.0 - padding);
     }
 }
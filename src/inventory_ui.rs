@@ -0,0 +1,304 @@
+//! bevy_ui backend of the hotbar, see [`InventoryUiPlugin`]
+
+use bevy::{prelude::*, ui::FocusPolicy};
+
+use crate::{
+    inventory::{
+        ActiveInventoryQuery, ActiveInventoryQueryMut, BaseInventorySettings, ClickConsumed,
+        InventoryPanel, InventorySet, InventorySettings,
+    },
+    items::{ItemCode, ItemImage, ItemPreview},
+    place_selected_item,
+};
+
+/// Space between the bottom of the window and the bar
+const UI_BAR_BOTTOM_MARGIN: f32 = 10.0;
+
+/// Full width node holding the bar at the bottom of the window
+#[derive(Component)]
+pub struct UiInventoryBar;
+
+/// Background of the bar behind its slots, a press on it does not reach the world either
+#[derive(Component)]
+pub struct UiInventoryBarPanel;
+
+/// Button of a slot of the bar, its border is the selection highlight
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiInventorySlot {
+    /// 1-indexed
+    pub slot: usize,
+}
+
+/// Image of the item in a [`UiInventorySlot`], a child of it
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiInventorySlotImage {
+    pub slot: usize,
+}
+
+/// Stack count in the corner of a [`UiInventorySlot`], a child of it
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiInventoryStackCount {
+    pub slot: usize,
+}
+
+/// Draws the hotbar with bevy_ui nodes instead of world space sprites
+///
+/// The bar stays at the bottom of the window however the camera moves and the window is
+/// resized. Slots are selected by clicking their buttons. Needs the resources of the
+/// [`InventoryPlugin`](crate::inventory::InventoryPlugin), which adds this plugin for
+/// [`InventoryBackend::Ui`](crate::inventory::InventoryBackend::Ui). Container and backpack
+/// panels stay sprites.
+pub struct InventoryUiPlugin;
+
+impl Plugin for InventoryUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                sync_ui_inventory_bar,
+                (render_ui_inventory_items, highlight_ui_selected_slot)
+                    .after(sync_ui_inventory_bar),
+                click_ui_slot.before(place_selected_item),
+            )
+                .in_set(InventorySet),
+        );
+    }
+}
+
+/// Spawns the bar with a slot per slot of the active inventory, rebuilds it when the slot
+/// count or the settings change
+pub fn sync_ui_inventory_bar(
+    mut commands: Commands,
+    inventory: ActiveInventoryQuery,
+    settings: Res<BaseInventorySettings>,
+    bars: Query<Entity, With<UiInventoryBar>>,
+    slots: Query<(), With<UiInventorySlot>>,
+) {
+    let n_slots = inventory
+        .get()
+        .map_or(0, |inventory| inventory.slot_count());
+    if !bars.is_empty() && slots.iter().len() == n_slots && !settings.is_changed() {
+        return;
+    }
+    for bar in bars.iter() {
+        commands.entity(bar).despawn_recursive();
+    }
+    spawn_ui_inventory_bar(&mut commands, &settings, n_slots);
+}
+
+/// Spawns a bar of `n_slots` slots sized by `settings`, returns the [`UiInventoryBar`]
+pub fn spawn_ui_inventory_bar(
+    commands: &mut Commands,
+    settings: &InventorySettings,
+    n_slots: usize,
+) -> Entity {
+    let slot_size = Val::Px(settings.slot_size);
+    let image_size = Val::Px(settings.slot_size - settings.slot_margin);
+    commands
+        .spawn((
+            UiInventoryBar,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(UI_BAR_BOTTOM_MARGIN),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                UiInventoryBarPanel,
+                Interaction::default(),
+                NodeBundle {
+                    style: Style {
+                        padding: UiRect::axes(
+                            Val::Px(settings.w_padding),
+                            Val::Px(settings.h_padding),
+                        ),
+                        column_gap: Val::Px(settings.w_mid_step),
+                        ..Default::default()
+                    },
+                    background_color: settings.background_color.into(),
+                    focus_policy: FocusPolicy::Block,
+                    ..Default::default()
+                },
+            ))
+            .with_children(|slots| {
+                for slot in 1..=n_slots {
+                    slots
+                        .spawn((
+                            UiInventorySlot { slot },
+                            ButtonBundle {
+                                style: Style {
+                                    width: slot_size,
+                                    height: slot_size,
+                                    border: UiRect::all(Val::Px(settings.highlight_thickness)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                background_color: settings.slot_color.into(),
+                                border_color: Color::NONE.into(),
+                                ..Default::default()
+                            },
+                        ))
+                        .with_children(|button| {
+                            button.spawn((
+                                UiInventorySlotImage { slot },
+                                ImageBundle {
+                                    style: Style {
+                                        width: image_size,
+                                        height: image_size,
+                                        ..Default::default()
+                                    },
+                                    visibility: Visibility::Hidden,
+                                    ..Default::default()
+                                },
+                            ));
+                            button.spawn((
+                                UiInventoryStackCount { slot },
+                                TextBundle {
+                                    style: Style {
+                                        position_type: PositionType::Absolute,
+                                        right: Val::Px(settings.slot_margin),
+                                        bottom: Val::Px(0.0),
+                                        ..Default::default()
+                                    },
+                                    visibility: Visibility::Hidden,
+                                    ..TextBundle::from_section(
+                                        "",
+                                        TextStyle {
+                                            font: settings.count_font.clone(),
+                                            font_size: settings.slot_size * 0.4,
+                                            color: settings.count_color,
+                                        },
+                                    )
+                                },
+                            ));
+                        });
+                }
+            });
+        })
+        .id()
+}
+
+/// UI twin of [`render_items_in_base_inventory`](crate::inventory::render_items_in_base_inventory)
+#[allow(clippy::type_complexity)]
+pub fn render_ui_inventory_items(
+    inventory: ActiveInventoryQuery,
+    preview_items: Query<(&ItemCode, &ItemImage), With<ItemPreview>>,
+    changed_previews: Query<(), (With<ItemPreview>, Changed<ItemImage>)>,
+    mut images: Query<(Ref<UiInventorySlotImage>, &mut UiImage, &mut Visibility)>,
+    mut counts: Query<
+        (Ref<UiInventoryStackCount>, &mut Text, &mut Visibility),
+        Without<UiInventorySlotImage>,
+    >,
+) {
+    let changed = !changed_previews.is_empty()
+        || inventory.panel_changed(&InventoryPanel { inventory: None });
+    let inventory = inventory.get();
+    let item = |slot| inventory.and_then(|inventory| inventory.get_item(slot));
+    for (image, mut ui_image, mut visibility) in images.iter_mut() {
+        if !changed && !image.is_added() {
+            continue;
+        }
+        let item_image = item(image.slot).and_then(|item| {
+            preview_items
+                .iter()
+                .find(|(item_code, _)| **item_code == item.code)
+        });
+        match item_image {
+            Some((_, item_image)) => {
+                if ui_image.texture != item_image.0 {
+                    ui_image.texture = item_image.0.clone();
+                }
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+    for (count, mut text, mut visibility) in counts.iter_mut() {
+        if !changed && !count.is_added() {
+            continue;
+        }
+        match item(count.slot).filter(|item| item.count > 1) {
+            Some(item) => {
+                let count = item.count.to_string();
+                if text.sections[0].value != count {
+                    text.sections[0].value = count;
+                }
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+}
+
+/// Colors the border of the selected slot with [`InventorySettings::highlight_color`] and
+/// the slots by whether they are locked or selected
+///
+/// [`InventorySettings::highlight_color`]: crate::inventory::InventorySettings::highlight_color
+pub fn highlight_ui_selected_slot(
+    inventory: ActiveInventoryQuery,
+    settings: Res<BaseInventorySettings>,
+    mut slots: Query<(&UiInventorySlot, &mut BorderColor, &mut BackgroundColor)>,
+) {
+    let inventory = inventory.get();
+    let selected = inventory.and_then(|inventory| inventory.selected_slot());
+    for (slot, mut border_color, mut background_color) in slots.iter_mut() {
+        let is_selected = selected == Some(slot.slot);
+        let color = if is_selected {
+            settings.highlight_color
+        } else {
+            Color::NONE
+        };
+        if border_color.0 != color {
+            border_color.0 = color;
+        }
+        let color = if inventory.is_some_and(|inventory| inventory.is_locked(slot.slot)) {
+            settings.locked_slot_color
+        } else if is_selected {
+            settings.selected_slot_color
+        } else {
+            settings.slot_color
+        };
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+    }
+}
+
+/// Selects the slot whose button is pressed, a press anywhere on the bar does not reach the
+/// world
+#[allow(clippy::type_complexity)]
+pub fn click_ui_slot(
+    slots: Query<(&UiInventorySlot, &Interaction), Changed<Interaction>>,
+    panels: Query<&Interaction, (With<UiInventoryBarPanel>, Changed<Interaction>)>,
+    mut inventory: ActiveInventoryQueryMut,
+    mut click_consumed: ResMut<ClickConsumed>,
+) {
+    if panels
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        click_consumed.0 = true;
+    }
+    let Some((slot, _)) = slots
+        .iter()
+        .find(|(_, interaction)| **interaction == Interaction::Pressed)
+    else {
+        return;
+    };
+    if let Some(mut inventory) = inventory.get_mut() {
+        // The bar may still show slots of a shrunk inventory for a frame
+        let _ = inventory.select_item(slot.slot);
+    }
+    click_consumed.0 = true;
+}
@@ -1,26 +1,128 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
 
-use crate::{inventory::BaseInventory, DummyImage};
+use crate::{grid::GridLayer, inventory::BaseInventory, DummyImage};
 
 #[derive(Component, Clone, Copy)]
 pub struct ItemPreview;
 
-#[derive(Component, Clone, Copy, Deref, DerefMut, PartialEq, Eq, PartialOrd, Ord)]
+/// On an [`ItemPreview`], placed copies are a [`Container`](crate::inventory::Container)
+/// with an empty inventory of `slots` slots
+#[derive(Component, Clone, Copy)]
+pub struct ContainerItem {
+    pub slots: usize,
+}
+
+#[derive(Component, Debug, Clone, Copy, Deref, DerefMut, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemCode(pub usize);
 
-#[derive(Clone)]
+/// Kind of item, matched by [`SlotFilter::Category`](crate::inventory::SlotFilter::Category)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemCategory(pub u32);
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     pub code: ItemCode,
+    /// Amount in the stack
+    pub count: u32,
+    /// Largest stack of this item a slot holds, 1 for items that do not stack
+    pub max_stack: u32,
+    /// Placed items only collide with items on the same layer
+    pub layer: GridLayer,
+    /// Placed at [`GridSettings::snap_divisions`](crate::grid::GridSettings::snap_divisions)
+    /// resolution, blocked by other sub-cell items and by items taking the whole cell
+    pub sub_cell: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub category: Option<ItemCategory>,
+    /// Of a single item, counted against
+    /// [`DynInventory::max_weight`](crate::inventory::DynInventory::max_weight)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub weight: f32,
+}
+
+impl Item {
+    /// Weightless item on the [`GridLayer::OBJECT`] layer without a category
+    pub fn new(code: ItemCode, count: u32, max_stack: u32) -> Self {
+        Self {
+            code,
+            count,
+            max_stack,
+            layer: GridLayer::OBJECT,
+            sub_cell: false,
+            category: None,
+            weight: 0.0,
+        }
+    }
+
+    /// Weight of the whole stack
+    pub fn stack_weight(&self) -> f32 {
+        self.weight * self.count as f32
+    }
 }
 
 #[derive(Component, Default, Clone)]
 pub struct ItemImage(pub Handle<Image>);
 
+/// Definitions of the items of the game, by their code
+#[derive(Resource, Debug, Default, Clone)]
+pub struct ItemRegistry {
+    names: BTreeMap<ItemCode, String>,
+}
+
+impl ItemRegistry {
+    /// Replaces the name `code` was registered with
+    pub fn register(&mut self, code: ItemCode, name: impl Into<String>) {
+        self.names.insert(code, name.into());
+    }
+
+    pub fn name(&self, code: ItemCode) -> Option<&str> {
+        self.names.get(&code).map(String::as_str)
+    }
+
+    /// Registered name of `code`, `Item #<code>` for unregistered codes
+    pub fn display_name(&self, code: ItemCode) -> String {
+        match self.name(code) {
+            Some(name) => name.to_string(),
+            None => format!("Item #{}", code.0),
+        }
+    }
+}
+
+/// Item lying in the world, picked up by an [`ItemCollector`] walking over it
+///
+/// Spawned by [`drop_selected_item`](crate::hotbar::drop_selected_item), sized by its transform scale.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct DroppedItem {
+    pub item: Item,
+}
+
+/// Picks up the [`DroppedItem`]s its transform scale overlaps into the [`BaseInventory`]
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct ItemCollector;
+
+/// Sent when an item is dropped from an inventory into the world
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ItemDropped {
+    /// Entity of the spawned [`DroppedItem`]
+    pub entity: Entity,
+    pub item: Item,
+    pub position: Vec2,
+}
+
 pub fn spawn_item_prototypes(
     mut commands: Commands,
     dummy_image: Res<DummyImage>,
     mut inventory: ResMut<BaseInventory>,
+    mut registry: ResMut<ItemRegistry>,
 ) {
+    registry.register(ItemCode(1), "Plank");
+    registry.register(ItemCode(2), "Crate");
+    registry.register(ItemCode(3), "Chest");
+
     // 0: Rectangle item
     commands.spawn((
         ItemPreview,
@@ -38,7 +140,13 @@ pub fn spawn_item_prototypes(
             ..Default::default()
         },
     ));
-    inventory.put_item(1, Item { code: ItemCode(1) });
+    inventory.put_item_unchecked(
+        1,
+        Item {
+            layer: GridLayer::FLOOR,
+            ..Item::new(ItemCode(1), 20, 64)
+        },
+    );
 
     // 1: Square object
     commands.spawn((
@@ -57,5 +165,25 @@ pub fn spawn_item_prototypes(
             ..Default::default()
         },
     ));
-    inventory.put_item(2, Item { code: ItemCode(2) });
+    inventory.put_item_unchecked(2, Item::new(ItemCode(2), 20, 64));
+
+    // 2: Chest
+    commands.spawn((
+        ItemPreview,
+        ItemCode(3),
+        ItemImage(dummy_image.0.clone()),
+        ContainerItem { slots: 6 },
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::ORANGE_RED.with_a(0.5),
+                anchor: bevy::sprite::Anchor::Center,
+                ..Default::default()
+            },
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0))
+                .with_scale(Vec3::new(30.0, 20.0, 1.0)),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+    inventory.put_item_unchecked(3, Item::new(ItemCode(3), 4, 16));
 }
@@ -1,37 +1,28 @@
 use std::time::Duration;
 
-use animation::{
-    Animation, AnimationCurve, AnimationStep, Animator, Delay, Repeat, ScaleLens, SequenceAnimator,
-    TranslationLens,
-};
+use animation::{Animation, AnimationCurve, Animator, Repeat, ScaleLens, SequenceAnimator};
 use bevy::{prelude::*, window::PrimaryWindow};
-use grid::{AsGridCoord, GridSettings};
 use interpolation::EaseFunction;
-use inventory::BaseInventory;
-use items::{ItemCode, ItemPreview};
-use utils::cursor_to_window_coord;
 
 pub mod animation;
+pub mod animation_test_harness;
 pub mod grid;
+pub mod hotbar;
 pub mod inventory;
+pub mod inventory_ui;
 pub mod items;
+pub mod nine_slice;
 pub mod utils;
 
-const DUMMY_IMAGE_PATH: &'static str = "happy-tree.png";
-const BACKGROUND_COLOR: Color = Color::rgba(0.0, 180.0 / 255.0, 1.0, 1.0);
+pub use hotbar::{
+    clear_selection_on_input, drop_selected_item, log_selected_item, pick_up_dropped_items,
+    place_selected_item, remove_finished_shakes, scroll_select_item, select_item,
+    show_selected_item, HotbarScrollSettings, InfinitePlacement, InventoryKeyBindings,
+    PlacementShake, CLEAR_SELECTION_SLOT,
+};
 
-const NUMERIC_KEY_CODES: &'static [(KeyCode, usize)] = &[
-    (KeyCode::Key0, 0),
-    (KeyCode::Key1, 1),
-    (KeyCode::Key2, 2),
-    (KeyCode::Key3, 3),
-    (KeyCode::Key4, 4),
-    (KeyCode::Key5, 5),
-    (KeyCode::Key6, 6),
-    (KeyCode::Key7, 7),
-    (KeyCode::Key8, 8),
-    (KeyCode::Key9, 9),
-];
+const DUMMY_IMAGE_PATH: &str = "happy-tree.png";
+const BACKGROUND_COLOR: Color = Color::rgba(0.0, 180.0 / 255.0, 1.0, 1.0);
 
 #[derive(Resource)]
 pub struct DummyImage(pub Handle<Image>);
@@ -46,7 +37,10 @@ pub fn spawn_initial(
 
     commands.spawn(Camera2dBundle::default());
 
-    let primary_window = primary_window.single();
+    // Headless apps and closed windows have nothing to lay out against
+    let Ok(primary_window) = primary_window.get_single() else {
+        return;
+    };
     let window_h = primary_window.height();
     let window_w = primary_window.width();
 
@@ -96,34 +90,17 @@ pub fn spawn_initial(
         //         end: pos2,
         //     },
         // ),
-        SequenceAnimator::new(
-            vec![
-                AnimationStep::Animation(
-                    Animation {
-                        duration: Duration::from_secs(2),
-                        curve: EaseFunction::QuadraticInOut.into(),
-                    },
-                    TranslationLens {
-                        start: pos1,
-                        end: pos2,
-                    },
-                ),
-                AnimationStep::Delay(Delay {
-                    duration: Duration::from_secs(2),
-                }),
-                AnimationStep::Animation(
-                    Animation {
-                        duration: Duration::from_secs(2),
-                        curve: AnimationCurve::Linear,
-                    },
-                    TranslationLens {
-                        start: pos2,
-                        end: pos3,
-                    },
-                ),
-            ],
-            Repeat::Mirrored,
-        ),
+        SequenceAnimator::builder()
+            .move_to(
+                pos1,
+                pos2,
+                Duration::from_secs(2),
+                EaseFunction::QuadraticInOut,
+            )
+            .wait(Duration::from_secs(2))
+            .then_move(pos3, Duration::from_secs(2), AnimationCurve::Linear)
+            .repeat(Repeat::Mirrored)
+            .build(),
         Animator::new(
             Animation {
                 duration: Duration::from_secs(3),
@@ -137,86 +114,3 @@ pub fn spawn_initial(
         ),
     ));
 }
-
-pub fn select_item(key: Res<Input<KeyCode>>, mut inventory: ResMut<BaseInventory>) {
-    for (keycode, num) in NUMERIC_KEY_CODES.iter() {
-        if key.pressed(*keycode) {
-            inventory.select_item(*num);
-        }
-    }
-}
-
-pub fn show_selected_item(
-    primary_window: Query<&Window, With<PrimaryWindow>>,
-    grid_settings: Res<GridSettings>,
-    inventory: Res<BaseInventory>,
-    mut preview_items: Query<(&ItemCode, &mut Transform, &mut Visibility), With<ItemPreview>>,
-) {
-    let grid_size = grid_settings.size;
-
-    let primary_window = primary_window.single();
-    let window_h = primary_window.height();
-    let window_w = primary_window.width();
-    let cursor = &primary_window.cursor_position();
-
-    let selected_item = inventory.selected_item();
-    for (item_code, mut transform, mut visibility) in preview_items.iter_mut() {
-        *visibility = Visibility::Hidden;
-        if let Some(selected_item) = selected_item {
-            if item_code.eq(&selected_item.code) {
-                *visibility = Visibility::Visible;
-                if let Some(cursor) = cursor {
-                    // debug!("{:?}", cursor);
-                    let cursor_in_window =
-                        cursor_to_window_coord(cursor.clone(), window_h, window_w);
-                    let grid_translation = cursor_in_window
-                        .as_grid_coord(grid_size)
-                        .translation(grid_size);
-                    transform.translation.x = grid_translation.x;
-                    transform.translation.y = grid_translation.y;
-                }
-            }
-        }
-    }
-}
-
-pub fn place_selected_item(
-    mut commands: Commands,
-    mouse: Res<Input<MouseButton>>,
-    inventory: Res<BaseInventory>,
-    preview_items: Query<(&ItemCode, &Sprite, &Transform), With<ItemPreview>>,
-) {
-    if !(mouse.just_pressed(MouseButton::Left)) {
-        return;
-    }
-    let Some(selected_item) = inventory.selected_item() else {
-        return;
-    };
-    let Some((_, sprite, transform)) = preview_items
-        .iter()
-        .find(|(item_code, _, _)| **item_code == selected_item.code) else {
-            return;
-        };
-    commands.spawn(SpriteBundle {
-        sprite: Sprite {
-            color: sprite.color.with_a(1.0),
-            ..Default::default()
-        },
-        transform: transform.clone(),
-        visibility: Visibility::Visible,
-        ..Default::default()
-    });
-}
-
-pub fn log_selected_item(
-    inventory: Res<BaseInventory>,
-    preview_items: Query<(&ItemCode, &Visibility), With<ItemPreview>>,
-) {
-    if let Some(item) = inventory.selected_item() {
-        let visible = preview_items
-            .iter()
-            .find(|(item_code, _)| **item_code == item.code)
-            .map(|(_, v)| v);
-        info!("Selected: {} - {:?}", item.code.0, visible);
-    }
-}
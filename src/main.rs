@@ -7,13 +7,11 @@ use bevy::{
     window::PrimaryWindow,
 };
 use bevy_toolbox::{
-    grid::GridSettings,
-    inventory::{
-        render_items_in_base_inventory, spawn_base_inventory, BaseInventory, BaseInventorySettings,
-        InventorySettings,
-    },
+    animation::AnimationPlugin,
+    grid::GridPlugin,
+    inventory::{InventoryPlugin, InventorySet, InventoryUiState},
     items::spawn_item_prototypes,
-    log_selected_item, place_selected_item, select_item, show_selected_item, spawn_initial, animation::AnimationPlugin,
+    log_selected_item, spawn_initial,
 };
 
 #[derive(Resource)]
@@ -83,8 +81,13 @@ fn init_window(
     );
 }
 
-fn exit_on_close(key: Res<Input<KeyCode>>, mut app_exit: EventWriter<AppExit>) {
-    if key.pressed(KeyCode::Escape) {
+fn exit_on_close(
+    key: Res<Input<KeyCode>>,
+    inventory_ui: InventoryUiState,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    // Escape cancels a drag or closes an open panel first
+    if key.just_pressed(KeyCode::Escape) && !inventory_ui.is_busy() {
         app_exit.send_default();
     }
 }
@@ -111,31 +114,19 @@ fn main() {
                     ..Default::default()
                 }),
         )
-        .add_plugins(AnimationPlugin)
+        .add_plugins(AnimationPlugin::default())
         // -- General --
         .init_resource::<Resolution>()
         .add_systems(PreStartup, init_window)
         .add_systems(PreUpdate, toggle_fullscreen)
-        .add_systems(Update, exit_on_close)
+        .add_systems(Update, exit_on_close.before(InventorySet))
         // -- Library Base --
         // TODO: find a better way to order systems
-        .insert_resource(GridSettings { size: 100 })
+        .add_plugins(GridPlugin::default())
         .add_systems(Startup, spawn_initial)
-        // -- Inventory System --
-        .init_resource::<BaseInventory>()
-        .insert_resource(BaseInventorySettings(InventorySettings {
-            w_padding: 5.0,
-            w_mid_step: 4.0,
-            h_padding: 3.0,
-            slot_margin: 2.0,
-            slot_size: 50.0,
-        }))
         .add_systems(PostStartup, spawn_item_prototypes)
-        .add_systems(PostStartup, spawn_base_inventory)
-        .add_systems(Update, select_item)
-        .add_systems(Update, show_selected_item)
-        .add_systems(Update, place_selected_item)
-        .add_systems(Update, render_items_in_base_inventory)
+        // -- Inventory System --
+        .add_plugins(InventoryPlugin::default())
         // .add_systems(Update, log_selected_item)
         // ----- END -----
         .run();
@@ -0,0 +1,171 @@
+//! Sprites stretched without distorting their corners, see [`NineSlice`]
+
+use bevy::prelude::*;
+
+/// Just above the sliced sprite
+const PIECE_Z: f32 = 0.1;
+
+/// Borders of an image in texture pixels that keep their size when the image is stretched,
+/// the edges stretch along their side and the middle both ways
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NineSlice {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+/// Part of a nine-sliced image, see [`NineSlice::pieces`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlicedPiece {
+    /// Part of the texture in pixels, y down
+    pub rect: Rect,
+    /// Relative to the center of the whole, y up
+    pub center: Vec2,
+    pub size: Vec2,
+}
+
+impl NineSlice {
+    pub fn uniform(border: f32) -> Self {
+        Self {
+            left: border,
+            right: border,
+            top: border,
+            bottom: border,
+        }
+    }
+
+    /// Pieces of an image of `texture` size drawn at `size`, row by row from the top left
+    ///
+    /// Borders wider or taller than `size` shrink to fit, empty pieces are left out.
+    pub fn pieces(&self, texture: Vec2, size: Vec2) -> Vec<SlicedPiece> {
+        let fit = |a: f32, b: f32, room: f32| {
+            let scale = if a + b > room { room / (a + b) } else { 1.0 };
+            (a * scale, b * scale)
+        };
+        let (left, right) = fit(self.left, self.right, size.x);
+        let (top, bottom) = fit(self.top, self.bottom, size.y);
+        let columns = [
+            (0.0, self.left, left),
+            (self.left, texture.x - self.right, size.x - left - right),
+            (texture.x - self.right, texture.x, right),
+        ];
+        let rows = [
+            (0.0, self.top, top),
+            (self.top, texture.y - self.bottom, size.y - top - bottom),
+            (texture.y - self.bottom, texture.y, bottom),
+        ];
+        let mut pieces = Vec::new();
+        let mut y = size.y / 2.0;
+        for (y0, y1, height) in rows {
+            let mut x = -size.x / 2.0;
+            for (x0, x1, width) in columns {
+                if width > 0.0 && height > 0.0 {
+                    pieces.push(SlicedPiece {
+                        rect: Rect::new(x0, y0, x1, y1),
+                        center: Vec2::new(x + width / 2.0, y - height / 2.0),
+                        size: Vec2::new(width, height),
+                    });
+                }
+                x += width;
+            }
+            y -= height;
+        }
+        pieces
+    }
+}
+
+/// Piece of a nine-sliced sprite, a child of the sprite, see [`spawn_nine_slice`]
+#[derive(Component)]
+pub struct NineSlicePiece;
+
+/// Spawns the pieces of `image`, whose texture is `texture` pixels large, as children of
+/// `parent`, a sprite scaled to `size` by its transform
+///
+/// The pieces take the color of the parent, which should draw nothing itself, see
+/// [`tint_nine_slice_pieces`].
+pub fn spawn_nine_slice(
+    commands: &mut Commands,
+    parent: Entity,
+    image: &Handle<Image>,
+    texture: Vec2,
+    size: Vec2,
+    slice: &NineSlice,
+    color: Color,
+) {
+    commands.entity(parent).with_children(|parent| {
+        for piece in slice.pieces(texture, size) {
+            parent.spawn((
+                NineSlicePiece,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::ONE),
+                        rect: Some(piece.rect),
+                        ..Default::default()
+                    },
+                    texture: image.clone(),
+                    // In the units of the scaled parent
+                    transform: Transform::from_translation((piece.center / size).extend(PIECE_Z))
+                        .with_scale((piece.size / size).extend(1.0)),
+                    ..Default::default()
+                },
+            ));
+        }
+    });
+}
+
+/// Copies the color of nine-sliced sprites to their pieces
+#[allow(clippy::type_complexity)]
+pub fn tint_nine_slice_pieces(
+    parents: Query<(&Sprite, &Children), (Changed<Sprite>, Without<NineSlicePiece>)>,
+    mut pieces: Query<&mut Sprite, With<NineSlicePiece>>,
+) {
+    for (sprite, children) in parents.iter() {
+        let mut children = pieces.iter_many_mut(children);
+        while let Some(mut piece) = children.fetch_next() {
+            if piece.color != sprite.color {
+                piece.color = sprite.color;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Rect, Vec2};
+
+    use super::NineSlice;
+
+    #[test]
+    fn corners_keep_their_size() {
+        let slice = NineSlice {
+            left: 4.0,
+            right: 2.0,
+            top: 3.0,
+            bottom: 1.0,
+        };
+        let pieces = slice.pieces(Vec2::new(16.0, 8.0), Vec2::new(100.0, 20.0));
+        assert_eq!(pieces.len(), 9);
+        let top_left = pieces[0];
+        assert_eq!(top_left.rect, Rect::new(0.0, 0.0, 4.0, 3.0));
+        assert_eq!(top_left.size, Vec2::new(4.0, 3.0));
+        assert_eq!(top_left.center, Vec2::new(-48.0, 8.5));
+        let middle = pieces[4];
+        assert_eq!(middle.rect, Rect::new(4.0, 3.0, 14.0, 7.0));
+        assert_eq!(middle.size, Vec2::new(94.0, 16.0));
+        assert_eq!(middle.center, Vec2::new(1.0, -1.0));
+        let bottom_right = pieces[8];
+        assert_eq!(bottom_right.rect, Rect::new(14.0, 7.0, 16.0, 8.0));
+        assert_eq!(bottom_right.center, Vec2::new(49.0, -9.5));
+    }
+
+    #[test]
+    fn borders_shrink_to_fit() {
+        let pieces = NineSlice::uniform(10.0).pieces(Vec2::splat(32.0), Vec2::new(10.0, 40.0));
+        // No middle column left
+        assert_eq!(pieces.len(), 6);
+        assert_eq!(pieces[0].size, Vec2::new(5.0, 10.0));
+        assert_eq!(pieces[1].center, Vec2::new(2.5, 15.0));
+    }
+}
@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use bevy_toolbox::{
+    inventory::{
+        begin_item_drag, click_select_slot, end_item_drag, spawn_backpack, spawn_base_inventory,
+        sync_backpack_panel, toggle_backpack, update_item_drag, Backpack, BackpackOpen,
+        BackpackPanel, BackpackSettings, BaseInventory, BaseInventoryBackground, DraggedItem,
+        DynInventory, InventoryPanel, InventorySlotBackground,
+    },
+    items::ItemCode,
+};
+
+mod common;
+
+use common::{frame, inventory_app, item};
+
+fn app() -> App {
+    let mut app = inventory_app();
+    app.init_resource::<BackpackSettings>()
+        .init_resource::<BackpackOpen>()
+        .init_resource::<DraggedItem>()
+        .add_systems(Startup, (spawn_base_inventory, spawn_backpack))
+        .add_systems(
+            Update,
+            (
+                click_select_slot,
+                toggle_backpack,
+                begin_item_drag,
+                update_item_drag,
+                end_item_drag,
+                sync_backpack_panel,
+            )
+                .chain(),
+        );
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(1, item(1))
+        .unwrap();
+    app.update();
+    app
+}
+
+fn key(app: &mut App, key: KeyCode) {
+    frame(app, Vec2::ZERO, |app| {
+        app.world.resource_mut::<Input<KeyCode>>().press(key);
+        app.world.resource_mut::<Input<KeyCode>>().release(key);
+    });
+}
+
+fn drag(app: &mut App, from: Vec2, to: Vec2) {
+    frame(app, from, |app| {
+        app.world
+            .resource_mut::<Input<MouseButton>>()
+            .press(MouseButton::Left)
+    });
+    frame(app, to, |app| {
+        app.world
+            .resource_mut::<Input<MouseButton>>()
+            .release(MouseButton::Left)
+    });
+}
+
+fn backpack(app: &mut App) -> Entity {
+    app.world
+        .query_filtered::<Entity, With<Backpack>>()
+        .single(&app.world)
+}
+
+fn backpack_panel(app: &mut App) -> Option<Entity> {
+    app.world
+        .query_filtered::<Entity, With<BackpackPanel>>()
+        .get_single(&app.world)
+        .ok()
+}
+
+fn slot_centers(app: &mut App, panel: Entity) -> Vec<(usize, Vec2)> {
+    let mut slots: Vec<_> = app
+        .world
+        .query::<(&InventorySlotBackground, &Transform)>()
+        .iter(&app.world)
+        .filter(|(background, _)| background.base == panel)
+        .map(|(background, transform)| (background.slot, transform.translation.truncate()))
+        .collect();
+    slots.sort_by_key(|(slot, _)| *slot);
+    slots
+}
+
+#[test]
+fn backpack_toggles_a_grid_above_the_hotbar() {
+    let mut app = app();
+    assert!(backpack_panel(&mut app).is_none());
+
+    key(&mut app, KeyCode::Tab);
+    assert!(app.world.resource::<BackpackOpen>().0);
+    let panel = backpack_panel(&mut app).unwrap();
+    let backpack = backpack(&mut app);
+    assert_eq!(
+        app.world.get::<InventoryPanel>(panel).unwrap().inventory,
+        Some(backpack)
+    );
+    let slots = slot_centers(&mut app, panel);
+    assert_eq!(slots.len(), 27);
+    // Row by row from the top left
+    let (_, first) = slots[0];
+    let (_, second) = slots[1];
+    let (_, below) = slots[9];
+    assert_eq!(first.y, second.y);
+    assert!(second.x > first.x);
+    assert_eq!(first.x, below.x);
+    assert!(below.y < first.y);
+    let hotbar = app
+        .world
+        .query_filtered::<&Transform, With<BaseInventoryBackground>>()
+        .single(&app.world)
+        .translation;
+    assert!(slots.iter().all(|(_, center)| center.y > hotbar.y));
+
+    key(&mut app, KeyCode::E);
+    assert!(!app.world.resource::<BackpackOpen>().0);
+    assert!(backpack_panel(&mut app).is_none());
+    assert!(slot_centers(&mut app, panel).is_empty());
+
+    key(&mut app, KeyCode::E);
+    assert!(backpack_panel(&mut app).is_some());
+    key(&mut app, KeyCode::Escape);
+    assert!(backpack_panel(&mut app).is_none());
+}
+
+#[test]
+fn backpack_keeps_items_dragged_into_it() {
+    let mut app = app();
+    key(&mut app, KeyCode::Tab);
+    let panel = backpack_panel(&mut app).unwrap();
+    let (_, backpack_12) = slot_centers(&mut app, panel)[11];
+    let hotbar_panel = app
+        .world
+        .query_filtered::<Entity, With<BaseInventoryBackground>>()
+        .single(&app.world);
+    let (_, hotbar_1) = slot_centers(&mut app, hotbar_panel)[0];
+
+    drag(&mut app, hotbar_1, backpack_12);
+    assert!(app.world.resource::<BaseInventory>().get_item(1).is_none());
+
+    key(&mut app, KeyCode::Tab);
+    key(&mut app, KeyCode::Tab);
+    let backpack = backpack(&mut app);
+    let inventory = app.world.get::<DynInventory>(backpack).unwrap();
+    assert_eq!(inventory.slot_count(), 27);
+    assert_eq!(
+        inventory.get_item(12).map(|item| item.code),
+        Some(ItemCode(1))
+    );
+}
@@ -0,0 +1,59 @@
+//! Setup shared by the inventory tests, each test crate uses part of it
+#![allow(dead_code)]
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_toolbox::{
+    grid::CursorWorldPosition,
+    inventory::{BaseInventory, BaseInventorySettings, ClickConsumed, InventorySettings},
+    items::{Item, ItemCode},
+    InventoryKeyBindings,
+};
+
+/// A single item of `code` that does not stack
+pub fn item(code: usize) -> Item {
+    Item::new(ItemCode(code), 1, 1)
+}
+
+/// Empty hotbar with the default settings, input and cursor resources and an 800x600
+/// primary window, systems are up to the test
+pub fn inventory_app() -> App {
+    let mut app = App::new();
+    app.init_resource::<BaseInventory>()
+        .insert_resource(BaseInventorySettings(InventorySettings::default()))
+        .init_resource::<Input<MouseButton>>()
+        .init_resource::<Input<KeyCode>>()
+        .init_resource::<InventoryKeyBindings>()
+        .init_resource::<CursorWorldPosition>()
+        .init_resource::<ClickConsumed>();
+    spawn_primary_window(&mut app);
+    app
+}
+
+/// Runs a frame with the cursor at `cursor` and the input set by `input`, then clears the
+/// just pressed and released input
+pub fn frame(app: &mut App, cursor: Vec2, input: impl FnOnce(&mut App)) {
+    app.world.resource_mut::<CursorWorldPosition>().0 = Some(cursor);
+    input(app);
+    app.update();
+    app.world.resource_mut::<Input<MouseButton>>().clear();
+    app.world.resource_mut::<Input<KeyCode>>().clear();
+}
+
+/// Frames in which [`count_inventory_changes`] saw the [`BaseInventory`] change
+#[derive(Resource, Default)]
+pub struct InventoryChanges(pub usize);
+
+pub fn count_inventory_changes(
+    inventory: Res<BaseInventory>,
+    mut changes: ResMut<InventoryChanges>,
+) {
+    if inventory.is_changed() {
+        changes.0 += 1;
+    }
+}
+
+pub fn spawn_primary_window(app: &mut App) -> Entity {
+    let mut window = Window::default();
+    window.resolution.set(800.0, 600.0);
+    app.world.spawn((window, PrimaryWindow)).id()
+}
@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy_toolbox::{
+    grid::CursorWorldPosition,
+    inventory::{
+        click_select_slot, close_container_on_escape, open_container_on_click,
+        quick_transfer_on_shift_click, reset_click_consumed, spawn_base_inventory,
+        sync_container_panel, BaseInventory, BaseInventoryBackground, ClickConsumed, Container,
+        ContainerPanel, DynInventory, InventorySlotBackground, OpenContainer,
+    },
+    items::{Item, ItemCode},
+    select_item,
+};
+
+mod common;
+
+use common::inventory_app;
+
+fn app() -> App {
+    let mut app = inventory_app();
+    app.init_resource::<OpenContainer>()
+        .add_systems(Startup, spawn_base_inventory)
+        .add_systems(First, reset_click_consumed)
+        .add_systems(
+            Update,
+            (
+                select_item,
+                quick_transfer_on_shift_click,
+                click_select_slot,
+                open_container_on_click,
+                close_container_on_escape,
+                sync_container_panel,
+            )
+                .chain(),
+        );
+    app
+}
+
+fn click(app: &mut App, cursor: Vec2) {
+    app.world.resource_mut::<CursorWorldPosition>().0 = Some(cursor);
+    app.world
+        .resource_mut::<Input<MouseButton>>()
+        .press(MouseButton::Left);
+    app.update();
+    app.world.resource_mut::<Input<MouseButton>>().reset_all();
+}
+
+fn press(app: &mut App, key: KeyCode, cursor: Vec2) {
+    app.world.resource_mut::<CursorWorldPosition>().0 = Some(cursor);
+    app.world.resource_mut::<Input<KeyCode>>().press(key);
+    app.update();
+    app.world.resource_mut::<Input<KeyCode>>().reset_all();
+}
+
+fn panel(app: &mut App) -> Option<(Entity, Vec3)> {
+    app.world
+        .query_filtered::<(Entity, &Transform), With<ContainerPanel>>()
+        .get_single(&app.world)
+        .ok()
+        .map(|(entity, transform)| (entity, transform.translation))
+}
+
+fn slot_backgrounds(app: &mut App, base: Entity) -> Vec<(usize, Vec2)> {
+    app.world
+        .query::<(&InventorySlotBackground, &Transform)>()
+        .iter(&app.world)
+        .filter(|(background, _)| background.base == base)
+        .map(|(background, transform)| (background.slot, transform.translation.truncate()))
+        .collect()
+}
+
+/// Count of the stack in `slot` of `inventory`, the hotbar for None
+fn count(app: &App, inventory: Option<Entity>, slot: usize) -> Option<u32> {
+    let inventory = match inventory {
+        Some(entity) => app.world.get::<DynInventory>(entity).unwrap(),
+        None => app.world.resource::<BaseInventory>(),
+    };
+    inventory.get_item(slot).map(|item| item.count)
+}
+
+fn selected(app: &App, entity: Entity) -> Option<usize> {
+    app.world
+        .get::<DynInventory>(entity)
+        .unwrap()
+        .selected_slot()
+}
+
+#[test]
+fn container_panel_opens_and_closes() {
+    let mut app = app();
+    let chest = app
+        .world
+        .spawn((
+            Container,
+            DynInventory::with_capacity(4),
+            Transform::from_xyz(100.0, 100.0, 1.0).with_scale(Vec3::new(30.0, 20.0, 1.0)),
+        ))
+        .id();
+    app.update();
+    assert!(panel(&mut app).is_none());
+
+    click(&mut app, Vec2::new(110.0, 95.0));
+    assert_eq!(app.world.resource::<OpenContainer>().0, Some(chest));
+    assert!(app.world.resource::<ClickConsumed>().0);
+    let (panel_entity, panel_translation) = panel(&mut app).unwrap();
+    let hotbar_y = app
+        .world
+        .query_filtered::<&Transform, With<BaseInventoryBackground>>()
+        .single(&app.world)
+        .translation
+        .y;
+    assert!(panel_translation.y > hotbar_y);
+    let container_slots = slot_backgrounds(&mut app, panel_entity);
+    assert_eq!(container_slots.len(), 4);
+
+    // Clicks and number keys go to the panel under the cursor
+    let (_, slot_2) = container_slots.iter().find(|(slot, _)| *slot == 2).unwrap();
+    click(&mut app, *slot_2);
+    assert_eq!(selected(&app, chest), Some(2));
+    press(&mut app, KeyCode::Key3, *slot_2);
+    assert_eq!(selected(&app, chest), Some(3));
+    press(&mut app, KeyCode::Key5, Vec2::ZERO);
+    assert_eq!(selected(&app, chest), Some(3));
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(5)
+    );
+
+    press(&mut app, KeyCode::Escape, Vec2::ZERO);
+    assert_eq!(app.world.resource::<OpenContainer>().0, None);
+    assert!(panel(&mut app).is_none());
+    assert!(slot_backgrounds(&mut app, panel_entity).is_empty());
+    assert_eq!(
+        app.world
+            .query::<&InventorySlotBackground>()
+            .iter(&app.world)
+            .count(),
+        9
+    );
+
+    // A despawned container closes its panel
+    click(&mut app, Vec2::new(100.0, 100.0));
+    assert!(panel(&mut app).is_some());
+    app.world.despawn(chest);
+    app.update();
+    assert_eq!(app.world.resource::<OpenContainer>().0, None);
+    assert!(panel(&mut app).is_none());
+}
+
+fn shift_click(app: &mut App, cursor: Vec2) {
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::ShiftLeft);
+    click(app, cursor);
+    app.world.resource_mut::<Input<KeyCode>>().reset_all();
+}
+
+#[test]
+fn shift_click_moves_stacks_between_hotbar_and_container() {
+    let mut app = app();
+    let mut chest_inventory = DynInventory::with_capacity(2);
+    chest_inventory
+        .put_item(2, Item::new(ItemCode(1), 60, 64))
+        .unwrap();
+    let chest = app
+        .world
+        .spawn((
+            Container,
+            chest_inventory,
+            Transform::from_xyz(100.0, 100.0, 1.0).with_scale(Vec3::new(30.0, 20.0, 1.0)),
+        ))
+        .id();
+    let mut hotbar = app.world.resource_mut::<BaseInventory>();
+    hotbar.put_item(4, Item::new(ItemCode(1), 10, 64)).unwrap();
+    hotbar.put_item(5, Item::new(ItemCode(2), 1, 64)).unwrap();
+    hotbar.select_item(4).unwrap();
+    app.update();
+    let hotbar_panel = app
+        .world
+        .query_filtered::<Entity, With<BaseInventoryBackground>>()
+        .single(&app.world);
+    let hotbar_slot = |app: &mut App, slot: usize| {
+        slot_backgrounds(app, hotbar_panel)
+            .into_iter()
+            .find(|(background, _)| *background == slot)
+            .unwrap()
+            .1
+    };
+
+    // Nothing to trade with while no container is open
+    let slot_4 = hotbar_slot(&mut app, 4);
+    shift_click(&mut app, slot_4);
+    assert_eq!(count(&app, None, 4), Some(10));
+
+    app.insert_resource(OpenContainer(Some(chest)));
+    app.update();
+    shift_click(&mut app, slot_4);
+    // The stack in the chest fills up first, the rest goes to its empty slot
+    assert_eq!(count(&app, Some(chest), 2), Some(64));
+    assert_eq!(count(&app, Some(chest), 1), Some(6));
+    assert_eq!(count(&app, None, 4), None);
+    // The click neither selected nor moved the selection
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(4)
+    );
+
+    // The chest is full, the item stays
+    let slot_5 = hotbar_slot(&mut app, 5);
+    shift_click(&mut app, slot_5);
+    assert_eq!(count(&app, None, 5), Some(1));
+
+    let (panel_entity, _) = panel(&mut app).unwrap();
+    let (_, chest_1) = slot_backgrounds(&mut app, panel_entity)
+        .into_iter()
+        .find(|(slot, _)| *slot == 1)
+        .unwrap();
+    shift_click(&mut app, chest_1);
+    assert_eq!(count(&app, Some(chest), 1), None);
+    assert_eq!(count(&app, None, 1), Some(6));
+    assert_eq!(selected(&app, chest), None);
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_toolbox::animation::{
+    Animation, AnimationAppExt, AnimationCurve, AnimationLens, AnimationPlugin, Animator, Repeat,
+};
+
+#[derive(AnimationLens)]
+#[lens(component = "Transform", field = "translation")]
+struct SlideLens {
+    start: Vec3,
+    end: Vec3,
+}
+
+#[derive(AnimationLens)]
+#[lens(component = "Sprite", field = "color")]
+struct FadeLens {
+    start: Color,
+    end: Color,
+}
+
+#[test]
+fn derived_lenses_animate() {
+    let mut app = App::new();
+    app.add_plugins(AnimationPlugin::default())
+        .init_resource::<Time>()
+        .add_animation_lens::<SlideLens>()
+        .add_animation_lens::<FadeLens>();
+
+    let animation = || Animation {
+        duration: Duration::from_secs(2),
+        curve: AnimationCurve::Linear,
+    };
+    let entity = app
+        .world
+        .spawn((
+            Transform::default(),
+            Sprite::default(),
+            Animator::new(
+                animation(),
+                Repeat::Once,
+                SlideLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::new(4.0, 2.0, 0.0),
+                },
+            ),
+            Animator::new(
+                animation(),
+                Repeat::Once,
+                FadeLens {
+                    start: Color::rgba(0.0, 0.0, 0.0, 1.0),
+                    end: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                },
+            ),
+        ))
+        .id();
+
+    let start = app.world.resource::<Time>().startup();
+    for secs in [0, 1] {
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_secs(secs));
+        app.update();
+    }
+
+    let transform = app.world.get::<Transform>(entity).unwrap();
+    assert_eq!(transform.translation, Vec3::new(2.0, 1.0, 0.0));
+    let sprite = app.world.get::<Sprite>(entity).unwrap();
+    assert_eq!(sprite.color, Color::rgba(0.5, 0.5, 0.5, 0.5));
+}
+
+#[test]
+fn derive_compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass_*.rs");
+    t.compile_fail("tests/ui/fail_*.rs");
+}
+
+#[test]
+fn reversed_lens_registered_with_base() {
+    let mut app = App::new();
+    app.add_plugins(AnimationPlugin::default())
+        .init_resource::<Time>()
+        .add_animation_lens::<SlideLens>()
+        // Registering again must not tick twice
+        .add_animation_lens::<SlideLens>();
+
+    let entity = app
+        .world
+        .spawn((
+            Transform::default(),
+            Animator::new(
+                Animation {
+                    duration: Duration::from_secs(4),
+                    curve: AnimationCurve::Linear,
+                },
+                Repeat::Once,
+                SlideLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::new(4.0, 0.0, 0.0),
+                }
+                .reversed(),
+            ),
+        ))
+        .id();
+
+    let start = app.world.resource::<Time>().startup();
+    for secs in [0, 1] {
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_secs(secs));
+        app.update();
+    }
+
+    let transform = app.world.get::<Transform>(entity).unwrap();
+    assert_eq!(transform.translation, Vec3::new(3.0, 0.0, 0.0));
+}
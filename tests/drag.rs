@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use bevy_toolbox::{
+    inventory::{
+        begin_item_drag, click_select_slot, close_container_on_escape, end_item_drag,
+        spawn_base_inventory, sync_container_panel, update_item_drag, BaseInventory, Container,
+        ContainerPanel, DraggedItem, DraggedItemSprite, DynInventory, InventoryPanel,
+        InventorySlotBackground, OpenContainer,
+    },
+    items::{Item, ItemCode},
+    select_item,
+};
+
+mod common;
+
+use common::{frame, inventory_app, item};
+
+fn app() -> App {
+    let mut app = inventory_app();
+    app.init_resource::<OpenContainer>()
+        .init_resource::<DraggedItem>()
+        .add_systems(Startup, spawn_base_inventory)
+        .add_systems(
+            Update,
+            (
+                select_item,
+                click_select_slot,
+                close_container_on_escape,
+                begin_item_drag,
+                update_item_drag,
+                end_item_drag,
+                sync_container_panel,
+            )
+                .chain(),
+        );
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    inventory.put_item(1, item(1)).unwrap();
+    inventory.put_item(2, item(2)).unwrap();
+    app.update();
+    app
+}
+
+/// Center of `slot` in the panel showing `inventory`
+fn slot_center(app: &mut App, inventory: Option<Entity>, slot: usize) -> Vec2 {
+    let panel = app
+        .world
+        .query::<(Entity, &InventoryPanel)>()
+        .iter(&app.world)
+        .find(|(_, panel)| panel.inventory == inventory)
+        .unwrap()
+        .0;
+    app.world
+        .query::<(&InventorySlotBackground, &Transform)>()
+        .iter(&app.world)
+        .find(|(background, _)| background.base == panel && background.slot == slot)
+        .unwrap()
+        .1
+        .translation
+        .truncate()
+}
+
+fn press(app: &mut App, cursor: Vec2) {
+    frame(app, cursor, |app| {
+        app.world
+            .resource_mut::<Input<MouseButton>>()
+            .press(MouseButton::Left)
+    });
+}
+
+fn release(app: &mut App, cursor: Vec2) {
+    frame(app, cursor, |app| {
+        app.world
+            .resource_mut::<Input<MouseButton>>()
+            .release(MouseButton::Left)
+    });
+}
+
+fn codes(inventory: &DynInventory) -> Vec<Option<usize>> {
+    (1..=inventory.slot_count())
+        .map(|slot| inventory.get_item(slot).map(|item| item.code.0))
+        .collect()
+}
+
+fn base_codes(app: &App) -> Vec<Option<usize>> {
+    codes(app.world.resource::<BaseInventory>())
+}
+
+fn dragging(app: &mut App) -> bool {
+    let sprites = app
+        .world
+        .query_filtered::<(), With<DraggedItemSprite>>()
+        .iter(&app.world)
+        .count();
+    assert_eq!(
+        sprites,
+        app.world.resource::<DraggedItem>().is_some() as usize
+    );
+    sprites == 1
+}
+
+#[test]
+fn drag_moves_and_swaps() {
+    let mut app = app();
+    let [slot_1, slot_2, slot_4] = [1, 2, 4].map(|slot| slot_center(&mut app, None, slot));
+
+    press(&mut app, slot_1);
+    assert!(dragging(&mut app));
+    frame(&mut app, Vec2::ZERO, |_| {});
+    let sprite = app
+        .world
+        .query_filtered::<&Transform, With<DraggedItemSprite>>()
+        .single(&app.world);
+    assert_eq!(sprite.translation.truncate(), Vec2::ZERO);
+    assert!(sprite.translation.z > 50.0);
+    release(&mut app, slot_4);
+    assert!(!dragging(&mut app));
+    let mut expected = vec![None; 9];
+    expected[1] = Some(2);
+    expected[3] = Some(1);
+    assert_eq!(base_codes(&app), expected);
+
+    press(&mut app, slot_2);
+    release(&mut app, slot_4);
+    expected.swap(1, 3);
+    assert_eq!(base_codes(&app), expected);
+
+    // Released away from the slots, nothing moves
+    press(&mut app, slot_4);
+    release(&mut app, Vec2::ZERO);
+    assert!(!dragging(&mut app));
+    assert_eq!(base_codes(&app), expected);
+
+    // Empty slots do not start a drag
+    press(&mut app, slot_1);
+    assert!(!dragging(&mut app));
+    release(&mut app, slot_1);
+}
+
+#[test]
+fn escape_cancels_and_selection_waits() {
+    let mut app = app();
+    let [slot_1, slot_3] = [1, 3].map(|slot| slot_center(&mut app, None, slot));
+    let before = base_codes(&app);
+
+    press(&mut app, slot_1);
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(1)
+    );
+    frame(&mut app, slot_3, |app| {
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Key5)
+    });
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(1)
+    );
+    frame(&mut app, slot_3, |app| {
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Escape)
+    });
+    assert!(!dragging(&mut app));
+    release(&mut app, slot_3);
+    assert_eq!(base_codes(&app), before);
+}
+
+#[test]
+fn drag_between_hotbar_and_container() {
+    let mut app = app();
+    let mut chest_inventory = DynInventory::with_capacity(3);
+    chest_inventory.put_item(3, item(7)).unwrap();
+    let chest = app.world.spawn((Container, chest_inventory)).id();
+    app.insert_resource(OpenContainer(Some(chest)));
+    app.update();
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<ContainerPanel>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+    let hotbar_1 = slot_center(&mut app, None, 1);
+    let hotbar_2 = slot_center(&mut app, None, 2);
+    let [chest_1, chest_3] = [1, 3].map(|slot| slot_center(&mut app, Some(chest), slot));
+
+    press(&mut app, hotbar_1);
+    release(&mut app, chest_1);
+    press(&mut app, chest_3);
+    release(&mut app, hotbar_2);
+
+    let mut expected = vec![None; 9];
+    expected[1] = Some(7);
+    assert_eq!(base_codes(&app), expected);
+    assert_eq!(
+        codes(app.world.get::<DynInventory>(chest).unwrap()),
+        [Some(1), None, Some(2)]
+    );
+
+    // Escape cancels the drag only, the container stays open
+    press(&mut app, hotbar_2);
+    frame(&mut app, hotbar_2, |app| {
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Escape)
+    });
+    assert!(!dragging(&mut app));
+    assert_eq!(app.world.resource::<OpenContainer>().0, Some(chest));
+}
+
+#[test]
+fn drag_respects_weight_limits() {
+    let mut app = app();
+    let heavy = Item {
+        weight: 2.0,
+        ..Item::new(ItemCode(3), 1, 1)
+    };
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(3, heavy)
+        .unwrap();
+    let mut chest_inventory = DynInventory::with_capacity(3);
+    chest_inventory.put_item(2, item(7)).unwrap();
+    chest_inventory.set_max_weight(Some(1.0));
+    let chest = app.world.spawn((Container, chest_inventory)).id();
+    app.insert_resource(OpenContainer(Some(chest)));
+    app.update();
+    let [hotbar_3, hotbar_4] = [3, 4].map(|slot| slot_center(&mut app, None, slot));
+    let [chest_1, chest_2] = [1, 2].map(|slot| slot_center(&mut app, Some(chest), slot));
+    let before = (
+        base_codes(&app),
+        codes(app.world.get::<DynInventory>(chest).unwrap()),
+    );
+    let after = |app: &App| {
+        (
+            base_codes(app),
+            codes(app.world.get::<DynInventory>(chest).unwrap()),
+        )
+    };
+
+    // Too heavy for the chest
+    press(&mut app, hotbar_3);
+    release(&mut app, chest_1);
+    assert_eq!(after(&app), before);
+
+    // The displaced item does not fit back in the chest either
+    press(&mut app, chest_2);
+    release(&mut app, hotbar_3);
+    assert_eq!(after(&app), before);
+    assert_eq!(
+        app.world
+            .get::<DynInventory>(chest)
+            .unwrap()
+            .current_weight(),
+        0.0
+    );
+
+    // Weightless items still move
+    press(&mut app, chest_2);
+    release(&mut app, hotbar_4);
+    assert_eq!(base_codes(&app)[3], Some(7));
+}
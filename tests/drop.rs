@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy_toolbox::{
+    drop_selected_item,
+    grid::{CursorGridCoord, GridCoord, GridSettings},
+    inventory::BaseInventory,
+    items::{DroppedItem, Item, ItemCode, ItemCollector, ItemDropped, ItemPreview},
+    pick_up_dropped_items,
+};
+
+mod common;
+
+use common::{count_inventory_changes, inventory_app, item, InventoryChanges};
+
+fn app() -> App {
+    let mut app = inventory_app();
+    app.init_resource::<CursorGridCoord>()
+        .insert_resource(GridSettings::from_u32(10))
+        .add_event::<ItemDropped>()
+        .add_systems(Update, (drop_selected_item, pick_up_dropped_items).chain());
+    app.world.spawn((
+        ItemPreview,
+        ItemCode(1),
+        SpriteBundle {
+            transform: Transform::from_scale(Vec3::new(20.0, 20.0, 1.0)),
+            ..Default::default()
+        },
+    ));
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    inventory
+        .put_item(3, Item::new(ItemCode(1), 2, 64))
+        .unwrap();
+    inventory.select_item(3).unwrap();
+    app
+}
+
+fn drop(app: &mut App) {
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Q);
+    app.update();
+    app.world.resource_mut::<Input<KeyCode>>().reset_all();
+}
+
+fn drops(app: &mut App) -> Vec<(u32, Vec3)> {
+    app.world
+        .query::<(&DroppedItem, &Transform)>()
+        .iter(&app.world)
+        .map(|(dropped, transform)| (dropped.item.count, transform.translation))
+        .collect()
+}
+
+#[test]
+fn dropping_takes_one_to_the_cursor_cell() {
+    let mut app = app();
+    app.world.resource_mut::<CursorGridCoord>().0 = Some(GridCoord::new(2, 0));
+    drop(&mut app);
+
+    let dropped = drops(&mut app);
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].1.truncate(), Vec2::new(25.0, 5.0));
+    let inventory = app.world.resource::<BaseInventory>();
+    assert_eq!(inventory.get_item(3).unwrap().count, 1);
+    assert_eq!(inventory.selected_slot(), Some(3));
+    let events = app.world.resource::<Events<ItemDropped>>();
+    let event = events.iter_current_update_events().next().unwrap();
+    assert_eq!((event.item.code, event.item.count), (ItemCode(1), 1));
+    assert_eq!(event.position, Vec2::new(25.0, 5.0));
+
+    // Dropping the last one empties the slot and clears the selection
+    drop(&mut app);
+    assert_eq!(drops(&mut app).len(), 2);
+    let inventory = app.world.resource::<BaseInventory>();
+    assert_eq!(inventory.get_item(3), None);
+    assert_eq!(inventory.selected_slot(), None);
+    drop(&mut app);
+    assert_eq!(drops(&mut app).len(), 2);
+}
+
+#[test]
+fn collectors_pick_up_what_fits() {
+    let mut app = app();
+    let collector = app
+        .world
+        .spawn((
+            ItemCollector,
+            Transform::from_xyz(0.0, 0.0, 0.0).with_scale(Vec3::new(10.0, 10.0, 1.0)),
+        ))
+        .id();
+    // Without a cursor the item lands in front of the collector, out of its reach
+    drop(&mut app);
+    let dropped = drops(&mut app);
+    assert_eq!(dropped.len(), 1);
+    assert!(dropped[0].1.x > 15.0);
+
+    // Fill the inventory, the drop waits
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    for slot in 1..=9 {
+        if inventory.get_item(slot).is_none() {
+            inventory.put_item(slot, item(2)).unwrap();
+        }
+    }
+    inventory.remove_item(3).unwrap();
+    inventory.put_item(3, item(2)).unwrap();
+    app.world
+        .get_mut::<Transform>(collector)
+        .unwrap()
+        .translation = dropped[0].1;
+    app.update();
+    app.init_resource::<InventoryChanges>()
+        .add_systems(Update, count_inventory_changes.after(pick_up_dropped_items));
+    app.update();
+    app.world.resource_mut::<InventoryChanges>().0 = 0;
+    app.update();
+    assert_eq!(drops(&mut app).len(), 1);
+    assert_eq!(app.world.resource::<InventoryChanges>().0, 0);
+
+    app.world
+        .resource_mut::<BaseInventory>()
+        .remove_item(5)
+        .unwrap();
+    app.update();
+    assert!(drops(&mut app).is_empty());
+    assert_eq!(
+        app.world
+            .resource::<BaseInventory>()
+            .get_item(5)
+            .map(|item| item.code),
+        Some(ItemCode(1))
+    );
+}
+
+#[test]
+fn overlapping_collectors_pick_up_a_drop_once() {
+    let mut app = app();
+    app.world.resource_mut::<CursorGridCoord>().0 = Some(GridCoord::new(2, 0));
+    drop(&mut app);
+    let dropped = drops(&mut app);
+    assert_eq!(dropped.len(), 1);
+    for _ in 0..2 {
+        app.world.spawn((
+            ItemCollector,
+            Transform::from_translation(dropped[0].1).with_scale(Vec3::new(10.0, 10.0, 1.0)),
+        ));
+    }
+    app.update();
+
+    assert!(drops(&mut app).is_empty());
+    // The one left in the slot and the one picked up
+    assert_eq!(
+        app.world.resource::<BaseInventory>().count_of(ItemCode(1)),
+        2
+    );
+}
@@ -0,0 +1,386 @@
+use bevy::{
+    prelude::*,
+    render::camera::{camera_system, ManualTextureViews},
+    window::{WindowCreated, WindowResized},
+};
+use bevy_toolbox::grid::{
+    cursor_grid_coord, snap, spawn_grid_cursor, visible_cells, CursorGridCoord,
+    CursorWorldPosition, DenseGridMap, GridBounds, GridCoord, GridCursor, GridCursorBlocker,
+    GridCursorLabel, GridCursorStyle, GridDebug, GridHeatmapCell, GridHeatmapSettings, GridLabel,
+    GridPlugin, GridRect, GridSettings,
+};
+
+mod common;
+
+use common::spawn_primary_window;
+
+#[derive(Resource, Default)]
+struct CursorChanges(u32);
+
+fn count_cursor_changes(cursor: Res<CursorGridCoord>, mut changes: ResMut<CursorChanges>) {
+    if cursor.is_changed() {
+        changes.0 += 1;
+    }
+}
+
+/// Headless app with a 800x600 primary window, the camera projection is computed in [`First`]
+fn app(camera_transform: Transform) -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Image>()
+        .add_event::<WindowCreated>()
+        .add_event::<WindowResized>()
+        .init_resource::<ManualTextureViews>()
+        .add_systems(First, camera_system::<OrthographicProjection>)
+        .add_plugins(GridPlugin {
+            size: 10.0,
+            ..Default::default()
+        })
+        .init_resource::<CursorChanges>()
+        .add_systems(Update, count_cursor_changes);
+
+    let window = spawn_primary_window(&mut app);
+    let camera = app
+        .world
+        .spawn(Camera2dBundle {
+            transform: camera_transform,
+            global_transform: camera_transform.into(),
+            ..Default::default()
+        })
+        .id();
+    (app, window, camera)
+}
+
+fn set_cursor(app: &mut App, window: Entity, cursor: Option<Vec2>) {
+    let mut window = app.world.get_mut::<Window>(window).unwrap();
+    window.set_cursor_position(cursor);
+}
+
+fn update(app: &mut App) -> (Option<GridCoord>, u32) {
+    app.world.resource_mut::<CursorChanges>().0 = 0;
+    app.update();
+    let cursor = app.world.resource::<CursorGridCoord>().0;
+    (cursor, app.world.resource::<CursorChanges>().0)
+}
+
+#[test]
+fn cursor_grid_coord_changes() {
+    let (mut app, window, _) = app(Transform::default());
+    set_cursor(&mut app, window, Some(Vec2::new(425.0, 305.0)));
+
+    // Window y points down
+    assert_eq!(update(&mut app), (Some(GridCoord::new(2, -1)), 1));
+    assert_eq!(update(&mut app), (Some(GridCoord::new(2, -1)), 0));
+
+    set_cursor(&mut app, window, None);
+    assert_eq!(update(&mut app), (None, 1));
+}
+
+#[test]
+fn moved_camera_shifts_snap_target() {
+    let cursor = Vec2::new(425.0, 305.0);
+    let snap_target = |camera_transform: Transform| {
+        let (mut app, window, _) = app(camera_transform);
+        set_cursor(&mut app, window, Some(cursor));
+        app.update();
+        let position = app.world.resource::<CursorWorldPosition>().0.unwrap();
+        let settings = app.world.resource::<GridSettings>();
+        (
+            app.world.resource::<CursorGridCoord>().0.unwrap(),
+            snap(position, settings, settings.snap_mode),
+        )
+    };
+
+    assert_eq!(
+        snap_target(Transform::default()),
+        (GridCoord::new(2, -1), Vec2::new(25.0, -5.0))
+    );
+    assert_eq!(
+        snap_target(Transform::from_xyz(100.0, -40.0, 0.0)),
+        (GridCoord::new(12, -5), Vec2::new(125.0, -45.0))
+    );
+    // Zoomed out 2x, the cursor offset from the center doubles in world units
+    assert_eq!(
+        snap_target(Transform::from_xyz(100.0, 0.0, 0.0).with_scale(Vec3::new(2.0, 2.0, 1.0))),
+        (GridCoord::new(15, -1), Vec2::new(155.0, -5.0))
+    );
+}
+
+#[test]
+fn unprojection_requires_computed_viewport() {
+    let settings = GridSettings {
+        size: 10.0,
+        snap_mode: Default::default(),
+        topology: Default::default(),
+        snap_divisions: 1,
+    };
+    let camera = Camera::default();
+    let transform = GlobalTransform::default();
+    assert_eq!(
+        cursor_grid_coord(&camera, &transform, Vec2::ZERO, &settings),
+        None
+    );
+
+    let (mut app, window, camera) = app(Transform::from_xyz(-100.0, 0.0, 0.0));
+    set_cursor(&mut app, window, Some(Vec2::new(400.0, 300.0)));
+    app.update();
+    let (camera, transform) = app
+        .world
+        .query::<(&Camera, &GlobalTransform)>()
+        .get(&app.world, camera)
+        .unwrap();
+    assert_eq!(
+        cursor_grid_coord(camera, transform, Vec2::new(400.0, 300.0), &settings),
+        Some(GridCoord::new(-10, 0))
+    );
+}
+
+#[test]
+fn debug_labels_are_pooled() {
+    let (mut app, window, camera) = app(Transform::default());
+    app.world.resource_mut::<GridSettings>().size = 100.0;
+    app.world.resource_mut::<GridDebug>().show_labels = true;
+    set_cursor(&mut app, window, Some(Vec2::new(425.0, 305.0)));
+    app.update();
+    app.update();
+
+    let labels = |app: &mut App| {
+        let mut labels: Vec<_> = app
+            .world
+            .query_filtered::<(Entity, &Text, &Visibility), With<GridLabel>>()
+            .iter(&app.world)
+            .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+            .map(|(entity, text, _)| (entity, text.sections[0].value.clone()))
+            .collect();
+        labels.sort();
+        labels
+    };
+    let cursor_label = |app: &mut App| {
+        let mut cursor_labels = app.world.query_filtered::<&Text, With<GridCursorLabel>>();
+        let texts: Vec<_> = cursor_labels
+            .iter(&app.world)
+            .map(|text| text.sections[0].value.clone())
+            .collect();
+        texts
+    };
+
+    // 800x600 view over 100 wide cells
+    let before = labels(&mut app);
+    assert_eq!(before.len(), 48);
+    assert!(before.iter().any(|(_, text)| text == "(-4,-3)"));
+    assert!(before.iter().any(|(_, text)| text == "(3,2)"));
+    assert_eq!(cursor_label(&mut app), ["cursor (0,-1)"]);
+
+    // Panning by half a cell shows another column, the old labels are reused
+    *app.world.get_mut::<GlobalTransform>(camera).unwrap() =
+        Transform::from_xyz(50.0, 0.0, 0.0).into();
+    app.update();
+    app.update();
+    let after = labels(&mut app);
+    assert_eq!(after.len(), 54);
+    assert!(after.iter().any(|(_, text)| text == "(4,2)"));
+    assert!(before
+        .iter()
+        .all(|(entity, _)| after.iter().any(|(reused, _)| reused == entity)));
+    // The cursor moved along to world x = 75
+    assert_eq!(cursor_label(&mut app), ["cursor (0,-1)"]);
+
+    app.world.resource_mut::<GridDebug>().show_labels = false;
+    app.update();
+    assert!(labels(&mut app).is_empty());
+    assert!(cursor_label(&mut app).is_empty());
+}
+
+#[test]
+fn grid_cursor_follows_cursor() {
+    let (mut app, window, _) = app(Transform::default());
+    app.add_systems(
+        Startup,
+        |mut commands: Commands, settings: Res<GridSettings>, style: Res<GridCursorStyle>| {
+            spawn_grid_cursor(&mut commands, &settings, &style);
+        },
+    );
+    let grid_cursor = |app: &mut App| {
+        let (cursor, transform, visibility) = app
+            .world
+            .query::<(&GridCursor, &Transform, &Visibility)>()
+            .single(&app.world);
+        (cursor.coord, transform.translation, *visibility)
+    };
+
+    set_cursor(&mut app, window, Some(Vec2::new(425.0, 305.0)));
+    app.update();
+    assert_eq!(
+        grid_cursor(&mut app),
+        (
+            Some(GridCoord::new(2, -1)),
+            Vec3::new(25.0, -5.0, 10.0),
+            Visibility::Visible
+        )
+    );
+
+    // A 100x40 panel around the origin covers world (25, -5)
+    let bar = app
+        .world
+        .spawn((
+            GridCursorBlocker,
+            GlobalTransform::from(Transform::from_scale(Vec3::new(100.0, 40.0, 1.0))),
+            Visibility::Visible,
+        ))
+        .id();
+    app.update();
+    assert_eq!(grid_cursor(&mut app).0, None);
+    assert_eq!(grid_cursor(&mut app).2, Visibility::Hidden);
+
+    // Hidden panels do not block
+    app.world.entity_mut(bar).insert(Visibility::Hidden);
+    app.update();
+    assert_eq!(grid_cursor(&mut app).0, Some(GridCoord::new(2, -1)));
+
+    app.world.despawn(bar);
+    app.world.resource_mut::<GridCursorStyle>().z = 3.0;
+    app.update();
+    assert_eq!(
+        grid_cursor(&mut app),
+        (
+            Some(GridCoord::new(2, -1)),
+            Vec3::new(25.0, -5.0, 3.0),
+            Visibility::Visible
+        )
+    );
+
+    set_cursor(&mut app, window, None);
+    app.update();
+    assert_eq!(grid_cursor(&mut app).0, None);
+    assert_eq!(grid_cursor(&mut app).2, Visibility::Hidden);
+}
+
+#[test]
+fn visible_cells_follow_zoom() {
+    let visible = |app: &App, window: Entity, camera: Entity| {
+        let world = &app.world;
+        visible_cells(
+            world.get::<Camera>(camera).unwrap(),
+            world.get::<GlobalTransform>(camera).unwrap(),
+            world.get::<Window>(window).unwrap(),
+            world.resource::<GridSettings>(),
+        )
+        .unwrap()
+    };
+    let cells = |min: (i32, i32), max: (i32, i32)| GridRect {
+        min: GridCoord::new(min.0, min.1),
+        max: GridCoord::new(max.0, max.1),
+    };
+
+    // Zoomed out 2x through the transform, 1600x1200 world units over 10 wide cells
+    let (mut zoomed_out, window, camera) = app(Transform::from_scale(Vec3::new(2.0, 2.0, 1.0)));
+    zoomed_out.update();
+    let rect = visible(&zoomed_out, window, camera);
+    assert_eq!((rect.width(), rect.height()), (160, 120));
+    assert_eq!(rect, cells((-80, -60), (79, 59)));
+
+    // Zoomed in 4x through the projection, off the cell edges a partial column shows up
+    let (mut app, window, camera) = app(Transform::from_xyz(5.0, 0.0, 0.0));
+    app.world
+        .get_mut::<OrthographicProjection>(camera)
+        .unwrap()
+        .scale = 0.25;
+    app.update();
+    let rect = visible(&app, window, camera);
+    assert_eq!((rect.width(), rect.height()), (21, 16));
+    assert_eq!(rect, cells((-10, -8), (10, 7)));
+}
+
+#[test]
+fn debug_labels_within_bounds() {
+    let (mut app, _, _) = app(Transform::default());
+    app.world.resource_mut::<GridSettings>().size = 100.0;
+    app.world.resource_mut::<GridDebug>().show_labels = true;
+    app.insert_resource(GridBounds {
+        min: GridCoord::new(-1, -1),
+        max: GridCoord::new(10, 0),
+    });
+    app.update();
+    app.update();
+
+    let mut labels: Vec<_> = app
+        .world
+        .query_filtered::<(&Text, &Visibility), With<GridLabel>>()
+        .iter(&app.world)
+        .filter(|(_, visibility)| **visibility != Visibility::Hidden)
+        .map(|(text, _)| text.sections[0].value.clone())
+        .collect();
+    labels.sort();
+    // Columns -1 to 3 of the bounds are in view
+    assert_eq!(labels.len(), 10);
+    assert!(labels.iter().all(|label| !label.starts_with("(4,")));
+}
+
+#[test]
+fn heatmap_cells_are_pooled() {
+    let (mut app, _, camera) = app(Transform::default());
+    app.world.resource_mut::<GridSettings>().size = 100.0;
+    app.world.resource_mut::<GridDebug>().show_heatmap = true;
+    let rect = GridRect {
+        min: GridCoord::new(-6, -2),
+        max: GridCoord::new(1, 1),
+    };
+    let mut values = DenseGridMap::new(rect, 0.0);
+    for coord in rect.iter() {
+        *values.get_mut(coord).unwrap() = (coord.0.x + coord.0.y) as f32;
+    }
+    app.insert_resource(values);
+    app.update();
+    app.update();
+
+    let cells = |app: &mut App| {
+        let mut cells: Vec<_> = app
+            .world
+            .query_filtered::<(&Transform, &Sprite, &Visibility), With<GridHeatmapCell>>()
+            .iter(&app.world)
+            .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+            .map(|(transform, sprite, _)| (transform.translation.truncate(), sprite.color))
+            .collect();
+        cells.sort_by(|a, b| a.0.x.total_cmp(&b.0.x).then(a.0.y.total_cmp(&b.0.y)));
+        cells
+    };
+    let pool = |app: &mut App| {
+        app.world
+            .query_filtered::<(), With<GridHeatmapCell>>()
+            .iter(&app.world)
+            .count()
+    };
+
+    // Columns -4 to 1 of the map are in the 800x600 view
+    let visible = cells(&mut app);
+    assert_eq!(visible.len(), 24);
+    let gradient = app.world.resource::<GridHeatmapSettings>().gradient.clone();
+    // Auto range over the whole map, -8 to 2
+    assert_eq!(
+        visible.last().unwrap(),
+        &(Vec2::new(150.0, 150.0), gradient[2])
+    );
+
+    // New values every frame reuse the same quads
+    for frame in 0..3 {
+        app.world
+            .resource_mut::<DenseGridMap<f32>>()
+            .fill(frame as f32);
+        app.update();
+        assert_eq!(pool(&mut app), 24);
+        assert!(cells(&mut app)
+            .iter()
+            .all(|(_, color)| *color == gradient[0]));
+    }
+
+    // Panning right leaves fewer map cells in view, the spare quads are hidden
+    *app.world.get_mut::<GlobalTransform>(camera).unwrap() =
+        Transform::from_xyz(300.0, 0.0, 0.0).into();
+    app.update();
+    assert_eq!(cells(&mut app).len(), 12);
+    assert_eq!(pool(&mut app), 24);
+
+    app.world.resource_mut::<GridDebug>().show_heatmap = false;
+    app.update();
+    assert_eq!(pool(&mut app), 0);
+}
@@ -0,0 +1,1459 @@
+use std::time::Duration;
+
+use bevy::{
+    asset::HandleId,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy_toolbox::{
+    animation::{Animation, AnimationCurve, AnimationPlugin, Animator, Repeat, ScalePulseLens},
+    grid::{
+        CursorGridCoord, CursorWorldPosition, GridCoord, GridLayer, GridSelection, GridSettings,
+        LayeredGridMap, RectMap, SubCellMap,
+    },
+    inventory::{
+        apply_inventory_images, apply_inventory_theme, click_select_slot, highlight_selected_slot,
+        inventory_panel_size, layout_slots, pulse_selected_slot, pulse_unlocked_slots,
+        relayout_inventory_panels, remove_finished_pulses, render_items_in_base_inventory,
+        reset_click_consumed, resize_base_inventory_bar, send_inventory_events,
+        sort_inventory_on_key, spawn_base_inventory, tick_slot_cooldowns, update_cooldown_overlays,
+        update_inventory_tooltip, update_selected_item_name, update_slot_backgrounds,
+        update_slot_numbers, ActiveInventory, BaseInventory, BaseInventoryBackground,
+        BaseInventorySettings, ClickConsumed, DynInventory, Inventory, InventoryChanged,
+        InventoryImage, InventoryItemName, InventorySettings, InventorySlot,
+        InventorySlotBackground, InventorySlotCooldown, InventorySlotHighlight,
+        InventorySlotNumber, InventoryStackCount, InventoryTooltip, SelectionChanged,
+        SlotCooldowns, SortSettings, SortStrategy,
+    },
+    items::{Item, ItemCode, ItemImage, ItemPreview, ItemRegistry},
+    nine_slice::{tint_nine_slice_pieces, NineSlice, NineSlicePiece},
+    place_selected_item, scroll_select_item, select_item, HotbarScrollSettings, InfinitePlacement,
+    InventoryKeyBindings, CLEAR_SELECTION_SLOT,
+};
+
+mod common;
+
+use common::{count_inventory_changes, inventory_app, item, InventoryChanges};
+
+fn app(settings: HotbarScrollSettings) -> App {
+    let mut app = App::new();
+    app.add_event::<MouseWheel>()
+        .init_resource::<Input<KeyCode>>()
+        .init_resource::<InventoryKeyBindings>()
+        .init_resource::<BaseInventory>()
+        .insert_resource(settings)
+        .add_systems(Update, scroll_select_item);
+    app
+}
+
+fn scroll(app: &mut App, unit: MouseScrollUnit, y: f32) -> Option<usize> {
+    app.world.send_event(MouseWheel {
+        unit,
+        x: 0.0,
+        y,
+        window: Entity::PLACEHOLDER,
+    });
+    app.update();
+    app.world.resource::<BaseInventory>().selected_slot()
+}
+
+#[test]
+fn wheel_notches_cycle_slots() {
+    let mut app = app(HotbarScrollSettings::default());
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Line, -1.0), Some(1));
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Line, -2.0), Some(3));
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Line, 3.0), Some(9));
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Line, -1.0), Some(1));
+}
+
+#[test]
+fn touchpad_deltas_accumulate() {
+    let mut app = app(HotbarScrollSettings {
+        invert: true,
+        include_none: true,
+        pixels_per_notch: 10.0,
+    });
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Pixel, 4.0), None);
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Pixel, 4.0), None);
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Pixel, 4.0), Some(1));
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Pixel, -25.0), Some(9));
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Pixel, 15.0), None);
+}
+
+#[test]
+fn number_keys_win_over_wheel() {
+    let mut app = app(HotbarScrollSettings::default());
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(4)
+        .unwrap();
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::Key4);
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Line, -1.0), Some(4));
+}
+
+fn highlight(app: &mut App) -> (Vec3, Visibility) {
+    app.update();
+    let (transform, visibility) = app
+        .world
+        .query_filtered::<(&Transform, &Visibility), With<InventorySlotHighlight>>()
+        .single(&app.world);
+    (transform.translation, *visibility)
+}
+
+fn slot_translation(app: &mut App, slot: usize) -> Vec3 {
+    app.world
+        .query::<(&InventorySlotBackground, &Transform)>()
+        .iter(&app.world)
+        .find(|(background, _)| background.slot == slot)
+        .unwrap()
+        .1
+        .translation
+}
+
+fn bar_app() -> App {
+    let mut app = inventory_app();
+    app.add_systems(Startup, spawn_base_inventory);
+    app
+}
+
+#[test]
+fn highlight_follows_selection() {
+    let mut app = bar_app();
+    app.add_systems(Update, highlight_selected_slot);
+
+    assert_eq!(highlight(&mut app).1, Visibility::Hidden);
+
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(3)
+        .unwrap();
+    let (translation, visibility) = highlight(&mut app);
+    let slot = slot_translation(&mut app, 3);
+    assert_eq!(visibility, Visibility::Visible);
+    assert_eq!(translation.truncate(), slot.truncate());
+    assert!(translation.z < slot.z);
+
+    // Follows the slot when the bar moves
+    let moved = app
+        .world
+        .query::<(&InventorySlotBackground, &mut Transform)>()
+        .iter_mut(&mut app.world)
+        .find(|(background, _)| background.slot == 3)
+        .map(|(_, mut transform)| {
+            transform.translation.y += 20.0;
+            transform.translation
+        })
+        .unwrap();
+    assert_eq!(highlight(&mut app).0.truncate(), moved.truncate());
+
+    app.world.resource_mut::<BaseInventory>().clear_selection();
+    assert_eq!(highlight(&mut app).1, Visibility::Hidden);
+}
+
+#[test]
+fn held_key_selects_once() {
+    let mut app = app(HotbarScrollSettings::default());
+    app.add_event::<SelectionChanged>()
+        .add_event::<InventoryChanged>()
+        .add_systems(Update, select_item)
+        .add_systems(PostUpdate, send_inventory_events);
+    let mut events = app
+        .world
+        .resource_mut::<Events<SelectionChanged>>()
+        .get_reader();
+
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::Key5);
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        app.update();
+        received.extend(
+            events
+                .iter(app.world.resource::<Events<SelectionChanged>>())
+                .copied(),
+        );
+    }
+    assert_eq!(
+        received,
+        [SelectionChanged {
+            inventory: None,
+            previous: 0,
+            current: 5
+        }]
+    );
+    assert!(!app.world.resource::<BaseInventory>().has_pending_events());
+}
+
+#[test]
+fn clicking_a_slot_selects_it() {
+    let mut app = bar_app();
+    app.init_resource::<Input<MouseButton>>()
+        .init_resource::<Input<KeyCode>>()
+        .init_resource::<CursorWorldPosition>()
+        .init_resource::<ClickConsumed>()
+        .init_resource::<InfinitePlacement>()
+        .insert_resource(CursorGridCoord(Some(GridCoord::new(0, 0))))
+        .insert_resource(GridSettings::from_u32(10))
+        .init_resource::<GridSelection>()
+        .init_resource::<LayeredGridMap<Entity>>()
+        .init_resource::<SubCellMap<Entity>>()
+        .init_resource::<RectMap<Entity>>()
+        .add_systems(First, reset_click_consumed)
+        .add_systems(
+            Update,
+            (
+                click_select_slot,
+                place_selected_item.after(click_select_slot),
+            ),
+        );
+    app.world
+        .spawn((ItemPreview, ItemCode(1), SpriteBundle::default()));
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(2, Item::new(ItemCode(1), 5, 64))
+        .unwrap();
+    app.update();
+
+    let placed = |app: &mut App| {
+        app.world
+            .query_filtered::<(), With<GridLayer>>()
+            .iter(&app.world)
+            .count()
+    };
+    let click = |app: &mut App, cursor: Vec2| {
+        app.world.resource_mut::<CursorWorldPosition>().0 = Some(cursor);
+        let mut mouse = app.world.resource_mut::<Input<MouseButton>>();
+        mouse.clear();
+        mouse.press(MouseButton::Left);
+        app.update();
+        app.world.resource_mut::<Input<MouseButton>>().reset_all();
+        (
+            app.world.resource::<BaseInventory>().selected_slot(),
+            app.world.resource::<ClickConsumed>().0,
+        )
+    };
+
+    // Slot 7 is empty, it is selected all the same
+    let slot = slot_translation(&mut app, 7).truncate();
+    assert_eq!(
+        click(&mut app, slot + Vec2::new(24.0, -24.0)),
+        (Some(7), true)
+    );
+
+    // Misses fall through to the world
+    assert_eq!(
+        click(&mut app, slot + Vec2::new(0.0, 29.0)),
+        (Some(7), false)
+    );
+    assert_eq!(click(&mut app, Vec2::ZERO), (Some(7), false));
+
+    let slot = slot_translation(&mut app, 2).truncate();
+    assert_eq!(click(&mut app, slot), (Some(2), true));
+    assert_eq!(placed(&mut app), 0);
+
+    // The gap to the next slot and the padding above are part of the bar
+    assert_eq!(
+        click(&mut app, slot + Vec2::new(27.0, 0.0)),
+        (Some(2), true)
+    );
+    assert_eq!(
+        click(&mut app, slot + Vec2::new(0.0, 26.5)),
+        (Some(2), true)
+    );
+    assert_eq!(placed(&mut app), 0);
+
+    // Off the bar the selected item is placed
+    assert_eq!(
+        click(&mut app, slot + Vec2::new(0.0, 29.0)),
+        (Some(2), false)
+    );
+    assert_eq!(placed(&mut app), 1);
+
+    // Reset on the next frame
+    app.update();
+    assert!(!app.world.resource::<ClickConsumed>().0);
+}
+
+/// A hotbar smaller than the default one
+type FourSlots = Inventory<4>;
+
+#[test]
+fn keys_only_select_slots_of_a_smaller_bar() {
+    let mut app = bar_app();
+    app.insert_resource(BaseInventory(FourSlots::default().0))
+        .init_resource::<Input<KeyCode>>()
+        .add_systems(Update, select_item);
+    app.update();
+    assert_eq!(
+        app.world
+            .query::<&InventorySlotBackground>()
+            .iter(&app.world)
+            .count(),
+        4
+    );
+
+    let press = |app: &mut App, key: KeyCode| {
+        let mut input = app.world.resource_mut::<Input<KeyCode>>();
+        input.reset_all();
+        input.press(key);
+        app.update();
+        app.world.resource::<BaseInventory>().selected_slot()
+    };
+    assert_eq!(press(&mut app, KeyCode::Key4), Some(4));
+    assert_eq!(press(&mut app, KeyCode::Key5), Some(4));
+    assert_eq!(press(&mut app, KeyCode::Key0), None);
+}
+
+#[test]
+fn rebound_keys_select_and_label_slots() {
+    let mut app = bar_app();
+    app.init_resource::<Input<KeyCode>>()
+        .add_systems(Update, (select_item, update_slot_numbers));
+    app.update();
+
+    let mut bindings = app.world.resource_mut::<InventoryKeyBindings>();
+    bindings.rebind(2, KeyCode::Q);
+    assert_eq!(bindings.binding_for(2), Some(KeyCode::Q));
+    assert_eq!(bindings.slot_for(KeyCode::Key2), None);
+
+    let press = |app: &mut App, key: KeyCode| {
+        let mut input = app.world.resource_mut::<Input<KeyCode>>();
+        input.reset_all();
+        input.press(key);
+        app.update();
+        app.world.resource::<BaseInventory>().selected_slot()
+    };
+    assert_eq!(press(&mut app, KeyCode::Q), Some(2));
+    assert_eq!(press(&mut app, KeyCode::Key3), Some(3));
+    assert_eq!(press(&mut app, KeyCode::Key2), Some(3));
+
+    let label = |app: &mut App, slot: usize| {
+        app.world
+            .query::<(&InventorySlotNumber, &Text, &Visibility)>()
+            .iter(&app.world)
+            .find(|(number, ..)| number.slot == slot)
+            .map(|(_, text, visibility)| (text.sections[0].value.clone(), *visibility))
+            .unwrap()
+    };
+    assert_eq!(label(&mut app, 2), ("Q".to_string(), Visibility::Visible));
+    assert_eq!(label(&mut app, 3), ("3".to_string(), Visibility::Visible));
+
+    // Taking the key of slot 2 leaves it without one
+    app.world
+        .resource_mut::<InventoryKeyBindings>()
+        .rebind(4, KeyCode::Q);
+    app.update();
+    assert_eq!(label(&mut app, 2), (String::new(), Visibility::Hidden));
+    assert_eq!(label(&mut app, 4), ("Q".to_string(), Visibility::Visible));
+}
+
+#[test]
+fn bar_spawns_inventory_slot_count() {
+    let mut app = bar_app();
+    app.world.resource_mut::<BaseInventory>().grow(3);
+    app.update();
+    let slots: Vec<_> = app
+        .world
+        .query::<&InventorySlotBackground>()
+        .iter(&app.world)
+        .map(|background| background.slot)
+        .collect();
+    assert_eq!(slots.len(), 12);
+    assert!((1..=12).all(|slot| slots.contains(&slot)));
+}
+
+#[test]
+fn only_the_active_inventory_is_selected() {
+    let mut app = app(HotbarScrollSettings::default());
+    app.init_resource::<ActiveInventory>()
+        .add_event::<SelectionChanged>()
+        .add_event::<InventoryChanged>()
+        .add_systems(Update, select_item.before(scroll_select_item))
+        .add_systems(PostUpdate, send_inventory_events);
+    let first = app.world.spawn(DynInventory::with_capacity(4)).id();
+    let second = app.world.spawn(DynInventory::with_capacity(4)).id();
+    let mut events = app
+        .world
+        .resource_mut::<Events<SelectionChanged>>()
+        .get_reader();
+    let selected = |app: &App, entity| {
+        app.world
+            .get::<DynInventory>(entity)
+            .unwrap()
+            .selected_slot()
+    };
+
+    app.world.resource_mut::<ActiveInventory>().0 = Some(second);
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::Key3);
+    app.update();
+    app.world.resource_mut::<Input<KeyCode>>().reset_all();
+    assert_eq!(selected(&app, first), None);
+    assert_eq!(selected(&app, second), Some(3));
+    assert_eq!(app.world.resource::<BaseInventory>().selected_slot(), None);
+
+    app.world.resource_mut::<ActiveInventory>().0 = Some(first);
+    assert_eq!(scroll(&mut app, MouseScrollUnit::Line, -1.0), None);
+    assert_eq!(selected(&app, first), Some(1));
+    assert_eq!(selected(&app, second), Some(3));
+
+    let received: Vec<_> = events
+        .iter(app.world.resource::<Events<SelectionChanged>>())
+        .copied()
+        .collect();
+    assert_eq!(
+        received,
+        [(second, 3), (first, 1)].map(|(inventory, current)| SelectionChanged {
+            inventory: Some(inventory),
+            previous: 0,
+            current,
+        })
+    );
+}
+
+fn slot_image(app: &mut App, slot: usize) -> (Handle<Image>, Visibility) {
+    app.update();
+    let (_, image, visibility) = app
+        .world
+        .query::<(&InventorySlot, &Handle<Image>, &Visibility)>()
+        .iter(&app.world)
+        .find(|(inventory_slot, ..)| inventory_slot.slot == slot)
+        .unwrap();
+    (image.clone(), *visibility)
+}
+
+#[test]
+fn removed_items_hide_their_slot() {
+    let mut app = bar_app();
+    app.add_systems(Update, render_items_in_base_inventory);
+    let images: Vec<_> = (1..=2)
+        .map(|code| {
+            let image = Handle::weak(HandleId::random::<Image>());
+            app.world
+                .spawn((ItemPreview, ItemCode(code), ItemImage(image.clone())));
+            image
+        })
+        .collect();
+
+    assert_eq!(slot_image(&mut app, 2).1, Visibility::Hidden);
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(2, item(1))
+        .unwrap();
+    assert_eq!(
+        slot_image(&mut app, 2),
+        (images[0].clone(), Visibility::Visible)
+    );
+
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    inventory.remove_item(2).unwrap();
+    inventory.put_item(2, item(2)).unwrap();
+    assert_eq!(
+        slot_image(&mut app, 2),
+        (images[1].clone(), Visibility::Visible)
+    );
+
+    app.world
+        .resource_mut::<BaseInventory>()
+        .remove_item(2)
+        .unwrap();
+    assert_eq!(
+        slot_image(&mut app, 2),
+        (Handle::default(), Visibility::Hidden)
+    );
+}
+
+#[derive(Resource, Default)]
+struct SlotWrites(usize);
+
+#[allow(clippy::type_complexity)]
+fn count_slot_writes(
+    slots: Query<(Ref<Handle<Image>>, Ref<Visibility>), With<InventorySlot>>,
+    mut writes: ResMut<SlotWrites>,
+) {
+    if slots.iter().any(|(image, visibility)| {
+        (image.is_changed() && !image.is_added())
+            || (visibility.is_changed() && !visibility.is_added())
+    }) {
+        writes.0 += 1;
+    }
+}
+
+#[test]
+fn slots_render_only_when_the_inventory_changes() {
+    let mut app = bar_app();
+    app.init_resource::<SlotWrites>().add_systems(
+        Update,
+        (render_items_in_base_inventory, count_slot_writes).chain(),
+    );
+    app.world.spawn((
+        ItemPreview,
+        ItemCode(1),
+        ItemImage(Handle::weak(HandleId::random::<Image>())),
+    ));
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(1, Item::new(ItemCode(1), 1, 1))
+        .unwrap();
+
+    // Slots spawned after the inventory changed still show its items, the write on the
+    // frame they spawn is not counted
+    assert_eq!(slot_image(&mut app, 1).1, Visibility::Visible);
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<SlotWrites>().0, 0);
+
+    // Left alone while the inventory stays as it is
+    let (slot, _) = app
+        .world
+        .query::<(Entity, &InventorySlot)>()
+        .iter(&app.world)
+        .find(|(_, slot)| slot.slot == 1)
+        .unwrap();
+    *app.world.get_mut::<Visibility>(slot).unwrap() = Visibility::Hidden;
+    app.update();
+    assert_eq!(slot_image(&mut app, 1).1, Visibility::Hidden);
+    let writes = app.world.resource::<SlotWrites>().0;
+
+    app.world
+        .resource_mut::<BaseInventory>()
+        .remove_item(1)
+        .unwrap();
+    app.update();
+    app.update();
+    assert_eq!(app.world.resource::<SlotWrites>().0, writes + 1);
+}
+
+fn stack_count(app: &mut App, slot: usize) -> (String, Visibility) {
+    app.update();
+    let children = app
+        .world
+        .query::<(&InventorySlot, &Children)>()
+        .iter(&app.world)
+        .find(|(inventory_slot, _)| inventory_slot.slot == slot)
+        .unwrap()
+        .1
+        .to_vec();
+    let (text, visibility) = app
+        .world
+        .query_filtered::<(&Text, &Visibility), With<InventoryStackCount>>()
+        .get(&app.world, children[0])
+        .unwrap();
+    (text.sections[0].value.clone(), *visibility)
+}
+
+#[test]
+fn stack_counts_show_above_one() {
+    let mut app = bar_app();
+    app.add_systems(Update, render_items_in_base_inventory);
+    app.world.spawn((
+        ItemPreview,
+        ItemCode(1),
+        ItemImage(Handle::weak(HandleId::random::<Image>())),
+    ));
+
+    assert_eq!(stack_count(&mut app, 4).1, Visibility::Hidden);
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(4, Item::new(ItemCode(1), 12, 64))
+        .unwrap();
+    assert_eq!(
+        stack_count(&mut app, 4),
+        ("12".to_string(), Visibility::Inherited)
+    );
+
+    app.world
+        .resource_mut::<BaseInventory>()
+        .take_one(4)
+        .unwrap();
+    assert_eq!(stack_count(&mut app, 4).0, "11");
+
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    let mut item = inventory.remove_item(4).unwrap();
+    item.count = 1;
+    inventory.put_item(4, item).unwrap();
+    assert_eq!(stack_count(&mut app, 4).1, Visibility::Hidden);
+}
+
+#[test]
+fn slot_numbers_sit_in_their_slot_corner() {
+    let mut app = bar_app();
+    app.add_systems(Update, update_slot_numbers);
+    app.world.resource_mut::<BaseInventory>().grow(2);
+    app.update();
+
+    let mut numbers: Vec<_> = app
+        .world
+        .query::<(&InventorySlotNumber, &Text, &Transform, &Visibility)>()
+        .iter(&app.world)
+        .map(|(number, text, transform, visibility)| {
+            (
+                number.slot,
+                text.sections[0].value.clone(),
+                transform.translation,
+                *visibility,
+            )
+        })
+        .collect();
+    numbers.sort_by_key(|(slot, ..)| *slot);
+    // Slots past 9 have no key
+    assert_eq!(numbers.len(), 11);
+    assert!(numbers[9..]
+        .iter()
+        .all(|(_, text, _, visibility)| text.is_empty() && *visibility == Visibility::Hidden));
+    let (slot, text, translation, visibility) = numbers[2].clone();
+    assert_eq!(
+        (slot, text.as_str(), visibility),
+        (3, "3", Visibility::Visible)
+    );
+    let background = slot_translation(&mut app, 3);
+    // Top left, inset by the 2.0 slot margin of the 50.0 slot
+    assert_eq!(
+        translation.truncate(),
+        background.truncate() + Vec2::new(-23.0, 23.0)
+    );
+
+    app.world
+        .resource_mut::<BaseInventorySettings>()
+        .show_slot_numbers = false;
+    app.update();
+    assert!(app
+        .world
+        .query_filtered::<&Visibility, With<InventorySlotNumber>>()
+        .iter(&app.world)
+        .all(|visibility| *visibility == Visibility::Hidden));
+}
+
+#[test]
+fn tooltip_shows_after_hovering_a_filled_slot() {
+    let mut app = bar_app();
+    app.init_resource::<Time>()
+        .init_resource::<CursorWorldPosition>()
+        .init_resource::<ItemRegistry>()
+        .add_systems(Update, update_inventory_tooltip);
+    let mut now = app.world.resource_mut::<Time>().startup();
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(2, Item::new(ItemCode(7), 1, 1))
+        .unwrap();
+    app.update();
+
+    let mut hover = |app: &mut App, slot: usize, millis: u64| {
+        let cursor = slot_translation(app, slot).truncate();
+        app.world.resource_mut::<CursorWorldPosition>().0 = Some(cursor);
+        now += Duration::from_millis(millis);
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+        let (transform, visibility, children) = app
+            .world
+            .query_filtered::<(&Transform, &Visibility, &Children), With<InventoryTooltip>>()
+            .single(&app.world);
+        let text = app.world.get::<Text>(children[0]).unwrap();
+        (
+            transform.translation,
+            *visibility,
+            text.sections[0].value.clone(),
+        )
+    };
+
+    // The delay counts from the first frame on the slot
+    assert_eq!(hover(&mut app, 2, 100).1, Visibility::Hidden);
+    assert_eq!(hover(&mut app, 2, 100).1, Visibility::Hidden);
+    let (translation, visibility, text) = hover(&mut app, 2, 200);
+    assert_eq!(
+        (visibility, text.as_str()),
+        (Visibility::Visible, "Item #7")
+    );
+    let slot = slot_translation(&mut app, 2);
+    assert_eq!(translation.x, slot.x);
+    assert!(translation.y > slot.y + 25.0);
+
+    // Empty slots have no tooltip, coming back waits again
+    assert_eq!(hover(&mut app, 3, 400).1, Visibility::Hidden);
+    assert_eq!(hover(&mut app, 2, 400).1, Visibility::Hidden);
+    assert_eq!(hover(&mut app, 2, 400).1, Visibility::Visible);
+}
+
+#[test]
+fn cooldown_overlay_shrinks_with_the_cooldown() {
+    let mut app = bar_app();
+    app.init_resource::<Time>()
+        .init_resource::<SlotCooldowns>()
+        .add_systems(
+            Update,
+            (tick_slot_cooldowns, update_cooldown_overlays).chain(),
+        );
+    let mut now = app.world.resource_mut::<Time>().startup();
+    app.world.resource_mut::<Time>().update_with_instant(now);
+    app.update();
+    app.world
+        .resource_mut::<SlotCooldowns>()
+        .start_cooldown(None, 5, Duration::from_secs(2));
+
+    let mut overlay = |app: &mut App, millis: u64| {
+        now += Duration::from_millis(millis);
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+        let (_, transform, visibility) = app
+            .world
+            .query::<(&InventorySlotCooldown, &Transform, &Visibility)>()
+            .iter(&app.world)
+            .find(|(overlay, ..)| overlay.slot == 5)
+            .unwrap();
+        (*transform, *visibility)
+    };
+
+    let (transform, visibility) = overlay(&mut app, 500);
+    let slot = slot_translation(&mut app, 5);
+    assert_eq!(visibility, Visibility::Visible);
+    assert_eq!(transform.scale, Vec3::new(50.0, 37.5, 1.0));
+    // Filled from the bottom of the slot
+    assert_eq!(
+        transform.translation.truncate(),
+        slot.truncate() - Vec2::Y * 25.0
+    );
+    assert_eq!(overlay(&mut app, 1000).0.scale.y, 12.5);
+    assert_eq!(overlay(&mut app, 500).1, Visibility::Hidden);
+}
+
+#[test]
+fn numpad_digits_select_like_the_number_row() {
+    let mut app = bar_app();
+    app.init_resource::<Input<KeyCode>>()
+        .add_systems(Update, select_item);
+    app.update();
+
+    let press = |app: &mut App, key: KeyCode| {
+        let mut input = app.world.resource_mut::<Input<KeyCode>>();
+        input.reset_all();
+        input.press(key);
+        app.update();
+        app.world.resource::<BaseInventory>().selected_slot()
+    };
+    assert_eq!(press(&mut app, KeyCode::Numpad4), Some(4));
+    // Zero clears the selection instead of selecting a slot 0
+    assert_eq!(
+        app.world
+            .resource::<InventoryKeyBindings>()
+            .slot_for(KeyCode::Key0),
+        Some(CLEAR_SELECTION_SLOT)
+    );
+    assert_eq!(press(&mut app, KeyCode::Numpad0), None);
+    assert_eq!(press(&mut app, KeyCode::Numpad7), Some(7));
+    assert_eq!(press(&mut app, KeyCode::Key0), None);
+
+    // Numpad digits keep selecting their own slot when the number row is rebound
+    app.world
+        .resource_mut::<InventoryKeyBindings>()
+        .rebind(2, KeyCode::Key5);
+    assert_eq!(press(&mut app, KeyCode::Key5), Some(2));
+    assert_eq!(press(&mut app, KeyCode::Numpad5), Some(5));
+    assert_eq!(press(&mut app, KeyCode::Numpad2), Some(2));
+
+    // A bound numpad key selects its bound slot
+    app.world
+        .resource_mut::<InventoryKeyBindings>()
+        .rebind(8, KeyCode::Numpad3);
+    assert_eq!(press(&mut app, KeyCode::Numpad3), Some(8));
+
+    app.world.resource_mut::<InventoryKeyBindings>().numpad = false;
+    assert_eq!(press(&mut app, KeyCode::Numpad4), Some(8));
+    assert_eq!(press(&mut app, KeyCode::Numpad3), Some(8));
+    assert_eq!(press(&mut app, KeyCode::Key1), Some(1));
+    assert_eq!(press(&mut app, KeyCode::Numpad3), Some(8));
+}
+
+#[test]
+fn holding_a_key_selects_once() {
+    let mut app = bar_app();
+    app.init_resource::<Input<KeyCode>>()
+        .init_resource::<InventoryChanges>()
+        .add_systems(Update, (select_item, count_inventory_changes).chain());
+    app.update();
+    app.world.resource_mut::<InventoryChanges>().0 = 0;
+
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::Key3);
+    for _ in 0..5 {
+        app.update();
+        app.world.resource_mut::<Input<KeyCode>>().clear();
+    }
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(3)
+    );
+    assert_eq!(app.world.resource::<InventoryChanges>().0, 1);
+
+    // Pressing the selected slot again changes nothing
+    let mut input = app.world.resource_mut::<Input<KeyCode>>();
+    input.reset_all();
+    input.press(KeyCode::Numpad3);
+    app.update();
+    assert_eq!(app.world.resource::<InventoryChanges>().0, 1);
+
+    // The lowest of the slots pressed together wins
+    let mut input = app.world.resource_mut::<Input<KeyCode>>();
+    input.reset_all();
+    input.press(KeyCode::Key6);
+    input.press(KeyCode::Key5);
+    app.update();
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(5)
+    );
+}
+
+fn slot_scale(app: &mut App, slot: usize) -> (f32, bool) {
+    let (_, transform, pulse) = app
+        .world
+        .query::<(
+            &InventorySlotBackground,
+            &Transform,
+            Option<&Animator<ScalePulseLens>>,
+        )>()
+        .iter(&app.world)
+        .find(|(background, ..)| background.slot == slot)
+        .unwrap();
+    (transform.scale.x, pulse.is_some())
+}
+
+#[test]
+fn selected_slot_pulses_once() {
+    let mut app = bar_app();
+    app.add_plugins(AnimationPlugin::default())
+        .init_resource::<Time>()
+        .add_event::<SelectionChanged>()
+        .add_event::<InventoryChanged>()
+        .add_systems(
+            PostUpdate,
+            (
+                send_inventory_events,
+                pulse_selected_slot.after(send_inventory_events),
+                pulse_unlocked_slots.after(send_inventory_events),
+                remove_finished_pulses
+                    .before(pulse_selected_slot)
+                    .before(pulse_unlocked_slots),
+            ),
+        );
+    let mut now = app.world.resource_mut::<Time>().startup();
+    let mut step = |app: &mut App, millis: u64| {
+        now += Duration::from_millis(millis);
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+    };
+    step(&mut app, 0);
+
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(3)
+        .unwrap();
+    step(&mut app, 0);
+    assert_eq!(slot_scale(&mut app, 3), (50.0, true));
+    assert_eq!(slot_scale(&mut app, 4), (50.0, false));
+
+    step(&mut app, 100);
+    let (scale, pulsing) = slot_scale(&mut app, 3);
+    assert!(pulsing);
+    assert!((scale - 57.5).abs() < 1e-3, "{scale}");
+
+    // Finishes at the slot size and leaves no animator behind
+    step(&mut app, 150);
+    assert_eq!(slot_scale(&mut app, 3), (50.0, false));
+
+    // A pulse restarting in the frame the last one finishes keeps running
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(2)
+        .unwrap();
+    step(&mut app, 0);
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    inventory.lock_slot(2).unwrap();
+    inventory.unlock_slot(2).unwrap();
+    step(&mut app, 250);
+    assert_eq!(slot_scale(&mut app, 2), (50.0, true));
+    step(&mut app, 100);
+    let (scale, pulsing) = slot_scale(&mut app, 2);
+    assert!(pulsing);
+    assert!((scale - 57.5).abs() < 1e-3, "{scale}");
+    step(&mut app, 150);
+    assert_eq!(slot_scale(&mut app, 2), (50.0, false));
+
+    // Disabled pulses leave the slots alone
+    app.world
+        .resource_mut::<BaseInventorySettings>()
+        .pulse_selected = false;
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(4)
+        .unwrap();
+    step(&mut app, 0);
+    step(&mut app, 100);
+    assert_eq!(slot_scale(&mut app, 4), (50.0, false));
+
+    // Scale pulses that are not slot pulses are left alone, even with the id of one
+    let background = app
+        .world
+        .query::<(Entity, &InventorySlotBackground)>()
+        .iter(&app.world)
+        .find(|(_, background)| background.slot == 5)
+        .unwrap()
+        .0;
+    app.world.entity_mut(background).insert(
+        Animator::new(
+            Animation {
+                duration: Duration::from_millis(100),
+                curve: AnimationCurve::Linear,
+            },
+            Repeat::Once,
+            ScalePulseLens {
+                base: Vec3::new(50.0, 50.0, 1.0),
+                peak: 1.2,
+            },
+        )
+        .with_id(0),
+    );
+    step(&mut app, 0);
+    step(&mut app, 150);
+    assert_eq!(slot_scale(&mut app, 5), (50.0, true));
+}
+
+#[test]
+fn locked_slots_are_tinted_and_ignore_their_key() {
+    let mut app = bar_app();
+    app.init_resource::<Input<KeyCode>>()
+        .add_systems(Update, (select_item, update_slot_backgrounds));
+    app.update();
+    app.world
+        .resource_mut::<BaseInventory>()
+        .lock_slot(6)
+        .unwrap();
+
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::Key6);
+    app.update();
+    assert_eq!(app.world.resource::<BaseInventory>().selected_slot(), None);
+
+    let slot = |app: &mut App, slot: usize| {
+        let (_, sprite, children) = app
+            .world
+            .query::<(&InventorySlotBackground, &Sprite, &Children)>()
+            .iter(&app.world)
+            .find(|(background, ..)| background.slot == slot)
+            .unwrap();
+        let color = sprite.color;
+        let lock = app.world.get::<Visibility>(children[0]).copied().unwrap();
+        (color, lock)
+    };
+    let locked_color = InventorySettings::default().locked_slot_color;
+    assert_eq!(slot(&mut app, 6), (locked_color, Visibility::Inherited));
+    assert_ne!(slot(&mut app, 5).0, locked_color);
+    assert_eq!(slot(&mut app, 5).1, Visibility::Hidden);
+
+    app.world
+        .resource_mut::<BaseInventory>()
+        .unlock_slot(6)
+        .unwrap();
+    app.world.resource_mut::<Input<KeyCode>>().reset_all();
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::Key6);
+    app.update();
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(6)
+    );
+    assert_eq!(slot(&mut app, 6), slot(&mut app, 5));
+}
+
+#[test]
+fn selected_item_name_shows_under_its_slot() {
+    let mut app = bar_app();
+    app.init_resource::<ItemRegistry>()
+        .add_systems(Update, update_selected_item_name);
+    let mut registry = app.world.resource_mut::<ItemRegistry>();
+    registry.register(ItemCode(1), "Plank");
+    registry.register(ItemCode(2), "Reinforced oak plank of the north");
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    for (slot, code) in [(1, 1), (2, 2), (3, 9)] {
+        inventory.put_item(slot, item(code)).unwrap();
+    }
+
+    let label = |app: &mut App, slot: usize| {
+        app.world
+            .resource_mut::<BaseInventory>()
+            .select_item(slot)
+            .unwrap();
+        app.update();
+        let (text, transform, visibility) = app
+            .world
+            .query_filtered::<(&Text, &Transform, &Visibility), With<InventoryItemName>>()
+            .single(&app.world);
+        (
+            text.sections[0].value.clone(),
+            transform.translation,
+            *visibility,
+        )
+    };
+
+    let (text, translation, visibility) = label(&mut app, 1);
+    let slot = slot_translation(&mut app, 1);
+    assert_eq!((text.as_str(), visibility), ("Plank", Visibility::Visible));
+    assert_eq!(translation.x, slot.x);
+    assert!(translation.y < slot.y - 25.0);
+
+    // Cut to the default budget of 19 characters
+    let (text, ..) = label(&mut app, 2);
+    assert_eq!(text, "Reinforced oak pla…");
+    assert_eq!(label(&mut app, 3).0, "Item #9");
+
+    for slot in [4, 0] {
+        let (text, _, visibility) = label(&mut app, slot);
+        assert_eq!((text.as_str(), visibility), ("", Visibility::Hidden));
+    }
+
+    // Restyled while hidden
+    let mut settings = app.world.resource_mut::<BaseInventorySettings>();
+    settings.item_name_font_size = 20.0;
+    settings.item_name_color = Color::RED;
+    app.update();
+    let style = |app: &mut App| {
+        let style = &app
+            .world
+            .query_filtered::<&Text, With<InventoryItemName>>()
+            .single(&app.world)
+            .sections[0]
+            .style;
+        (style.font_size, style.color)
+    };
+    assert_eq!(style(&mut app), (20.0, Color::RED));
+    assert_eq!(label(&mut app, 1).2, Visibility::Visible);
+    assert_eq!(style(&mut app), (20.0, Color::RED));
+}
+
+#[test]
+fn selected_item_name_spawns_styled() {
+    let mut app = bar_app();
+    app.world
+        .resource_mut::<BaseInventorySettings>()
+        .item_name_color = Color::GREEN;
+    app.update();
+    let settings = app.world.resource::<BaseInventorySettings>().0.clone();
+    let style = app
+        .world
+        .query_filtered::<&Text, With<InventoryItemName>>()
+        .single(&app.world)
+        .sections[0]
+        .style
+        .clone();
+    assert_eq!(
+        (style.font, style.font_size, style.color),
+        (
+            settings.count_font,
+            settings.item_name_font_size,
+            Color::GREEN
+        )
+    );
+}
+
+#[test]
+fn sort_key_sorts_past_the_kept_slots() {
+    let mut app = app(HotbarScrollSettings::default());
+    app.insert_resource(SortSettings {
+        strategy: SortStrategy::ByCode,
+        keep_slots: 1,
+    })
+    .add_systems(Update, sort_inventory_on_key);
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    for (slot, code) in [(1, 9), (3, 5), (4, 2)] {
+        inventory.put_item(slot, item(code)).unwrap();
+    }
+
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::R);
+    app.update();
+    let codes: Vec<_> = app
+        .world
+        .resource::<BaseInventory>()
+        .iter_items()
+        .map(|(slot, item)| (slot, item.code.0))
+        .collect();
+    assert_eq!(codes, [(1, 9), (2, 2), (3, 5)]);
+}
+
+#[test]
+fn theme_changes_restyle_the_bar() {
+    let mut app = bar_app();
+    app.add_systems(
+        Update,
+        (
+            apply_inventory_theme,
+            update_slot_backgrounds,
+            highlight_selected_slot,
+        ),
+    );
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(2)
+        .unwrap();
+    app.update();
+
+    let panel = |app: &mut App| {
+        let (sprite, transform) = app
+            .world
+            .query_filtered::<(&Sprite, &Transform), With<BaseInventoryBackground>>()
+            .single(&app.world);
+        (sprite.color, transform.translation.z)
+    };
+    let slot = |app: &mut App, slot: usize| {
+        let (_, sprite, transform) = app
+            .world
+            .query::<(&InventorySlotBackground, &Sprite, &Transform)>()
+            .iter(&app.world)
+            .find(|(background, ..)| background.slot == slot)
+            .unwrap();
+        (sprite.color, transform.translation.z)
+    };
+    let item_z = |app: &mut App| {
+        app.world
+            .query_filtered::<&Transform, With<InventorySlot>>()
+            .iter(&app.world)
+            .next()
+            .unwrap()
+            .translation
+            .z
+    };
+
+    // The default look
+    let slot_color = Color::rgba(0.9, 0.9, 0.9, 1.0);
+    assert_eq!(panel(&mut app), (Color::GRAY, 42.0));
+    assert_eq!(slot(&mut app, 1), (slot_color, 43.0));
+    assert_eq!(slot(&mut app, 2), (slot_color, 43.0));
+    assert_eq!(highlight(&mut app).0.z, 42.5);
+    assert_eq!(item_z(&mut app), 44.0);
+
+    let mut settings = app.world.resource_mut::<BaseInventorySettings>();
+    settings.background_color = Color::BLACK;
+    settings.slot_color = Color::BLUE;
+    settings.selected_slot_color = Color::YELLOW;
+    settings.base_z = 10.0;
+    settings.z_offsets.item = 5.0;
+    app.update();
+    assert_eq!(panel(&mut app), (Color::BLACK, 10.0));
+    assert_eq!(slot(&mut app, 1), (Color::BLUE, 11.0));
+    assert_eq!(slot(&mut app, 2), (Color::YELLOW, 11.0));
+    assert_eq!(highlight(&mut app).0.z, 10.5);
+    assert_eq!(item_z(&mut app), 15.0);
+
+    // The selected color follows the selection
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(1)
+        .unwrap();
+    app.update();
+    assert_eq!(slot(&mut app, 1).0, Color::YELLOW);
+    assert_eq!(slot(&mut app, 2).0, Color::BLUE);
+}
+
+#[test]
+fn images_texture_the_bar() {
+    let mut app = bar_app();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Image>()
+        .add_systems(
+            Update,
+            (
+                update_slot_backgrounds,
+                apply_inventory_images.after(update_slot_backgrounds),
+                tint_nine_slice_pieces.after(apply_inventory_images),
+            ),
+        );
+    let mut images = app.world.resource_mut::<Assets<Image>>();
+    let image = |color: [u8; 4]| {
+        Image::new_fill(
+            Extent3d {
+                width: 16,
+                height: 16,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &color,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    };
+    let frame = images.add(image([255, 0, 0, 255]));
+    let slot_frame = images.add(image([0, 255, 0, 255]));
+    let mut settings = app.world.resource_mut::<BaseInventorySettings>();
+    settings.background_image = Some(InventoryImage {
+        source: frame.clone().into(),
+        slice: Some(NineSlice::uniform(4.0)),
+    });
+    settings.slot_image = Some(InventoryImage {
+        source: slot_frame.clone().into(),
+        slice: None,
+    });
+    app.update();
+    app.update();
+
+    let panel = |app: &mut App| {
+        let (entity, sprite, texture) = app
+            .world
+            .query_filtered::<(Entity, &Sprite, &Handle<Image>), With<BaseInventoryBackground>>()
+            .single(&app.world);
+        (entity, sprite.custom_size, texture.clone())
+    };
+    let pieces = |app: &mut App, parent: Entity| {
+        app.world
+            .query_filtered::<(&Parent, &Sprite, &Handle<Image>), With<NineSlicePiece>>()
+            .iter(&app.world)
+            .filter(|(piece_parent, ..)| piece_parent.get() == parent)
+            .map(|(_, sprite, texture)| (sprite.color, texture.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    // The panel draws its image in pieces, the slots stretch theirs
+    let (entity, size, texture) = panel(&mut app);
+    assert_eq!(size, Some(Vec2::ZERO));
+    assert_eq!(texture, Handle::default());
+    let panel_pieces = pieces(&mut app, entity);
+    assert_eq!(panel_pieces.len(), 9);
+    assert!(panel_pieces
+        .iter()
+        .all(|piece| *piece == (Color::GRAY, frame.clone())));
+    for (_, sprite, texture) in app
+        .world
+        .query::<(&InventorySlotBackground, &Sprite, &Handle<Image>)>()
+        .iter(&app.world)
+    {
+        assert_eq!(sprite.custom_size, Some(Vec2::ONE));
+        assert_eq!(*texture, slot_frame);
+    }
+
+    // The pieces are tinted with the panel
+    app.world
+        .resource_mut::<BaseInventorySettings>()
+        .background_color = Color::BLACK;
+    app.add_systems(Update, apply_inventory_theme.before(apply_inventory_images));
+    app.update();
+    app.update();
+    let panel_pieces = pieces(&mut app, entity);
+    assert_eq!(panel_pieces.len(), 9);
+    assert!(panel_pieces.iter().all(|(color, _)| *color == Color::BLACK));
+
+    // And go away with the image
+    app.world
+        .resource_mut::<BaseInventorySettings>()
+        .background_image = None;
+    app.update();
+    assert_eq!(panel(&mut app), (entity, None, Handle::default()));
+    assert!(pieces(&mut app, entity).is_empty());
+}
+
+#[test]
+fn bar_follows_the_slot_count() {
+    let mut app = bar_app();
+    app.add_systems(Update, resize_base_inventory_bar);
+    app.update();
+
+    let bar = |app: &mut App| {
+        let (entity, transform) = app
+            .world
+            .query_filtered::<(Entity, &Transform), With<BaseInventoryBackground>>()
+            .single(&app.world);
+        (entity, transform.translation)
+    };
+    let count = |app: &mut App, base: Entity| {
+        let backgrounds = app
+            .world
+            .query::<&InventorySlotBackground>()
+            .iter(&app.world)
+            .filter(|background| background.base == base)
+            .count();
+        let slots = app
+            .world
+            .query::<&InventorySlot>()
+            .iter(&app.world)
+            .filter(|slot| slot.base == base)
+            .count();
+        let cooldowns = app
+            .world
+            .query::<&InventorySlotCooldown>()
+            .iter(&app.world)
+            .filter(|cooldown| cooldown.base == base)
+            .count();
+        let numbers = app
+            .world
+            .query::<&InventorySlotNumber>()
+            .iter(&app.world)
+            .filter(|number| number.base == base)
+            .count();
+        assert_eq!(slots, backgrounds);
+        assert_eq!(cooldowns, backgrounds);
+        assert_eq!(numbers, backgrounds);
+        backgrounds
+    };
+    let (first, center) = bar(&mut app);
+    assert_eq!(count(&mut app, first), 9);
+
+    app.world.resource_mut::<BaseInventory>().grow(3);
+    app.update();
+    let (grown, grown_center) = bar(&mut app);
+    assert_ne!(grown, first);
+    assert_eq!(grown_center, center);
+    assert_eq!(count(&mut app, grown), 12);
+    // Nothing of the old bar is left
+    assert_eq!(
+        app.world
+            .query::<&InventorySlotBackground>()
+            .iter(&app.world)
+            .count(),
+        12
+    );
+    assert_eq!(
+        app.world
+            .query::<&InventorySlotHighlight>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+    assert_eq!(
+        app.world
+            .query::<&InventoryItemName>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+
+    app.world.resource_mut::<BaseInventory>().shrink(7);
+    app.update();
+    let (shrunk, _) = bar(&mut app);
+    assert_eq!(count(&mut app, shrunk), 5);
+    assert_eq!(
+        app.world
+            .query::<&InventorySlotCooldown>()
+            .iter(&app.world)
+            .count(),
+        5
+    );
+
+    // Same slot count, the bar stays
+    app.world.resource_mut::<BaseInventory>().set_changed();
+    app.update();
+    assert_eq!(bar(&mut app).0, shrunk);
+}
+
+#[test]
+fn image_paths_follow_the_settings() {
+    let mut app = bar_app();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .add_asset::<Image>()
+        .add_systems(Update, apply_inventory_images);
+    app.world.resource_mut::<BaseInventorySettings>().slot_image = Some(InventoryImage {
+        source: "slot.png".into(),
+        slice: None,
+    });
+    let textures = |app: &mut App| {
+        app.world
+            .query_filtered::<&Handle<Image>, With<InventorySlotBackground>>()
+            .iter(&app.world)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let path = |app: &App, path: &str| {
+        app.world
+            .resource::<AssetServer>()
+            .get_handle::<Image, _>(path)
+    };
+
+    app.update();
+    app.update();
+    let slot = path(&app, "slot.png");
+    assert_eq!(textures(&mut app), vec![slot.clone(); 9]);
+
+    app.world.resource_mut::<BaseInventorySettings>().slot_image = Some(InventoryImage {
+        source: "other.png".into(),
+        slice: None,
+    });
+    app.update();
+    let other = path(&app, "other.png");
+    assert_ne!(other, slot);
+    assert_eq!(textures(&mut app), vec![other; 9]);
+}
+
+#[test]
+fn settings_changes_relayout_the_bar() {
+    let mut app = bar_app();
+    app.add_systems(Update, relayout_inventory_panels);
+    app.update();
+
+    let panel = |app: &mut App| {
+        *app.world
+            .query_filtered::<&Transform, With<BaseInventoryBackground>>()
+            .single(&app.world)
+    };
+    let before = panel(&mut app);
+    let mut settings = app.world.resource_mut::<BaseInventorySettings>();
+    settings.w_padding = 10.0;
+    settings.slot_size = 30.0;
+    settings.slot_margin = 4.0;
+    let settings = settings.0.clone();
+    app.update();
+
+    // Same center, new size
+    let after = panel(&mut app);
+    assert_eq!(after.translation, before.translation);
+    assert_eq!(
+        after.scale.truncate(),
+        inventory_panel_size(&settings, 1, 9)
+    );
+    let centers = layout_slots(&settings, 1, 9, after.translation.truncate());
+    for (background, transform) in app
+        .world
+        .query::<(&InventorySlotBackground, &Transform)>()
+        .iter(&app.world)
+    {
+        assert_eq!(
+            transform.translation.truncate(),
+            centers[background.slot - 1]
+        );
+        assert_eq!(transform.scale, Vec3::new(30.0, 30.0, 1.0));
+    }
+    for (slot, sprite, transform) in app
+        .world
+        .query::<(&InventorySlot, &Sprite, &Transform)>()
+        .iter(&app.world)
+    {
+        assert_eq!(transform.translation.truncate(), centers[slot.slot - 1]);
+        assert_eq!(sprite.custom_size, Some(Vec2::splat(26.0)));
+    }
+    for (text, transform) in app
+        .world
+        .query_filtered::<(&Text, &Transform), With<InventoryStackCount>>()
+        .iter(&app.world)
+    {
+        assert_eq!(transform.translation, Vec3::new(13.0, -13.0, 1.0));
+        assert_eq!(text.sections[0].style.font_size, 12.0);
+    }
+}
+
+#[test]
+fn no_bar_without_a_primary_window() {
+    let mut app = App::new();
+    app.init_resource::<BaseInventory>()
+        .insert_resource(BaseInventorySettings(InventorySettings::default()))
+        .add_systems(Startup, spawn_base_inventory);
+    app.update();
+    assert_eq!(
+        app.world
+            .query::<&InventorySlotBackground>()
+            .iter(&app.world)
+            .count(),
+        0
+    );
+}
@@ -0,0 +1,373 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_toolbox::{
+    animation::{AnimationPlugin, Animator, TranslationShakeLens},
+    clear_selection_on_input,
+    grid::{
+        CursorGridCoord, CursorWorldPosition, GridCoord, GridLayer, GridOverride, GridSelection,
+        GridSettings, LayeredGridMap, RectMap, SubCellMap,
+    },
+    inventory::{
+        tick_slot_cooldowns, ActiveInventory, BackpackOpen, BaseInventory, ClickConsumed,
+        Container, DynInventory, OpenContainer, SlotCooldowns,
+    },
+    items::{ContainerItem, Item, ItemCode, ItemPreview},
+    place_selected_item, remove_finished_shakes, show_selected_item, InfinitePlacement,
+    InventoryKeyBindings, PlacementShake,
+};
+
+fn app(count: u32, infinite: bool) -> App {
+    let mut app = App::new();
+    app.init_resource::<Input<MouseButton>>()
+        .init_resource::<ClickConsumed>()
+        .insert_resource(InfinitePlacement(infinite))
+        .init_resource::<BaseInventory>()
+        .init_resource::<CursorGridCoord>()
+        .insert_resource(GridSettings::from_u32(10))
+        .init_resource::<GridSelection>()
+        .init_resource::<LayeredGridMap<Entity>>()
+        .init_resource::<SubCellMap<Entity>>()
+        .init_resource::<RectMap<Entity>>()
+        .add_systems(Update, place_selected_item);
+    app.world
+        .spawn((ItemPreview, ItemCode(1), SpriteBundle::default()));
+
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    inventory
+        .put_item(3, Item::new(ItemCode(1), count, 64))
+        .unwrap();
+    inventory.select_item(3).unwrap();
+    app
+}
+
+/// Clicks on a new cell every time, returns the number of placed items
+fn click(app: &mut App, x: i32) -> usize {
+    app.world.resource_mut::<CursorGridCoord>().0 = Some(GridCoord::new(x, 0));
+    click_here(app)
+}
+
+/// Clicks wherever the cursor is, returns the number of placed items
+fn click_here(app: &mut App) -> usize {
+    app.world
+        .resource_mut::<Input<MouseButton>>()
+        .press(MouseButton::Left);
+    app.update();
+    app.world.resource_mut::<Input<MouseButton>>().reset_all();
+    app.world
+        .query_filtered::<(), (With<Sprite>, Without<ItemPreview>)>()
+        .iter(&app.world)
+        .count()
+}
+
+#[test]
+fn placing_consumes_the_stack() {
+    let mut app = app(2, false);
+    assert_eq!(click(&mut app, 0), 1);
+    assert_eq!(
+        app.world
+            .resource::<BaseInventory>()
+            .get_item(3)
+            .unwrap()
+            .count,
+        1
+    );
+    assert_eq!(click(&mut app, 1), 2);
+    assert_eq!(click(&mut app, 2), 2);
+
+    let inventory = app.world.resource::<BaseInventory>();
+    assert_eq!(inventory.get_item(3), None);
+    assert_eq!(inventory.selected_slot(), None);
+}
+
+#[test]
+fn infinite_placement_keeps_the_stack() {
+    let mut app = app(1, true);
+    for x in 0..3 {
+        click(&mut app, x);
+    }
+    assert_eq!(click(&mut app, 3), 4);
+    let inventory = app.world.resource::<BaseInventory>();
+    assert_eq!(inventory.get_item(3).unwrap().count, 1);
+    assert_eq!(inventory.selected_slot(), Some(3));
+}
+
+#[test]
+fn placing_takes_from_the_active_inventory() {
+    let mut app = app(5, false);
+    let mut chest = DynInventory::with_capacity(2);
+    chest.put_item(2, Item::new(ItemCode(1), 3, 64)).unwrap();
+    chest.select_item(2).unwrap();
+    let other = app.world.spawn(DynInventory::with_capacity(2)).id();
+    let chest = app.world.spawn(chest).id();
+    app.insert_resource(ActiveInventory(Some(chest)));
+
+    assert_eq!(click(&mut app, 0), 1);
+    assert_eq!(
+        app.world
+            .get::<DynInventory>(chest)
+            .unwrap()
+            .get_item(2)
+            .unwrap()
+            .count,
+        2
+    );
+    assert_eq!(
+        app.world
+            .resource::<BaseInventory>()
+            .get_item(3)
+            .unwrap()
+            .count,
+        5
+    );
+
+    // Nothing selected in the other inventory, nothing to place
+    app.insert_resource(ActiveInventory(Some(other)));
+    assert_eq!(click(&mut app, 1), 1);
+}
+
+#[test]
+fn no_placement_while_a_container_is_open() {
+    let mut app = app(5, false);
+    let chest = app.world.spawn(DynInventory::with_capacity(2)).id();
+    app.insert_resource(OpenContainer(Some(chest)));
+    assert_eq!(click(&mut app, 0), 0);
+    app.insert_resource(OpenContainer(None));
+    assert_eq!(click(&mut app, 0), 1);
+}
+
+#[test]
+fn placed_container_items_get_an_inventory() {
+    let mut app = app(1, false);
+    let preview = app
+        .world
+        .query_filtered::<Entity, With<ItemPreview>>()
+        .single(&app.world);
+    app.world
+        .entity_mut(preview)
+        .insert(ContainerItem { slots: 6 });
+    assert_eq!(click(&mut app, 0), 1);
+    let slots: Vec<_> = app
+        .world
+        .query_filtered::<&DynInventory, With<Container>>()
+        .iter(&app.world)
+        .map(DynInventory::slot_count)
+        .collect();
+    assert_eq!(slots, [6]);
+}
+
+#[test]
+fn slots_on_cooldown_place_nothing() {
+    let mut app = app(5, false);
+    app.init_resource::<Time>()
+        .init_resource::<SlotCooldowns>()
+        .add_systems(Update, tick_slot_cooldowns.before(place_selected_item));
+    let mut now = app.world.resource_mut::<Time>().startup();
+    app.world.resource_mut::<Time>().update_with_instant(now);
+    app.world
+        .resource_mut::<SlotCooldowns>()
+        .start_cooldown(None, 3, Duration::from_secs(1));
+
+    let mut step = |app: &mut App, millis: u64, x: i32| {
+        now += Duration::from_millis(millis);
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        click(app, x)
+    };
+    assert_eq!(step(&mut app, 0, 0), 0);
+    assert_eq!(step(&mut app, 600, 1), 0);
+    assert_eq!(
+        app.world.resource::<SlotCooldowns>().remaining(None, 3),
+        Some(Duration::from_millis(400))
+    );
+    // Over at zero, the same frame places again
+    assert_eq!(step(&mut app, 400, 2), 1);
+    assert!(!app
+        .world
+        .resource::<SlotCooldowns>()
+        .is_cooling_down(None, 3));
+
+    // The same slot of another inventory has a cooldown of its own
+    let mut chest = DynInventory::with_capacity(3);
+    chest.put_item(3, Item::new(ItemCode(1), 5, 64)).unwrap();
+    chest.select_item(3).unwrap();
+    let chest = app.world.spawn(chest).id();
+    app.insert_resource(ActiveInventory(Some(chest)));
+    app.world
+        .resource_mut::<SlotCooldowns>()
+        .start_cooldown(None, 3, Duration::from_secs(1));
+    assert_eq!(step(&mut app, 0, 3), 2);
+    app.world.resource_mut::<SlotCooldowns>().start_cooldown(
+        Some(chest),
+        3,
+        Duration::from_secs(1),
+    );
+    assert_eq!(step(&mut app, 0, 4), 2);
+}
+
+#[test]
+fn no_placement_while_the_backpack_is_open() {
+    let mut app = app(5, false);
+    app.insert_resource(BackpackOpen(true));
+    assert_eq!(click(&mut app, 0), 0);
+    app.insert_resource(BackpackOpen(false));
+    assert_eq!(click(&mut app, 0), 1);
+}
+
+#[test]
+fn cleared_selection_places_nothing() {
+    let mut app = app(5, false);
+    app.init_resource::<Input<KeyCode>>()
+        .init_resource::<InventoryKeyBindings>()
+        .add_systems(Update, clear_selection_on_input.before(place_selected_item));
+    let preview = app
+        .world
+        .query_filtered::<Entity, With<ItemPreview>>()
+        .single(&app.world);
+    *app.world.get_mut::<Visibility>(preview).unwrap() = Visibility::Visible;
+
+    app.world
+        .resource_mut::<Input<MouseButton>>()
+        .press(MouseButton::Right);
+    app.update();
+    app.world.resource_mut::<Input<MouseButton>>().reset_all();
+    assert_eq!(app.world.resource::<BaseInventory>().selected_slot(), None);
+    assert_eq!(
+        app.world.get::<Visibility>(preview),
+        Some(&Visibility::Hidden)
+    );
+    assert_eq!(click(&mut app, 0), 0);
+
+    // A bound key clears too
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(3)
+        .unwrap();
+    app.world.resource_mut::<InventoryKeyBindings>().clear_key = Some(KeyCode::X);
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::X);
+    app.update();
+    assert_eq!(app.world.resource::<BaseInventory>().selected_slot(), None);
+}
+
+#[test]
+fn placement_shake_is_opt_in_and_cleaned_up() {
+    let shaking = |app: &mut App| {
+        app.world
+            .query::<&Animator<TranslationShakeLens>>()
+            .iter(&app.world)
+            .count()
+    };
+    let mut app = app(4, false);
+    app.add_plugins(AnimationPlugin::default())
+        .init_resource::<Time>()
+        .add_systems(PostUpdate, remove_finished_shakes);
+    let mut now = app.world.resource_mut::<Time>().startup();
+    app.world.resource_mut::<Time>().update_with_instant(now);
+    assert_eq!(click(&mut app, 0), 1);
+    assert_eq!(shaking(&mut app), 0);
+
+    app.insert_resource(PlacementShake(true));
+    assert_eq!(click(&mut app, 1), 2);
+    assert_eq!(shaking(&mut app), 1);
+    for _ in 0..2 {
+        now += Duration::from_millis(150);
+        app.world.resource_mut::<Time>().update_with_instant(now);
+        app.update();
+    }
+    assert_eq!(shaking(&mut app), 0);
+}
+
+#[test]
+fn sub_cell_items_respect_whole_cell_and_overridden_items() {
+    let mut app = app(5, false);
+    app.world.resource_mut::<GridSettings>().snap_divisions = 2;
+    for (_, item) in app.world.resource_mut::<BaseInventory>().iter_mut() {
+        item.sub_cell = true;
+    }
+    let occupant = app.world.spawn_empty().id();
+    let mut grid_map = app.world.resource_mut::<LayeredGridMap<Entity>>();
+    grid_map.insert(GridLayer::OBJECT, GridCoord::new(0, 0), occupant);
+    let mut rect_map = app.world.resource_mut::<RectMap<Entity>>();
+    rect_map.insert(
+        GridLayer::OBJECT,
+        Rect::new(12.0, -5.0, 20.0, 3.0),
+        occupant,
+    );
+    let move_preview = |app: &mut App, x: f32, y: f32| {
+        let mut previews = app
+            .world
+            .query_filtered::<&mut Transform, With<ItemPreview>>();
+        previews.single_mut(&mut app.world).translation = Vec3::new(x, y, 0.0);
+    };
+
+    // Bottom left sub-cell of a cell taken by a whole cell item
+    move_preview(&mut app, 2.5, 2.5);
+    assert_eq!(click(&mut app, 0), 0);
+    // Bottom left sub-cell of (1, 0) overlaps the overridden item
+    move_preview(&mut app, 12.5, 2.5);
+    assert_eq!(click(&mut app, 1), 0);
+    // Top right sub-cell of (1, 0) is free
+    move_preview(&mut app, 17.5, 7.5);
+    assert_eq!(click(&mut app, 1), 1);
+    assert_eq!(app.world.resource::<SubCellMap<Entity>>().len(), 1);
+}
+
+#[test]
+fn overlapping_override_grids_snap_apart_and_block_each_other() {
+    let mut app = app(5, false);
+    app.init_resource::<CursorWorldPosition>()
+        .add_systems(Update, show_selected_item.before(place_selected_item));
+    let mut previews = app.world.query_filtered::<Entity, With<ItemPreview>>();
+    let large = previews.single(&app.world);
+    app.world.entity_mut(large).insert(GridOverride {
+        size: UVec2::splat(30),
+        origin: None,
+    });
+    let small = app
+        .world
+        .spawn((
+            ItemPreview,
+            ItemCode(2),
+            SpriteBundle::default(),
+            GridOverride {
+                size: UVec2::splat(20),
+                origin: None,
+            },
+        ))
+        .id();
+    app.world
+        .resource_mut::<BaseInventory>()
+        .put_item(4, Item::new(ItemCode(2), 5, 64))
+        .unwrap();
+
+    let click_at = |app: &mut App, position: Vec2| {
+        app.world.resource_mut::<CursorWorldPosition>().0 = Some(position);
+        app.world.resource_mut::<CursorGridCoord>().0 = Some(GridCoord::new(
+            (position.x / 10.0).floor() as i32,
+            (position.y / 10.0).floor() as i32,
+        ));
+        click_here(app)
+    };
+    let translation = |app: &App, preview| {
+        app.world
+            .get::<Transform>(preview)
+            .unwrap()
+            .translation
+            .truncate()
+    };
+
+    // Same cursor, each preview snaps to the center of its own cell
+    assert_eq!(click_at(&mut app, Vec2::new(12.0, 12.0)), 1);
+    assert_eq!(translation(&app, large), Vec2::new(15.0, 15.0));
+    app.world
+        .resource_mut::<BaseInventory>()
+        .select_item(4)
+        .unwrap();
+    assert_eq!(click_at(&mut app, Vec2::new(12.0, 12.0)), 1);
+    assert_eq!(translation(&app, small), Vec2::new(10.0, 10.0));
+    assert_eq!(app.world.resource::<RectMap<Entity>>().len(), 1);
+
+    // Clear of the large item
+    assert_eq!(click_at(&mut app, Vec2::new(45.0, 45.0)), 2);
+    assert_eq!(translation(&app, small), Vec2::new(50.0, 50.0));
+    assert_eq!(app.world.resource::<RectMap<Entity>>().len(), 2);
+}
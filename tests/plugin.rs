@@ -0,0 +1,84 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState, InputPlugin},
+    prelude::*,
+    time::TimePlugin,
+};
+use bevy_toolbox::{
+    grid::GridPlugin,
+    inventory::{
+        BaseInventory, BaseInventoryBackground, BaseInventorySettings, InventoryPlugin,
+        InventorySet, InventorySettings, InventorySlotBackground, SelectionChanged,
+    },
+};
+
+mod common;
+
+use common::spawn_primary_window;
+
+#[derive(Resource, Default)]
+struct SelectedAfterInventory(Option<usize>);
+
+#[test]
+fn plugin_wires_the_hotbar() {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        InputPlugin,
+        GridPlugin::default(),
+        InventoryPlugin {
+            spawn_schedule: Box::new(Startup),
+            ..InventoryPlugin::with_settings(InventorySettings {
+                slot_size: 40.0,
+                ..Default::default()
+            })
+        },
+    ))
+    .init_resource::<SelectedAfterInventory>()
+    .add_systems(
+        Update,
+        (|inventory: Res<BaseInventory>, mut selected: ResMut<SelectedAfterInventory>| {
+            selected.0 = inventory.selected_slot();
+        })
+        .after(InventorySet),
+    );
+    spawn_primary_window(&mut app);
+
+    app.update();
+    assert_eq!(
+        app.world.resource::<BaseInventorySettings>().slot_size,
+        40.0
+    );
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<BaseInventoryBackground>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+    assert_eq!(
+        app.world
+            .query::<&InventorySlotBackground>()
+            .iter(&app.world)
+            .count(),
+        9
+    );
+
+    // Input is cleared before the update, so the press goes through bevy's input events
+    app.world.send_event(KeyboardInput {
+        scan_code: 0,
+        key_code: Some(KeyCode::Key3),
+        state: ButtonState::Pressed,
+        window: Entity::PLACEHOLDER,
+    });
+    app.update();
+    assert_eq!(app.world.resource::<SelectedAfterInventory>().0, Some(3));
+    let events = app.world.resource::<Events<SelectionChanged>>();
+    assert_eq!(
+        events.iter_current_update_events().next(),
+        Some(&SelectionChanged {
+            inventory: None,
+            previous: 0,
+            current: 3,
+        })
+    );
+}
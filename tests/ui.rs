@@ -0,0 +1,140 @@
+use bevy::{asset::HandleId, prelude::*, reflect::TypeUuid};
+use bevy_toolbox::{
+    inventory::{BaseInventory, ClickConsumed},
+    inventory_ui::{
+        InventoryUiPlugin, UiInventoryBar, UiInventoryBarPanel, UiInventorySlot,
+        UiInventorySlotImage, UiInventoryStackCount,
+    },
+    items::{Item, ItemCode, ItemImage, ItemPreview},
+};
+
+mod common;
+
+use common::inventory_app;
+
+fn app() -> App {
+    let mut app = inventory_app();
+    app.add_plugins(InventoryUiPlugin);
+    app.world
+        .spawn((ItemPreview, ItemCode(1), ItemImage(image(1))));
+    app
+}
+
+fn image(id: u64) -> Handle<Image> {
+    Handle::weak(HandleId::new(Image::TYPE_UUID, id))
+}
+
+fn slot(app: &mut App, slot: usize) -> Entity {
+    app.world
+        .query::<(Entity, &UiInventorySlot)>()
+        .iter(&app.world)
+        .find(|(_, ui_slot)| ui_slot.slot == slot)
+        .unwrap()
+        .0
+}
+
+fn slot_image(app: &mut App, slot: usize) -> (Handle<Image>, Visibility) {
+    app.world
+        .query::<(&UiInventorySlotImage, &UiImage, &Visibility)>()
+        .iter(&app.world)
+        .find(|(image, ..)| image.slot == slot)
+        .map(|(_, ui_image, visibility)| (ui_image.texture.clone(), *visibility))
+        .unwrap()
+}
+
+fn stack_count(app: &mut App, slot: usize) -> (String, Visibility) {
+    app.world
+        .query::<(&UiInventoryStackCount, &Text, &Visibility)>()
+        .iter(&app.world)
+        .find(|(count, ..)| count.slot == slot)
+        .map(|(_, text, visibility)| (text.sections[0].value.clone(), *visibility))
+        .unwrap()
+}
+
+#[test]
+fn ui_bar_shows_items_and_selection() {
+    let mut app = app();
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<UiInventoryBar>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+    let slots = app
+        .world
+        .query::<(&UiInventorySlot, &Style)>()
+        .iter(&app.world)
+        .map(|(_, style)| style.width)
+        .collect::<Vec<_>>();
+    assert_eq!(slots.len(), 9);
+    assert!(slots.iter().all(|width| *width == Val::Px(50.0)));
+
+    let mut inventory = app.world.resource_mut::<BaseInventory>();
+    inventory
+        .put_item(2, Item::new(ItemCode(1), 5, 64))
+        .unwrap();
+    inventory.select_item(2).unwrap();
+    app.update();
+    assert_eq!(slot_image(&mut app, 2), (image(1), Visibility::Inherited));
+    assert_eq!(slot_image(&mut app, 3).1, Visibility::Hidden);
+    assert_eq!(
+        stack_count(&mut app, 2),
+        ("5".to_string(), Visibility::Inherited)
+    );
+    let selected = slot(&mut app, 2);
+    assert_eq!(
+        app.world.get::<BorderColor>(selected).unwrap().0,
+        Color::ORANGE
+    );
+    let other = slot(&mut app, 3);
+    assert_eq!(app.world.get::<BorderColor>(other).unwrap().0, Color::NONE);
+
+    // Pressing a slot button selects it and keeps the click from the world
+    *app.world.get_mut::<Interaction>(other).unwrap() = Interaction::Pressed;
+    app.update();
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(3)
+    );
+    assert!(app.world.resource::<ClickConsumed>().0);
+
+    // So does pressing the bar between the slots, without changing the selection
+    *app.world.get_mut::<Interaction>(other).unwrap() = Interaction::None;
+    app.world.resource_mut::<ClickConsumed>().0 = false;
+    app.update();
+    assert!(!app.world.resource::<ClickConsumed>().0);
+    let panel = app
+        .world
+        .query_filtered::<Entity, With<UiInventoryBarPanel>>()
+        .single(&app.world);
+    *app.world.get_mut::<Interaction>(panel).unwrap() = Interaction::Pressed;
+    app.update();
+    assert!(app.world.resource::<ClickConsumed>().0);
+    assert_eq!(
+        app.world.resource::<BaseInventory>().selected_slot(),
+        Some(3)
+    );
+
+    // The bar follows the slot count
+    app.world.resource_mut::<BaseInventory>().grow(2);
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world
+            .query::<&UiInventorySlot>()
+            .iter(&app.world)
+            .count(),
+        11
+    );
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<UiInventoryBar>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+    assert_eq!(slot_image(&mut app, 2), (image(1), Visibility::Inherited));
+}
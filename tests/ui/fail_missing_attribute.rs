@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+use bevy_toolbox::animation::AnimationLens;
+
+#[derive(AnimationLens)]
+struct NoAttribute {
+    start: Vec3,
+    end: Vec3,
+}
+
+#[derive(AnimationLens)]
+#[lens(component = "Transform")]
+struct NoField {
+    start: Vec3,
+    end: Vec3,
+}
+
+#[derive(AnimationLens)]
+#[lens(component = "Transform", field = "translation", speed = "2")]
+struct UnknownKey {
+    start: Vec3,
+    end: Vec3,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+use bevy_toolbox::animation::AnimationLens;
+
+#[derive(AnimationLens)]
+#[lens(component = "Transform", field = "translation")]
+struct NoEnd {
+    start: Vec3,
+}
+
+#[derive(AnimationLens)]
+#[lens(component = "Transform", field = "scale")]
+struct Tuple(Vec3, Vec3);
+
+fn main() {}
@@ -0,0 +1,11 @@
+use bevy::prelude::*;
+use bevy_toolbox::animation::AnimationLens;
+
+#[derive(AnimationLens)]
+#[lens(component = "Sprite", field = "flip_x")]
+struct Flip {
+    start: bool,
+    end: bool,
+}
+
+fn main() {}
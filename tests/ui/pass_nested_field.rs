@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+use bevy_toolbox::animation::AnimationLens;
+
+#[derive(AnimationLens)]
+#[lens(component = "Transform", field = "translation.x")]
+struct SlideX {
+    start: f32,
+    end: f32,
+}
+
+#[derive(AnimationLens)]
+#[lens(component = "Transform", field = "rotation")]
+struct Spin {
+    start: Quat,
+    end: Quat,
+}
+
+fn main() {
+    let mut transform = Transform::default();
+    SlideX {
+        start: 0.0,
+        end: 2.0,
+    }
+    .lerp(&mut transform, 0.5);
+    assert_eq!(transform.translation.x, 1.0);
+
+    Spin {
+        start: Quat::IDENTITY,
+        end: Quat::IDENTITY,
+    }
+    .lerp(&mut transform, 0.5);
+}